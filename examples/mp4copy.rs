@@ -7,6 +7,7 @@ use std::path::Path;
 use mp4::{
     AacConfig,
     AvcConfig,
+    Error,
     HevcConfig,
     Vp9Config,
     TtxtConfig,
@@ -60,10 +61,12 @@ fn copy<P: AsRef<Path>>(src_filename: &P, dst_filename: &P) -> Result<()> {
             MediaType::H265 => MediaConfig::HevcConfig(HevcConfig {
                 width: track.width(),
                 height: track.height(),
+                ..HevcConfig::default()
             }),
             MediaType::VP9 => MediaConfig::Vp9Config(Vp9Config {
                 width: track.width(),
                 height: track.height(),
+                ..Vp9Config::default()
             }),
             MediaType::AAC => MediaConfig::AacConfig(AacConfig {
                 bitrate: track.bitrate(),
@@ -72,6 +75,7 @@ fn copy<P: AsRef<Path>>(src_filename: &P, dst_filename: &P) -> Result<()> {
                 chan_conf: track.channel_config()?,
             }),
             MediaType::TTXT => MediaConfig::TtxtConfig(TtxtConfig {}),
+            MediaType::MP3 => return Err(Error::InvalidData("mp3 tracks are not supported")),
         };
 
         let track_conf = TrackConfig {
@@ -79,6 +83,11 @@ fn copy<P: AsRef<Path>>(src_filename: &P, dst_filename: &P) -> Result<()> {
             timescale: track.timescale(),
             language: track.language().to_string(),
             media_conf,
+            data_references: Vec::new(),
+            data_reference_index: 1,
+            layer: track.layer(),
+            alternate_group: track.alternate_group(),
+            balance: track.trak.mdia.minf.smhd.as_ref().map(|smhd| smhd.balance()).unwrap_or(0.0),
         };
 
         mp4_writer.add_track(&track_conf)?;
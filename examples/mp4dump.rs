@@ -57,13 +57,34 @@ fn get_boxes(file: File) -> Result<Vec<Box>> {
         if let Some(mehd) = &mvex.mehd {
             boxes.push(build_box(mehd));
         }
-        boxes.push(build_box(&mvex.trex));
+        for trex in mvex.trexs.iter() {
+            boxes.push(build_box(trex));
+        }
+    }
+
+    if let Some(ref udta) = &mp4.moov.udta {
+        boxes.push(build_box(udta));
+        if let Some(ref cprt) = udta.cprt {
+            boxes.push(build_box(cprt));
+        }
+        if let Some(ref yrrc) = udta.yrrc {
+            boxes.push(build_box(yrrc));
+        }
+        for asset in udta.assets.iter() {
+            boxes.push(build_box(asset));
+        }
     }
 
     // trak.
     for track in mp4.tracks().values() {
         boxes.push(build_box(&track.trak));
         boxes.push(build_box(&track.trak.tkhd));
+        if let Some(ref tref) = track.trak.tref {
+            boxes.push(build_box(tref));
+        }
+        if let Some(ref tapt) = track.trak.tapt {
+            boxes.push(build_box(tapt));
+        }
         if let Some(ref edts) = track.trak.edts {
             boxes.push(build_box(edts));
             if let Some(ref elst) = edts.elst {
@@ -93,17 +114,37 @@ fn get_boxes(file: File) -> Result<Vec<Box>> {
         boxes.push(build_box(&stbl.stsd));
         if let Some(ref avc1) = &stbl.stsd.avc1 {
             boxes.push(build_box(avc1));
+            if let Some(ref colr) = &avc1.colr {
+                boxes.push(build_box(colr));
+            }
         }
         if let Some(ref hev1) = &stbl.stsd.hev1 {
             boxes.push(build_box(hev1));
+            if let Some(ref colr) = &hev1.colr {
+                boxes.push(build_box(colr));
+            }
+        }
+        if let Some(ref av01) = &stbl.stsd.av01 {
+            boxes.push(build_box(av01));
+            boxes.push(build_box(&av01.av1c));
+            if let Some(ref colr) = &av01.colr {
+                boxes.push(build_box(colr));
+            }
         }
         if let Some(ref mp4a) = &stbl.stsd.mp4a {
             boxes.push(build_box(mp4a));
         }
+        if let Some(ref samr) = &stbl.stsd.samr {
+            boxes.push(build_box(samr));
+            boxes.push(build_box(&samr.damr));
+        }
         boxes.push(build_box(&stbl.stts));
         if let Some(ref ctts) = &stbl.ctts {
             boxes.push(build_box(ctts));
         }
+        if let Some(ref cslg) = &stbl.cslg {
+            boxes.push(build_box(cslg));
+        }
         if let Some(ref stss) = &stbl.stss {
             boxes.push(build_box(stss));
         }
@@ -115,6 +156,51 @@ fn get_boxes(file: File) -> Result<Vec<Box>> {
         if let Some(ref co64) = &stbl.co64 {
             boxes.push(build_box(co64));
         }
+        for sgpd in stbl.sgpds.iter() {
+            boxes.push(build_box(sgpd));
+        }
+        for sbgp in stbl.sbgps.iter() {
+            boxes.push(build_box(sbgp));
+        }
+    }
+
+    // Add file-level meta boxes (HEIF-like items).
+    if let Some(ref meta) = &mp4.meta {
+        boxes.push(build_box(meta));
+        if let Some(ref pitm) = &meta.pitm {
+            boxes.push(build_box(pitm));
+        }
+        if let Some(ref iloc) = &meta.iloc {
+            boxes.push(build_box(iloc));
+        }
+        if let Some(ref iref) = &meta.iref {
+            boxes.push(build_box(iref));
+        }
+        if let Some(ref ipro) = &meta.ipro {
+            boxes.push(build_box(ipro));
+            for sinf in ipro.sinfs.iter() {
+                boxes.push(build_box(sinf));
+            }
+        }
+        if let Some(ref iinf) = &meta.iinf {
+            boxes.push(build_box(iinf));
+            for infe in iinf.entries.iter() {
+                boxes.push(build_box(infe));
+            }
+        }
+        if let Some(ref xml) = &meta.xml {
+            boxes.push(build_box(xml));
+        }
+        if let Some(ref bxml) = &meta.bxml {
+            boxes.push(build_box(bxml));
+        }
+    }
+
+    for sidx in mp4.sidxs.iter() {
+        boxes.push(build_box(sidx));
+    }
+    for ssix in mp4.ssixs.iter() {
+        boxes.push(build_box(ssix));
     }
 
     // If fragmented, add moof boxes.
@@ -124,6 +210,9 @@ fn get_boxes(file: File) -> Result<Vec<Box>> {
         for traf in moof.trafs.iter() {
             boxes.push(build_box(traf));
             boxes.push(build_box(&traf.tfhd));
+            if let Some(ref tfdt) = &traf.tfdt {
+                boxes.push(build_box(tfdt));
+            }
             if let Some(ref trun) = &traf.trun {
                 boxes.push(build_box(trun));
             }
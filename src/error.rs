@@ -1,27 +1,63 @@
-use thiserror::Error;
+use core::fmt;
 
 use crate::mp4box::BoxType;
 
-#[derive(Error, Debug)]
+#[derive(Debug)]
 pub enum Error {
-    #[error("{0}")]
-    IoError(#[from] std::io::Error),
-    #[error("{0}")]
+    #[cfg(feature = "std")]
+    IoError(std::io::Error),
     InvalidData(&'static str),
-    #[error("{0} not found")]
     BoxNotFound(BoxType),
-    #[error("{0} and {1} not found")]
     Box2NotFound(BoxType, BoxType),
-    #[error("trak[{0}] not found")]
     TrakNotFound(u32),
-    #[error("trak[{0}].{1} not found")]
     BoxInTrakNotFound(u32, BoxType),
-    #[error("traf[{0}].{1} not found")]
     BoxInTrafNotFound(u32, BoxType),
-    #[error("trak[{0}].stbl.{1} not found")]
     BoxInStblNotFound(u32, BoxType),
-    #[error("trak[{0}].stbl.{1}.entry[{2}] not found")]
     EntryInStblNotFound(u32, BoxType, u32),
-    #[error("traf[{0}].trun.{1}.entry[{2}] not found")]
     EntryInTrunNotFound(u32, BoxType, u32),
+    InvalidEntry(BoxType, u32),
+    UnexpectedEof(u32, u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Error::IoError(err) => write!(f, "{}", err),
+            Error::InvalidData(msg) => write!(f, "{}", msg),
+            Error::BoxNotFound(t) => write!(f, "{} not found", t),
+            Error::Box2NotFound(t1, t2) => write!(f, "{} and {} not found", t1, t2),
+            Error::TrakNotFound(id) => write!(f, "trak[{}] not found", id),
+            Error::BoxInTrakNotFound(id, t) => write!(f, "trak[{}].{} not found", id, t),
+            Error::BoxInTrafNotFound(id, t) => write!(f, "traf[{}].{} not found", id, t),
+            Error::BoxInStblNotFound(id, t) => write!(f, "trak[{}].stbl.{} not found", id, t),
+            Error::EntryInStblNotFound(id, t, entry) => {
+                write!(f, "trak[{}].stbl.{}.entry[{}] not found", id, t, entry)
+            }
+            Error::EntryInTrunNotFound(id, t, entry) => {
+                write!(f, "traf[{}].trun.{}.entry[{}] not found", id, t, entry)
+            }
+            Error::InvalidEntry(t, entry) => write!(f, "{}.entry[{}] is invalid", t, entry),
+            Error::UnexpectedEof(id, sample) => {
+                write!(f, "trak[{}].sample[{}] extends past the end of the file", id, sample)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::IoError(err)
+    }
 }
@@ -63,32 +63,73 @@
 //! 
 //! [mp4box]: https://github.com/alfg/mp4-rust/blob/master/src/mp4box/mod.rs
 //! [examples]: https://github.com/alfg/mp4-rust/blob/master/src/examples
+//!
+//! # no_std
+//!
+//! Reading and writing files through `std::io` needs the default `std`
+//! feature. Without it, the crate is built on `alloc`: the box types, the
+//! types in this module and [Error] are still available, and [slice]
+//! decodes boxes such as mvhd and tkhd from an in-memory buffer. The tests
+//! of that build run with `cargo test --no-default-features --lib`.
 #![doc(html_root_url = "https://docs.rs/mp4/*")]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
 
+#[cfg(feature = "std")]
 use std::io::{BufReader};
+#[cfg(feature = "std")]
 use std::fs::File;
 
+// What the std prelude would otherwise provide.
+#[cfg(not(feature = "std"))]
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
 mod error;
 pub use error::Error;
 
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
 
 mod types;
 pub use types::*;
 
+// Without std, the helpers only the Read/Write impls call go unused.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
 mod mp4box;
-pub use mp4box::{Mp4Box};
+pub use mp4box::{EmsgBox, FtypBox, MoofBox, MoovBox, Mp4Box, WriteBox};
+pub use mp4box::{hdlr::HdlrBox, mdhd::MdhdBox, mvhd::MvhdBox, tkhd::TkhdBox, traf::TrafBox};
 
+#[cfg(feature = "std")]
 mod track;
-pub use track::{Mp4Track, TrackConfig};
+#[cfg(feature = "std")]
+pub use track::{Chunk, DurationMismatch, EditListIssue, FrameRate, Mp4Track, SampleEntry, SampleTiming, Thumbnail, TrackConfig};
 
+#[cfg(feature = "std")]
 mod reader;
-pub use reader::Mp4Reader;
+#[cfg(feature = "std")]
+pub use reader::{ChunkOutOfBounds, DecodeTimeDiscontinuity, FreeSpace, Mp4Reader};
 
+mod nal;
+pub use nal::{AvcNal, AvcSampleSplitter, HevcNal, HevcSampleSplitter};
+
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub use registry::{BoxParserRegistry, CustomBox, ParsedBox};
+
+#[cfg(feature = "std")]
 mod writer;
-pub use writer::{Mp4Config, Mp4Writer};
+#[cfg(feature = "std")]
+pub use writer::{FragmentWriter, Mp4Config, Mp4StreamWriter, Mp4Writer};
+
+pub mod slice;
 
+#[cfg(feature = "std")]
 pub fn read_mp4(f: File) -> Result<Mp4Reader<BufReader<File>>> {
     let size = f.metadata()?.len();
     let reader = BufReader::new(f);
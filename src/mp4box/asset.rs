@@ -0,0 +1,163 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, SeekFrom, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+#[cfg(feature = "std")]
+use crate::mp4box::cprt::read_language_tagged_string;
+#[cfg(feature = "std")]
+use crate::mp4box::mdhd::{language_code, language_string};
+
+/// A 3GPP asset information box (3GPP TS 26.244) from udta: titl, dscp,
+/// perf, auth or gnre. Each holds one language-tagged string, laid out like
+/// cprt.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AssetBox {
+    pub asset_type: FourCC,
+    pub version: u8,
+    pub flags: u32,
+    pub language: String,
+    pub value: String,
+}
+
+impl Default for AssetBox {
+    fn default() -> Self {
+        AssetBox {
+            asset_type: AssetBox::TITLE,
+            version: 0,
+            flags: 0,
+            language: String::from("und"),
+            value: String::new(),
+        }
+    }
+}
+
+impl AssetBox {
+    pub const TITLE: FourCC = FourCC { value: *b"titl" };
+    pub const DESCRIPTION: FourCC = FourCC { value: *b"dscp" };
+    pub const PERFORMER: FourCC = FourCC { value: *b"perf" };
+    pub const AUTHOR: FourCC = FourCC { value: *b"auth" };
+    pub const GENRE: FourCC = FourCC { value: *b"gnre" };
+
+    pub fn new(asset_type: FourCC, language: &str, value: &str) -> Self {
+        AssetBox {
+            asset_type,
+            language: language.to_string(),
+            value: value.to_string(),
+            ..AssetBox::default()
+        }
+    }
+
+    pub fn get_type(&self) -> BoxType {
+        BoxType::from(u32::from(&self.asset_type))
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE + 2 + self.value.len() as u64 + 1
+    }
+}
+
+impl Mp4Box for AssetBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("language={} value={}", self.language, self.value);
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for AssetBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        // All asset boxes share one layout, so take the type from the header.
+        reader.seek(SeekFrom::Start(start + 4))?;
+        let asset_type = FourCC::from(reader.read_u32::<BigEndian>()?);
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let language = language_string(reader.read_u16::<BigEndian>()?);
+
+        let value_size = size
+            .checked_sub(HEADER_SIZE + HEADER_EXT_SIZE + 2)
+            .ok_or(Error::InvalidData("asset box too small"))?;
+        let value = read_language_tagged_string(reader, value_size)?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(AssetBox {
+            asset_type,
+            version,
+            flags,
+            language,
+            value,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for AssetBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        writer.write_u16::<BigEndian>(language_code(&self.language))?;
+        writer.write_all(self.value.as_bytes())?;
+        writer.write_u8(0)?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_titl() {
+        let src_box = AssetBox::new(AssetBox::TITLE, "eng", "A Title");
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+        assert_eq!(&buf[4..8], b"titl");
+        // The packed language code: 5 bits per letter, each minus 0x60.
+        assert_eq!(&buf[12..14], &[0x15, 0xC7]);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = AssetBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_dscp_utf16() {
+        let mut buf = Vec::new();
+        BoxHeader::new(BoxType::DscpBox, 22).write(&mut buf).unwrap();
+        buf.extend_from_slice(&[0, 0, 0, 0]);
+        buf.extend_from_slice(&language_code("fra").to_be_bytes());
+        buf.extend_from_slice(&[0xFE, 0xFF, 0, b'o', 0, b'u', 0, 0]);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = AssetBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(dst_box.asset_type, AssetBox::DESCRIPTION);
+        assert_eq!(dst_box.language, "fra");
+        assert_eq!(dst_box.value, "ou");
+    }
+}
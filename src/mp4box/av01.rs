@@ -0,0 +1,599 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+use crate::mp4box::colr::ColrBox;
+
+/// AV1 sample entry.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Av01Box {
+    pub data_reference_index: u16,
+    pub width: u16,
+    pub height: u16,
+
+    #[serde(with = "value_f64")]
+    pub horizresolution: FixedPointU16,
+
+    #[serde(with = "value_f64")]
+    pub vertresolution: FixedPointU16,
+    pub frame_count: u16,
+    pub depth: u16,
+    pub av1c: Av1CBox,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colr: Option<ColrBox>,
+
+    /// Child boxes this crate doesn't parse, such as pasp or btrt, in the
+    /// order they were read.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_boxes: Vec<RawBox>,
+}
+
+impl Default for Av01Box {
+    fn default() -> Self {
+        Av01Box {
+            data_reference_index: 0,
+            width: 0,
+            height: 0,
+            horizresolution: FixedPointU16::new(0x48),
+            vertresolution: FixedPointU16::new(0x48),
+            frame_count: 1,
+            depth: 0x0018,
+            av1c: Av1CBox::default(),
+            colr: None,
+            unknown_boxes: Vec::new(),
+        }
+    }
+}
+
+impl Av01Box {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::Av01Box
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + 8 + 70 + self.av1c.box_size();
+        if let Some(ref colr) = self.colr {
+            size += colr.box_size();
+        }
+        for unknown in self.unknown_boxes.iter() {
+            size += unknown.get_size();
+        }
+        size
+    }
+}
+
+impl Mp4Box for Av01Box {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("data_reference_index={} width={} height={} frame_count={}",
+            self.data_reference_index, self.width, self.height, self.frame_count);
+        Ok(s)
+    }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = vec![&self.av1c];
+        if let Some(ref colr) = self.colr {
+            children.push(colr);
+        }
+        children
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for Av01Box {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        reader.read_u32::<BigEndian>()?; // reserved
+        reader.read_u16::<BigEndian>()?; // reserved
+        let data_reference_index = reader.read_u16::<BigEndian>()?;
+
+        reader.read_u32::<BigEndian>()?; // pre-defined, reserved
+        reader.read_u64::<BigEndian>()?; // pre-defined
+        reader.read_u32::<BigEndian>()?; // pre-defined
+        let width = reader.read_u16::<BigEndian>()?;
+        let height = reader.read_u16::<BigEndian>()?;
+        let horizresolution = FixedPointU16::new_raw(reader.read_u32::<BigEndian>()?);
+        let vertresolution = FixedPointU16::new_raw(reader.read_u32::<BigEndian>()?);
+        reader.read_u32::<BigEndian>()?; // reserved
+        let frame_count = reader.read_u16::<BigEndian>()?;
+        skip_bytes(reader, 32)?; // compressorname
+        let depth = reader.read_u16::<BigEndian>()?;
+        reader.read_i16::<BigEndian>()?; // pre-defined
+
+        let mut av1c = None;
+        let mut colr = None;
+        let mut unknown_boxes = Vec::new();
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current + HEADER_SIZE <= end {
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+
+            match name {
+                BoxType::Av1CBox => {
+                    av1c = Some(Av1CBox::read_box(reader, s)?);
+                }
+                BoxType::ColrBox => {
+                    colr = Some(ColrBox::read_box(reader, s)?);
+                }
+                _ if current + s <= end => {
+                    unknown_boxes.push(RawBox::read(reader, &header)?);
+                }
+                _ => {
+                    skip_box(reader, s)?;
+                }
+            }
+            current = reader.stream_position()?;
+        }
+        let av1c = av1c.ok_or(Error::InvalidData("av1C not found"))?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(Av01Box {
+            data_reference_index,
+            width,
+            height,
+            horizresolution,
+            vertresolution,
+            frame_count,
+            depth,
+            av1c,
+            colr,
+            unknown_boxes,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for Av01Box {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        writer.write_u32::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(self.data_reference_index)?;
+
+        writer.write_u32::<BigEndian>(0)?; // pre-defined, reserved
+        writer.write_u64::<BigEndian>(0)?; // pre-defined
+        writer.write_u32::<BigEndian>(0)?; // pre-defined
+        writer.write_u16::<BigEndian>(self.width)?;
+        writer.write_u16::<BigEndian>(self.height)?;
+        writer.write_u32::<BigEndian>(self.horizresolution.raw_value())?;
+        writer.write_u32::<BigEndian>(self.vertresolution.raw_value())?;
+        writer.write_u32::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(self.frame_count)?;
+        // skip compressorname
+        write_zeros(writer, 32)?;
+        writer.write_u16::<BigEndian>(self.depth)?;
+        writer.write_i16::<BigEndian>(-1)?; // pre-defined
+
+        self.av1c.write_box(writer)?;
+        if let Some(ref colr) = self.colr {
+            colr.write_box(writer)?;
+        }
+        for unknown in self.unknown_boxes.iter() {
+            unknown.write(writer)?;
+        }
+
+        Ok(size)
+    }
+}
+
+/// AV1 codec configuration record.
+///
+/// When `config_obus` holds a sequence header OBU, the profile, level, tier
+/// and colour fields are read from it rather than from the record, whose
+/// copies some muxers leave zeroed.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct Av1CBox {
+    pub seq_profile: u8,
+    pub seq_level_idx_0: u8,
+    pub seq_tier_0: u8,
+    pub high_bitdepth: bool,
+    pub twelve_bit: bool,
+    pub monochrome: bool,
+    pub chroma_subsampling_x: bool,
+    pub chroma_subsampling_y: bool,
+    pub chroma_sample_position: u8,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_presentation_delay_minus_one: Option<u8>,
+
+    /// Zero or more OBUs, normally a sequence header and metadata.
+    #[serde(skip_serializing)]
+    pub config_obus: Vec<u8>,
+}
+
+impl Av1CBox {
+    const OBU_SEQUENCE_HEADER: u8 = 1;
+
+    pub fn get_type(&self) -> BoxType {
+        BoxType::Av1CBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + 4 + self.config_obus.len() as u64
+    }
+
+    pub fn bit_depth(&self) -> u8 {
+        if self.twelve_bit {
+            12
+        } else if self.high_bitdepth {
+            10
+        } else {
+            8
+        }
+    }
+
+    /// Returns the codec string for the sample entry, e.g. "av01.0.08M.10"
+    /// for a main profile, level 4.0, main tier, 10 bit stream. The optional
+    /// colour fields are left out.
+    pub fn codec_string(&self) -> String {
+        let tier = if self.seq_tier_0 == 0 { 'M' } else { 'H' };
+        format!("av01.{}.{:02}{}.{:02}", self.seq_profile, self.seq_level_idx_0, tier, self.bit_depth())
+    }
+
+    // Finds the first sequence header OBU in config_obus.
+    fn sequence_header(&self) -> Option<&[u8]> {
+        let mut data = &self.config_obus[..];
+        while !data.is_empty() {
+            let header = data[0];
+            let obu_type = (header >> 3) & 0x0F;
+            let extension_size = ((header >> 2) & 1) as usize;
+            let has_size = (header >> 1) & 1 == 1;
+            let mut pos = 1 + extension_size;
+
+            let obu_size = if has_size {
+                let (size, leb128_size) = read_leb128(data.get(pos..)?)?;
+                pos += leb128_size;
+                size as usize
+            } else {
+                data.len().checked_sub(pos)?
+            };
+            let payload = data.get(pos..pos.checked_add(obu_size)?)?;
+            if obu_type == Av1CBox::OBU_SEQUENCE_HEADER {
+                return Some(payload);
+            }
+            data = &data[pos + obu_size..];
+        }
+        None
+    }
+
+    // Overwrites the record fields with those of the sequence header, when
+    // it can be parsed.
+    fn update_from_sequence_header(&mut self) {
+        let mut bits = match self.sequence_header() {
+            Some(payload) => BitReader::new(payload),
+            None => return,
+        };
+        let mut av1c = self.clone();
+        if read_sequence_header(&mut bits, &mut av1c).is_some() {
+            *self = av1c;
+        }
+    }
+}
+
+impl Mp4Box for Av1CBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("codec={} monochrome={} chroma_subsampling={}{} config_obus_size={}",
+            self.codec_string(), self.monochrome, self.chroma_subsampling_x as u8,
+            self.chroma_subsampling_y as u8, self.config_obus.len());
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for Av1CBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let marker_version = reader.read_u8()?;
+        if marker_version & 0x80 == 0 {
+            return Err(Error::InvalidData("av1C marker bit not set"));
+        }
+        let byte = reader.read_u8()?;
+        let seq_profile = byte >> 5;
+        let seq_level_idx_0 = byte & 0x1F;
+        let byte = reader.read_u8()?;
+        let byte3 = reader.read_u8()?;
+        let initial_presentation_delay_minus_one = if byte3 & 0x10 != 0 {
+            Some(byte3 & 0x0F)
+        } else {
+            None
+        };
+
+        let obus_size = size
+            .checked_sub(HEADER_SIZE + 4)
+            .ok_or(Error::InvalidData("av1C box too small"))?;
+        let mut config_obus = vec![0; obus_size as usize];
+        reader.read_exact(&mut config_obus)?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        let mut av1c = Av1CBox {
+            seq_profile,
+            seq_level_idx_0,
+            seq_tier_0: byte >> 7,
+            high_bitdepth: byte & 0x40 != 0,
+            twelve_bit: byte & 0x20 != 0,
+            monochrome: byte & 0x10 != 0,
+            chroma_subsampling_x: byte & 0x08 != 0,
+            chroma_subsampling_y: byte & 0x04 != 0,
+            chroma_sample_position: byte & 0x03,
+            initial_presentation_delay_minus_one,
+            config_obus,
+        };
+        av1c.update_from_sequence_header();
+        Ok(av1c)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for Av1CBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        writer.write_u8(0x81)?; // marker, version 1
+        writer.write_u8((self.seq_profile << 5) | (self.seq_level_idx_0 & 0x1F))?;
+        writer.write_u8(
+            (self.seq_tier_0 << 7)
+                | ((self.high_bitdepth as u8) << 6)
+                | ((self.twelve_bit as u8) << 5)
+                | ((self.monochrome as u8) << 4)
+                | ((self.chroma_subsampling_x as u8) << 3)
+                | ((self.chroma_subsampling_y as u8) << 2)
+                | (self.chroma_sample_position & 0x03),
+        )?;
+        match self.initial_presentation_delay_minus_one {
+            Some(delay) => writer.write_u8(0x10 | (delay & 0x0F))?,
+            None => writer.write_u8(0)?,
+        }
+        writer.write_all(&self.config_obus)?;
+
+        Ok(size)
+    }
+}
+
+// Returns an OBU size field and the number of bytes it took.
+fn read_leb128(data: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in data.iter().take(8).enumerate() {
+        value |= ((byte & 0x7F) as u64) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+// uvlc() of the AV1 spec, used by timing_info.
+fn read_uvlc(bits: &mut BitReader) -> Option<u32> {
+    let mut leading_zeros = 0;
+    while !bits.read_flag()? {
+        leading_zeros += 1;
+    }
+    if leading_zeros >= 32 {
+        return Some(u32::MAX);
+    }
+    Some(bits.read(leading_zeros)? + (1 << leading_zeros) - 1)
+}
+
+// Parses sequence_header_obu() (AV1 spec 5.5) up to the end of color_config,
+// taking the fields the av1C record mirrors.
+fn read_sequence_header(bits: &mut BitReader, av1c: &mut Av1CBox) -> Option<()> {
+    let seq_profile = bits.read(3)? as u8;
+    bits.read(1)?; // still_picture
+    let reduced_still_picture_header = bits.read_flag()?;
+
+    if reduced_still_picture_header {
+        av1c.seq_level_idx_0 = bits.read(5)? as u8;
+        av1c.seq_tier_0 = 0;
+    } else {
+        let mut decoder_model_info_present = false;
+        let mut buffer_delay_length = 0;
+        if bits.read_flag()? {
+            // timing_info
+            bits.read(32)?; // num_units_in_display_tick
+            bits.read(32)?; // time_scale
+            if bits.read_flag()? {
+                read_uvlc(bits)?; // num_ticks_per_picture_minus_1
+            }
+            decoder_model_info_present = bits.read_flag()?;
+            if decoder_model_info_present {
+                buffer_delay_length = bits.read(5)? as usize + 1;
+                bits.read(32)?; // num_units_in_decoding_tick
+                bits.read(5)?; // buffer_removal_time_length_minus_1
+                bits.read(5)?; // frame_presentation_time_length_minus_1
+            }
+        }
+        let initial_display_delay_present = bits.read_flag()?;
+        let operating_points = bits.read(5)? + 1;
+        for i in 0..operating_points {
+            bits.read(12)?; // operating_point_idc
+            let seq_level_idx = bits.read(5)? as u8;
+            let seq_tier = if seq_level_idx > 7 { bits.read(1)? as u8 } else { 0 };
+            if decoder_model_info_present && bits.read_flag()? {
+                bits.read(buffer_delay_length)?; // decoder_buffer_delay
+                bits.read(buffer_delay_length)?; // encoder_buffer_delay
+                bits.read(1)?; // low_delay_mode_flag
+            }
+            if initial_display_delay_present && bits.read_flag()? {
+                bits.read(4)?; // initial_display_delay_minus_1
+            }
+            if i == 0 {
+                av1c.seq_level_idx_0 = seq_level_idx;
+                av1c.seq_tier_0 = seq_tier;
+            }
+        }
+    }
+
+    let frame_width_bits = bits.read(4)? as usize + 1;
+    let frame_height_bits = bits.read(4)? as usize + 1;
+    bits.read(frame_width_bits)?; // max_frame_width_minus_1
+    bits.read(frame_height_bits)?; // max_frame_height_minus_1
+    if !reduced_still_picture_header && bits.read_flag()? {
+        bits.read(4)?; // delta_frame_id_length_minus_2
+        bits.read(3)?; // additional_frame_id_length_minus_1
+    }
+    bits.read(3)?; // use_128x128_superblock, enable_filter_intra, enable_intra_edge_filter
+    if !reduced_still_picture_header {
+        // enable_interintra_compound, enable_masked_compound,
+        // enable_warped_motion, enable_dual_filter
+        bits.read(4)?;
+        let enable_order_hint = bits.read_flag()?;
+        if enable_order_hint {
+            bits.read(2)?; // enable_jnt_comp, enable_ref_frame_mvs
+        }
+        let seq_force_screen_content_tools = if bits.read_flag()? {
+            2 // SELECT_SCREEN_CONTENT_TOOLS
+        } else {
+            bits.read(1)?
+        };
+        if seq_force_screen_content_tools > 0 && !bits.read_flag()? {
+            bits.read(1)?; // seq_force_integer_mv
+        }
+        if enable_order_hint {
+            bits.read(3)?; // order_hint_bits_minus_1
+        }
+    }
+    bits.read(3)?; // enable_superres, enable_cdef, enable_restoration
+
+    // color_config
+    let high_bitdepth = bits.read_flag()?;
+    let twelve_bit = seq_profile == 2 && high_bitdepth && bits.read_flag()?;
+    let monochrome = seq_profile != 1 && bits.read_flag()?;
+    let (mut primaries, mut transfer, mut matrix) = (2, 2, 2);
+    if bits.read_flag()? {
+        primaries = bits.read(8)?;
+        transfer = bits.read(8)?;
+        matrix = bits.read(8)?;
+    }
+    let (subsampling_x, subsampling_y, sample_position) = if monochrome {
+        bits.read(1)?; // color_range
+        (true, true, 0)
+    } else if primaries == 1 && transfer == 13 && matrix == 0 {
+        // sRGB
+        (false, false, 0)
+    } else {
+        bits.read(1)?; // color_range
+        let (x, y) = match seq_profile {
+            0 => (true, true),
+            1 => (false, false),
+            _ if twelve_bit => {
+                let x = bits.read_flag()?;
+                (x, x && bits.read_flag()?)
+            }
+            _ => (true, false),
+        };
+        let position = if x && y { bits.read(2)? as u8 } else { 0 };
+        (x, y, position)
+    };
+
+    av1c.seq_profile = seq_profile;
+    av1c.high_bitdepth = high_bitdepth;
+    av1c.twelve_bit = twelve_bit;
+    av1c.monochrome = monochrome;
+    av1c.chroma_subsampling_x = subsampling_x;
+    av1c.chroma_subsampling_y = subsampling_y;
+    av1c.chroma_sample_position = sample_position;
+    Some(())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    // A main profile, level 4.0, 10 bit 4:2:0 sequence header OBU.
+    const SEQUENCE_HEADER_OBU: [u8; 16] = [
+        0x0A, 0x0E, 0x00, 0x00, 0x00, 0x42, 0xAB, 0xBF,
+        0xC3, 0x77, 0xFF, 0xE7, 0x42, 0x44, 0x02, 0x41,
+    ];
+
+    #[test]
+    fn test_av1c_codec_string() {
+        // The record fields are left zeroed, so everything below comes from
+        // the sequence header.
+        let mut data = vec![0x00, 0x00, 0x00, 0x1C, b'a', b'v', b'1', b'C', 0x81, 0x00, 0x00, 0x00];
+        data.extend_from_slice(&SEQUENCE_HEADER_OBU);
+
+        let mut reader = Cursor::new(&data);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::Av1CBox);
+        let av1c = Av1CBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(av1c.seq_profile, 0);
+        assert_eq!(av1c.seq_level_idx_0, 8);
+        assert_eq!(av1c.seq_tier_0, 0);
+        assert_eq!(av1c.bit_depth(), 10);
+        assert!(!av1c.monochrome);
+        assert!(av1c.chroma_subsampling_x && av1c.chroma_subsampling_y);
+        assert_eq!(av1c.codec_string(), "av01.0.08M.10");
+
+        let mut buf = Vec::new();
+        av1c.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), av1c.box_size() as usize);
+        assert_eq!(&buf[8..12], &[0x81, 0x08, 0x4C, 0x00]);
+    }
+
+    #[test]
+    fn test_av01() {
+        let src_box = Av01Box {
+            data_reference_index: 1,
+            width: 1920,
+            height: 1080,
+            av1c: Av1CBox {
+                seq_level_idx_0: 8,
+                high_bitdepth: true,
+                chroma_subsampling_x: true,
+                chroma_subsampling_y: true,
+                config_obus: SEQUENCE_HEADER_OBU.to_vec(),
+                ..Av1CBox::default()
+            },
+            colr: Some(ColrBox::default()),
+            ..Av01Box::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::Av01Box);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = Av01Box::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
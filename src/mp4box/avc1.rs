@@ -1,8 +1,12 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
 use crate::mp4box::*;
+use crate::mp4box::colr::ColrBox;
+use crate::mp4box::sinf::SinfBox;
 
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Avc1Box {
@@ -10,14 +14,26 @@ pub struct Avc1Box {
     pub width: u16,
     pub height: u16,
 
-    #[serde(with = "value_u32")]
+    #[serde(with = "value_f64")]
     pub horizresolution: FixedPointU16,
 
-    #[serde(with = "value_u32")]
+    #[serde(with = "value_f64")]
     pub vertresolution: FixedPointU16,
     pub frame_count: u16,
     pub depth: u16,
     pub avcc: AvcCBox,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colr: Option<ColrBox>,
+
+    /// Set for protected (encv) sample entries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sinf: Option<SinfBox>,
+
+    /// Child boxes this crate doesn't parse, such as fiel or gama, in the
+    /// order they were read.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_boxes: Vec<RawBox>,
 }
 
 impl Default for Avc1Box {
@@ -31,14 +47,25 @@ impl Default for Avc1Box {
             frame_count: 1,
             depth: 0x0018,
             avcc: AvcCBox::default(),
+            sinf: None,
+            colr: None,
+            unknown_boxes: Vec::new(),
         }
     }
 }
 
 impl Avc1Box {
-    pub fn new(config: &AvcConfig) -> Self {
+    /// Like `new`, but rejects a zero data_reference_index or empty
+    /// dimensions instead of producing an unplayable sample entry.
+    pub fn try_new(config: &AvcConfig, data_reference_index: u16) -> Result<Self> {
+        check_data_reference_index(data_reference_index)?;
+        check_dimensions(config.width, config.height)?;
+        Ok(Self::new(config, data_reference_index))
+    }
+
+    pub fn new(config: &AvcConfig, data_reference_index: u16) -> Self {
         Avc1Box {
-            data_reference_index: 1,
+            data_reference_index,
             width: config.width,
             height: config.height,
             horizresolution: FixedPointU16::new(0x48),
@@ -46,15 +73,32 @@ impl Avc1Box {
             frame_count: 1,
             depth: 0x0018,
             avcc: AvcCBox::new(&config.seq_param_set, &config.pic_param_set),
+            sinf: None,
+            colr: None,
+            unknown_boxes: Vec::new(),
         }
     }
 
     pub fn get_type(&self) -> BoxType {
-        BoxType::Avc1Box
+        if self.sinf.is_some() {
+            BoxType::EncvBox
+        } else {
+            BoxType::Avc1Box
+        }
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + 8 + 70 + self.avcc.box_size()
+        let mut size = HEADER_SIZE + 8 + 70 + self.avcc.box_size();
+        if let Some(ref sinf) = self.sinf {
+            size += sinf.box_size();
+        }
+        if let Some(ref colr) = self.colr {
+            size += colr.box_size();
+        }
+        for unknown in self.unknown_boxes.iter() {
+            size += unknown.get_size();
+        }
+        size
     }
 }
 
@@ -76,8 +120,20 @@ impl Mp4Box for Avc1Box {
             self.data_reference_index, self.width, self.height, self.frame_count);
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = vec![&self.avcc];
+        if let Some(ref sinf) = self.sinf {
+            children.push(sinf);
+        }
+        if let Some(ref colr) = self.colr {
+            children.push(colr);
+        }
+        children
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for Avc1Box {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -99,29 +155,57 @@ impl<R: Read + Seek> ReadBox<&mut R> for Avc1Box {
         let depth = reader.read_u16::<BigEndian>()?;
         reader.read_i16::<BigEndian>()?; // pre-defined
 
-        let header = BoxHeader::read(reader)?;
-        let BoxHeader { name, size: s } = header;
-        if name == BoxType::AvcCBox {
-            let avcc = AvcCBox::read_box(reader, s)?;
-
-            skip_bytes_to(reader, start + size)?;
-
-            Ok(Avc1Box {
-                data_reference_index,
-                width,
-                height,
-                horizresolution,
-                vertresolution,
-                frame_count,
-                depth,
-                avcc,
-            })
-        } else {
-            Err(Error::InvalidData("avcc not found"))
+        let mut avcc = None;
+        let mut colr = None;
+        let mut sinf = None;
+        let mut unknown_boxes = Vec::new();
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current + HEADER_SIZE <= end {
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+
+            match name {
+                BoxType::AvcCBox => {
+                    avcc = Some(AvcCBox::read_box(reader, s)?);
+                }
+                BoxType::ColrBox => {
+                    colr = Some(ColrBox::read_box(reader, s)?);
+                }
+                BoxType::SinfBox => {
+                    sinf = Some(SinfBox::read_box(reader, s)?);
+                }
+                _ if current + s <= end => {
+                    unknown_boxes.push(RawBox::read(reader, &header)?);
+                }
+                _ => {
+                    skip_box(reader, s)?;
+                }
+            }
+            current = reader.stream_position()?;
         }
+        let avcc = avcc.ok_or(Error::InvalidData("avcc not found"))?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(Avc1Box {
+            data_reference_index,
+            width,
+            height,
+            horizresolution,
+            vertresolution,
+            frame_count,
+            depth,
+            avcc,
+            colr,
+            sinf,
+            unknown_boxes,
+        })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for Avc1Box {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -146,6 +230,15 @@ impl<W: Write> WriteBox<&mut W> for Avc1Box {
         writer.write_i16::<BigEndian>(-1)?; // pre-defined
 
         self.avcc.write_box(writer)?;
+        if let Some(ref colr) = self.colr {
+            colr.write_box(writer)?;
+        }
+        if let Some(ref sinf) = self.sinf {
+            sinf.write_box(writer)?;
+        }
+        for unknown in self.unknown_boxes.iter() {
+            unknown.write(writer)?;
+        }
 
         Ok(size)
     }
@@ -203,6 +296,7 @@ impl Mp4Box for AvcCBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for AvcCBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -239,6 +333,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for AvcCBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for AvcCBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -279,6 +374,7 @@ impl NalUnit {
         2 + self.bytes.len()
     }
 
+    #[cfg(feature = "std")]
     fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
         let length = reader.read_u16::<BigEndian>()? as usize;
         let mut bytes = vec![0u8; length];
@@ -286,6 +382,7 @@ impl NalUnit {
         Ok(NalUnit { bytes })
     }
 
+    #[cfg(feature = "std")]
     fn write<W: Write>(&self, writer: &mut W) -> Result<u64> {
         writer.write_u16::<BigEndian>(self.bytes.len() as u16)?;
         writer.write(&self.bytes)?;
@@ -293,7 +390,7 @@ impl NalUnit {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -325,6 +422,9 @@ mod tests {
                     bytes: vec![0x68, 0xEB, 0xE3, 0xCB, 0x22, 0xC0],
                 }],
             },
+            sinf: None,
+            colr: None,
+            unknown_boxes: Vec::new(),
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -338,4 +438,51 @@ mod tests {
         let dst_box = Avc1Box::read_box(&mut reader, header.size).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_avc1_trailing_fiel() {
+        let config = AvcConfig {
+            width: 320,
+            height: 240,
+            seq_param_set: vec![0x67, 0x64, 0x00, 0x0D],
+            pic_param_set: vec![0x68, 0xEB, 0xE3, 0xCB],
+        };
+        let mut buf = Vec::new();
+        Avc1Box::new(&config, 1).write_box(&mut buf).unwrap();
+
+        // Append a fiel box after the avcC, as some encoders do.
+        buf.extend_from_slice(b"\0\0\0\x0afiel\x01\x00");
+        let size = buf.len() as u32;
+        buf[0..4].copy_from_slice(&size.to_be_bytes());
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let avc1 = Avc1Box::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(avc1.avcc.sequence_parameter_sets[0].bytes, config.seq_param_set);
+        assert_eq!(avc1.unknown_boxes.len(), 1);
+        assert_eq!(avc1.unknown_boxes[0].box_type, str::parse("fiel").unwrap());
+        assert_eq!(avc1.unknown_boxes[0].data, vec![1, 0]);
+
+        let mut out = Vec::new();
+        avc1.write_box(&mut out).unwrap();
+        assert_eq!(out, buf);
+    }
+
+    #[test]
+    fn test_avc1_try_new() {
+        let config = AvcConfig {
+            width: 320,
+            height: 240,
+            seq_param_set: vec![0x67, 0x64, 0x00, 0x0D],
+            pic_param_set: vec![0x68, 0xEB, 0xE3, 0xCB],
+        };
+        assert!(matches!(Avc1Box::try_new(&config, 0), Err(Error::InvalidData(_))));
+        assert_eq!(Avc1Box::try_new(&config, 1).unwrap(), Avc1Box::new(&config, 1));
+
+        let empty = AvcConfig {
+            width: 0,
+            ..config
+        };
+        assert!(Avc1Box::try_new(&empty, 1).is_err());
+    }
 }
@@ -0,0 +1,232 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+/// Channel layout of an audio sample entry (ISO/IEC 14496-12 12.2.4).
+/// Layouts and speaker positions use the ChannelConfiguration and
+/// OutputChannelPosition values of ISO/IEC 23091-3, e.g. defined layout 6
+/// for 5.1.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct ChnlBox {
+    pub version: u8,
+    pub flags: u32,
+    pub stream_structure: u8,
+
+    /// Zero if the layout is given by `speaker_positions` instead.
+    pub defined_layout: u8,
+    pub speaker_positions: Vec<SpeakerPosition>,
+    /// Bit i set means channel i of the defined layout is absent.
+    pub omitted_channels_map: u64,
+
+    pub object_count: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SpeakerPosition {
+    pub speaker_position: u8,
+    /// Only stored for the explicit position (126).
+    pub azimuth: i16,
+    pub elevation: i8,
+}
+
+impl SpeakerPosition {
+    pub const EXPLICIT: u8 = 126;
+}
+
+impl ChnlBox {
+    pub const CHANNEL_STRUCTURED: u8 = 0x01;
+    pub const OBJECT_STRUCTURED: u8 = 0x02;
+
+    pub fn get_type(&self) -> BoxType {
+        BoxType::ChnlBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 1;
+        if self.is_channel_structured() {
+            size += 1;
+            if self.defined_layout == 0 {
+                for position in self.speaker_positions.iter() {
+                    size += if position.speaker_position == SpeakerPosition::EXPLICIT { 4 } else { 1 };
+                }
+            } else {
+                size += 8;
+            }
+        }
+        if self.is_object_structured() {
+            size += 1;
+        }
+        size
+    }
+
+    pub fn is_channel_structured(&self) -> bool {
+        self.stream_structure & Self::CHANNEL_STRUCTURED != 0
+    }
+
+    pub fn is_object_structured(&self) -> bool {
+        self.stream_structure & Self::OBJECT_STRUCTURED != 0
+    }
+}
+
+impl Mp4Box for ChnlBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("stream_structure={} defined_layout={} speakers={} objects={}",
+            self.stream_structure, self.defined_layout, self.speaker_positions.len(), self.object_count);
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for ChnlBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let stream_structure = reader.read_u8()?;
+        let mut chnl = ChnlBox {
+            version,
+            flags,
+            stream_structure,
+            ..ChnlBox::default()
+        };
+
+        // The number of speaker positions is the channel count of the sample
+        // entry, so read them up to the end of the box, leaving the object
+        // count if there is one.
+        let end = start + size;
+        let trailer = if chnl.is_object_structured() { 1 } else { 0 };
+        if chnl.is_channel_structured() {
+            chnl.defined_layout = reader.read_u8()?;
+            if chnl.defined_layout == 0 {
+                while reader.stream_position()? + trailer < end {
+                    let speaker_position = reader.read_u8()?;
+                    let mut position = SpeakerPosition {
+                        speaker_position,
+                        ..SpeakerPosition::default()
+                    };
+                    if speaker_position == SpeakerPosition::EXPLICIT {
+                        position.azimuth = reader.read_i16::<BigEndian>()?;
+                        position.elevation = reader.read_i8()?;
+                    }
+                    chnl.speaker_positions.push(position);
+                }
+            } else {
+                chnl.omitted_channels_map = reader.read_u64::<BigEndian>()?;
+            }
+        }
+        if chnl.is_object_structured() {
+            chnl.object_count = reader.read_u8()?;
+        }
+
+        skip_bytes_to(reader, end)?;
+
+        Ok(chnl)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for ChnlBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        writer.write_u8(self.stream_structure)?;
+        if self.is_channel_structured() {
+            writer.write_u8(self.defined_layout)?;
+            if self.defined_layout == 0 {
+                for position in self.speaker_positions.iter() {
+                    writer.write_u8(position.speaker_position)?;
+                    if position.speaker_position == SpeakerPosition::EXPLICIT {
+                        writer.write_i16::<BigEndian>(position.azimuth)?;
+                        writer.write_i8(position.elevation)?;
+                    }
+                }
+            } else {
+                writer.write_u64::<BigEndian>(self.omitted_channels_map)?;
+            }
+        }
+        if self.is_object_structured() {
+            writer.write_u8(self.object_count)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_chnl_defined_layout() {
+        // 5.1 (L, R, C, LFE, Ls, Rs) with every channel present.
+        let src_box = ChnlBox {
+            version: 0,
+            flags: 0,
+            stream_structure: ChnlBox::CHANNEL_STRUCTURED,
+            defined_layout: 6,
+            speaker_positions: Vec::new(),
+            omitted_channels_map: 0,
+            object_count: 0,
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::ChnlBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = ChnlBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_chnl_speaker_positions() {
+        let src_box = ChnlBox {
+            version: 0,
+            flags: 0,
+            stream_structure: ChnlBox::CHANNEL_STRUCTURED | ChnlBox::OBJECT_STRUCTURED,
+            defined_layout: 0,
+            speaker_positions: vec![
+                SpeakerPosition { speaker_position: 0, ..SpeakerPosition::default() },
+                SpeakerPosition { speaker_position: 1, ..SpeakerPosition::default() },
+                SpeakerPosition {
+                    speaker_position: SpeakerPosition::EXPLICIT,
+                    azimuth: -110,
+                    elevation: 35,
+                },
+            ],
+            omitted_channels_map: 0,
+            object_count: 2,
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = ChnlBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
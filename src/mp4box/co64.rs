@@ -1,4 +1,6 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -42,6 +44,7 @@ impl Mp4Box for Co64Box {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for Co64Box {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -65,6 +68,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for Co64Box {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for Co64Box {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -81,7 +85,7 @@ impl<W: Write> WriteBox<&mut W> for Co64Box {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -106,4 +110,22 @@ mod tests {
         let dst_box = Co64Box::read_box(&mut reader, header.size).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_co64_large_offset() {
+        let src_box = Co64Box {
+            version: 0,
+            flags: 0,
+            entries: vec![48, u32::MAX as u64 + 1, 0x1_2345_6789],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(&buf[buf.len() - 8..], &[0, 0, 0, 1, 0x23, 0x45, 0x67, 0x89]);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = Co64Box::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(dst_box.entries[1], 0x1_0000_0000);
+        assert_eq!(src_box, dst_box);
+    }
 }
@@ -0,0 +1,205 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+/// Colour information of a visual sample entry. The layout of the payload
+/// depends on `colour_type`: nclx (ISO), nclc (QuickTime, no full range
+/// byte), or an ICC profile for rICC and prof.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColrBox {
+    pub colour_type: FourCC,
+
+    /// Only used for nclx and nclc, as ISO/IEC 23091-2 code points.
+    pub colour_primaries: u16,
+    pub transfer_characteristics: u16,
+    pub matrix_coefficients: u16,
+    /// Only used for nclx.
+    pub full_range: bool,
+
+    /// The ICC profile for rICC and prof, or the raw payload of an
+    /// unrecognised colour type.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub data: Vec<u8>,
+}
+
+impl Default for ColrBox {
+    fn default() -> Self {
+        ColrBox {
+            colour_type: ColrBox::NCLX,
+            colour_primaries: 2,
+            transfer_characteristics: 2,
+            matrix_coefficients: 2,
+            full_range: false,
+            data: Vec::new(),
+        }
+    }
+}
+
+impl ColrBox {
+    pub const NCLX: FourCC = FourCC { value: *b"nclx" };
+    pub const NCLC: FourCC = FourCC { value: *b"nclc" };
+    pub const RICC: FourCC = FourCC { value: *b"rICC" };
+    pub const PROF: FourCC = FourCC { value: *b"prof" };
+
+    pub fn get_type(&self) -> BoxType {
+        BoxType::ColrBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let size = HEADER_SIZE + 4;
+        if self.colour_type == ColrBox::NCLX {
+            size + 7
+        } else if self.colour_type == ColrBox::NCLC {
+            size + 6
+        } else {
+            size + self.data.len() as u64
+        }
+    }
+
+    pub fn is_icc(&self) -> bool {
+        self.colour_type == ColrBox::RICC || self.colour_type == ColrBox::PROF
+    }
+}
+
+impl Mp4Box for ColrBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = if self.colour_type == ColrBox::NCLX || self.colour_type == ColrBox::NCLC {
+            format!("colour_type={} primaries={} transfer={} matrix={} full_range={}",
+                self.colour_type, self.colour_primaries, self.transfer_characteristics,
+                self.matrix_coefficients, self.full_range)
+        } else {
+            format!("colour_type={} size={}", self.colour_type, self.data.len())
+        };
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for ColrBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        let end = start + size;
+
+        let colour_type = FourCC::from(reader.read_u32::<BigEndian>()?);
+        let mut colr = ColrBox {
+            colour_type,
+            ..ColrBox::default()
+        };
+        if colour_type == ColrBox::NCLX || colour_type == ColrBox::NCLC {
+            colr.colour_primaries = reader.read_u16::<BigEndian>()?;
+            colr.transfer_characteristics = reader.read_u16::<BigEndian>()?;
+            colr.matrix_coefficients = reader.read_u16::<BigEndian>()?;
+            if colour_type == ColrBox::NCLX {
+                colr.full_range = reader.read_u8()? & 0x80 != 0;
+            }
+        } else {
+            let remaining = end.saturating_sub(reader.stream_position()?);
+            colr.data = vec![0; remaining as usize];
+            reader.read_exact(&mut colr.data)?;
+        }
+
+        skip_bytes_to(reader, end)?;
+
+        Ok(colr)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for ColrBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        writer.write_u32::<BigEndian>((&self.colour_type).into())?;
+        if self.colour_type == ColrBox::NCLX || self.colour_type == ColrBox::NCLC {
+            writer.write_u16::<BigEndian>(self.colour_primaries)?;
+            writer.write_u16::<BigEndian>(self.transfer_characteristics)?;
+            writer.write_u16::<BigEndian>(self.matrix_coefficients)?;
+            if self.colour_type == ColrBox::NCLX {
+                writer.write_u8(if self.full_range { 0x80 } else { 0 })?;
+            }
+        } else {
+            writer.write_all(&self.data)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_colr_nclc() {
+        // BT.709 primaries, transfer function and matrix.
+        let data = [
+            0x00, 0x00, 0x00, 0x12, b'c', b'o', b'l', b'r',
+            b'n', b'c', b'l', b'c', 0x00, 0x01, 0x00, 0x01, 0x00, 0x01,
+        ];
+        let mut reader = Cursor::new(&data);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::ColrBox);
+
+        let colr = ColrBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(colr.colour_type, ColrBox::NCLC);
+        assert_eq!(colr.colour_primaries, 1);
+        assert_eq!(colr.transfer_characteristics, 1);
+        assert_eq!(colr.matrix_coefficients, 1);
+        assert!(!colr.full_range);
+        assert_eq!(colr.box_size(), header.size);
+
+        let mut buf = Vec::new();
+        colr.write_box(&mut buf).unwrap();
+        assert_eq!(buf, data);
+    }
+
+    #[test]
+    fn test_colr_variants() {
+        let boxes = [
+            ColrBox {
+                colour_type: ColrBox::NCLX,
+                colour_primaries: 9,
+                transfer_characteristics: 16,
+                matrix_coefficients: 9,
+                full_range: true,
+                data: Vec::new(),
+            },
+            ColrBox {
+                colour_type: ColrBox::PROF,
+                data: vec![1, 2, 3, 4, 5],
+                ..ColrBox::default()
+            },
+        ];
+        for src_box in boxes.iter() {
+            let mut buf = Vec::new();
+            src_box.write_box(&mut buf).unwrap();
+            assert_eq!(buf.len(), src_box.box_size() as usize);
+
+            let mut reader = Cursor::new(&buf);
+            let header = BoxHeader::read(&mut reader).unwrap();
+            assert_eq!(header.name, BoxType::ColrBox);
+            let dst_box = ColrBox::read_box(&mut reader, header.size).unwrap();
+            assert_eq!(src_box, &dst_box);
+        }
+        assert!(boxes[1].is_icc());
+    }
+}
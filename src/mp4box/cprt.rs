@@ -0,0 +1,182 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use core::char::{decode_utf16, REPLACEMENT_CHARACTER};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+#[cfg(feature = "std")]
+use crate::mp4box::mdhd::{language_code, language_string};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CprtBox {
+    pub version: u8,
+    pub flags: u32,
+    pub language: String,
+    pub notice: String,
+}
+
+impl Default for CprtBox {
+    fn default() -> Self {
+        CprtBox {
+            version: 0,
+            flags: 0,
+            language: String::from("und"),
+            notice: String::new(),
+        }
+    }
+}
+
+impl CprtBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::CprtBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE + 2 + self.notice.len() as u64 + 1
+    }
+}
+
+impl Mp4Box for CprtBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("language={} notice={}", self.language, self.notice);
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for CprtBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let language = language_string(reader.read_u16::<BigEndian>()?);
+
+        let notice_size = size
+            .checked_sub(HEADER_SIZE + HEADER_EXT_SIZE + 2)
+            .ok_or(Error::InvalidData("cprt box too small"))?;
+        let notice = read_language_tagged_string(reader, notice_size)?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(CprtBox {
+            version,
+            flags,
+            language,
+            notice,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for CprtBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        writer.write_u16::<BigEndian>(language_code(&self.language))?;
+        writer.write_all(self.notice.as_bytes())?;
+        writer.write_u8(0)?;
+
+        Ok(size)
+    }
+}
+
+/// Read a null-terminated string that is UTF-16 if it starts with a byte
+/// order mark and UTF-8 otherwise, as used by cprt and the 3GPP asset boxes.
+/// `size` is the rest of the box; the buffer only grows as bytes are read,
+/// so a box header claiming more than the file holds fails without first
+/// allocating it.
+#[cfg(feature = "std")]
+pub(crate) fn read_language_tagged_string<R: Read>(reader: &mut R, size: u64) -> Result<String> {
+    let mut buf = Vec::new();
+    reader.take(size).read_to_end(&mut buf)?;
+    if (buf.len() as u64) < size {
+        return Err(Error::InvalidData("string extends past the end of the file"));
+    }
+
+    if buf.starts_with(&[0xFE, 0xFF]) {
+        let units = buf[2..]
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .take_while(|u| *u != 0);
+        Ok(decode_utf16(units)
+            .map(|r| r.unwrap_or(REPLACEMENT_CHARACTER))
+            .collect())
+    } else {
+        let end = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+        Ok(String::from_utf8_lossy(&buf[..end]).into_owned())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_cprt() {
+        let src_box = CprtBox {
+            version: 0,
+            flags: 0,
+            language: String::from("eng"),
+            notice: String::from("Copyright 2023 Example Corp."),
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::CprtBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = CprtBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_cprt_utf16() {
+        let mut buf = Vec::new();
+        BoxHeader::new(BoxType::CprtBox, 24).write(&mut buf).unwrap();
+        buf.extend_from_slice(&[0, 0, 0, 0]);
+        buf.extend_from_slice(&language_code("eng").to_be_bytes());
+        buf.extend_from_slice(&[0xFE, 0xFF, 0, b'(', 0, b'c', 0, b')', 0, 0]);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let cprt = CprtBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(cprt.language, "eng");
+        assert_eq!(cprt.notice, "(c)");
+    }
+
+    #[test]
+    fn test_cprt_too_large() {
+        // A 4 GiB notice in an 18 byte file.
+        let mut buf = Vec::new();
+        BoxHeader::new(BoxType::CprtBox, u32::MAX as u64).write(&mut buf).unwrap();
+        buf.extend_from_slice(&[0, 0, 0, 0]);
+        buf.extend_from_slice(&language_code("eng").to_be_bytes());
+        buf.extend_from_slice(b"(c)\0");
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert!(CprtBox::read_box(&mut reader, header.size).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+/// Composition to decode timeline mapping, written next to a ctts with
+/// negative offsets. Adding `composition_to_dts_shift` to every
+/// composition time makes it no earlier than the decode time.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct CslgBox {
+    /// Version 0 stores 32-bit fields, version 1 64-bit ones.
+    pub version: u8,
+    pub flags: u32,
+
+    pub composition_to_dts_shift: i64,
+    pub least_decode_to_display_delta: i64,
+    pub greatest_decode_to_display_delta: i64,
+    pub composition_start_time: i64,
+    pub composition_end_time: i64,
+}
+
+impl CslgBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::CslgBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let field_size = if self.version == 1 { 8 } else { 4 };
+        HEADER_SIZE + HEADER_EXT_SIZE + 5 * field_size
+    }
+
+    fn fields(&self) -> [i64; 5] {
+        [
+            self.composition_to_dts_shift,
+            self.least_decode_to_display_delta,
+            self.greatest_decode_to_display_delta,
+            self.composition_start_time,
+            self.composition_end_time,
+        ]
+    }
+}
+
+impl Mp4Box for CslgBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("composition_to_dts_shift={} least_delta={} greatest_delta={}",
+            self.composition_to_dts_shift, self.least_decode_to_display_delta,
+            self.greatest_decode_to_display_delta);
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for CslgBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let mut fields = [0i64; 5];
+        for field in fields.iter_mut() {
+            *field = if version == 1 {
+                reader.read_i64::<BigEndian>()?
+            } else {
+                reader.read_i32::<BigEndian>()? as i64
+            };
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(CslgBox {
+            version,
+            flags,
+            composition_to_dts_shift: fields[0],
+            least_decode_to_display_delta: fields[1],
+            greatest_decode_to_display_delta: fields[2],
+            composition_start_time: fields[3],
+            composition_end_time: fields[4],
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for CslgBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        for field in self.fields().iter() {
+            if self.version == 1 {
+                writer.write_i64::<BigEndian>(*field)?;
+            } else {
+                let field: i32 = (*field).try_into()
+                    .map_err(|_| Error::InvalidData("cslg field out of range"))?;
+                writer.write_i32::<BigEndian>(field)?;
+            }
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_cslg() {
+        for version in 0..=1 {
+            let src_box = CslgBox {
+                version,
+                flags: 0,
+                composition_to_dts_shift: 1024,
+                least_decode_to_display_delta: -1024,
+                greatest_decode_to_display_delta: 2048,
+                composition_start_time: 0,
+                composition_end_time: 90000,
+            };
+            let mut buf = Vec::new();
+            src_box.write_box(&mut buf).unwrap();
+            assert_eq!(buf.len(), src_box.box_size() as usize);
+
+            let mut reader = Cursor::new(&buf);
+            let header = BoxHeader::read(&mut reader).unwrap();
+            assert_eq!(header.name, BoxType::CslgBox);
+            assert_eq!(src_box.box_size(), header.size);
+
+            let dst_box = CslgBox::read_box(&mut reader, header.size).unwrap();
+            assert_eq!(src_box, dst_box);
+        }
+    }
+}
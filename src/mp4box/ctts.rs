@@ -1,4 +1,6 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -6,6 +8,8 @@ use crate::mp4box::*;
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct CttsBox {
+    /// Version 0 stores unsigned offsets, version 1 signed ones. On write the
+    /// version is chosen from the entries, so this only reflects what was read.
     pub version: u8,
     pub flags: u32,
 
@@ -21,12 +25,21 @@ impl CttsBox {
     pub fn get_size(&self) -> u64 {
         HEADER_SIZE + HEADER_EXT_SIZE + 4 + (8 * self.entries.len() as u64)
     }
+
+    /// Version 1 is needed if any composition offset is negative.
+    pub fn required_version(&self) -> u8 {
+        if self.entries.iter().any(|entry| entry.sample_offset < 0) {
+            1
+        } else {
+            0
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct CttsEntry {
     pub sample_count: u32,
-    pub sample_offset: i32,
+    pub sample_offset: i64,
 }
 
 impl Mp4Box for CttsBox {
@@ -48,6 +61,7 @@ impl Mp4Box for CttsBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for CttsBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -57,9 +71,15 @@ impl<R: Read + Seek> ReadBox<&mut R> for CttsBox {
         let entry_count = reader.read_u32::<BigEndian>()?;
         let mut entries = Vec::with_capacity(entry_count as usize);
         for _ in 0..entry_count {
+            let sample_count = reader.read_u32::<BigEndian>()?;
+            let sample_offset = if version == 0 {
+                reader.read_u32::<BigEndian>()? as i64
+            } else {
+                reader.read_i32::<BigEndian>()? as i64
+            };
             let entry = CttsEntry {
-                sample_count: reader.read_u32::<BigEndian>()?,
-                sample_offset: reader.read_i32::<BigEndian>()?,
+                sample_count,
+                sample_offset,
             };
             entries.push(entry);
         }
@@ -74,24 +94,34 @@ impl<R: Read + Seek> ReadBox<&mut R> for CttsBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for CttsBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
         BoxHeader::new(self.box_type(), size).write(writer)?;
 
-        write_box_header_ext(writer, self.version, self.flags)?;
+        let version = self.required_version();
+        write_box_header_ext(writer, version, self.flags)?;
 
         writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
         for entry in self.entries.iter() {
             writer.write_u32::<BigEndian>(entry.sample_count)?;
-            writer.write_i32::<BigEndian>(entry.sample_offset)?;
+            if version == 0 {
+                let offset: u32 = entry.sample_offset.try_into()
+                    .map_err(|_| Error::InvalidData("ctts sample offset out of range"))?;
+                writer.write_u32::<BigEndian>(offset)?;
+            } else {
+                let offset: i32 = entry.sample_offset.try_into()
+                    .map_err(|_| Error::InvalidData("ctts sample offset out of range"))?;
+                writer.write_i32::<BigEndian>(offset)?;
+            }
         }
 
         Ok(size)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -100,7 +130,7 @@ mod tests {
     #[test]
     fn test_ctts() {
         let src_box = CttsBox {
-            version: 0,
+            version: 1,
             flags: 0,
             entries: vec![
                 CttsEntry {
@@ -125,4 +155,36 @@ mod tests {
         let dst_box = CttsBox::read_box(&mut reader, header.size).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_ctts_version_from_offsets() {
+        let mut src_box = CttsBox {
+            version: 0,
+            flags: 0,
+            entries: vec![CttsEntry {
+                sample_count: 1,
+                sample_offset: 3_000_000_000,
+            }],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+
+        // A large version 0 offset must not be read back as negative.
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = CttsBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(dst_box.version, 0);
+        assert_eq!(src_box, dst_box);
+
+        // A negative offset selects version 1 and survives the round trip.
+        src_box.entries[0].sample_offset = -1024;
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = CttsBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(dst_box.version, 1);
+        assert_eq!(dst_box.entries[0].sample_offset, -1024);
+    }
 }
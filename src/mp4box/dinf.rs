@@ -1,3 +1,4 @@
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -5,7 +6,7 @@ use crate::mp4box::*;
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct DinfBox {
-    dref: DrefBox,
+    pub dref: DrefBox,
 }
 
 impl DinfBox {
@@ -35,8 +36,13 @@ impl Mp4Box for DinfBox {
         let s = format!("");
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        vec![&self.dref]
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for DinfBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -75,6 +81,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for DinfBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for DinfBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -89,8 +96,7 @@ pub struct DrefBox {
     pub version: u8,
     pub flags: u32,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub url: Option<UrlBox>,
+    pub entries: Vec<DataEntryBox>,
 }
 
 impl Default for DrefBox {
@@ -98,7 +104,7 @@ impl Default for DrefBox {
         DrefBox {
             version: 0,
             flags: 0,
-            url: Some(UrlBox::default()),
+            entries: vec![DataEntryBox::Url(UrlBox::default())],
         }
     }
 }
@@ -110,8 +116,8 @@ impl DrefBox {
 
     pub fn get_size(&self) -> u64 {
         let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 4;
-        if let Some(ref url) = self.url {
-            size += url.box_size();
+        for entry in self.entries.iter() {
+            size += entry.box_size();
         }
         size
     }
@@ -131,23 +137,34 @@ impl Mp4Box for DrefBox {
     }
 
     fn summary(&self) -> Result<String> {
-        let s = format!("");
+        let s = format!("entries={}", self.entries.len());
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        self.entries
+            .iter()
+            .filter_map(|entry| match entry {
+                DataEntryBox::Url(url) => Some(url as &dyn Mp4Box),
+                DataEntryBox::Urn(urn) => Some(urn as &dyn Mp4Box),
+                DataEntryBox::Other { .. } => None,
+            })
+            .collect()
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for DrefBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
 
-        let mut current = reader.seek(SeekFrom::Current(0))?;
-
         let (version, flags) = read_box_header_ext(reader)?;
         let end = start + size;
 
-        let mut url = None;
+        let mut entries = Vec::new();
 
         let entry_count = reader.read_u32::<BigEndian>()?;
+        let mut current = reader.seek(SeekFrom::Current(0))?;
         for _i in 0..entry_count {
             if current >= end {
                 break;
@@ -156,15 +173,28 @@ impl<R: Read + Seek> ReadBox<&mut R> for DrefBox {
             // Get box header.
             let header = BoxHeader::read(reader)?;
             let BoxHeader { name, size: s } = header;
+            if current + s > end {
+                return Err(Error::InvalidData("dref entry extends past the dref"));
+            }
 
-            match name {
-                BoxType::UrlBox => {
-                   url = Some(UrlBox::read_box(reader, s)?);
-                }
+            // Keep every entry so that sample entries' data_reference_index
+            // still lines up with this table.
+            let entry = match name {
+                BoxType::UrlBox => DataEntryBox::Url(UrlBox::read_box(reader, s)?),
+                BoxType::UrnBox => DataEntryBox::Urn(UrnBox::read_box(reader, s)?),
                 _ => {
-                    skip_box(reader, s)?;
+                    if s < HEADER_SIZE {
+                        return Err(Error::InvalidData("invalid dref entry size"));
+                    }
+                    let mut data = vec![0u8; (s - HEADER_SIZE) as usize];
+                    reader.read_exact(&mut data)?;
+                    DataEntryBox::Other {
+                        entry_type: name.into(),
+                        data,
+                    }
                 }
-            }
+            };
+            entries.push(entry);
 
             current = reader.seek(SeekFrom::Current(0))?;
         }
@@ -174,11 +204,12 @@ impl<R: Read + Seek> ReadBox<&mut R> for DrefBox {
         Ok(DrefBox {
             version,
             flags,
-            url,
+            entries,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for DrefBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -186,16 +217,72 @@ impl<W: Write> WriteBox<&mut W> for DrefBox {
 
         write_box_header_ext(writer, self.version, self.flags)?;
 
-        writer.write_u32::<BigEndian>(1)?;
+        writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
 
-        if let Some(ref url) = self.url {
-            url.write_box(writer)?;
+        for entry in self.entries.iter() {
+            entry.write_box(writer)?;
         }
 
         Ok(size)
     }
 }
 
+/// A single entry of the dref box. Entries other than 'url ' and 'urn '
+/// (e.g. QuickTime 'alis') are kept as raw data.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum DataEntryBox {
+    #[serde(rename = "url")]
+    Url(UrlBox),
+    #[serde(rename = "urn")]
+    Urn(UrnBox),
+    Other {
+        entry_type: FourCC,
+        #[serde(skip_serializing)]
+        data: Vec<u8>,
+    },
+}
+
+impl DataEntryBox {
+    pub fn box_size(&self) -> u64 {
+        match self {
+            DataEntryBox::Url(url) => url.box_size(),
+            DataEntryBox::Urn(urn) => urn.box_size(),
+            DataEntryBox::Other { data, .. } => HEADER_SIZE + data.len() as u64,
+        }
+    }
+
+    /// Returns true if the media data is in the same file as the movie box.
+    pub fn is_self_contained(&self) -> bool {
+        let flags = match self {
+            DataEntryBox::Url(url) => url.flags,
+            DataEntryBox::Urn(urn) => urn.flags,
+            DataEntryBox::Other { data, .. } => {
+                if data.len() < 4 {
+                    return false;
+                }
+                u32::from_be_bytes([0, data[1], data[2], data[3]])
+            }
+        };
+        flags & UrlBox::FLAG_SELF_CONTAINED != 0
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for DataEntryBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        match self {
+            DataEntryBox::Url(url) => url.write_box(writer),
+            DataEntryBox::Urn(urn) => urn.write_box(writer),
+            DataEntryBox::Other { entry_type, data } => {
+                let size = self.box_size();
+                BoxHeader::new(BoxType::from(u32::from(*entry_type)), size).write(writer)?;
+                writer.write_all(data)?;
+                Ok(size)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct UrlBox {
     pub version: u8,
@@ -214,6 +301,17 @@ impl Default for UrlBox {
 }
 
 impl UrlBox {
+    pub const FLAG_SELF_CONTAINED: u32 = 0x000001;
+
+    /// Create an entry for `location`, or a self-contained entry if empty.
+    pub fn new(location: &str) -> Self {
+        UrlBox {
+            version: 0,
+            flags: if location.is_empty() { UrlBox::FLAG_SELF_CONTAINED } else { 0 },
+            location: location.to_string(),
+        }
+    }
+
     pub fn get_type(&self) -> BoxType {
         BoxType::UrlBox
     }
@@ -248,6 +346,7 @@ impl Mp4Box for UrlBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for UrlBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -281,6 +380,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for UrlBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for UrlBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -296,3 +396,150 @@ impl<W: Write> WriteBox<&mut W> for UrlBox {
         Ok(size)
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct UrnBox {
+    pub version: u8,
+    pub flags: u32,
+    pub name: String,
+    pub location: String,
+}
+
+impl UrnBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::UrnBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + self.name.len() as u64 + 1;
+        if !self.location.is_empty() {
+            size += self.location.len() as u64 + 1;
+        }
+        size
+    }
+}
+
+impl Mp4Box for UrnBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("name={} location={}", self.name, self.location);
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for UrnBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        let buf_size = size
+            .checked_sub(HEADER_SIZE + HEADER_EXT_SIZE)
+            .ok_or(Error::InvalidData("urn box too small"))?;
+        let mut buf = vec![0u8; buf_size as usize];
+        reader.read_exact(&mut buf)?;
+
+        // Two null-terminated strings, the location being optional.
+        let mut strings = buf.split(|b| *b == 0).map(|s| String::from_utf8_lossy(s).into_owned());
+        let name = strings.next().unwrap_or_default();
+        let location = strings.next().unwrap_or_default();
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(UrnBox {
+            version,
+            flags,
+            name,
+            location,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for UrnBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_all(self.name.as_bytes())?;
+        writer.write_u8(0)?;
+        if !self.location.is_empty() {
+            writer.write_all(self.location.as_bytes())?;
+            writer.write_u8(0)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_dref_multiple_entries() {
+        let src_box = DrefBox {
+            version: 0,
+            flags: 0,
+            entries: vec![
+                DataEntryBox::Url(UrlBox::default()),
+                DataEntryBox::Url(UrlBox::new("http://example.com/media.mp4")),
+                DataEntryBox::Urn(UrnBox {
+                    version: 0,
+                    flags: 0,
+                    name: String::from("urn:example:media"),
+                    location: String::from("media.mp4"),
+                }),
+            ],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::DrefBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = DrefBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert!(dst_box.entries[0].is_self_contained());
+        assert!(!dst_box.entries[1].is_self_contained());
+    }
+
+    #[test]
+    fn test_dref_entry_too_large() {
+        let src_box = DrefBox {
+            version: 0,
+            flags: 0,
+            entries: vec![DataEntryBox::Other {
+                entry_type: str::parse("alis").unwrap(),
+                data: vec![0; 4],
+            }],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        // Claim a 4 GiB entry.
+        buf[16..20].copy_from_slice(&u32::MAX.to_be_bytes());
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert!(DrefBox::read_box(&mut reader, header.size).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -44,8 +45,16 @@ impl Mp4Box for EdtsBox {
         let s = format!("");
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        match self.elst {
+            Some(ref elst) => vec![elst],
+            None => Vec::new(),
+        }
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for EdtsBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -69,6 +78,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for EdtsBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for EdtsBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -1,4 +1,6 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -17,10 +19,26 @@ pub struct ElstBox {
 pub struct ElstEntry {
     pub segment_duration: u64,
     pub media_time: u64,
+
+    /// Integer part of the playback rate, a signed value.
     pub media_rate: u16,
     pub media_rate_fraction: u16,
 }
 
+impl ElstEntry {
+    /// Returns the playback rate of the segment, e.g. 0.5 for half speed or
+    /// 0 for a dwell that holds a single frame.
+    pub fn media_rate(&self) -> f32 {
+        self.media_rate as i16 as f32 + self.media_rate_fraction as f32 / 65536.0
+    }
+
+    /// Returns true for a dwell, an edit with rate 0 that presents the
+    /// sample at media_time for the whole segment without advancing.
+    pub fn is_dwell(&self) -> bool {
+        self.media_rate == 0 && self.media_rate_fraction == 0
+    }
+}
+
 impl ElstBox {
     pub fn get_type(&self) -> BoxType {
         BoxType::ElstBox
@@ -56,6 +74,7 @@ impl Mp4Box for ElstBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for ElstBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -96,6 +115,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for ElstBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for ElstBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -120,7 +140,7 @@ impl<W: Write> WriteBox<&mut W> for ElstBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -175,4 +195,40 @@ mod tests {
         let dst_box = ElstBox::read_box(&mut reader, header.size).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_elst_media_rate() {
+        let src_box = ElstBox {
+            version: 0,
+            flags: 0,
+            entries: vec![
+                ElstEntry {
+                    segment_duration: 1000,
+                    media_time: 0,
+                    media_rate: 1,
+                    media_rate_fraction: 0,
+                },
+                ElstEntry {
+                    segment_duration: 2000,
+                    media_time: 1000,
+                    media_rate: 0,
+                    media_rate_fraction: 0x8000,
+                },
+                ElstEntry {
+                    segment_duration: 500,
+                    media_time: 2000,
+                    media_rate: 2,
+                    media_rate_fraction: 0,
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = ElstBox::read_box(&mut reader, header.size).unwrap();
+        let rates: Vec<f32> = dst_box.entries.iter().map(|entry| entry.media_rate()).collect();
+        assert_eq!(rates, vec![1.0, 0.5, 2.0]);
+    }
 }
@@ -1,6 +1,9 @@
-use std::ffi::CStr;
+#[cfg(feature = "std")]
+use core::ffi::CStr;
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use serde::Serialize;
 
@@ -58,6 +61,7 @@ impl Mp4Box for EmsgBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for EmsgBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -117,6 +121,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for EmsgBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for EmsgBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -151,6 +156,7 @@ impl<W: Write> WriteBox<&mut W> for EmsgBox {
     }
 }
 
+#[cfg(feature = "std")]
 fn read_null_terminated_utf8_string<R: Read + Seek>(reader: &mut R) -> Result<String> {
     let mut bytes = Vec::new();
     loop {
@@ -165,6 +171,7 @@ fn read_null_terminated_utf8_string<R: Read + Seek>(reader: &mut R) -> Result<St
     }
 }
 
+#[cfg(feature = "std")]
 fn write_null_terminated_str<W: Write>(writer: &mut W, string: &str) -> Result<()> {
     for byte in string.bytes() {
         writer.write_u8(byte)?;
@@ -173,7 +180,7 @@ fn write_null_terminated_str<W: Write>(writer: &mut W, string: &str) -> Result<(
     Ok(())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use std::io::Cursor;
 
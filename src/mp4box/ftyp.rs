@@ -1,4 +1,6 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -19,6 +21,12 @@ impl FtypBox {
     pub fn get_size(&self) -> u64 {
         HEADER_SIZE + 8 + (4 * self.compatible_brands.len() as u64)
     }
+
+    /// Returns true if `brand` is the major brand or one of the compatible
+    /// brands.
+    pub fn is_compatible_with(&self, brand: FourCC) -> bool {
+        self.major_brand == brand || self.compatible_brands.contains(&brand)
+    }
 }
 
 impl Mp4Box for FtypBox {
@@ -45,6 +53,7 @@ impl Mp4Box for FtypBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for FtypBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -72,6 +81,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for FtypBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for FtypBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -86,7 +96,7 @@ impl<W: Write> WriteBox<&mut W> for FtypBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -1,28 +1,46 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use std::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, SeekFrom, Write};
 use serde::{Serialize};
 
 use crate::mp4box::*;
+use crate::mp4box::colr::ColrBox;
+use crate::mp4box::avc1::NalUnit;
+use crate::nal::HevcNal;
 
+/// HEVC sample entry, either hev1 or hvc1.
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Hev1Box {
+    /// Set for hvc1 sample entries, whose parameter sets are all in the
+    /// hvcC and never in the samples.
+    pub hvc1: bool,
     pub data_reference_index: u16,
     pub width: u16,
     pub height: u16,
 
-    #[serde(with = "value_u32")]
+    #[serde(with = "value_f64")]
     pub horizresolution: FixedPointU16,
 
-    #[serde(with = "value_u32")]
+    #[serde(with = "value_f64")]
     pub vertresolution: FixedPointU16,
     pub frame_count: u16,
     pub depth: u16,
     pub hvcc: HvcCBox,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colr: Option<ColrBox>,
+
+    /// Child boxes this crate doesn't parse, such as fiel or gama, in the
+    /// order they were read.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_boxes: Vec<RawBox>,
 }
 
 impl Default for Hev1Box {
     fn default() -> Self {
         Hev1Box {
+            hvc1: false,
             data_reference_index: 0,
             width: 0,
             height: 0,
@@ -31,30 +49,76 @@ impl Default for Hev1Box {
             frame_count: 1,
             depth: 0x0018,
             hvcc: HvcCBox::default(),
+            colr: None,
+            unknown_boxes: Vec::new(),
         }
     }
 }
 
 impl Hev1Box {
-    pub fn new(config: &HevcConfig) -> Self {
+    /// Like `new`, but rejects a zero data_reference_index, empty dimensions
+    /// or an SPS that can't be parsed instead of producing an unplayable
+    /// sample entry.
+    pub fn try_new(config: &HevcConfig, data_reference_index: u16) -> Result<Self> {
+        check_data_reference_index(data_reference_index)?;
+        check_dimensions(config.width, config.height)?;
+        if let Some(sps) = config.seq_param_sets.first() {
+            HvcCBox::new().update_from_sps(sps)?;
+        }
+        Ok(Self::new(config, data_reference_index))
+    }
+
+    /// The hvcC holds the parameter sets of `config`, with its profile, tier,
+    /// level and bit depths taken from the first SPS. If that SPS can't be
+    /// parsed they keep their defaults.
+    pub fn new(config: &HevcConfig, data_reference_index: u16) -> Self {
+        let mut hvcc = HvcCBox::new();
+        let parameter_sets = [
+            (HevcNal::TYPE_VPS, &config.vid_param_sets),
+            (HevcNal::TYPE_SPS, &config.seq_param_sets),
+            (HevcNal::TYPE_PPS, &config.pic_param_sets),
+        ];
+        for (nal_unit_type, nalus) in parameter_sets.iter() {
+            for nalu in nalus.iter() {
+                hvcc.add_nal_unit(*nal_unit_type, nalu);
+            }
+        }
+        if let Some(sps) = config.seq_param_sets.first() {
+            hvcc.update_from_sps(sps).ok();
+        }
+
         Hev1Box {
-            data_reference_index: 1,
+            hvc1: false,
+            data_reference_index,
             width: config.width,
             height: config.height,
             horizresolution: FixedPointU16::new(0x48),
             vertresolution: FixedPointU16::new(0x48),
             frame_count: 1,
             depth: 0x0018,
-            hvcc: HvcCBox::new(),
+            hvcc,
+            colr: None,
+            unknown_boxes: Vec::new(),
         }
     }
 
     pub fn get_type(&self) -> BoxType {
-        BoxType::Hev1Box
+        if self.hvc1 {
+            BoxType::Hvc1Box
+        } else {
+            BoxType::Hev1Box
+        }
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + 8 + 70 + self.hvcc.box_size()
+        let mut size = HEADER_SIZE + 8 + 70 + self.hvcc.box_size();
+        if let Some(ref colr) = self.colr {
+            size += colr.box_size();
+        }
+        for unknown in self.unknown_boxes.iter() {
+            size += unknown.get_size();
+        }
+        size
     }
 }
 
@@ -76,11 +140,23 @@ impl Mp4Box for Hev1Box {
             self.data_reference_index, self.width, self.height, self.frame_count);
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = vec![&self.hvcc];
+        if let Some(ref colr) = self.colr {
+            children.push(colr);
+        }
+        children
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for Hev1Box {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
+        // The entry type is the only difference between hev1 and hvc1.
+        reader.seek(SeekFrom::Start(start + 4))?;
+        let hvc1 = BoxType::from(reader.read_u32::<BigEndian>()?) == BoxType::Hvc1Box;
 
         reader.read_u32::<BigEndian>()?; // reserved
         reader.read_u16::<BigEndian>()?; // reserved
@@ -99,29 +175,53 @@ impl<R: Read + Seek> ReadBox<&mut R> for Hev1Box {
         let depth = reader.read_u16::<BigEndian>()?;
         reader.read_i16::<BigEndian>()?; // pre-defined
 
-        let header = BoxHeader::read(reader)?;
-        let BoxHeader { name, size: s } = header;
-        if name == BoxType::HvcCBox {
-            let hvcc = HvcCBox::read_box(reader, s)?;
+        let mut hvcc = None;
+        let mut colr = None;
+        let mut unknown_boxes = Vec::new();
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current + HEADER_SIZE <= end {
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+
+            match name {
+                BoxType::HvcCBox => {
+                    hvcc = Some(HvcCBox::read_box(reader, s)?);
+                }
+                BoxType::ColrBox => {
+                    colr = Some(ColrBox::read_box(reader, s)?);
+                }
+                _ if current + s <= end => {
+                    unknown_boxes.push(RawBox::read(reader, &header)?);
+                }
+                _ => {
+                    skip_box(reader, s)?;
+                }
+            }
+            current = reader.stream_position()?;
+        }
+        let hvcc = hvcc.ok_or(Error::InvalidData("hvcc not found"))?;
 
-            skip_bytes_to(reader, start + size)?;
+        skip_bytes_to(reader, start + size)?;
 
-            Ok(Hev1Box {
-                data_reference_index,
-                width,
-                height,
-                horizresolution,
-                vertresolution,
-                frame_count,
-                depth,
-                hvcc,
-            })
-        } else {
-            Err(Error::InvalidData("hvcc not found"))
-        }
+        Ok(Hev1Box {
+            hvc1,
+            data_reference_index,
+            width,
+            height,
+            horizresolution,
+            vertresolution,
+            frame_count,
+            depth,
+            hvcc,
+            colr,
+            unknown_boxes,
+        })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for Hev1Box {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -146,22 +246,166 @@ impl<W: Write> WriteBox<&mut W> for Hev1Box {
         writer.write_i16::<BigEndian>(-1)?; // pre-defined
 
         self.hvcc.write_box(writer)?;
+        if let Some(ref colr) = self.colr {
+            colr.write_box(writer)?;
+        }
+        for unknown in self.unknown_boxes.iter() {
+            unknown.write(writer)?;
+        }
 
         Ok(size)
     }
 }
 
+/// HEVC decoder configuration record.
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct HvcCBox {
     pub configuration_version: u8,
+    pub general_profile_space: u8,
+    pub general_tier_flag: bool,
+    pub general_profile_idc: u8,
+    pub general_profile_compatibility_flags: u32,
+
+    /// The 48 bit general_constraint_indicator_flags.
+    pub general_constraint_indicator_flags: u64,
+    pub general_level_idc: u8,
+    pub min_spatial_segmentation_idc: u16,
+    pub parallelism_type: u8,
+    pub chroma_format_idc: u8,
+    pub bit_depth_luma_minus8: u8,
+    pub bit_depth_chroma_minus8: u8,
+    pub avg_frame_rate: u16,
+    pub constant_frame_rate: u8,
+    pub num_temporal_layers: u8,
+    pub temporal_id_nested: bool,
+    pub length_size_minus_one: u8,
+    pub arrays: Vec<HvcCArray>,
+}
+
+/// The NAL units of one type, e.g. the VPSs, held by an hvcC.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct HvcCArray {
+    /// Set if every NAL unit of this type is in the array rather than in
+    /// the samples, as required for hvc1.
+    pub completeness: bool,
+    pub nal_unit_type: u8,
+    pub nalus: Vec<NalUnit>,
 }
 
 impl HvcCBox {
     pub fn new() -> Self {
         Self {
             configuration_version: 1,
+            chroma_format_idc: 1,
+            length_size_minus_one: 3, // length_size = 4
+            ..Self::default()
+        }
+    }
+
+    /// Returns the array holding the NAL units of the given type.
+    pub fn array(&self, nal_unit_type: u8) -> Option<&HvcCArray> {
+        self.arrays.iter().find(|array| array.nal_unit_type == nal_unit_type)
+    }
+
+    /// Adds a parameter set NAL unit, including its header, to the array of
+    /// its type unless the array already holds it.
+    pub fn add_nal_unit(&mut self, nal_unit_type: u8, data: &[u8]) {
+        let index = match self.arrays.iter().position(|a| a.nal_unit_type == nal_unit_type) {
+            Some(index) => index,
+            None => {
+                self.arrays.push(HvcCArray {
+                    completeness: false,
+                    nal_unit_type,
+                    nalus: Vec::new(),
+                });
+                self.arrays.len() - 1
+            }
+        };
+        let array = &mut self.arrays[index];
+        if !array.nalus.iter().any(|nalu| nalu.bytes == data) {
+            array.nalus.push(NalUnit::from(data));
+        }
+    }
+
+    /// Sets the profile, tier, level, chroma format, bit depths and temporal
+    /// layers from an SPS NAL unit, including its header, so they agree
+    /// with the parameter sets. Nothing changes if the SPS is truncated.
+    pub fn update_from_sps(&mut self, sps: &[u8]) -> Result<()> {
+        let rbsp = nal_unit_payload(sps.get(2..).unwrap_or_default());
+        let mut updated = self.clone();
+        read_sps(&mut BitReader::new(&rbsp), &mut updated)
+            .ok_or(Error::InvalidData("truncated HEVC SPS"))?;
+        *self = updated;
+        Ok(())
+    }
+}
+
+// Strips the emulation prevention bytes, the 3 in each 00 00 03, from a NAL
+// unit payload.
+fn nal_unit_payload(data: &[u8]) -> Vec<u8> {
+    let mut rbsp = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+    for &byte in data {
+        if zeros >= 2 && byte == 3 {
+            zeros = 0;
+            continue;
         }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        rbsp.push(byte);
     }
+    rbsp
+}
+
+// Reads seq_parameter_set_rbsp (ISO/IEC 23008-2 7.3.2.2) up to the bit
+// depths.
+fn read_sps(bits: &mut BitReader, hvcc: &mut HvcCBox) -> Option<()> {
+    bits.read(4)?; // sps_video_parameter_set_id
+    let max_sub_layers_minus1 = bits.read(3)? as u8;
+    hvcc.temporal_id_nested = bits.read_flag()?;
+    hvcc.num_temporal_layers = max_sub_layers_minus1 + 1;
+
+    // profile_tier_level(1, sps_max_sub_layers_minus1)
+    hvcc.general_profile_space = bits.read(2)? as u8;
+    hvcc.general_tier_flag = bits.read_flag()?;
+    hvcc.general_profile_idc = bits.read(5)? as u8;
+    hvcc.general_profile_compatibility_flags = bits.read(32)?;
+    hvcc.general_constraint_indicator_flags =
+        (bits.read(16)? as u64) << 32 | bits.read(32)? as u64;
+    hvcc.general_level_idc = bits.read(8)? as u8;
+    let mut sub_layers = Vec::with_capacity(max_sub_layers_minus1 as usize);
+    for _ in 0..max_sub_layers_minus1 {
+        sub_layers.push((bits.read_flag()?, bits.read_flag()?));
+    }
+    if max_sub_layers_minus1 > 0 {
+        bits.read(2 * (8 - max_sub_layers_minus1 as usize))?; // reserved_zero_2bits
+    }
+    for (profile_present, level_present) in sub_layers {
+        if profile_present {
+            bits.read(32)?;
+            bits.read(32)?;
+            bits.read(24)?;
+        }
+        if level_present {
+            bits.read(8)?;
+        }
+    }
+
+    bits.read_ue()?; // sps_seq_parameter_set_id
+    let chroma_format_idc = bits.read_ue()?;
+    if chroma_format_idc == 3 {
+        bits.read(1)?; // separate_colour_plane_flag
+    }
+    bits.read_ue()?; // pic_width_in_luma_samples
+    bits.read_ue()?; // pic_height_in_luma_samples
+    if bits.read_flag()? {
+        for _ in 0..4 {
+            bits.read_ue()?; // conf_win offsets
+        }
+    }
+    hvcc.chroma_format_idc = chroma_format_idc as u8 & 0x03;
+    hvcc.bit_depth_luma_minus8 = bits.read_ue()? as u8 & 0x07;
+    hvcc.bit_depth_chroma_minus8 = bits.read_ue()? as u8 & 0x07;
+    Some(())
 }
 
 impl Mp4Box for HvcCBox {
@@ -170,7 +414,13 @@ impl Mp4Box for HvcCBox {
     }
 
     fn box_size(&self) -> u64 {
-        let size = HEADER_SIZE + 1;
+        let mut size = HEADER_SIZE + 23;
+        for array in self.arrays.iter() {
+            size += 3;
+            for nalu in array.nalus.iter() {
+                size += 2 + nalu.bytes.len() as u64;
+            }
+        }
         size
     }
 
@@ -179,37 +429,127 @@ impl Mp4Box for HvcCBox {
     }
 
     fn summary(&self) -> Result<String> {
-        let s = format!("configuration_version={}",
-            self.configuration_version);
+        let s = format!("configuration_version={} general_profile_idc={} general_level_idc={} arrays={}",
+            self.configuration_version, self.general_profile_idc, self.general_level_idc,
+            self.arrays.len());
         Ok(s)
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for HvcCBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
 
         let configuration_version = reader.read_u8()?;
+        // Older versions of this crate wrote only the configuration version.
+        if size < HEADER_SIZE + 23 {
+            skip_bytes_to(reader, start + size)?;
+            return Ok(HvcCBox {
+                configuration_version,
+                ..HvcCBox::default()
+            });
+        }
+
+        let profile = reader.read_u8()?;
+        let general_profile_compatibility_flags = reader.read_u32::<BigEndian>()?;
+        let general_constraint_indicator_flags = read_u48(reader)?;
+        let general_level_idc = reader.read_u8()?;
+        let min_spatial_segmentation_idc = reader.read_u16::<BigEndian>()? & 0x0FFF;
+        let parallelism_type = reader.read_u8()? & 0x03;
+        let chroma_format_idc = reader.read_u8()? & 0x03;
+        let bit_depth_luma_minus8 = reader.read_u8()? & 0x07;
+        let bit_depth_chroma_minus8 = reader.read_u8()? & 0x07;
+        let avg_frame_rate = reader.read_u16::<BigEndian>()?;
+        let layers = reader.read_u8()?;
+
+        let num_of_arrays = reader.read_u8()?;
+        let mut arrays = Vec::with_capacity(num_of_arrays as usize);
+        for _ in 0..num_of_arrays {
+            let array_type = reader.read_u8()?;
+            let num_nalus = reader.read_u16::<BigEndian>()?;
+            let mut nalus = Vec::with_capacity(num_nalus as usize);
+            for _ in 0..num_nalus {
+                let length = reader.read_u16::<BigEndian>()? as usize;
+                let mut bytes = vec![0u8; length];
+                reader.read_exact(&mut bytes)?;
+                nalus.push(NalUnit { bytes });
+            }
+            arrays.push(HvcCArray {
+                completeness: array_type & 0x80 != 0,
+                nal_unit_type: array_type & 0x3F,
+                nalus,
+            });
+        }
 
         skip_bytes_to(reader, start + size)?;
 
         Ok(HvcCBox {
             configuration_version,
+            general_profile_space: profile >> 6,
+            general_tier_flag: profile & 0x20 != 0,
+            general_profile_idc: profile & 0x1F,
+            general_profile_compatibility_flags,
+            general_constraint_indicator_flags,
+            general_level_idc,
+            min_spatial_segmentation_idc,
+            parallelism_type,
+            chroma_format_idc,
+            bit_depth_luma_minus8,
+            bit_depth_chroma_minus8,
+            avg_frame_rate,
+            constant_frame_rate: layers >> 6,
+            num_temporal_layers: (layers >> 3) & 0x07,
+            temporal_id_nested: layers & 0x04 != 0,
+            length_size_minus_one: layers & 0x03,
+            arrays,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for HvcCBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
         BoxHeader::new(self.box_type(), size).write(writer)?;
 
         writer.write_u8(self.configuration_version)?;
+        writer.write_u8(
+            self.general_profile_space << 6
+                | (self.general_tier_flag as u8) << 5
+                | self.general_profile_idc & 0x1F,
+        )?;
+        writer.write_u32::<BigEndian>(self.general_profile_compatibility_flags)?;
+        write_u48(writer, self.general_constraint_indicator_flags)?;
+        writer.write_u8(self.general_level_idc)?;
+        writer.write_u16::<BigEndian>(self.min_spatial_segmentation_idc | 0xF000)?;
+        writer.write_u8(self.parallelism_type | 0xFC)?;
+        writer.write_u8(self.chroma_format_idc | 0xFC)?;
+        writer.write_u8(self.bit_depth_luma_minus8 | 0xF8)?;
+        writer.write_u8(self.bit_depth_chroma_minus8 | 0xF8)?;
+        writer.write_u16::<BigEndian>(self.avg_frame_rate)?;
+        writer.write_u8(
+            self.constant_frame_rate << 6
+                | (self.num_temporal_layers & 0x07) << 3
+                | (self.temporal_id_nested as u8) << 2
+                | self.length_size_minus_one & 0x03,
+        )?;
+
+        writer.write_u8(self.arrays.len() as u8)?;
+        for array in self.arrays.iter() {
+            writer.write_u8((array.completeness as u8) << 7 | array.nal_unit_type & 0x3F)?;
+            writer.write_u16::<BigEndian>(array.nalus.len() as u16)?;
+            for nalu in array.nalus.iter() {
+                writer.write_u16::<BigEndian>(nalu.bytes.len() as u16)?;
+                writer.write_all(&nalu.bytes)?;
+            }
+        }
+
         Ok(size)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -218,6 +558,7 @@ mod tests {
     #[test]
     fn test_hev1() {
         let src_box = Hev1Box {
+            hvc1: false,
             data_reference_index: 1,
             width: 320,
             height: 240,
@@ -227,7 +568,10 @@ mod tests {
             depth: 24,
             hvcc: HvcCBox {
                 configuration_version: 1,
+                ..HvcCBox::default()
             },
+            colr: None,
+            unknown_boxes: Vec::new(),
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -241,4 +585,140 @@ mod tests {
         let dst_box = Hev1Box::read_box(&mut reader, header.size).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    // Writes (value, width) fields, where a width of 0 means ue(v), then
+    // adds emulation prevention bytes and the SPS NAL unit header.
+    fn sps(fields: &[(u32, usize)]) -> Vec<u8> {
+        let mut bits = Vec::new();
+        for (value, width) in fields.iter() {
+            let (code, width) = if *width == 0 {
+                let code = *value as u64 + 1;
+                let width = 64 - code.leading_zeros() as usize;
+                bits.extend((1..width).map(|_| false));
+                (code, width)
+            } else {
+                (*value as u64, *width)
+            };
+            bits.extend((0..width).rev().map(|i| (code >> i) & 1 == 1));
+        }
+        bits.push(true); // rbsp_stop_one_bit
+        let rbsp: Vec<u8> = bits.chunks(8)
+            .map(|byte| byte.iter().enumerate().fold(0, |acc, (i, bit)| acc | (*bit as u8) << (7 - i)))
+            .collect();
+
+        let mut nal = vec![0x42, 0x01];
+        let mut zeros = 0;
+        for byte in rbsp {
+            if zeros == 2 && byte <= 3 {
+                nal.push(3);
+                zeros = 0;
+            }
+            zeros = if byte == 0 { zeros + 1 } else { 0 };
+            nal.push(byte);
+        }
+        nal
+    }
+
+    #[test]
+    fn test_hvcc_from_sps() {
+        // Main 10, main tier, level 4 (120), one temporal layer, 1920x1080
+        // with a conformance window.
+        let sps = sps(&[
+            (0, 4), (0, 3), (1, 1),
+            (0, 2), (0, 1), (2, 5), (0x2000_0000, 32), (0x9000, 16), (0, 32), (120, 8),
+            (0, 0), (1, 0), (1920, 0), (1088, 0),
+            (1, 1), (0, 0), (0, 0), (0, 0), (4, 0),
+            (2, 0), (2, 0),
+        ]);
+        // The compatibility and constraint flags hold zero runs that need
+        // emulation prevention.
+        assert!(sps.windows(3).any(|w| w == [0, 0, 3]));
+
+        let config = HevcConfig {
+            width: 1920,
+            height: 1080,
+            vid_param_sets: vec![vec![0x40, 0x01, 0x0c]],
+            seq_param_sets: vec![sps.clone(), vec![0x42, 0x01, 0x01]],
+            pic_param_sets: vec![vec![0x44, 0x01, 0xc1]],
+        };
+        let src_box = Hev1Box::try_new(&config, 1).unwrap();
+        let hvcc = &src_box.hvcc;
+        assert_eq!(hvcc.general_profile_idc, 2);
+        assert!(!hvcc.general_tier_flag);
+        assert_eq!(hvcc.general_profile_compatibility_flags, 0x2000_0000);
+        assert_eq!(hvcc.general_constraint_indicator_flags, 0x9000_0000_0000);
+        assert_eq!(hvcc.general_level_idc, 120);
+        assert_eq!(hvcc.chroma_format_idc, 1);
+        assert_eq!((hvcc.bit_depth_luma_minus8, hvcc.bit_depth_chroma_minus8), (2, 2));
+        assert_eq!(hvcc.num_temporal_layers, 1);
+        assert!(hvcc.temporal_id_nested);
+        let types: Vec<u8> = hvcc.arrays.iter().map(|array| array.nal_unit_type).collect();
+        assert_eq!(types, vec![32, 33, 34]);
+        assert_eq!(hvcc.array(33).unwrap().nalus.len(), 2);
+
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = Hev1Box::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(dst_box.hvcc.general_level_idc, 120);
+
+        // A truncated SPS is rejected by try_new and ignored by new.
+        let config = HevcConfig {
+            seq_param_sets: vec![vec![0x42, 0x01, 0x01]],
+            ..config
+        };
+        assert!(Hev1Box::try_new(&config, 1).is_err());
+        assert_eq!(Hev1Box::new(&config, 1).hvcc.general_level_idc, 0);
+    }
+
+    #[test]
+    fn test_hvc1() {
+        let mut hvcc = HvcCBox {
+            general_profile_idc: 1,
+            general_tier_flag: true,
+            general_profile_compatibility_flags: 0x6000_0000,
+            general_constraint_indicator_flags: 0x9000_0000_0000,
+            general_level_idc: 93,
+            num_temporal_layers: 1,
+            temporal_id_nested: true,
+            ..HvcCBox::new()
+        };
+        hvcc.add_nal_unit(32, &[0x40, 0x01, 0x0c]);
+        hvcc.add_nal_unit(33, &[0x42, 0x01, 0x01]);
+        hvcc.add_nal_unit(34, &[0x44, 0x01, 0xc1]);
+        hvcc.add_nal_unit(33, &[0x42, 0x01, 0x01]);
+        assert_eq!(hvcc.array(33).unwrap().nalus.len(), 1);
+        hvcc.arrays[0].completeness = true;
+
+        let src_box = Hev1Box {
+            hvc1: true,
+            width: 1920,
+            height: 1080,
+            hvcc,
+            ..Hev1Box::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::Hvc1Box);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = Hev1Box::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_hev1_json() {
+        let hev1 = Hev1Box {
+            horizresolution: FixedPointU16::new_raw(0x00488000),
+            ..Hev1Box::default()
+        };
+        let json: serde_json::Value = serde_json::from_str(&hev1.to_json().unwrap()).unwrap();
+        assert_eq!(json["horizresolution"], 72.5);
+        assert_eq!(json["vertresolution"], 72.0);
+    }
 }
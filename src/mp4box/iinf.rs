@@ -0,0 +1,330 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+const ITEM_TYPE_MIME: [u8; 4] = *b"mime";
+const ITEM_TYPE_URI: [u8; 4] = *b"uri ";
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct IinfBox {
+    pub version: u8,
+    pub flags: u32,
+
+    #[serde(rename = "infe")]
+    pub entries: Vec<InfeBox>,
+}
+
+impl IinfBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::IinfBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let count_size = if self.version == 0 { 2 } else { 4 };
+        HEADER_SIZE + HEADER_EXT_SIZE + count_size
+            + self.entries.iter().map(|infe| infe.box_size()).sum::<u64>()
+    }
+
+    pub fn item(&self, item_id: u32) -> Option<&InfeBox> {
+        self.entries.iter().find(|infe| infe.item_id == item_id)
+    }
+}
+
+impl Mp4Box for IinfBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("entry_count={}", self.entries.len());
+        Ok(s)
+    }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        self.entries.iter().map(|infe| infe as &dyn Mp4Box).collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for IinfBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let entry_count = if version == 0 {
+            reader.read_u16::<BigEndian>()? as u32
+        } else {
+            reader.read_u32::<BigEndian>()?
+        };
+
+        let mut entries = Vec::new();
+        let end = start + size;
+        for _ in 0..entry_count {
+            let current = reader.stream_position()?;
+            if current + HEADER_SIZE > end {
+                return Err(Error::InvalidData("iinf entry count exceeds box size"));
+            }
+
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+            if name == BoxType::InfeBox {
+                entries.push(InfeBox::read_box(reader, s)?);
+            } else {
+                skip_box(reader, s)?;
+            }
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(IinfBox {
+            version,
+            flags,
+            entries,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for IinfBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        if self.version == 0 {
+            writer.write_u16::<BigEndian>(self.entries.len() as u16)?;
+        } else {
+            writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+        }
+        for infe in self.entries.iter() {
+            infe.write_box(writer)?;
+        }
+
+        Ok(size)
+    }
+}
+
+/// An item information entry. Versions 2 and 3 carry an item_type; versions
+/// 0 and 1 describe MIME-typed items only.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct InfeBox {
+    pub version: u8,
+    pub flags: u32,
+    pub item_id: u32,
+    pub item_protection_index: u16,
+    pub item_type: FourCC,
+    pub item_name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_uri_type: Option<String>,
+}
+
+impl InfeBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::InfeBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 2 + 2;
+        match self.version {
+            0 | 1 => {}
+            2 => size += 4,
+            _ => size += 2 + 4,
+        }
+        for s in self.strings() {
+            size += s.len() as u64 + 1;
+        }
+        size
+    }
+
+    /// Returns true if the item is protected, i.e. has a sinf in ipro.
+    pub fn is_protected(&self) -> bool {
+        self.item_protection_index != 0
+    }
+
+    fn has_content_type(&self) -> bool {
+        self.version < 2 || self.item_type.value == ITEM_TYPE_MIME
+    }
+
+    fn strings(&self) -> Vec<&str> {
+        let mut strings = vec![self.item_name.as_str()];
+        if self.has_content_type() {
+            strings.push(self.content_type.as_deref().unwrap_or(""));
+            if let Some(ref encoding) = self.content_encoding {
+                strings.push(encoding);
+            }
+        } else if self.item_type.value == ITEM_TYPE_URI {
+            strings.push(self.item_uri_type.as_deref().unwrap_or(""));
+        }
+        strings
+    }
+}
+
+impl Mp4Box for InfeBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("item_id={} item_type={} item_name={}",
+            self.item_id, self.item_type, self.item_name);
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for InfeBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let item_id = if version == 3 {
+            reader.read_u32::<BigEndian>()?
+        } else {
+            reader.read_u16::<BigEndian>()? as u32
+        };
+        let item_protection_index = reader.read_u16::<BigEndian>()?;
+        let item_type = if version >= 2 {
+            FourCC::from(reader.read_u32::<BigEndian>()?)
+        } else {
+            FourCC::default()
+        };
+
+        let current = reader.stream_position()?;
+        let remaining = (start + size)
+            .checked_sub(current)
+            .ok_or(Error::InvalidData("infe box too small"))?;
+        let mut buf = vec![0u8; remaining as usize];
+        reader.read_exact(&mut buf)?;
+        // Version 1 entries may be followed by an extension, which is ignored.
+        let mut strings = buf
+            .split(|b| *b == 0)
+            .map(|s| String::from_utf8_lossy(s).into_owned());
+
+        let item_name = strings.next().unwrap_or_default();
+        let mut content_type = None;
+        let mut content_encoding = None;
+        let mut item_uri_type = None;
+        if version < 2 || item_type.value == ITEM_TYPE_MIME {
+            content_type = strings.next();
+            content_encoding = strings.next().filter(|s| !s.is_empty());
+        } else if item_type.value == ITEM_TYPE_URI {
+            item_uri_type = strings.next();
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(InfeBox {
+            version,
+            flags,
+            item_id,
+            item_protection_index,
+            item_type,
+            item_name,
+            content_type,
+            content_encoding,
+            item_uri_type,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for InfeBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        if self.version == 3 {
+            writer.write_u32::<BigEndian>(self.item_id)?;
+        } else {
+            writer.write_u16::<BigEndian>(self.item_id as u16)?;
+        }
+        writer.write_u16::<BigEndian>(self.item_protection_index)?;
+        if self.version >= 2 {
+            writer.write_u32::<BigEndian>((&self.item_type).into())?;
+        }
+        for s in self.strings() {
+            writer.write_all(s.as_bytes())?;
+            writer.write_u8(0)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_iinf() {
+        let src_box = IinfBox {
+            version: 0,
+            flags: 0,
+            entries: vec![
+                InfeBox {
+                    version: 2,
+                    item_id: 1,
+                    item_type: str::parse("hvc1").unwrap(),
+                    item_name: String::from("Image"),
+                    ..Default::default()
+                },
+                InfeBox {
+                    version: 3,
+                    item_id: 0x10000,
+                    item_type: str::parse("hvc1").unwrap(),
+                    ..Default::default()
+                },
+                InfeBox {
+                    version: 2,
+                    item_id: 3,
+                    item_type: str::parse("mime").unwrap(),
+                    item_name: String::from("XMP"),
+                    content_type: Some(String::from("application/rdf+xml")),
+                    ..Default::default()
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::IinfBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = IinfBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.item(0x10000).unwrap().item_type.to_string(), "hvc1");
+    }
+}
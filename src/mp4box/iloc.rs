@@ -0,0 +1,315 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct IlocBox {
+    pub version: u8,
+    pub flags: u32,
+
+    /// Field sizes in bytes, each one of 0, 4 or 8.
+    pub offset_size: u8,
+    pub length_size: u8,
+    pub base_offset_size: u8,
+    pub index_size: u8,
+
+    pub items: Vec<IlocItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct IlocItem {
+    pub item_id: u32,
+    pub construction_method: u8,
+    pub data_reference_index: u16,
+    pub base_offset: u64,
+    pub extents: Vec<IlocExtent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct IlocExtent {
+    pub index: u64,
+    pub offset: u64,
+    pub length: u64,
+}
+
+impl IlocItem {
+    /// Data is located by file offset.
+    pub const CONSTRUCTION_FILE_OFFSET: u8 = 0;
+    /// Data is located in the idat box of the same meta box.
+    pub const CONSTRUCTION_IDAT_OFFSET: u8 = 1;
+}
+
+impl IlocBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::IlocBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 2;
+        size += if self.version < 2 { 2 } else { 4 };
+        for item in self.items.iter() {
+            size += if self.version < 2 { 2 } else { 4 };
+            if self.version == 1 || self.version == 2 {
+                size += 2;
+            }
+            size += 2 + self.base_offset_size as u64 + 2;
+            for _ in item.extents.iter() {
+                if (self.version == 1 || self.version == 2) && self.index_size > 0 {
+                    size += self.index_size as u64;
+                }
+                size += self.offset_size as u64 + self.length_size as u64;
+            }
+        }
+        size
+    }
+
+    pub fn item(&self, item_id: u32) -> Option<&IlocItem> {
+        self.items.iter().find(|item| item.item_id == item_id)
+    }
+}
+
+impl Mp4Box for IlocBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("items={}", self.items.len());
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+fn read_sized<R: Read>(reader: &mut R, size: u8) -> Result<u64> {
+    match size {
+        0 => Ok(0),
+        4 => Ok(reader.read_u32::<BigEndian>()? as u64),
+        8 => Ok(reader.read_u64::<BigEndian>()?),
+        _ => Err(Error::InvalidData("invalid iloc field size")),
+    }
+}
+
+#[cfg(feature = "std")]
+fn write_sized<W: Write>(writer: &mut W, size: u8, value: u64) -> Result<()> {
+    match size {
+        0 => {}
+        4 => writer.write_u32::<BigEndian>(value as u32)?,
+        8 => writer.write_u64::<BigEndian>(value)?,
+        _ => return Err(Error::InvalidData("invalid iloc field size")),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for IlocBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        if version > 2 {
+            return Err(Error::InvalidData("unsupported iloc version"));
+        }
+
+        let b = reader.read_u8()?;
+        let offset_size = b >> 4;
+        let length_size = b & 0x0F;
+        let b = reader.read_u8()?;
+        let base_offset_size = b >> 4;
+        let index_size = if version == 1 || version == 2 { b & 0x0F } else { 0 };
+
+        let item_count = if version < 2 {
+            reader.read_u16::<BigEndian>()? as u32
+        } else {
+            reader.read_u32::<BigEndian>()?
+        };
+
+        let mut items = Vec::with_capacity(item_count as usize);
+        for _ in 0..item_count {
+            let item_id = if version < 2 {
+                reader.read_u16::<BigEndian>()? as u32
+            } else {
+                reader.read_u32::<BigEndian>()?
+            };
+            let construction_method = if version == 1 || version == 2 {
+                (reader.read_u16::<BigEndian>()? & 0x0F) as u8
+            } else {
+                0
+            };
+            let data_reference_index = reader.read_u16::<BigEndian>()?;
+            let base_offset = read_sized(reader, base_offset_size)?;
+
+            let extent_count = reader.read_u16::<BigEndian>()?;
+            let mut extents = Vec::with_capacity(extent_count as usize);
+            for _ in 0..extent_count {
+                let index = if (version == 1 || version == 2) && index_size > 0 {
+                    read_sized(reader, index_size)?
+                } else {
+                    0
+                };
+                let offset = read_sized(reader, offset_size)?;
+                let length = read_sized(reader, length_size)?;
+                extents.push(IlocExtent {
+                    index,
+                    offset,
+                    length,
+                });
+            }
+
+            items.push(IlocItem {
+                item_id,
+                construction_method,
+                data_reference_index,
+                base_offset,
+                extents,
+            });
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(IlocBox {
+            version,
+            flags,
+            offset_size,
+            length_size,
+            base_offset_size,
+            index_size,
+            items,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for IlocBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u8(self.offset_size << 4 | self.length_size)?;
+        let index_size = if self.version == 1 || self.version == 2 {
+            self.index_size
+        } else {
+            0
+        };
+        writer.write_u8(self.base_offset_size << 4 | index_size)?;
+
+        if self.version < 2 {
+            writer.write_u16::<BigEndian>(self.items.len() as u16)?;
+        } else {
+            writer.write_u32::<BigEndian>(self.items.len() as u32)?;
+        }
+
+        for item in self.items.iter() {
+            if self.version < 2 {
+                writer.write_u16::<BigEndian>(item.item_id as u16)?;
+            } else {
+                writer.write_u32::<BigEndian>(item.item_id)?;
+            }
+            if self.version == 1 || self.version == 2 {
+                writer.write_u16::<BigEndian>(item.construction_method as u16 & 0x0F)?;
+            }
+            writer.write_u16::<BigEndian>(item.data_reference_index)?;
+            write_sized(writer, self.base_offset_size, item.base_offset)?;
+
+            writer.write_u16::<BigEndian>(item.extents.len() as u16)?;
+            for extent in item.extents.iter() {
+                if index_size > 0 {
+                    write_sized(writer, index_size, extent.index)?;
+                }
+                write_sized(writer, self.offset_size, extent.offset)?;
+                write_sized(writer, self.length_size, extent.length)?;
+            }
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    fn test_iloc(src_box: IlocBox) {
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::IlocBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = IlocBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_iloc_v0() {
+        test_iloc(IlocBox {
+            version: 0,
+            flags: 0,
+            offset_size: 4,
+            length_size: 4,
+            base_offset_size: 0,
+            index_size: 0,
+            items: vec![IlocItem {
+                item_id: 1,
+                construction_method: 0,
+                data_reference_index: 0,
+                base_offset: 0,
+                extents: vec![IlocExtent {
+                    index: 0,
+                    offset: 1234,
+                    length: 5678,
+                }],
+            }],
+        });
+    }
+
+    #[test]
+    fn test_iloc_v1() {
+        test_iloc(IlocBox {
+            version: 1,
+            flags: 0,
+            offset_size: 8,
+            length_size: 4,
+            base_offset_size: 4,
+            index_size: 4,
+            items: vec![
+                IlocItem {
+                    item_id: 1,
+                    construction_method: IlocItem::CONSTRUCTION_IDAT_OFFSET,
+                    data_reference_index: 0,
+                    base_offset: 16,
+                    extents: vec![IlocExtent {
+                        index: 1,
+                        offset: 0,
+                        length: 100,
+                    }],
+                },
+                IlocItem {
+                    item_id: 2,
+                    construction_method: 0,
+                    data_reference_index: 0,
+                    base_offset: 0,
+                    extents: vec![],
+                },
+            ],
+        });
+    }
+}
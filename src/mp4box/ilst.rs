@@ -0,0 +1,403 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+/// iTunes-style metadata item list, found in moov/udta/meta.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct IlstBox {
+    pub items: Vec<IlstItem>,
+}
+
+/// A metadata item, e.g. ©nam or covr, with the payload of its data box.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct IlstItem {
+    pub key: FourCC,
+
+    /// Well-known type indicator of the data box, e.g. 1 for UTF-8.
+    pub data_type: u32,
+    pub locale: u32,
+
+    #[serde(skip_serializing)]
+    pub data: Vec<u8>,
+
+    /// Children other than the data box, kept as is and written in front
+    /// of it: the mean and name boxes of a freeform "----" item.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra_boxes: Vec<RawBox>,
+}
+
+impl IlstItem {
+    pub const DATA_TYPE_UTF8: u32 = 1;
+    pub const DATA_TYPE_JPEG: u32 = 13;
+    pub const DATA_TYPE_PNG: u32 = 14;
+    pub const DATA_TYPE_BMP: u32 = 27;
+
+    pub const KEY_NAME: [u8; 4] = [0xA9, b'n', b'a', b'm'];
+    pub const KEY_DAY: [u8; 4] = [0xA9, b'd', b'a', b'y'];
+    pub const KEY_COVER: [u8; 4] = *b"covr";
+    pub const KEY_FREEFORM: [u8; 4] = *b"----";
+
+    pub fn text(key: [u8; 4], text: &str) -> Self {
+        IlstItem {
+            key: key.into(),
+            data_type: IlstItem::DATA_TYPE_UTF8,
+            locale: 0,
+            data: text.as_bytes().to_vec(),
+            extra_boxes: Vec::new(),
+        }
+    }
+
+    /// Returns the payload as a string if the data box holds UTF-8 text.
+    pub fn as_text(&self) -> Option<&str> {
+        if self.data_type == IlstItem::DATA_TYPE_UTF8 {
+            core::str::from_utf8(&self.data).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the image format from the data type, falling back to the
+    /// magic bytes for writers that leave the type as 0 (binary).
+    pub fn image_format(&self) -> ImageFormat {
+        match self.data_type {
+            IlstItem::DATA_TYPE_JPEG => ImageFormat::Jpeg,
+            IlstItem::DATA_TYPE_PNG => ImageFormat::Png,
+            IlstItem::DATA_TYPE_BMP => ImageFormat::Bmp,
+            _ if self.data.starts_with(&[0xFF, 0xD8, 0xFF]) => ImageFormat::Jpeg,
+            _ if self.data.starts_with(b"\x89PNG") => ImageFormat::Png,
+            _ if self.data.starts_with(b"BM") => ImageFormat::Bmp,
+            _ => ImageFormat::Unknown,
+        }
+    }
+
+    fn data_size(&self) -> u64 {
+        HEADER_SIZE + 8 + self.data.len() as u64
+    }
+
+    fn size(&self) -> u64 {
+        HEADER_SIZE
+            + self.extra_boxes.iter().map(|raw| raw.get_size()).sum::<u64>()
+            + self.data_size()
+    }
+}
+
+impl IlstBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::IlstBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + self.items.iter().map(|item| item.size()).sum::<u64>()
+    }
+
+    pub fn item(&self, key: [u8; 4]) -> Option<&IlstItem> {
+        self.items.iter().find(|item| item.key.value == key)
+    }
+
+    /// Returns the covr image, if there is one.
+    pub fn cover_art(&self) -> Option<CoverArt> {
+        let item = self.item(IlstItem::KEY_COVER)?;
+        Some(CoverArt {
+            format: item.image_format(),
+            bytes: item.data.clone(),
+        })
+    }
+
+    /// Parses the ©day recording date.
+    #[cfg(feature = "std")]
+    pub fn creation_date(&self) -> Option<SystemTime> {
+        parse_iso8601(self.item(IlstItem::KEY_DAY)?.as_text()?)
+    }
+}
+
+impl Mp4Box for IlstBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("item_count={}", self.items.len());
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for IlstBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let mut items = Vec::new();
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current < end {
+            // Each item is a box named by its key, holding a data box.
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+            let item_end = current + s;
+
+            // Only the first data box is kept; later ones, holding further
+            // values of the item, are dropped.
+            let mut item = None;
+            let mut extra_boxes = Vec::new();
+            let mut item_current = reader.stream_position()?;
+            while item_current + HEADER_SIZE <= item_end {
+                let header = BoxHeader::read(reader)?;
+                let BoxHeader { name: child, size: cs } = header;
+                if cs < HEADER_SIZE || item_current + cs > item_end {
+                    return Err(Error::InvalidData("ilst item child exceeds item"));
+                }
+                if child == BoxType::DataBox && cs >= HEADER_SIZE + 8 && item.is_none() {
+                    let data_type = reader.read_u32::<BigEndian>()?;
+                    let locale = reader.read_u32::<BigEndian>()?;
+                    let mut data = vec![0u8; (cs - HEADER_SIZE - 8) as usize];
+                    reader.read_exact(&mut data)?;
+                    item = Some(IlstItem {
+                        key: name.into(),
+                        data_type,
+                        locale,
+                        data,
+                        extra_boxes: Vec::new(),
+                    });
+                } else if child != BoxType::DataBox {
+                    extra_boxes.push(RawBox::read(reader, &header)?);
+                } else {
+                    skip_box(reader, cs)?;
+                }
+                item_current = reader.stream_position()?;
+            }
+            if let Some(mut item) = item {
+                item.extra_boxes = extra_boxes;
+                items.push(item);
+            }
+
+            skip_bytes_to(reader, item_end)?;
+            current = reader.stream_position()?;
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(IlstBox { items })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for IlstBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        for item in self.items.iter() {
+            BoxHeader::new(BoxType::from(u32::from(item.key)), item.size()).write(writer)?;
+            for raw in item.extra_boxes.iter() {
+                raw.write(writer)?;
+            }
+            BoxHeader::new(BoxType::DataBox, item.data_size()).write(writer)?;
+            writer.write_u32::<BigEndian>(item.data_type)?;
+            writer.write_u32::<BigEndian>(item.locale)?;
+            writer.write_all(&item.data)?;
+        }
+
+        Ok(size)
+    }
+}
+
+/// Parses the ISO 8601 dates used by ©day: a year, a date, or a date and
+/// time with an optional UTC offset, e.g. "2023-06-15T10:30:00Z".
+#[cfg(feature = "std")]
+pub(crate) fn parse_iso8601(s: &str) -> Option<SystemTime> {
+    let s = s.trim();
+    let (date, time) = match s.find('T') {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+
+    let mut parts = date.split('-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next().map_or(Some(1), |m| m.parse().ok())?;
+    let day: u32 = parts.next().map_or(Some(1), |d| d.parse().ok())?;
+    if parts.next().is_some()
+        || !(0..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+    {
+        return None;
+    }
+
+    let mut seconds = days_from_civil(year, month, day) * 86400;
+    if let Some(time) = time {
+        let (clock, offset) = match time.find(['Z', '+', '-']) {
+            Some(i) => (&time[..i], &time[i..]),
+            None => (time, ""),
+        };
+        let mut fields = clock.split(':');
+        let hour: i64 = fields.next()?.parse().ok()?;
+        let minute: i64 = fields.next().map_or(Some(0), |m| m.parse().ok())?;
+        // Fractional seconds are dropped.
+        let second: i64 = fields
+            .next()
+            .map_or(Some(0), |s| s.split('.').next()?.parse().ok())?;
+        if !(0..=24).contains(&hour) || !(0..=59).contains(&minute) || !(0..=60).contains(&second) {
+            return None;
+        }
+        seconds += hour * 3600 + minute * 60 + second;
+
+        if offset.len() > 1 {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let digits: String = offset[1..].chars().filter(|c| *c != ':').collect();
+            if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let hours: i64 = digits[..2].parse().ok()?;
+            let minutes: i64 = digits[2..].parse().ok()?;
+            seconds -= sign * (hours * 3600 + minutes * 60);
+        }
+    }
+
+    if seconds >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(seconds as u64))
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(seconds.unsigned_abs()))
+    }
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_ilst() {
+        let src_box = IlstBox {
+            items: vec![
+                IlstItem::text(IlstItem::KEY_NAME, "Title"),
+                IlstItem::text(IlstItem::KEY_DAY, "2023-06-15T10:30:00Z"),
+            ],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::IlstBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = IlstBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.item(IlstItem::KEY_NAME).unwrap().as_text(), Some("Title"));
+        assert_eq!(
+            dst_box.creation_date(),
+            Some(UNIX_EPOCH + Duration::from_secs(1686825000))
+        );
+    }
+
+    #[test]
+    fn test_cover_art() {
+        let jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+        let src_box = IlstBox {
+            items: vec![IlstItem {
+                key: IlstItem::KEY_COVER.into(),
+                data_type: IlstItem::DATA_TYPE_JPEG,
+                locale: 0,
+                data: jpeg.clone(),
+                extra_boxes: Vec::new(),
+            }],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = IlstBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(
+            dst_box.cover_art(),
+            Some(CoverArt { format: ImageFormat::Jpeg, bytes: jpeg })
+        );
+
+        // A PNG stored with the binary data type is told apart by its magic.
+        let png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let ilst = IlstBox {
+            items: vec![IlstItem {
+                key: IlstItem::KEY_COVER.into(),
+                data_type: 0,
+                locale: 0,
+                data: png.clone(),
+                extra_boxes: Vec::new(),
+            }],
+        };
+        assert_eq!(
+            ilst.cover_art(),
+            Some(CoverArt { format: ImageFormat::Png, bytes: png })
+        );
+        assert_eq!(IlstBox::default().cover_art(), None);
+    }
+
+    #[test]
+    fn test_parse_iso8601() {
+        let expected = UNIX_EPOCH + Duration::from_secs(1686825000);
+        assert_eq!(parse_iso8601("2023-06-15T10:30:00Z"), Some(expected));
+        assert_eq!(parse_iso8601("2023-06-15T12:30:00+02:00"), Some(expected));
+        assert_eq!(parse_iso8601("2023-06-15T10:30:00.250Z"), Some(expected));
+        assert_eq!(
+            parse_iso8601("2023"),
+            Some(UNIX_EPOCH + Duration::from_secs(1672531200))
+        );
+        assert_eq!(parse_iso8601("June 2023"), None);
+        assert_eq!(parse_iso8601("2023-06-15T10:30:00+1é1"), None);
+        assert_eq!(parse_iso8601("99999999999999999999"), None);
+        assert_eq!(parse_iso8601("2023-06-15T99999999999999999:00Z"), None);
+    }
+
+    #[test]
+    fn test_freeform_item() {
+        let mean = RawBox {
+            box_type: str::parse("mean").unwrap(),
+            data: b"\0\0\0\0com.apple.iTunes".to_vec(),
+        };
+        let name = RawBox {
+            box_type: str::parse("name").unwrap(),
+            data: b"\0\0\0\0iTunNORM".to_vec(),
+        };
+        let src_box = IlstBox {
+            items: vec![IlstItem {
+                key: IlstItem::KEY_FREEFORM.into(),
+                extra_boxes: vec![mean, name],
+                ..IlstItem::text(IlstItem::KEY_FREEFORM, " 00000000")
+            }],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = IlstBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
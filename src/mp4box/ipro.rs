@@ -0,0 +1,151 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+use crate::mp4box::sinf::SinfBox;
+
+/// Item protection, holding the protection schemes of the protected items
+/// of a meta box. Items refer to a scheme by its 1-based index, the
+/// item_protection_index of their infe.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct IproBox {
+    pub version: u8,
+    pub flags: u32,
+
+    #[serde(rename = "sinf")]
+    pub sinfs: Vec<SinfBox>,
+}
+
+impl IproBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::IproBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE + 2
+            + self.sinfs.iter().map(|sinf| sinf.box_size()).sum::<u64>()
+    }
+
+    /// Returns the scheme for an item_protection_index, where 0 means the
+    /// item is not protected.
+    pub fn sinf(&self, item_protection_index: u16) -> Option<&SinfBox> {
+        let index = (item_protection_index as usize).checked_sub(1)?;
+        self.sinfs.get(index)
+    }
+}
+
+impl Mp4Box for IproBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("protection_count={}", self.sinfs.len());
+        Ok(s)
+    }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        self.sinfs.iter().map(|sinf| sinf as &dyn Mp4Box).collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for IproBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let protection_count = reader.read_u16::<BigEndian>()?;
+
+        let mut sinfs = Vec::new();
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while sinfs.len() < protection_count as usize && current + HEADER_SIZE <= end {
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+
+            match name {
+                BoxType::SinfBox => {
+                    sinfs.push(SinfBox::read_box(reader, s)?);
+                }
+                _ => {
+                    skip_box(reader, s)?;
+                }
+            }
+            current = reader.stream_position()?;
+        }
+
+        skip_bytes_to(reader, end)?;
+
+        Ok(IproBox {
+            version,
+            flags,
+            sinfs,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for IproBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        writer.write_u16::<BigEndian>(self.sinfs.len() as u16)?;
+        for sinf in self.sinfs.iter() {
+            sinf.write_box(writer)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::schm::SchmBox;
+    use crate::mp4box::sinf::FrmaBox;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_ipro() {
+        let src_box = IproBox {
+            version: 0,
+            flags: 0,
+            sinfs: vec![SinfBox {
+                frma: FrmaBox {
+                    original_format: str::parse("hvc1").unwrap(),
+                },
+                schm: Some(SchmBox::new(EncryptionScheme::Cenc)),
+                schi: None,
+            }],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::IproBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = IproBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert!(dst_box.sinf(0).is_none());
+        assert_eq!(dst_box.sinf(1).unwrap().encryption_scheme(), Some(EncryptionScheme::Cenc));
+        assert!(dst_box.sinf(2).is_none());
+    }
+}
@@ -0,0 +1,208 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+/// Item references of a meta box, e.g. the tiles a HEIF grid image is
+/// derived from. Version 0 stores 16-bit item IDs, version 1 32-bit ones.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct IrefBox {
+    pub version: u8,
+    pub flags: u32,
+
+    pub references: Vec<ItemReference>,
+}
+
+/// One SingleItemTypeReferenceBox: `from_item_id` refers to each of
+/// `to_item_ids`, in order, with the meaning given by `reference_type`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ItemReference {
+    pub reference_type: FourCC,
+    pub from_item_id: u32,
+    pub to_item_ids: Vec<u32>,
+}
+
+impl ItemReference {
+    /// The item is a derived image, such as a grid or overlay, of the
+    /// referenced images.
+    pub const DERIVED_IMAGE: FourCC = FourCC { value: *b"dimg" };
+    /// The item is a thumbnail of the referenced image.
+    pub const THUMBNAIL: FourCC = FourCC { value: *b"thmb" };
+    /// The item, such as Exif metadata, describes the referenced item.
+    pub const CONTENT_DESCRIBES: FourCC = FourCC { value: *b"cdsc" };
+    /// The item is an auxiliary image, such as an alpha plane.
+    pub const AUXILIARY: FourCC = FourCC { value: *b"auxl" };
+
+    fn size(&self, id_size: u64) -> u64 {
+        HEADER_SIZE + id_size + 2 + id_size * self.to_item_ids.len() as u64
+    }
+}
+
+impl IrefBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::IrefBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let id_size = self.id_size();
+        HEADER_SIZE + HEADER_EXT_SIZE
+            + self.references.iter().map(|reference| reference.size(id_size)).sum::<u64>()
+    }
+
+    fn id_size(&self) -> u64 {
+        if self.version == 0 { 2 } else { 4 }
+    }
+
+    /// Returns the items `from_item_id` refers to with `reference_type`, in
+    /// order, e.g. the tiles of a grid for [`ItemReference::DERIVED_IMAGE`].
+    pub fn references_from(&self, from_item_id: u32, reference_type: FourCC) -> Vec<u32> {
+        self.references
+            .iter()
+            .filter(|r| r.from_item_id == from_item_id && r.reference_type == reference_type)
+            .flat_map(|r| r.to_item_ids.iter().copied())
+            .collect()
+    }
+}
+
+impl Mp4Box for IrefBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("reference_count={}", self.references.len());
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for IrefBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let read_id = |reader: &mut R| -> Result<u32> {
+            if version == 0 {
+                Ok(reader.read_u16::<BigEndian>()? as u32)
+            } else {
+                Ok(reader.read_u32::<BigEndian>()?)
+            }
+        };
+
+        let mut references = Vec::new();
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current + HEADER_SIZE <= end {
+            let header = BoxHeader::read(reader)?;
+            let reference_start = box_start(reader)?;
+
+            let from_item_id = read_id(reader)?;
+            let reference_count = reader.read_u16::<BigEndian>()?;
+            let mut to_item_ids = Vec::with_capacity(reference_count as usize);
+            for _ in 0..reference_count {
+                to_item_ids.push(read_id(reader)?);
+            }
+            references.push(ItemReference {
+                reference_type: FourCC::from(header.name),
+                from_item_id,
+                to_item_ids,
+            });
+
+            skip_bytes_to(reader, reference_start + header.size)?;
+            current = reader.stream_position()?;
+        }
+
+        skip_bytes_to(reader, end)?;
+
+        Ok(IrefBox {
+            version,
+            flags,
+            references,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for IrefBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        let id_size = self.id_size();
+        let write_id = |writer: &mut W, item_id: u32| -> Result<()> {
+            if self.version == 0 {
+                let item_id: u16 = item_id.try_into()
+                    .map_err(|_| Error::InvalidData("iref item id needs version 1"))?;
+                writer.write_u16::<BigEndian>(item_id)?;
+            } else {
+                writer.write_u32::<BigEndian>(item_id)?;
+            }
+            Ok(())
+        };
+        for reference in self.references.iter() {
+            let box_type = BoxType::from(u32::from(&reference.reference_type));
+            BoxHeader::new(box_type, reference.size(id_size)).write(writer)?;
+            write_id(writer, reference.from_item_id)?;
+            writer.write_u16::<BigEndian>(reference.to_item_ids.len() as u16)?;
+            for item_id in reference.to_item_ids.iter() {
+                write_id(writer, *item_id)?;
+            }
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_iref() {
+        for version in 0..=1 {
+            let src_box = IrefBox {
+                version,
+                flags: 0,
+                references: vec![
+                    ItemReference {
+                        reference_type: ItemReference::DERIVED_IMAGE,
+                        from_item_id: 1,
+                        to_item_ids: vec![2, 3, 4, 5],
+                    },
+                    ItemReference {
+                        reference_type: ItemReference::THUMBNAIL,
+                        from_item_id: 6,
+                        to_item_ids: vec![1],
+                    },
+                ],
+            };
+            let mut buf = Vec::new();
+            src_box.write_box(&mut buf).unwrap();
+            assert_eq!(buf.len(), src_box.box_size() as usize);
+
+            let mut reader = Cursor::new(&buf);
+            let header = BoxHeader::read(&mut reader).unwrap();
+            assert_eq!(header.name, BoxType::IrefBox);
+            assert_eq!(src_box.box_size(), header.size);
+
+            let dst_box = IrefBox::read_box(&mut reader, header.size).unwrap();
+            assert_eq!(src_box, dst_box);
+            assert_eq!(dst_box.references_from(1, ItemReference::DERIVED_IMAGE), vec![2, 3, 4, 5]);
+            assert!(dst_box.references_from(1, ItemReference::THUMBNAIL).is_empty());
+        }
+    }
+}
@@ -1,5 +1,7 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use std::char::{decode_utf16, REPLACEMENT_CHARACTER};
+use core::char::{decode_utf16, REPLACEMENT_CHARACTER};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -32,6 +34,11 @@ impl MdhdBox {
         size += 4;
         size
     }
+
+    /// Returns true if the duration is the all-ones "unknown" value.
+    pub fn is_duration_unknown(&self) -> bool {
+        is_unknown_duration(self.version, self.duration)
+    }
 }
 
 impl Default for MdhdBox {
@@ -68,6 +75,7 @@ impl Mp4Box for MdhdBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for MdhdBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -108,6 +116,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for MdhdBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for MdhdBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -137,7 +146,7 @@ impl<W: Write> WriteBox<&mut W> for MdhdBox {
     }
 }
 
-fn language_string(language: u16) -> String {
+pub(crate) fn language_string(language: u16) -> String {
     let mut lang: [u16; 3] = [0; 3];
 
     lang[0] = ((language >> 10) & 0x1F) + 0x60;
@@ -152,7 +161,7 @@ fn language_string(language: u16) -> String {
     return lang_str;
 }
 
-fn language_code(language: &str) -> u16 {
+pub(crate) fn language_code(language: &str) -> u16 {
     let mut lang = language.encode_utf16();
     let mut code = (lang.next().unwrap_or(0) & 0x1F) << 10;
     code += (lang.next().unwrap_or(0) & 0x1F) << 5;
@@ -160,7 +169,7 @@ fn language_code(language: &str) -> u16 {
     code
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
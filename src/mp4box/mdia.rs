@@ -1,3 +1,4 @@
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, SeekFrom, Write};
 use serde::{Serialize};
 
@@ -9,6 +10,10 @@ pub struct MdiaBox {
     pub mdhd: MdhdBox,
     pub hdlr: HdlrBox,
     pub minf: MinfBox,
+
+    /// Other children, e.g. elng, kept as is.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_boxes: Vec<RawBox>,
 }
 
 impl MdiaBox {
@@ -17,7 +22,11 @@ impl MdiaBox {
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + self.mdhd.box_size() + self.hdlr.box_size() + self.minf.box_size()
+        let mut size = HEADER_SIZE + self.mdhd.box_size() + self.hdlr.box_size() + self.minf.box_size();
+        for raw in self.unknown_boxes.iter() {
+            size += raw.get_size();
+        }
+        size
     }
 }
 
@@ -38,8 +47,13 @@ impl Mp4Box for MdiaBox {
         let s = format!("");
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        vec![&self.mdhd, &self.hdlr, &self.minf]
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for MdiaBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -47,6 +61,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for MdiaBox {
         let mut mdhd = None;
         let mut hdlr = None;
         let mut minf = None;
+        let mut unknown_boxes = Vec::new();
 
         let mut current = reader.seek(SeekFrom::Current(0))?;
         let end = start + size;
@@ -65,6 +80,9 @@ impl<R: Read + Seek> ReadBox<&mut R> for MdiaBox {
                 BoxType::MinfBox => {
                     minf = Some(MinfBox::read_box(reader, s)?);
                 }
+                _ if current + s <= end => {
+                    unknown_boxes.push(RawBox::read(reader, &header)?);
+                }
                 _ => {
                     // XXX warn!()
                     skip_box(reader, s)?;
@@ -84,16 +102,26 @@ impl<R: Read + Seek> ReadBox<&mut R> for MdiaBox {
             return Err(Error::BoxNotFound(BoxType::MinfBox));
         }
 
+        // Video and audio tracks can't be decoded without a sample entry,
+        // while some metadata tracks legitimately have none.
+        let handler_type = hdlr.as_ref().unwrap().handler_type.value;
+        let is_media = handler_type == *b"vide" || handler_type == *b"soun";
+        if is_media && minf.as_ref().unwrap().stbl.stsd.is_empty() {
+            return Err(Error::InvalidData("media track has no sample entry"));
+        }
+
         skip_bytes_to(reader, start + size)?;
 
         Ok(MdiaBox {
             mdhd: mdhd.unwrap(),
             hdlr: hdlr.unwrap(),
             minf: minf.unwrap(),
+            unknown_boxes,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for MdiaBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -102,6 +130,9 @@ impl<W: Write> WriteBox<&mut W> for MdiaBox {
         self.mdhd.write_box(writer)?;
         self.hdlr.write_box(writer)?;
         self.minf.write_box(writer)?;
+        for raw in self.unknown_boxes.iter() {
+            raw.write(writer)?;
+        }
 
         Ok(size)
     }
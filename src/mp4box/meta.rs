@@ -0,0 +1,388 @@
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+use crate::mp4box::{hdlr::HdlrBox, iinf::IinfBox, ilst::IlstBox, iloc::IlocBox, pitm::PitmBox};
+use crate::mp4box::{ipro::IproBox, iref::IrefBox, sinf::SinfBox};
+use crate::mp4box::xml::{BxmlBox, XmlBox};
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct MetaBox {
+    pub version: u8,
+    pub flags: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hdlr: Option<HdlrBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pitm: Option<PitmBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iloc: Option<IlocBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iref: Option<IrefBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipro: Option<IproBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iinf: Option<IinfBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ilst: Option<IlstBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub xml: Option<XmlBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bxml: Option<BxmlBox>,
+
+    /// Payload of the idat box, addressed by iloc construction method 1.
+    #[serde(skip_serializing)]
+    pub idat: Option<Vec<u8>>,
+}
+
+impl MetaBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::MetaBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE;
+        if let Some(ref hdlr) = self.hdlr {
+            size += hdlr.box_size();
+        }
+        if let Some(ref pitm) = self.pitm {
+            size += pitm.box_size();
+        }
+        if let Some(ref iloc) = self.iloc {
+            size += iloc.box_size();
+        }
+        if let Some(ref iref) = self.iref {
+            size += iref.box_size();
+        }
+        if let Some(ref ipro) = self.ipro {
+            size += ipro.box_size();
+        }
+        if let Some(ref iinf) = self.iinf {
+            size += iinf.box_size();
+        }
+        if let Some(ref ilst) = self.ilst {
+            size += ilst.box_size();
+        }
+        if let Some(ref xml) = self.xml {
+            size += xml.box_size();
+        }
+        if let Some(ref bxml) = self.bxml {
+            size += bxml.box_size();
+        }
+        if let Some(ref idat) = self.idat {
+            size += HEADER_SIZE + idat.len() as u64;
+        }
+        size
+    }
+
+    /// Returns true for the meta of an image file such as HEIF, whose
+    /// handler type is "pict".
+    pub fn is_image(&self) -> bool {
+        self.hdlr.as_ref().map(|hdlr| hdlr.handler_type) == Some(FourCC { value: *b"pict" })
+    }
+
+    /// Make `item_id` the primary item. The item must be listed in iloc.
+    pub fn set_primary_item(&mut self, item_id: u32) -> Result<()> {
+        let iloc = self.iloc.as_ref().ok_or(Error::BoxNotFound(BoxType::IlocBox))?;
+        if iloc.item(item_id).is_none() {
+            return Err(Error::InvalidData("item not found in iloc"));
+        }
+        let version = if item_id > u16::MAX as u32 { 1 } else { 0 };
+        self.pitm = Some(PitmBox {
+            version,
+            flags: 0,
+            item_id,
+        });
+        Ok(())
+    }
+
+    /// Returns the protection scheme of a protected item, looked up in ipro
+    /// by the item_protection_index of its infe.
+    pub fn item_protection(&self, item_id: u32) -> Option<&SinfBox> {
+        let infe = self.iinf.as_ref()?.item(item_id)?;
+        self.ipro.as_ref()?.sinf(infe.item_protection_index)
+    }
+}
+
+impl Mp4Box for MetaBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let mut s = String::new();
+        if let Some(ref hdlr) = self.hdlr {
+            s.push_str(&format!("handler_type={}", hdlr.handler_type));
+        }
+        if let Some(ref pitm) = self.pitm {
+            s.push_str(&format!(" primary_item={}", pitm.item_id));
+        }
+        Ok(s.trim_start().to_string())
+    }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = Vec::new();
+        if let Some(ref hdlr) = self.hdlr {
+            children.push(hdlr);
+        }
+        if let Some(ref pitm) = self.pitm {
+            children.push(pitm);
+        }
+        if let Some(ref iloc) = self.iloc {
+            children.push(iloc);
+        }
+        if let Some(ref iref) = self.iref {
+            children.push(iref);
+        }
+        if let Some(ref ipro) = self.ipro {
+            children.push(ipro);
+        }
+        if let Some(ref iinf) = self.iinf {
+            children.push(iinf);
+        }
+        if let Some(ref ilst) = self.ilst {
+            children.push(ilst);
+        }
+        if let Some(ref xml) = self.xml {
+            children.push(xml);
+        }
+        if let Some(ref bxml) = self.bxml {
+            children.push(bxml);
+        }
+        children
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for MetaBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        let mut hdlr = None;
+        let mut pitm = None;
+        let mut iloc = None;
+        let mut iref = None;
+        let mut ipro = None;
+        let mut iinf = None;
+        let mut ilst = None;
+        let mut xml = None;
+        let mut bxml = None;
+        let mut idat = None;
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current < end {
+            // Get box header.
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+
+            match name {
+                BoxType::HdlrBox => {
+                    hdlr = Some(HdlrBox::read_box(reader, s)?);
+                }
+                BoxType::PitmBox => {
+                    pitm = Some(PitmBox::read_box(reader, s)?);
+                }
+                BoxType::IlocBox => {
+                    iloc = Some(IlocBox::read_box(reader, s)?);
+                }
+                BoxType::IrefBox => {
+                    iref = Some(IrefBox::read_box(reader, s)?);
+                }
+                BoxType::IproBox => {
+                    ipro = Some(IproBox::read_box(reader, s)?);
+                }
+                BoxType::IinfBox => {
+                    iinf = Some(IinfBox::read_box(reader, s)?);
+                }
+                BoxType::IlstBox => {
+                    ilst = Some(IlstBox::read_box(reader, s)?);
+                }
+                BoxType::XmlBox => {
+                    xml = Some(XmlBox::read_box(reader, s)?);
+                }
+                BoxType::BxmlBox => {
+                    bxml = Some(BxmlBox::read_box(reader, s)?);
+                }
+                BoxType::IdatBox => {
+                    if s < HEADER_SIZE {
+                        return Err(Error::InvalidData("invalid idat size"));
+                    }
+                    if current + s > end {
+                        return Err(Error::InvalidData("idat extends past the meta"));
+                    }
+                    let mut buf = vec![0u8; (s - HEADER_SIZE) as usize];
+                    reader.read_exact(&mut buf)?;
+                    idat = Some(buf);
+                }
+                _ => {
+                    // XXX warn!()
+                    skip_box(reader, s)?;
+                }
+            }
+
+            current = reader.stream_position()?;
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(MetaBox {
+            version,
+            flags,
+            hdlr,
+            pitm,
+            iloc,
+            iref,
+            ipro,
+            iinf,
+            ilst,
+            xml,
+            bxml,
+            idat,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for MetaBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        if let Some(ref hdlr) = self.hdlr {
+            hdlr.write_box(writer)?;
+        }
+        if let Some(ref pitm) = self.pitm {
+            pitm.write_box(writer)?;
+        }
+        if let Some(ref iloc) = self.iloc {
+            iloc.write_box(writer)?;
+        }
+        if let Some(ref iref) = self.iref {
+            iref.write_box(writer)?;
+        }
+        if let Some(ref ipro) = self.ipro {
+            ipro.write_box(writer)?;
+        }
+        if let Some(ref iinf) = self.iinf {
+            iinf.write_box(writer)?;
+        }
+        if let Some(ref ilst) = self.ilst {
+            ilst.write_box(writer)?;
+        }
+        if let Some(ref xml) = self.xml {
+            xml.write_box(writer)?;
+        }
+        if let Some(ref bxml) = self.bxml {
+            bxml.write_box(writer)?;
+        }
+        if let Some(ref idat) = self.idat {
+            BoxHeader::new(BoxType::IdatBox, HEADER_SIZE + idat.len() as u64).write(writer)?;
+            writer.write_all(idat)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::iloc::{IlocExtent, IlocItem};
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_meta() {
+        let src_box = MetaBox {
+            version: 0,
+            flags: 0,
+            hdlr: Some(HdlrBox {
+                version: 0,
+                flags: 0,
+                handler_type: str::parse::<FourCC>("pict").unwrap(),
+                name: String::new(),
+            }),
+            pitm: Some(PitmBox {
+                version: 0,
+                flags: 0,
+                item_id: 1,
+            }),
+            iloc: Some(IlocBox {
+                version: 1,
+                flags: 0,
+                offset_size: 4,
+                length_size: 4,
+                base_offset_size: 0,
+                index_size: 0,
+                items: vec![IlocItem {
+                    item_id: 1,
+                    construction_method: IlocItem::CONSTRUCTION_IDAT_OFFSET,
+                    data_reference_index: 0,
+                    base_offset: 0,
+                    extents: vec![IlocExtent {
+                        index: 0,
+                        offset: 0,
+                        length: 4,
+                    }],
+                }],
+            }),
+            iref: None,
+            ipro: None,
+            iinf: None,
+            ilst: None,
+            xml: None,
+            bxml: None,
+            idat: Some(vec![1, 2, 3, 4]),
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::MetaBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = MetaBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_meta_idat_too_large() {
+        let src_box = MetaBox {
+            idat: Some(vec![1, 2, 3, 4]),
+            ..MetaBox::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        // Claim a 4 GiB idat.
+        let idat = buf.windows(4).position(|w| w == b"idat").unwrap() - 4;
+        buf[idat..idat + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert!(MetaBox::read_box(&mut reader, header.size).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, SeekFrom, Write};
 use serde::{Serialize};
 
@@ -14,6 +15,10 @@ pub struct MinfBox {
 
     pub dinf: DinfBox,
     pub stbl: StblBox,
+
+    /// Other children, e.g. the hmhd or nmhd media header, kept as is.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_boxes: Vec<RawBox>,
 }
 
 impl MinfBox {
@@ -31,6 +36,9 @@ impl MinfBox {
         }
         size += self.dinf.box_size();
         size += self.stbl.box_size();
+        for raw in self.unknown_boxes.iter() {
+            size += raw.get_size();
+        }
         size
     }
 }
@@ -52,8 +60,22 @@ impl Mp4Box for MinfBox {
         let s = format!("");
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = Vec::new();
+        if let Some(ref vmhd) = self.vmhd {
+            children.push(vmhd);
+        }
+        if let Some(ref smhd) = self.smhd {
+            children.push(smhd);
+        }
+        children.push(&self.dinf);
+        children.push(&self.stbl);
+        children
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for MinfBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -62,6 +84,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for MinfBox {
         let mut smhd = None;
         let mut dinf = None;
         let mut stbl = None;
+        let mut unknown_boxes = Vec::new();
 
         let mut current = reader.seek(SeekFrom::Current(0))?;
         let end = start + size;
@@ -83,6 +106,9 @@ impl<R: Read + Seek> ReadBox<&mut R> for MinfBox {
                 BoxType::StblBox => {
                     stbl = Some(StblBox::read_box(reader, s)?);
                 }
+                _ if current + s <= end => {
+                    unknown_boxes.push(RawBox::read(reader, &header)?);
+                }
                 _ => {
                     // XXX warn!()
                     skip_box(reader, s)?;
@@ -99,17 +125,29 @@ impl<R: Read + Seek> ReadBox<&mut R> for MinfBox {
             return Err(Error::BoxNotFound(BoxType::StblBox));
         }
 
+        let dinf = dinf.unwrap();
+        let stbl = stbl.unwrap();
+
+        // data_reference_index is a 1-based index into the dref entries.
+        if let Some(index) = stbl.stsd.data_reference_index() {
+            if index == 0 || index as usize > dinf.dref.entries.len() {
+                return Err(Error::InvalidData("data_reference_index out of range"));
+            }
+        }
+
         skip_bytes_to(reader, start + size)?;
 
         Ok(MinfBox {
             vmhd,
             smhd,
-            dinf: dinf.unwrap(),
-            stbl: stbl.unwrap(),
+            dinf,
+            stbl,
+            unknown_boxes,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for MinfBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -123,6 +161,9 @@ impl<W: Write> WriteBox<&mut W> for MinfBox {
         }
         self.dinf.write_box(writer)?;
         self.stbl.write_box(writer)?;
+        for raw in self.unknown_boxes.iter() {
+            raw.write(writer)?;
+        }
 
         Ok(size)
     }
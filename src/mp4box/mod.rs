@@ -15,6 +15,11 @@
 //!     mvhd
 //!     trak
 //!         tkhd
+//!         tref
+//!         tapt
+//!             clef
+//!             prof
+//!             enof
 //!         mdia
 //!             mdhd
 //!             hdlr
@@ -23,8 +28,26 @@
 //!                     stsd
 //!                         avc1
 //!                         hev1
+//!                         hvc1
+//!                             colr
+//!                         av01
+//!                             av1C
+//!                             colr
 //!                         mp4a
+//!                             wave
+//!                                 esds
+//!                             chnl
+//!                         samr
+//!                         sawb
+//!                             damr
 //!                         tx3g
+//!                         encv
+//!                         enca
+//!                             sinf
+//!                                 frma
+//!                                 schm
+//!                                 schi
+//!                                     tenc
 //!                     stts
 //!                     stsc
 //!                     stsz
@@ -32,33 +55,76 @@
 //!                     stco
 //!                     co64
 //!                     ctts
+//!                     cslg
+//!                     padb
+//!                     sgpd
+//!                     sbgp
 //!                 dinf
 //!                     dref
 //!                 smhd
 //!                 vmhd
 //!         edts
 //!             elst
+//!     udta
+//!         cprt
+//!         yrrc
+//!         titl
+//!         dscp
+//!         perf
+//!         auth
+//!         gnre
+//!         meta
+//!             hdlr
+//!             ilst
+//!             xml
+//!             bxml
 //!     mvex
 //!         mehd
 //!         trex
+//!         trep
+//! meta
+//!     hdlr
+//!     pitm
+//!     iloc
+//!     iref
+//!     ipro
+//!         sinf
+//!     iinf
+//!         infe
+//!     idat
+//!     xml
+//!     bxml
+//! sidx
+//! ssix
 //! emsg
 //! moof
 //!     mfhd
 //!     traf
 //!         tfhd
+//!         tfdt
 //!         trun
 //! mdat
 //! free
 //! 
 
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use std::convert::TryInto;
+#[cfg(feature = "std")]
+use core::convert::TryInto;
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, SeekFrom, Write};
+use serde::Serialize;
 
 use crate::*;
 
+pub(crate) mod asset;
+pub(crate) mod av01;
 pub(crate) mod avc1;
+pub(crate) mod chnl;
 pub(crate) mod co64;
+pub(crate) mod colr;
+pub(crate) mod cprt;
+pub(crate) mod cslg;
 pub(crate) mod ctts;
 pub(crate) mod dinf;
 pub(crate) mod edts;
@@ -66,19 +132,35 @@ pub(crate) mod elst;
 pub(crate) mod ftyp;
 pub(crate) mod hev1;
 pub(crate) mod hdlr;
+pub(crate) mod iinf;
+pub(crate) mod ilst;
+pub(crate) mod iloc;
+pub(crate) mod iref;
+pub(crate) mod ipro;
 pub(crate) mod mdhd;
 pub(crate) mod mdia;
+pub(crate) mod meta;
 pub(crate) mod minf;
 pub(crate) mod moov;
 pub(crate) mod mvex;
 pub(crate) mod mehd;
 pub(crate) mod trex;
+pub(crate) mod trep;
 pub(crate) mod emsg;
 pub(crate) mod moof;
 pub(crate) mod mp4a;
 pub(crate) mod mvhd;
+pub(crate) mod padb;
+pub(crate) mod pitm;
 pub(crate) mod mfhd;
+pub(crate) mod samr;
+pub(crate) mod sbgp;
+pub(crate) mod schm;
+pub(crate) mod sgpd;
+pub(crate) mod sidx;
+pub(crate) mod sinf;
 pub(crate) mod smhd;
+pub(crate) mod ssix;
 pub(crate) mod stbl;
 pub(crate) mod stco;
 pub(crate) mod stsc;
@@ -86,15 +168,22 @@ pub(crate) mod stsd;
 pub(crate) mod stss;
 pub(crate) mod stsz;
 pub(crate) mod stts;
+pub(crate) mod tapt;
+pub(crate) mod tenc;
 pub(crate) mod tkhd;
 pub(crate) mod tfhd;
+pub(crate) mod tfdt;
 pub(crate) mod trak;
 pub(crate) mod traf;
+pub(crate) mod tref;
 pub(crate) mod trun;
 pub(crate) mod tx3g;
+pub(crate) mod udta;
 pub(crate) mod vmhd;
 pub(crate) mod vp09;
 pub(crate) mod vpcc;
+pub(crate) mod xml;
+pub(crate) mod yrrc;
 
 pub use ftyp::FtypBox;
 pub use moov::MoovBox;
@@ -138,15 +227,51 @@ boxtype! {
     MvhdBox => 0x6d766864,
     MfhdBox => 0x6d666864,
     FreeBox => 0x66726565,
+    SkipBox => 0x736b6970,
     MdatBox => 0x6d646174,
     MoovBox => 0x6d6f6f76,
     MvexBox => 0x6d766578,
     MehdBox => 0x6d656864,
     TrexBox => 0x74726578,
+    TrepBox => 0x74726570,
     EmsgBox => 0x656d7367,
+    SidxBox => 0x73696478,
+    SsixBox => 0x73736978,
+    MetaBox => 0x6d657461,
+    PitmBox => 0x7069746d,
+    IlocBox => 0x696c6f63,
+    IdatBox => 0x69646174,
+    Av01Box => 0x61763031,
+    Av1CBox => 0x61763143,
+    CslgBox => 0x63736c67,
+    IrefBox => 0x69726566,
+    YrrcBox => 0x79727263,
+    TitlBox => 0x7469746c,
+    DscpBox => 0x64736370,
+    PerfBox => 0x70657266,
+    AuthBox => 0x61757468,
+    GnreBox => 0x676e7265,
+    IproBox => 0x6970726f,
+    ColrBox => 0x636f6c72,
+    SbgpBox => 0x73626770,
+    ChnlBox => 0x63686e6c,
+    XmlBox => 0x786d6c20,
+    BxmlBox => 0x62786d6c,
+    IinfBox => 0x69696e66,
+    InfeBox => 0x696e6665,
+    IlstBox => 0x696c7374,
+    DataBox => 0x64617461,
+    EncvBox => 0x656e6376,
+    EncaBox => 0x656e6361,
+    SinfBox => 0x73696e66,
+    FrmaBox => 0x66726d61,
+    SchmBox => 0x7363686d,
+    SchiBox => 0x73636869,
+    TencBox => 0x74656e63,
     MoofBox => 0x6d6f6f66,
     TkhdBox => 0x746b6864,
     TfhdBox => 0x74666864,
+    TfdtBox => 0x74666474,
     EdtsBox => 0x65647473,
     MdiaBox => 0x6d646961,
     ElstBox => 0x656c7374,
@@ -163,30 +288,56 @@ boxtype! {
     StszBox => 0x7374737A,
     StcoBox => 0x7374636F,
     Co64Box => 0x636F3634,
+    PadbBox => 0x70616462,
+    SgpdBox => 0x73677064,
     TrakBox => 0x7472616b,
     TrafBox => 0x74726166,
+    TrefBox => 0x74726566,
+    TaptBox => 0x74617074,
+    ClefBox => 0x636c6566,
+    ProfBox => 0x70726f66,
+    EnofBox => 0x656e6f66,
     TrunBox => 0x7472756E,
     UdtaBox => 0x75647461,
+    CprtBox => 0x63707274,
     DinfBox => 0x64696e66,
     DrefBox => 0x64726566,
     UrlBox  => 0x75726C20,
+    UrnBox  => 0x75726E20,
     SmhdBox => 0x736d6864,
     Avc1Box => 0x61766331,
     AvcCBox => 0x61766343,
     Hev1Box => 0x68657631,
     HvcCBox => 0x68766343,
+    Hvc1Box => 0x68766331,
     Mp4aBox => 0x6d703461,
     EsdsBox => 0x65736473,
+    WaveBox => 0x77617665,
+    SamrBox => 0x73616d72,
+    SawbBox => 0x73617762,
+    DamrBox => 0x64616d72,
     Tx3gBox => 0x74783367,
     VpccBox => 0x76706343,
     Vp09Box => 0x76703039
 }
 
-pub trait Mp4Box: Sized {
+pub trait Mp4Box {
     fn box_type(&self) -> BoxType;
     fn box_size(&self) -> u64;
     fn to_json(&self) -> Result<String>;
     fn summary(&self) -> Result<String>;
+
+    /// Returns the parsed child boxes of a container box in the order they
+    /// are written. Boxes without parsed children return an empty list.
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        Vec::new()
+    }
+
+    /// Iterates over `child_boxes`, so tools can walk any container, e.g.
+    /// moov, trak or stbl, without knowing its fields.
+    fn children(&self) -> alloc::vec::IntoIter<&dyn Mp4Box> {
+        self.child_boxes().into_iter()
+    }
 }
 
 pub trait ReadBox<T>: Sized {
@@ -209,6 +360,7 @@ impl BoxHeader {
     }
 
     // TODO: if size is 0, then this box is the last one in the file
+    #[cfg(feature = "std")]
     pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
         // Create and read to buf.
         let mut buf = [0u8; 8]; // 8 bytes for box header.
@@ -240,6 +392,7 @@ impl BoxHeader {
         }
     }
 
+    #[cfg(feature = "std")]
     pub fn write<W: Write>(&self, writer: &mut W) -> Result<u64> {
         if self.size > u32::MAX as u64 {
             writer.write_u32::<BigEndian>(1)?;
@@ -254,38 +407,79 @@ impl BoxHeader {
     }
 }
 
+#[cfg(feature = "std")]
 pub fn read_box_header_ext<R: Read>(reader: &mut R) -> Result<(u8, u32)> {
     let version = reader.read_u8()?;
-    let flags = reader.read_u24::<BigEndian>()?;
+    let flags = read_u24(reader)?;
     Ok((version, flags))
 }
 
+#[cfg(feature = "std")]
 pub fn write_box_header_ext<W: Write>(w: &mut W, v: u8, f: u32) -> Result<u64> {
     w.write_u8(v)?;
-    w.write_u24::<BigEndian>(f)?;
+    write_u24(w, f)?;
     Ok(4)
 }
 
+/// Read a big-endian 24-bit value, e.g. the flags of a full box.
+#[cfg(feature = "std")]
+pub fn read_u24<R: Read>(reader: &mut R) -> Result<u32> {
+    Ok(reader.read_u24::<BigEndian>()?)
+}
+
+/// Write a big-endian 24-bit value. Fails rather than truncating if the
+/// value doesn't fit.
+#[cfg(feature = "std")]
+pub fn write_u24<W: Write>(writer: &mut W, value: u32) -> Result<()> {
+    if value > 0xFF_FFFF {
+        return Err(Error::InvalidData("value does not fit in 24 bits"));
+    }
+    writer.write_u24::<BigEndian>(value)?;
+    Ok(())
+}
+
+/// Read a big-endian 48-bit value.
+#[cfg(feature = "std")]
+pub fn read_u48<R: Read>(reader: &mut R) -> Result<u64> {
+    Ok(reader.read_u48::<BigEndian>()?)
+}
+
+/// Write a big-endian 48-bit value. Fails rather than truncating if the
+/// value doesn't fit.
+#[cfg(feature = "std")]
+pub fn write_u48<W: Write>(writer: &mut W, value: u64) -> Result<()> {
+    if value > 0xFFFF_FFFF_FFFF {
+        return Err(Error::InvalidData("value does not fit in 48 bits"));
+    }
+    writer.write_u48::<BigEndian>(value)?;
+    Ok(())
+}
+
+#[cfg(feature = "std")]
 pub fn box_start<R: Seek>(seeker: &mut R) -> Result<u64> {
     Ok(seeker.seek(SeekFrom::Current(0))? - HEADER_SIZE)
 }
 
+#[cfg(feature = "std")]
 pub fn skip_bytes<S: Seek>(seeker: &mut S, size: u64) -> Result<()> {
     seeker.seek(SeekFrom::Current(size as i64))?;
     Ok(())
 }
 
+#[cfg(feature = "std")]
 pub fn skip_bytes_to<S: Seek>(seeker: &mut S, pos: u64) -> Result<()> {
     seeker.seek(SeekFrom::Start(pos))?;
     Ok(())
 }
 
+#[cfg(feature = "std")]
 pub fn skip_box<S: Seek>(seeker: &mut S, size: u64) -> Result<()> {
     let start = box_start(seeker)?;
     skip_bytes_to(seeker, start + size)?;
     Ok(())
 }
 
+#[cfg(feature = "std")]
 pub fn write_zeros<W: Write>(writer: &mut W, size: u64) -> Result<()> {
     for _ in 0..size {
         writer.write_u8(0)?;
@@ -293,6 +487,113 @@ pub fn write_zeros<W: Write>(writer: &mut W, size: u64) -> Result<()> {
     Ok(())
 }
 
+/// A box this crate doesn't parse, kept as its type and payload so that it
+/// survives a round trip.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct RawBox {
+    pub box_type: FourCC,
+
+    #[serde(skip_serializing)]
+    pub data: Vec<u8>,
+}
+
+impl RawBox {
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + self.data.len() as u64
+    }
+
+    /// Reads the payload of the box whose header was just read.
+    #[cfg(feature = "std")]
+    pub fn read<R: Read>(reader: &mut R, header: &BoxHeader) -> Result<Self> {
+        if header.size < HEADER_SIZE {
+            return Err(Error::InvalidData("box size is smaller than its header"));
+        }
+        let mut data = vec![0; (header.size - HEADER_SIZE) as usize];
+        reader.read_exact(&mut data)?;
+        Ok(RawBox {
+            box_type: header.name.into(),
+            data,
+        })
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<u64> {
+        let size = self.get_size();
+        BoxHeader::new(BoxType::from(u32::from(&self.box_type)), size).write(writer)?;
+        writer.write_all(&self.data)?;
+        Ok(size)
+    }
+}
+
+/// Sample entries reference the dref table with a 1-based index.
+pub fn check_data_reference_index(data_reference_index: u16) -> Result<()> {
+    if data_reference_index == 0 {
+        return Err(Error::InvalidData("data_reference_index must be 1-based"));
+    }
+    Ok(())
+}
+
+pub fn check_dimensions(width: u16, height: u16) -> Result<()> {
+    if width == 0 || height == 0 {
+        return Err(Error::InvalidData("width and height must be non-zero"));
+    }
+    Ok(())
+}
+
+/// A duration of all ones in an mvhd, tkhd or mdhd means it is unknown, as
+/// in fragmented files whose length isn't known when the moov is written.
+pub fn is_unknown_duration(version: u8, duration: u64) -> bool {
+    duration == u64::MAX || (version == 0 && duration == u32::MAX as u64)
+}
+
+// MSB-first reader over codec configuration bitstreams, such as an
+// AudioSpecificConfig, an AV1 sequence header or an HEVC SPS.
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0 }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.pos
+    }
+
+    pub(crate) fn read(&mut self, bits: usize) -> Option<u32> {
+        if bits > self.remaining() {
+            return None;
+        }
+        let mut value = 0;
+        for _ in 0..bits {
+            let bit = (self.data[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+            value = (value << 1) | bit as u32;
+            self.pos += 1;
+        }
+        Some(value)
+    }
+
+    pub(crate) fn read_flag(&mut self) -> Option<bool> {
+        Some(self.read(1)? == 1)
+    }
+
+    // Unsigned Exp-Golomb code, ue(v) in the H.264 and H.265 syntax.
+    pub(crate) fn read_ue(&mut self) -> Option<u32> {
+        let mut leading_zeros = 0;
+        while !self.read_flag()? {
+            leading_zeros += 1;
+            if leading_zeros > 31 {
+                return None;
+            }
+        }
+        Some(((1u64 << leading_zeros) - 1 + self.read(leading_zeros)? as u64) as u32)
+    }
+}
+
+// Serializes the integer part of a 16.16 value, for fields such as the
+// audio samplerate that only ever hold whole numbers.
 mod value_u32 {
     use crate::types::FixedPointU16;
     use serde::{self, Serializer};
@@ -308,18 +609,20 @@ mod value_u32 {
         }
 }
 
-mod value_i16 {
-    use crate::types::FixedPointI8;
+// Serializes a 16.16 value as a float, for fields such as the video
+// resolution in dpi that may have a fractional part.
+mod value_f64 {
+    use crate::types::FixedPointU16;
     use serde::{self, Serializer};
 
     pub fn serialize<S>(
-        fixed: &FixedPointI8,
+        fixed: &FixedPointU16,
         serializer: S,
     ) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
         {
-            serializer.serialize_i8(fixed.value())
+            serializer.serialize_f64(fixed.raw_value() as f64 / 65536.0)
         }
 }
 
@@ -339,7 +642,7 @@ mod value_u8 {
 }
 
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
@@ -351,4 +654,52 @@ mod tests {
         let ftyp_fcc2: u32 = ftyp_value.into();
         assert_eq!(ftyp_fcc, ftyp_fcc2);
     }
+
+    #[test]
+    fn test_children() {
+        let stbl = stbl::StblBox {
+            ctts: Some(ctts::CttsBox::default()),
+            stss: Some(stss::StssBox::default()),
+            stco: Some(stco::StcoBox::default()),
+            ..stbl::StblBox::default()
+        };
+        let types: Vec<BoxType> = stbl.children().map(|child| child.box_type()).collect();
+        assert_eq!(types, vec![
+            BoxType::StsdBox,
+            BoxType::SttsBox,
+            BoxType::CttsBox,
+            BoxType::StssBox,
+            BoxType::StscBox,
+            BoxType::StszBox,
+            BoxType::StcoBox,
+        ]);
+        assert_eq!(stbl.children().count(), 7);
+        assert_eq!(stbl.stts.children().count(), 0);
+    }
+
+    #[test]
+    fn test_u24_u48() {
+        for value in [0, 1, 0x80_0000, 0xFF_FFFF] {
+            let mut buf = Vec::new();
+            write_u24(&mut buf, value).unwrap();
+            assert_eq!(buf.len(), 3);
+            assert_eq!(read_u24(&mut buf.as_slice()).unwrap(), value);
+        }
+        assert!(write_u24(&mut Vec::new(), 0x100_0000).is_err());
+
+        for value in [0, 0x8000_0000_0000, 0xFFFF_FFFF_FFFF] {
+            let mut buf = Vec::new();
+            write_u48(&mut buf, value).unwrap();
+            assert_eq!(buf.len(), 6);
+            assert_eq!(read_u48(&mut buf.as_slice()).unwrap(), value);
+        }
+        assert!(write_u48(&mut Vec::new(), 0x1_0000_0000_0000).is_err());
+
+        // The version byte and the 24-bit flags of a full box.
+        let mut buf = Vec::new();
+        write_box_header_ext(&mut buf, 1, 0xFF_FFFF).unwrap();
+        assert_eq!(buf, vec![1, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(read_box_header_ext(&mut buf.as_slice()).unwrap(), (1, 0xFF_FFFF));
+        assert!(write_box_header_ext(&mut Vec::new(), 0, 0x100_0000).is_err());
+    }
 }
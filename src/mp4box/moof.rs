@@ -1,3 +1,4 @@
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, SeekFrom, Write};
 use serde::{Serialize};
 
@@ -10,6 +11,10 @@ pub struct MoofBox {
 
     #[serde(rename = "traf")]
     pub trafs: Vec<TrafBox>,
+
+    /// Other children, e.g. pssh, kept as is.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_boxes: Vec<RawBox>,
 }
 
 impl MoofBox {
@@ -22,8 +27,16 @@ impl MoofBox {
         for traf in self.trafs.iter() {
             size += traf.box_size();
         }
+        for raw in self.unknown_boxes.iter() {
+            size += raw.get_size();
+        }
         size
     }
+
+    /// Returns the fragment's sequence number from mfhd.
+    pub fn sequence_number(&self) -> u32 {
+        self.mfhd.sequence_number
+    }
 }
 
 impl Mp4Box for MoofBox {
@@ -43,14 +56,24 @@ impl Mp4Box for MoofBox {
         let s = format!("trafs={}", self.trafs.len());
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = vec![&self.mfhd];
+        for traf in self.trafs.iter() {
+            children.push(traf);
+        }
+        children
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for MoofBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
 
         let mut mfhd = None;
         let mut trafs = Vec::new();
+        let mut unknown_boxes = Vec::new();
 
         let mut current = reader.seek(SeekFrom::Current(0))?;
         let end = start + size;
@@ -67,6 +90,9 @@ impl<R: Read + Seek> ReadBox<&mut R> for MoofBox {
                     let traf = TrafBox::read_box(reader, s)?;
                     trafs.push(traf);
                 }
+                _ if current + s <= end => {
+                    unknown_boxes.push(RawBox::read(reader, &header)?);
+                }
                 _ => {
                     // XXX warn!()
                     skip_box(reader, s)?;
@@ -84,10 +110,12 @@ impl<R: Read + Seek> ReadBox<&mut R> for MoofBox {
         Ok(MoofBox {
             mfhd: mfhd.unwrap(),
             trafs,
+            unknown_boxes,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for MoofBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -97,6 +125,10 @@ impl<W: Write> WriteBox<&mut W> for MoofBox {
         for traf in self.trafs.iter() {
             traf.write_box(writer)?;
         }
-        Ok(0)
+        for raw in self.unknown_boxes.iter() {
+            raw.write(writer)?;
+        }
+
+        Ok(size)
     }
 }
@@ -1,8 +1,9 @@
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, SeekFrom, Write};
 use serde::{Serialize};
 
 use crate::mp4box::*;
-use crate::mp4box::{mvhd::MvhdBox, mvex::MvexBox, trak::TrakBox};
+use crate::mp4box::{mvhd::MvhdBox, mvex::MvexBox, trak::TrakBox, udta::UdtaBox};
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct MoovBox {
@@ -13,6 +14,13 @@ pub struct MoovBox {
 
     #[serde(rename = "trak")]
     pub traks: Vec<TrakBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub udta: Option<UdtaBox>,
+
+    /// Other children, e.g. iods or meta, kept as is.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_boxes: Vec<RawBox>,
 }
 
 impl MoovBox {
@@ -25,6 +33,15 @@ impl MoovBox {
         for trak in self.traks.iter() {
             size += trak.box_size();
         }
+        if let Some(ref mvex) = self.mvex {
+            size += mvex.box_size();
+        }
+        if let Some(ref udta) = self.udta {
+            size += udta.box_size();
+        }
+        for raw in self.unknown_boxes.iter() {
+            size += raw.get_size();
+        }
         size
     }
 }
@@ -46,8 +63,23 @@ impl Mp4Box for MoovBox {
         let s = format!("traks={}", self.traks.len());
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = vec![&self.mvhd];
+        if let Some(ref mvex) = self.mvex {
+            children.push(mvex);
+        }
+        for trak in self.traks.iter() {
+            children.push(trak);
+        }
+        if let Some(ref udta) = self.udta {
+            children.push(udta);
+        }
+        children
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for MoovBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -55,6 +87,8 @@ impl<R: Read + Seek> ReadBox<&mut R> for MoovBox {
         let mut mvhd = None;
         let mut mvex = None;
         let mut traks = Vec::new();
+        let mut udta = None;
+        let mut unknown_boxes = Vec::new();
 
         let mut current = reader.seek(SeekFrom::Current(0))?;
         let end = start + size;
@@ -75,8 +109,10 @@ impl<R: Read + Seek> ReadBox<&mut R> for MoovBox {
                     traks.push(trak);
                 }
                 BoxType::UdtaBox => {
-                    // XXX warn!()
-                    skip_box(reader, s)?;
+                    udta = Some(UdtaBox::read_box(reader, s)?);
+                }
+                _ if current + s <= end => {
+                    unknown_boxes.push(RawBox::read(reader, &header)?);
                 }
                 _ => {
                     // XXX warn!()
@@ -97,10 +133,13 @@ impl<R: Read + Seek> ReadBox<&mut R> for MoovBox {
             mvhd: mvhd.unwrap(),
             mvex,
             traks,
+            udta,
+            unknown_boxes,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for MoovBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -110,6 +149,16 @@ impl<W: Write> WriteBox<&mut W> for MoovBox {
         for trak in self.traks.iter() {
             trak.write_box(writer)?;
         }
-        Ok(0)
+        if let Some(ref mvex) = self.mvex {
+            mvex.write_box(writer)?;
+        }
+        if let Some(ref udta) = self.udta {
+            udta.write_box(writer)?;
+        }
+        for raw in self.unknown_boxes.iter() {
+            raw.write(writer)?;
+        }
+
+        Ok(size)
     }
 }
@@ -1,8 +1,12 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
 use crate::mp4box::*;
+use crate::mp4box::chnl::ChnlBox;
+use crate::mp4box::sinf::SinfBox;
 
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Mp4aBox {
@@ -13,6 +17,18 @@ pub struct Mp4aBox {
     #[serde(with = "value_u32")]
     pub samplerate: FixedPointU16,
     pub esds: Option<EsdsBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chnl: Option<ChnlBox>,
+
+    /// Set for protected (enca) sample entries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sinf: Option<SinfBox>,
+
+    /// Child boxes this crate doesn't parse, such as chan or btrt, in the
+    /// order they were read.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_boxes: Vec<RawBox>,
 }
 
 impl Default for Mp4aBox {
@@ -23,23 +39,43 @@ impl Default for Mp4aBox {
             samplesize: 16,
             samplerate: FixedPointU16::new(48000),
             esds: Some(EsdsBox::default()),
+            chnl: None,
+            sinf: None,
+            unknown_boxes: Vec::new(),
         }
     }
 }
 
 impl Mp4aBox {
-    pub fn new(config: &AacConfig) -> Self {
+    /// Like `new`, but rejects a zero data_reference_index or a sample
+    /// rate that does not fit the 16.16 samplerate field.
+    pub fn try_new(config: &AacConfig, data_reference_index: u16) -> Result<Self> {
+        check_data_reference_index(data_reference_index)?;
+        if config.freq_index.freq() > u16::MAX as u32 {
+            return Err(Error::InvalidData("sample rate does not fit in mp4a samplerate"));
+        }
+        Ok(Self::new(config, data_reference_index))
+    }
+
+    pub fn new(config: &AacConfig, data_reference_index: u16) -> Self {
         Self {
-            data_reference_index: 1,
+            data_reference_index,
             channelcount: config.chan_conf as u16,
             samplesize: 16,
             samplerate: FixedPointU16::new(config.freq_index.freq() as u16),
             esds: Some(EsdsBox::new(config)),
+            chnl: None,
+            sinf: None,
+            unknown_boxes: Vec::new(),
         }
     }
 
     pub fn get_type(&self) -> BoxType {
-        BoxType::Mp4aBox
+        if self.sinf.is_some() {
+            BoxType::EncaBox
+        } else {
+            BoxType::Mp4aBox
+        }
     }
 
     pub fn get_size(&self) -> u64 {
@@ -47,6 +83,15 @@ impl Mp4aBox {
         if let Some(ref esds) = self.esds {
             size += esds.box_size();
         }
+        if let Some(ref chnl) = self.chnl {
+            size += chnl.box_size();
+        }
+        if let Some(ref sinf) = self.sinf {
+            size += sinf.box_size();
+        }
+        for unknown in self.unknown_boxes.iter() {
+            size += unknown.get_size();
+        }
         size
     }
 }
@@ -69,8 +114,23 @@ impl Mp4Box for Mp4aBox {
             self.channelcount, self.samplesize, self.samplerate.value());
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = Vec::new();
+        if let Some(ref esds) = self.esds {
+            children.push(esds);
+        }
+        if let Some(ref chnl) = self.chnl {
+            children.push(chnl);
+        }
+        if let Some(ref sinf) = self.sinf {
+            children.push(sinf);
+        }
+        children
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for Mp4aBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -79,18 +139,62 @@ impl<R: Read + Seek> ReadBox<&mut R> for Mp4aBox {
         reader.read_u16::<BigEndian>()?; // reserved
         let data_reference_index = reader.read_u16::<BigEndian>()?;
 
-        reader.read_u64::<BigEndian>()?; // reserved
-        let channelcount = reader.read_u16::<BigEndian>()?;
+        // QuickTime sound sample descriptions store a version here, which
+        // adds fields after the samplerate.
+        let version = reader.read_u16::<BigEndian>()?;
+        reader.read_u16::<BigEndian>()?; // revision level
+        reader.read_u32::<BigEndian>()?; // vendor
+        let mut channelcount = reader.read_u16::<BigEndian>()?;
         let samplesize = reader.read_u16::<BigEndian>()?;
         reader.read_u32::<BigEndian>()?; // pre-defined, reserved
-        let samplerate = FixedPointU16::new_raw(reader.read_u32::<BigEndian>()?);
-
-        let header = BoxHeader::read(reader)?;
-        let BoxHeader { name, size: s } = header;
+        let mut samplerate = FixedPointU16::new_raw(reader.read_u32::<BigEndian>()?);
+        match version {
+            1 => {
+                // samples per packet, bytes per packet, frame and sample
+                skip_bytes(reader, 16)?;
+            }
+            2 => {
+                reader.read_u32::<BigEndian>()?; // size of struct only
+                let rate = reader.read_f64::<BigEndian>()?;
+                samplerate = FixedPointU16::new(rate.min(u16::MAX as f64) as u16);
+                channelcount = reader.read_u32::<BigEndian>()? as u16;
+                skip_bytes(reader, 20)?;
+            }
+            _ => {}
+        }
 
         let mut esds = None;
-        if name == BoxType::EsdsBox {
-            esds = Some(EsdsBox::read_box(reader, s)?);
+        let mut chnl = None;
+        let mut sinf = None;
+        let mut unknown_boxes = Vec::new();
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current + HEADER_SIZE <= end {
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+
+            match name {
+                BoxType::EsdsBox => {
+                    esds = Some(EsdsBox::read_box(reader, s)?);
+                }
+                BoxType::ChnlBox => {
+                    chnl = Some(ChnlBox::read_box(reader, s)?);
+                }
+                BoxType::SinfBox => {
+                    sinf = Some(SinfBox::read_box(reader, s)?);
+                }
+                BoxType::WaveBox if esds.is_none() => {
+                    esds = read_wave_esds(reader, s)?;
+                }
+                _ if current + s <= end => {
+                    unknown_boxes.push(RawBox::read(reader, &header)?);
+                }
+                _ => {
+                    skip_box(reader, s)?;
+                }
+            }
+            current = reader.stream_position()?;
         }
         skip_bytes_to(reader, start + size)?;
 
@@ -100,10 +204,42 @@ impl<R: Read + Seek> ReadBox<&mut R> for Mp4aBox {
             samplesize,
             samplerate,
             esds,
+            chnl,
+            sinf,
+            unknown_boxes,
         })
     }
 }
 
+// QuickTime puts the esds inside a wave box, next to an frma, an mp4a atom
+// and a terminator.
+#[cfg(feature = "std")]
+fn read_wave_esds<R: Read + Seek>(reader: &mut R, size: u64) -> Result<Option<EsdsBox>> {
+    let start = box_start(reader)?;
+
+    let mut esds = None;
+    let mut current = reader.stream_position()?;
+    let end = start + size;
+    while current + HEADER_SIZE <= end {
+        let header = BoxHeader::read(reader)?;
+        let BoxHeader { name, size: s } = header;
+
+        match name {
+            BoxType::EsdsBox => {
+                esds = Some(EsdsBox::read_box(reader, s)?);
+            }
+            _ => {
+                skip_box(reader, s)?;
+            }
+        }
+        current = reader.stream_position()?;
+    }
+    skip_bytes_to(reader, start + size)?;
+
+    Ok(esds)
+}
+
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for Mp4aBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -122,6 +258,15 @@ impl<W: Write> WriteBox<&mut W> for Mp4aBox {
         if let Some(ref esds) = self.esds {
             esds.write_box(writer)?;
         }
+        if let Some(ref chnl) = self.chnl {
+            chnl.write_box(writer)?;
+        }
+        if let Some(ref sinf) = self.sinf {
+            sinf.write_box(writer)?;
+        }
+        for unknown in self.unknown_boxes.iter() {
+            unknown.write(writer)?;
+        }
 
         Ok(size)
     }
@@ -151,7 +296,7 @@ impl Mp4Box for EsdsBox {
 
     fn box_size(&self) -> u64 {
         HEADER_SIZE + HEADER_EXT_SIZE
-            + 1 + size_of_length(ESDescriptor::desc_size()) as u64 + ESDescriptor::desc_size() as u64
+            + 1 + size_of_length(self.es_desc.desc_size()) as u64 + self.es_desc.desc_size() as u64
     }
 
     fn to_json(&self) -> Result<String> {
@@ -164,6 +309,7 @@ impl Mp4Box for EsdsBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for EsdsBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -175,7 +321,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for EsdsBox {
         let mut current = reader.seek(SeekFrom::Current(0))?;
         let end = start + size;
         while current < end {
-            let (desc_tag, desc_size) = read_desc(reader)?;
+            let (desc_tag, desc_size) = read_child_desc(reader, end)?;
             match desc_tag {
                 0x03 => {
                     es_desc = Some(ESDescriptor::read_desc(reader, desc_size)?);
@@ -199,6 +345,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for EsdsBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for EsdsBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -214,7 +361,7 @@ impl<W: Write> WriteBox<&mut W> for EsdsBox {
 
 trait Descriptor: Sized {
     fn desc_tag() -> u8;
-    fn desc_size() -> u32;
+    fn desc_size(&self) -> u32;
 }
 
 trait ReadDesc<T>: Sized {
@@ -225,6 +372,7 @@ trait WriteDesc<T>: Sized {
     fn write_desc(&self, _: T) -> Result<u32>;
 }
 
+#[cfg(feature = "std")]
 fn read_desc<R: Read>(reader: &mut R) -> Result<(u8, u32)> {
     let tag = reader.read_u8()?;
 
@@ -240,6 +388,18 @@ fn read_desc<R: Read>(reader: &mut R) -> Result<(u8, u32)> {
     Ok((tag, size))
 }
 
+// Reads the tag and size of a descriptor nested in one that ends at `end`,
+// so that a corrupt size can't make the child read past its parent.
+#[cfg(feature = "std")]
+fn read_child_desc<R: Read + Seek>(reader: &mut R, end: u64) -> Result<(u8, u32)> {
+    let (tag, size) = read_desc(reader)?;
+    let start = reader.stream_position()?;
+    if start + size as u64 > end {
+        return Err(Error::InvalidData("descriptor exceeds its parent"));
+    }
+    Ok((tag, size))
+}
+
 fn size_of_length(size: u32) -> u32 {
     match size {
         0x0..=0x7F => 1,
@@ -249,10 +409,11 @@ fn size_of_length(size: u32) -> u32 {
     }
 }
 
+#[cfg(feature = "std")]
 fn write_desc<W: Write>(writer: &mut W, tag: u8, size: u32) -> Result<u64> {
     writer.write_u8(tag)?;
 
-    if size as u64 > std::u32::MAX as u64 {
+    if size as u64 > u32::MAX as u64 {
         return Err(Error::InvalidData("invalid descriptor length range"));
     }
 
@@ -292,13 +453,14 @@ impl Descriptor for ESDescriptor {
         0x03
     }
 
-    fn desc_size() -> u32 {
+    fn desc_size(&self) -> u32 {
         3
-        + 1 + size_of_length(DecoderConfigDescriptor::desc_size()) + DecoderConfigDescriptor::desc_size()
-        + 1 + size_of_length(SLConfigDescriptor::desc_size()) + SLConfigDescriptor::desc_size()
+        + 1 + size_of_length(self.dec_config.desc_size()) + self.dec_config.desc_size()
+        + 1 + size_of_length(self.sl_config.desc_size()) + self.sl_config.desc_size()
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadDesc<&mut R> for ESDescriptor {
     fn read_desc(reader: &mut R, size: u32) -> Result<Self> {
         let start = reader.seek(SeekFrom::Current(0))?;
@@ -312,7 +474,7 @@ impl<R: Read + Seek> ReadDesc<&mut R> for ESDescriptor {
         let mut current = reader.seek(SeekFrom::Current(0))?;
         let end = start + size as u64;
         while current < end {
-            let (desc_tag, desc_size) = read_desc(reader)?;
+            let (desc_tag, desc_size) = read_child_desc(reader, end)?;
             match desc_tag {
                 0x04 => {
                     dec_config = Some(DecoderConfigDescriptor::read_desc(reader, desc_size)?);
@@ -335,9 +497,10 @@ impl<R: Read + Seek> ReadDesc<&mut R> for ESDescriptor {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteDesc<&mut W> for ESDescriptor {
     fn write_desc(&self, writer: &mut W) -> Result<u32> {
-        let size = Self::desc_size();
+        let size = self.desc_size();
         write_desc(writer, Self::desc_tag(), size)?;
 
         writer.write_u16::<BigEndian>(self.es_id)?;
@@ -363,6 +526,11 @@ pub struct DecoderConfigDescriptor {
 }
 
 impl DecoderConfigDescriptor {
+    /// MPEG-1 audio (ISO/IEC 11172-3), which includes MP3.
+    pub const OBJECT_TYPE_MPEG1_AUDIO: u8 = 0x6B;
+    /// MPEG-2 audio (ISO/IEC 13818-3), MP3 at the lower sampling rates.
+    pub const OBJECT_TYPE_MPEG2_AUDIO: u8 = 0x69;
+
     pub fn new(config: &AacConfig) -> Self {
         Self {
             object_type_indication: 0x40, // XXX AAC
@@ -374,6 +542,18 @@ impl DecoderConfigDescriptor {
             dec_specific: DecoderSpecificDescriptor::new(config),
         }
     }
+
+    /// Returns true for MPEG-1/2 audio such as MP3, which carries no
+    /// AudioSpecificConfig, so `dec_specific` is left at its default and
+    /// isn't written.
+    pub fn is_mp3(&self) -> bool {
+        is_mp3(self.object_type_indication)
+    }
+}
+
+fn is_mp3(object_type_indication: u8) -> bool {
+    object_type_indication == DecoderConfigDescriptor::OBJECT_TYPE_MPEG1_AUDIO
+        || object_type_indication == DecoderConfigDescriptor::OBJECT_TYPE_MPEG2_AUDIO
 }
 
 impl Descriptor for DecoderConfigDescriptor {
@@ -381,11 +561,15 @@ impl Descriptor for DecoderConfigDescriptor {
         0x04
     }
 
-    fn desc_size() -> u32 {
-        13 + 1 + size_of_length(DecoderSpecificDescriptor::desc_size()) + DecoderSpecificDescriptor::desc_size()
+    fn desc_size(&self) -> u32 {
+        if self.is_mp3() {
+            return 13;
+        }
+        13 + 1 + size_of_length(self.dec_specific.desc_size()) + self.dec_specific.desc_size()
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadDesc<&mut R> for DecoderConfigDescriptor {
     fn read_desc(reader: &mut R, size: u32) -> Result<Self> {
         let start = reader.seek(SeekFrom::Current(0))?;
@@ -394,7 +578,7 @@ impl<R: Read + Seek> ReadDesc<&mut R> for DecoderConfigDescriptor {
         let byte_a = reader.read_u8()?;
         let stream_type = (byte_a & 0xFC) >> 2;
         let up_stream = byte_a & 0x02;
-        let buffer_size_db = reader.read_u24::<BigEndian>()?;
+        let buffer_size_db = read_u24(reader)?;
         let max_bitrate = reader.read_u32::<BigEndian>()?;
         let avg_bitrate = reader.read_u32::<BigEndian>()?;
 
@@ -403,9 +587,10 @@ impl<R: Read + Seek> ReadDesc<&mut R> for DecoderConfigDescriptor {
         let mut current = reader.seek(SeekFrom::Current(0))?;
         let end = start + size as u64;
         while current < end {
-            let (desc_tag, desc_size) = read_desc(reader)?;
+            let (desc_tag, desc_size) = read_child_desc(reader, end)?;
             match desc_tag {
-                0x05 => {
+                // Whatever MP3 muxers put here is not an AudioSpecificConfig.
+                0x05 if !is_mp3(object_type_indication) => {
                     dec_specific = Some(DecoderSpecificDescriptor::read_desc(reader, desc_size)?);
                 }
                 _ => {
@@ -427,18 +612,21 @@ impl<R: Read + Seek> ReadDesc<&mut R> for DecoderConfigDescriptor {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteDesc<&mut W> for DecoderConfigDescriptor {
     fn write_desc(&self, writer: &mut W) -> Result<u32> {
-        let size = Self::desc_size();
+        let size = self.desc_size();
         write_desc(writer, Self::desc_tag(), size)?;
 
         writer.write_u8(self.object_type_indication)?;
         writer.write_u8((self.stream_type << 2) + (self.up_stream & 0x02) + 1)?; // 1 reserved
-        writer.write_u24::<BigEndian>(self.buffer_size_db)?;
+        write_u24(writer, self.buffer_size_db)?;
         writer.write_u32::<BigEndian>(self.max_bitrate)?;
         writer.write_u32::<BigEndian>(self.avg_bitrate)?;
 
-        self.dec_specific.write_desc(writer)?;
+        if !self.is_mp3() {
+            self.dec_specific.write_desc(writer)?;
+        }
 
         Ok(size)
     }
@@ -449,6 +637,18 @@ pub struct DecoderSpecificDescriptor {
     pub profile: u8,
     pub freq_index: u8,
     pub chan_conf: u8,
+
+    /// Set for HE-AAC, signaled either hierarchically (profile 5 or 29) or
+    /// through a backward-compatible sync extension after an AAC-LC config.
+    pub sbr_present: bool,
+    /// Set for HE-AACv2, which adds parametric stereo to SBR.
+    pub ps_present: bool,
+
+    /// The AudioSpecificConfig as read, empty for a new descriptor. Only
+    /// the part after profile, freq_index and chan_conf is written back:
+    /// those always come from the fields above.
+    #[serde(skip_serializing)]
+    pub data: Vec<u8>,
 }
 
 impl DecoderSpecificDescriptor {
@@ -457,43 +657,153 @@ impl DecoderSpecificDescriptor {
             profile: config.profile as u8,
             freq_index: config.freq_index as u8,
             chan_conf: config.chan_conf as u8,
+            sbr_present: false,
+            ps_present: false,
+            data: Vec::new(),
+        }
+    }
+
+    /// Returns the AudioSpecificConfig, e.g. for the CodecPrivate of a
+    /// Matroska track: profile, freq_index and chan_conf in 13 bits,
+    /// followed by whatever came after them in `data`.
+    pub fn config_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![
+            (self.profile << 3) | (self.freq_index >> 1),
+            (self.freq_index << 7) | (self.chan_conf << 3),
+        ];
+        match self.data.as_slice() {
+            // The read config also starts with 13 bits of fields, without
+            // an escaped object type or an explicit frequency.
+            [first, second, tail @ ..]
+                if first >> 3 != 31 && (((first & 0x07) << 1) | (second >> 7)) != 0xF =>
+            {
+                bytes[1] |= second & 0x07;
+                bytes.extend_from_slice(tail);
+            }
+            // Otherwise the tail can only be kept if the fields are unchanged.
+            data if !data.is_empty() && self.read_fields_match() => return data.to_vec(),
+            _ => {}
+        }
+        bytes
+    }
+
+    // Whether `data` still holds profile, freq_index and chan_conf.
+    fn read_fields_match(&self) -> bool {
+        let mut bits = BitReader::new(&self.data);
+        let fields = (|| Some((bits.read_object_type()?, bits.read_freq_index()?, bits.read(4)? as u8)))();
+        fields == Some((self.profile, self.freq_index, self.chan_conf))
+    }
+
+    /// Returns the audio object type a codec string should name, e.g. 5 for
+    /// "mp4a.40.5" when SBR is signaled on top of AAC-LC.
+    pub fn audio_object_type(&self) -> u8 {
+        if self.ps_present {
+            AudioObjectType::ParametricStereo as u8
+        } else if self.sbr_present {
+            AudioObjectType::SpectralBandReplication as u8
+        } else {
+            self.profile
         }
     }
 }
 
+// AudioSpecificConfig fields.
+impl BitReader<'_> {
+    fn read_object_type(&mut self) -> Option<u8> {
+        let object_type = self.read(5)? as u8;
+        if object_type == 31 {
+            Some(32 + self.read(6)? as u8)
+        } else {
+            Some(object_type)
+        }
+    }
+
+    fn read_freq_index(&mut self) -> Option<u8> {
+        let freq_index = self.read(4)? as u8;
+        if freq_index == 0xF {
+            self.read(24)?; // explicit sampling frequency
+        }
+        Some(freq_index)
+    }
+}
+
+// Looks for SBR and PS signaling (ISO/IEC 14496-3 1.6.2.1 and 1.6.5) and
+// returns (sbr_present, ps_present).
+fn read_sbr_signaling(bits: &mut BitReader, profile: u8, chan_conf: u8) -> Option<(bool, bool)> {
+    if profile == AudioObjectType::SpectralBandReplication as u8 {
+        return Some((true, false));
+    }
+    if profile == AudioObjectType::ParametricStereo as u8 {
+        return Some((true, true));
+    }
+
+    // GASpecificConfig of the AAC object types; a program config element
+    // (channel configuration 0) is not parsed.
+    if !(1..=4).contains(&profile) || chan_conf == 0 {
+        return None;
+    }
+    bits.read(1)?; // frameLengthFlag
+    if bits.read(1)? == 1 {
+        bits.read(14)?; // coreCoderDelay
+    }
+    bits.read(1)?; // extensionFlag
+
+    if bits.remaining() < 16 || bits.read(11)? != 0x2B7 {
+        return None;
+    }
+    if bits.read_object_type()? != AudioObjectType::SpectralBandReplication as u8 {
+        return None;
+    }
+    if bits.read(1)? == 0 {
+        return Some((false, false));
+    }
+    bits.read_freq_index()?;
+    let ps_present = bits.remaining() >= 12 && bits.read(11)? == 0x548 && bits.read(1)? == 1;
+    Some((true, ps_present))
+}
+
 impl Descriptor for DecoderSpecificDescriptor {
     fn desc_tag() -> u8 {
         0x05
     }
 
-    fn desc_size() -> u32 {
-        2
+    fn desc_size(&self) -> u32 {
+        self.config_bytes().len() as u32
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadDesc<&mut R> for DecoderSpecificDescriptor {
-    fn read_desc(reader: &mut R, _size: u32) -> Result<Self> {
-        let byte_a = reader.read_u8()?;
-        let byte_b = reader.read_u8()?;
-        let profile = byte_a >> 3;
-        let freq_index = ((byte_a & 0x07) << 1) + (byte_b >> 7);
-        let chan_conf = (byte_b >> 3) & 0x0F;
+    fn read_desc(reader: &mut R, size: u32) -> Result<Self> {
+        let mut data = vec![0; size as usize];
+        reader.read_exact(&mut data)?;
+
+        let mut bits = BitReader::new(&data);
+        let (profile, freq_index, chan_conf) = (|| {
+            Some((bits.read_object_type()?, bits.read_freq_index()?, bits.read(4)? as u8))
+        })()
+        .ok_or(Error::InvalidData("truncated AudioSpecificConfig"))?;
+        let (sbr_present, ps_present) = read_sbr_signaling(&mut bits, profile, chan_conf)
+            .unwrap_or((false, false));
 
         Ok(DecoderSpecificDescriptor {
             profile,
             freq_index,
             chan_conf,
+            sbr_present,
+            ps_present,
+            data,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteDesc<&mut W> for DecoderSpecificDescriptor {
     fn write_desc(&self, writer: &mut W) -> Result<u32> {
-        let size = Self::desc_size();
+        let size = self.desc_size();
         write_desc(writer, Self::desc_tag(), size)?;
 
-        writer.write_u8((self.profile << 3) + (self.freq_index >> 1))?;
-        writer.write_u8((self.freq_index << 7) + (self.chan_conf << 3))?;
+        writer.write_all(&self.config_bytes())?;
 
         Ok(size)
     }
@@ -513,11 +823,12 @@ impl Descriptor for SLConfigDescriptor {
         0x06
     }
 
-    fn desc_size() -> u32 {
+    fn desc_size(&self) -> u32 {
         1
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadDesc<&mut R> for SLConfigDescriptor {
     fn read_desc(reader: &mut R, _size: u32) -> Result<Self> {
         reader.read_u8()?; // pre-defined
@@ -526,9 +837,10 @@ impl<R: Read + Seek> ReadDesc<&mut R> for SLConfigDescriptor {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteDesc<&mut W> for SLConfigDescriptor {
     fn write_desc(&self, writer: &mut W) -> Result<u32> {
-        let size = Self::desc_size();
+        let size = self.desc_size();
         write_desc(writer, Self::desc_tag(), size - 1)?;
 
         writer.write_u8(0)?; // pre-defined
@@ -536,7 +848,7 @@ impl<W: Write> WriteDesc<&mut W> for SLConfigDescriptor {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -565,11 +877,16 @@ mod tests {
                             profile: 2,
                             freq_index: 3,
                             chan_conf: 1,
+                            data: vec![0x11, 0x88],
+                            ..DecoderSpecificDescriptor::default()
                         },
                     },
                     sl_config: SLConfigDescriptor::default(),
                 },
             }),
+            chnl: None,
+            sinf: None,
+            unknown_boxes: Vec::new(),
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -584,6 +901,168 @@ mod tests {
         assert_eq!(src_box, dst_box);
     }
 
+    #[test]
+    fn test_mp4a_quicktime_wave() {
+        let esds = EsdsBox::new(&AacConfig::default());
+        let mut wave = Vec::new();
+        wave.extend_from_slice(b"\0\0\0\x0cfrmamp4a");
+        wave.extend_from_slice(b"\0\0\0\x0cmp4a\0\0\0\0");
+        esds.write_box(&mut wave).unwrap();
+        wave.extend_from_slice(b"\0\0\0\x08\0\0\0\0");
+
+        // Version 1 sound sample description, as written by QuickTime.
+        let mut buf = Vec::new();
+        let size = HEADER_SIZE + 28 + 16 + HEADER_SIZE + wave.len() as u64;
+        BoxHeader::new(BoxType::Mp4aBox, size).write(&mut buf).unwrap();
+        buf.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        buf.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+        buf.extend_from_slice(&[0, 2, 0, 16, 0xff, 0xfe, 0, 0]);
+        buf.extend_from_slice(&FixedPointU16::new(48000).raw_value().to_be_bytes());
+        buf.extend_from_slice(&[0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 2]);
+        BoxHeader::new(BoxType::WaveBox, HEADER_SIZE + wave.len() as u64)
+            .write(&mut buf)
+            .unwrap();
+        buf.extend_from_slice(&wave);
+        assert_eq!(buf.len() as u64, size);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let mp4a = Mp4aBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(mp4a.channelcount, 2);
+        assert_eq!(mp4a.samplerate.value(), 48000);
+        // Reading keeps the AudioSpecificConfig that was written.
+        let mut expected = esds.clone();
+        let dec_specific = &mut expected.es_desc.dec_config.dec_specific;
+        dec_specific.data = dec_specific.config_bytes();
+        assert_eq!(mp4a.esds, Some(expected));
+        assert_eq!(reader.position(), size);
+    }
+
+    #[test]
+    fn test_esds_mp3() {
+        // An MP3 esds whose DecoderSpecificInfo would read as 7.1 AAC at 8 kHz.
+        let mut esds = EsdsBox::default();
+        esds.es_desc.dec_config.object_type_indication =
+            DecoderConfigDescriptor::OBJECT_TYPE_MPEG1_AUDIO;
+        esds.es_desc.dec_config.dec_specific = DecoderSpecificDescriptor {
+            profile: 31,
+            freq_index: 11,
+            chan_conf: 7,
+            ..DecoderSpecificDescriptor::default()
+        };
+        let mut buf = Vec::new();
+        esds.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len() as u64, esds.box_size());
+        // The DecoderConfigDescriptor holds only its 13 bytes of fields.
+        assert!(buf.windows(3).any(|w| w == [0x04, 13, 0x6B]));
+        assert!(!buf.windows(2).any(|w| w == [0x05, 2]));
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = EsdsBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(dst_box.es_desc.dec_config.object_type_indication, 0x6B);
+        assert!(dst_box.es_desc.dec_config.is_mp3());
+        assert_eq!(dst_box.es_desc.dec_config.dec_specific, DecoderSpecificDescriptor::default());
+        assert!(!EsdsBox::default().es_desc.dec_config.is_mp3());
+    }
+
+    #[test]
+    fn test_dec_specific_sbr() {
+        // AAC-LC at 24 kHz with a backward-compatible SBR sync extension.
+        let asc = [0x13, 0x10, 0x56, 0xE5, 0x98];
+        let desc = DecoderSpecificDescriptor::read_desc(&mut Cursor::new(&asc), 5).unwrap();
+        assert_eq!((desc.profile, desc.freq_index, desc.chan_conf), (2, 6, 2));
+        assert!(desc.sbr_present);
+        assert!(!desc.ps_present);
+        assert_eq!(desc.audio_object_type(), 5);
+
+        // Hierarchical signaling: SBR at 48 kHz over AAC-LC.
+        let asc = [0x29, 0x91, 0x88];
+        let desc = DecoderSpecificDescriptor::read_desc(&mut Cursor::new(&asc), 3).unwrap();
+        assert_eq!((desc.profile, desc.freq_index, desc.chan_conf), (5, 3, 2));
+        assert!(desc.sbr_present);
+        assert_eq!(desc.audio_object_type(), 5);
+
+        // Plain AAC-LC.
+        let asc = [0x11, 0x90];
+        let desc = DecoderSpecificDescriptor::read_desc(&mut Cursor::new(&asc), 2).unwrap();
+        assert!(!desc.sbr_present);
+        assert_eq!(desc.audio_object_type(), 2);
+    }
+
+    #[test]
+    fn test_esds_dec_specific_too_large() {
+        let mut esds = EsdsBox::default();
+        esds.es_desc.dec_config.object_type_indication = 0x40;
+        esds.es_desc.dec_config.dec_specific = DecoderSpecificDescriptor::new(&AacConfig::default());
+        let mut buf = Vec::new();
+        esds.write_box(&mut buf).unwrap();
+
+        // Claim a 256 MiB DecoderSpecificInfo inside the DecoderConfig.
+        let pos = buf.windows(3).position(|w| w == [0x05, 0x02, 0x11]).unwrap();
+        buf.splice(pos + 1..pos + 2, [0xFF, 0xFF, 0xFF, 0x7F]);
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert!(EsdsBox::read_box(&mut reader, header.size).is_err());
+    }
+
+    #[test]
+    fn test_dec_specific_edit_fields() {
+        // AAC-LC at 48 kHz, mono, followed by a sync extension.
+        let asc = [0x11, 0x88, 0x56, 0xE5, 0x00];
+        let mut esds = EsdsBox::default();
+        esds.es_desc.dec_config.object_type_indication = 0x40;
+        esds.es_desc.dec_config.dec_specific =
+            DecoderSpecificDescriptor::read_desc(&mut Cursor::new(&asc), asc.len() as u32).unwrap();
+        esds.es_desc.dec_config.dec_specific.chan_conf = 2;
+
+        let mut buf = Vec::new();
+        esds.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len() as u64, esds.box_size());
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = EsdsBox::read_box(&mut reader, header.size).unwrap();
+        let dec_specific = &dst_box.es_desc.dec_config.dec_specific;
+        assert_eq!(dec_specific.data, [0x11, 0x90, 0x56, 0xE5, 0x00]);
+        assert_eq!(dec_specific.chan_conf, 2);
+
+        // An explicit frequency can't be kept once the fields change.
+        let asc = [0x17, 0x80, 0x56, 0x22, 0x10];
+        let mut desc =
+            DecoderSpecificDescriptor::read_desc(&mut Cursor::new(&asc), asc.len() as u32).unwrap();
+        assert_eq!(desc.config_bytes(), asc);
+        desc.freq_index = 3;
+        assert_eq!(desc.config_bytes(), [0x11, 0x90]);
+    }
+
+    #[test]
+    fn test_dec_specific_round_trip() {
+        // HE-AACv2 with SBR and PS signaled after AAC-LC at an explicit
+        // 44.1 kHz, which two bytes can't hold.
+        let asc = [0x17, 0x80, 0x56, 0x22, 0x2B, 0x8A, 0x80, 0xA8, 0x80];
+        let mut esds = EsdsBox::default();
+        esds.es_desc.dec_config.object_type_indication = 0x40;
+        esds.es_desc.dec_config.dec_specific =
+            DecoderSpecificDescriptor::read_desc(&mut Cursor::new(&asc), asc.len() as u32).unwrap();
+        assert_eq!(esds.es_desc.dec_config.dec_specific.config_bytes(), asc);
+
+        let mut buf = Vec::new();
+        esds.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len() as u64, esds.box_size());
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = EsdsBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(dst_box, esds);
+        assert_eq!(dst_box.es_desc.dec_config.dec_specific.data, asc);
+    }
+
+    #[test]
+    fn test_mp4a_json() {
+        let mp4a = Mp4aBox::default();
+        let json: serde_json::Value = serde_json::from_str(&mp4a.to_json().unwrap()).unwrap();
+        assert_eq!(json["samplerate"], 48000);
+    }
+
     #[test]
     fn test_mp4a_no_esds() {
         let src_box = Mp4aBox {
@@ -592,6 +1071,9 @@ mod tests {
             samplesize: 16,
             samplerate: FixedPointU16::new(48000),
             esds: None,
+            chnl: None,
+            sinf: None,
+            unknown_boxes: Vec::new(),
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -605,4 +1087,17 @@ mod tests {
         let dst_box = Mp4aBox::read_box(&mut reader, header.size).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_mp4a_try_new() {
+        let config = AacConfig::default();
+        assert!(matches!(Mp4aBox::try_new(&config, 0), Err(Error::InvalidData(_))));
+        assert_eq!(Mp4aBox::try_new(&config, 1).unwrap(), Mp4aBox::new(&config, 1));
+
+        let high_rate = AacConfig {
+            freq_index: SampleFreqIndex::Freq96000,
+            ..config
+        };
+        assert!(Mp4aBox::try_new(&high_rate, 1).is_err());
+    }
 }
@@ -1,22 +1,45 @@
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, SeekFrom, Write};
 use serde::{Serialize};
 
 use crate::mp4box::*;
-use crate::mp4box::{mehd::MehdBox, trex::TrexBox};
+use crate::mp4box::{mehd::MehdBox, trep::TrepBox, trex::TrexBox};
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct MvexBox {
     pub mehd: Option<MehdBox>,
-    pub trex: TrexBox,
+
+    #[serde(rename = "trex")]
+    pub trexs: Vec<TrexBox>,
+
+    #[serde(rename = "trep", skip_serializing_if = "Vec::is_empty")]
+    pub treps: Vec<TrepBox>,
 }
 
 impl MvexBox {
     pub fn get_type(&self) -> BoxType {
-        BoxType::MdiaBox
+        BoxType::MvexBox
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + self.mehd.as_ref().map(|x| x.box_size()).unwrap_or(0) + self.trex.box_size()
+        let mut size = HEADER_SIZE + self.mehd.as_ref().map(|x| x.box_size()).unwrap_or(0);
+        for trex in self.trexs.iter() {
+            size += trex.box_size();
+        }
+        for trep in self.treps.iter() {
+            size += trep.box_size();
+        }
+        size
+    }
+
+    /// Returns the trex box holding the fragment defaults for `track_id`.
+    pub fn trex(&self, track_id: u32) -> Option<&TrexBox> {
+        self.trexs.iter().find(|trex| trex.track_id == track_id)
+    }
+
+    /// Returns the trep box holding the extension properties of `track_id`.
+    pub fn trep(&self, track_id: u32) -> Option<&TrepBox> {
+        self.treps.iter().find(|trep| trep.track_id == track_id)
     }
 }
 
@@ -37,14 +60,30 @@ impl Mp4Box for MvexBox {
         let s = format!("");
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = Vec::new();
+        if let Some(ref mehd) = self.mehd {
+            children.push(mehd);
+        }
+        for trex in self.trexs.iter() {
+            children.push(trex);
+        }
+        for trep in self.treps.iter() {
+            children.push(trep);
+        }
+        children
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for MvexBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
 
         let mut mehd = None;
-        let mut trex = None;
+        let mut trexs = Vec::new();
+        let mut treps = Vec::new();
 
         let mut current = reader.seek(SeekFrom::Current(0))?;
         let end = start + size;
@@ -58,7 +97,10 @@ impl<R: Read + Seek> ReadBox<&mut R> for MvexBox {
                     mehd = Some(MehdBox::read_box(reader, s)?);
                 }
                 BoxType::TrexBox => {
-                    trex = Some(TrexBox::read_box(reader, s)?);
+                    trexs.push(TrexBox::read_box(reader, s)?);
+                }
+                BoxType::TrepBox => {
+                    treps.push(TrepBox::read_box(reader, s)?);
                 }
                 _ => {
                     // XXX warn!()
@@ -69,7 +111,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for MvexBox {
             current = reader.seek(SeekFrom::Current(0))?;
         }
 
-        if trex.is_none() {
+        if trexs.is_empty() {
             return Err(Error::BoxNotFound(BoxType::TrexBox));
         }
 
@@ -77,11 +119,13 @@ impl<R: Read + Seek> ReadBox<&mut R> for MvexBox {
 
         Ok(MvexBox {
             mehd,
-            trex: trex.unwrap(),
+            trexs,
+            treps,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for MvexBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -90,8 +134,52 @@ impl<W: Write> WriteBox<&mut W> for MvexBox {
         if let Some(mehd) = &self.mehd{
             mehd.write_box(writer)?;
         }
-        self.trex.write_box(writer)?;
+        for trex in self.trexs.iter() {
+            trex.write_box(writer)?;
+        }
+        for trep in self.treps.iter() {
+            trep.write_box(writer)?;
+        }
 
         Ok(size)
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_mvex_trep() {
+        let src_box = MvexBox {
+            mehd: None,
+            trexs: vec![TrexBox {
+                track_id: 1,
+                default_sample_description_index: 1,
+                ..TrexBox::default()
+            }],
+            treps: vec![TrepBox {
+                track_id: 1,
+                children: vec![RawBox {
+                    box_type: str::parse("abcd").unwrap(),
+                    data: vec![0xde, 0xad],
+                }],
+                ..TrepBox::default()
+            }],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::MvexBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = MvexBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert!(dst_box.trep(1).is_some());
+    }
+}
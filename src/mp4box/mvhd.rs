@@ -1,4 +1,6 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -15,6 +17,7 @@ pub struct MvhdBox {
 
     #[serde(with = "value_u32")]
     pub rate: FixedPointU16,
+    pub next_track_id: u32,
 }
 
 impl MvhdBox {
@@ -32,6 +35,11 @@ impl MvhdBox {
         size += 80;
         size
     }
+
+    /// Returns true if the duration is the all-ones "unknown" value.
+    pub fn is_duration_unknown(&self) -> bool {
+        is_unknown_duration(self.version, self.duration)
+    }
 }
 
 impl Default for MvhdBox {
@@ -44,6 +52,7 @@ impl Default for MvhdBox {
             timescale: 1000,
             duration: 0,
             rate: FixedPointU16::new(1),
+            next_track_id: 1,
         }
     }
 }
@@ -62,12 +71,13 @@ impl Mp4Box for MvhdBox {
     }
 
     fn summary(&self) -> Result<String> {
-        let s = format!("creation_time={} timescale={} duration={} rate={}",
-            self.creation_time, self.timescale, self.duration, self.rate.value());
+        let s = format!("creation_time={} timescale={} duration={} rate={} next_track_id={}",
+            self.creation_time, self.timescale, self.duration, self.rate.value(), self.next_track_id);
         Ok(s)
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for MvhdBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -93,6 +103,9 @@ impl<R: Read + Seek> ReadBox<&mut R> for MvhdBox {
         };
         let rate = FixedPointU16::new_raw(reader.read_u32::<BigEndian>()?);
 
+        skip_bytes(reader, 72)?; // volume, reserved, matrix, pre-defined
+        let next_track_id = reader.read_u32::<BigEndian>()?;
+
         skip_bytes_to(reader, start + size)?;
 
         Ok(MvhdBox {
@@ -103,10 +116,12 @@ impl<R: Read + Seek> ReadBox<&mut R> for MvhdBox {
             timescale,
             duration,
             rate,
+            next_track_id,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for MvhdBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -130,13 +145,14 @@ impl<W: Write> WriteBox<&mut W> for MvhdBox {
         writer.write_u32::<BigEndian>(self.rate.raw_value())?;
 
         // XXX volume, ...
-        write_zeros(writer, 76)?;
+        write_zeros(writer, 72)?;
+        writer.write_u32::<BigEndian>(self.next_track_id)?;
 
         Ok(size)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -152,6 +168,7 @@ mod tests {
             timescale: 1000,
             duration: 634634,
             rate: FixedPointU16::new(1),
+            next_track_id: 2,
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -176,6 +193,7 @@ mod tests {
             timescale: 1000,
             duration: 634634,
             rate: FixedPointU16::new(1),
+            next_track_id: 2,
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
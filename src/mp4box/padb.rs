@@ -0,0 +1,139 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct PadbBox {
+    pub version: u8,
+    pub flags: u32,
+
+    /// Number of padding bits (0-7) at the end of each sample.
+    #[serde(skip_serializing)]
+    pub pad_bits: Vec<u8>,
+}
+
+impl PadbBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::PadbBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        // Two samples are packed per byte.
+        HEADER_SIZE + HEADER_EXT_SIZE + 4 + (self.pad_bits.len() as u64).div_ceil(2)
+    }
+
+    /// Returns the padding bits of a sample, where sample_id starts at 1.
+    pub fn sample_padding(&self, sample_id: u32) -> Option<u8> {
+        if sample_id == 0 {
+            return None;
+        }
+        self.pad_bits.get(sample_id as usize - 1).copied()
+    }
+}
+
+impl Mp4Box for PadbBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("sample_count={}", self.pad_bits.len());
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for PadbBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        let sample_count = reader.read_u32::<BigEndian>()?;
+        let mut pad_bits = Vec::with_capacity(sample_count as usize);
+        for i in 0..sample_count.div_ceil(2) {
+            // reserved(1) pad1(3) reserved(1) pad2(3)
+            let b = reader.read_u8()?;
+            pad_bits.push((b >> 4) & 0x07);
+            if i * 2 + 1 < sample_count {
+                pad_bits.push(b & 0x07);
+            }
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(PadbBox {
+            version,
+            flags,
+            pad_bits,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for PadbBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(self.pad_bits.len() as u32)?;
+        for pair in self.pad_bits.chunks(2) {
+            let pad1 = pair[0] & 0x07;
+            // The trailing nibble of an odd sample count is left zero.
+            let pad2 = pair.get(1).map(|p| p & 0x07).unwrap_or(0);
+            writer.write_u8(pad1 << 4 | pad2)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    fn test_padb(pad_bits: Vec<u8>) {
+        let src_box = PadbBox {
+            version: 0,
+            flags: 0,
+            pad_bits,
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::PadbBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = PadbBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_padb_even() {
+        test_padb(vec![1, 7, 0, 3]);
+    }
+
+    #[test]
+    fn test_padb_odd() {
+        test_padb(vec![2, 5, 4]);
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct PitmBox {
+    pub version: u8,
+    pub flags: u32,
+    pub item_id: u32,
+}
+
+impl PitmBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::PitmBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        // Version 0 uses a 16-bit item ID.
+        if self.version == 0 {
+            HEADER_SIZE + HEADER_EXT_SIZE + 2
+        } else {
+            HEADER_SIZE + HEADER_EXT_SIZE + 4
+        }
+    }
+}
+
+impl Mp4Box for PitmBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("item_id={}", self.item_id);
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for PitmBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let item_id = if version == 0 {
+            reader.read_u16::<BigEndian>()? as u32
+        } else {
+            reader.read_u32::<BigEndian>()?
+        };
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(PitmBox {
+            version,
+            flags,
+            item_id,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for PitmBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        if self.version == 0 {
+            writer.write_u16::<BigEndian>(self.item_id as u16)?;
+        } else {
+            writer.write_u32::<BigEndian>(self.item_id)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_pitm() {
+        let src_box = PitmBox {
+            version: 0,
+            flags: 0,
+            item_id: 1,
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::PitmBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = PitmBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
@@ -0,0 +1,261 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, SeekFrom, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+/// 3GPP AMR audio sample entry (TS 26.244), either narrowband (samr) or
+/// wideband (sawb).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SamrBox {
+    /// Set for AMR-WB (sawb) sample entries.
+    pub wideband: bool,
+    pub data_reference_index: u16,
+    pub channelcount: u16,
+    pub samplesize: u16,
+
+    #[serde(with = "value_u32")]
+    pub samplerate: FixedPointU16,
+    pub damr: DamrBox,
+}
+
+impl Default for SamrBox {
+    fn default() -> Self {
+        Self {
+            wideband: false,
+            data_reference_index: 0,
+            channelcount: 1,
+            samplesize: 16,
+            samplerate: FixedPointU16::new(8000),
+            damr: DamrBox::default(),
+        }
+    }
+}
+
+impl SamrBox {
+    pub fn get_type(&self) -> BoxType {
+        if self.wideband {
+            BoxType::SawbBox
+        } else {
+            BoxType::SamrBox
+        }
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + 8 + 20 + self.damr.box_size()
+    }
+}
+
+impl Mp4Box for SamrBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("channel_count={} sample_size={} sample_rate={}",
+            self.channelcount, self.samplesize, self.samplerate.value());
+        Ok(s)
+    }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        vec![&self.damr]
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for SamrBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+        // The entry type is the only difference between samr and sawb.
+        reader.seek(SeekFrom::Start(start + 4))?;
+        let wideband = BoxType::from(reader.read_u32::<BigEndian>()?) == BoxType::SawbBox;
+
+        reader.read_u32::<BigEndian>()?; // reserved
+        reader.read_u16::<BigEndian>()?; // reserved
+        let data_reference_index = reader.read_u16::<BigEndian>()?;
+
+        reader.read_u64::<BigEndian>()?; // reserved
+        let channelcount = reader.read_u16::<BigEndian>()?;
+        let samplesize = reader.read_u16::<BigEndian>()?;
+        reader.read_u32::<BigEndian>()?; // pre-defined, reserved
+        let samplerate = FixedPointU16::new_raw(reader.read_u32::<BigEndian>()?);
+
+        let mut damr = None;
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current + HEADER_SIZE <= end {
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+
+            match name {
+                BoxType::DamrBox => {
+                    damr = Some(DamrBox::read_box(reader, s)?);
+                }
+                _ => {
+                    skip_box(reader, s)?;
+                }
+            }
+            current = reader.stream_position()?;
+        }
+        let damr = damr.ok_or(Error::BoxNotFound(BoxType::DamrBox))?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(SamrBox {
+            wideband,
+            data_reference_index,
+            channelcount,
+            samplesize,
+            samplerate,
+            damr,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for SamrBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        writer.write_u32::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(self.data_reference_index)?;
+
+        writer.write_u64::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(self.channelcount)?;
+        writer.write_u16::<BigEndian>(self.samplesize)?;
+        writer.write_u32::<BigEndian>(0)?; // reserved
+        writer.write_u32::<BigEndian>(self.samplerate.raw_value())?;
+
+        self.damr.write_box(writer)?;
+
+        Ok(size)
+    }
+}
+
+/// AMR decoder specific configuration.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct DamrBox {
+    pub vendor: FourCC,
+    pub decoder_version: u8,
+    pub mode_set: u16,
+    pub mode_change_period: u8,
+    pub frames_per_sample: u8,
+}
+
+impl DamrBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::DamrBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + 9
+    }
+}
+
+impl Mp4Box for DamrBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("vendor={} decoder_version={} mode_set={:#06x} frames_per_sample={}",
+            self.vendor, self.decoder_version, self.mode_set, self.frames_per_sample);
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for DamrBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let vendor = reader.read_u32::<BigEndian>()?.into();
+        let decoder_version = reader.read_u8()?;
+        let mode_set = reader.read_u16::<BigEndian>()?;
+        let mode_change_period = reader.read_u8()?;
+        let frames_per_sample = reader.read_u8()?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(DamrBox {
+            vendor,
+            decoder_version,
+            mode_set,
+            mode_change_period,
+            frames_per_sample,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for DamrBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        writer.write_u32::<BigEndian>((&self.vendor).into())?;
+        writer.write_u8(self.decoder_version)?;
+        writer.write_u16::<BigEndian>(self.mode_set)?;
+        writer.write_u8(self.mode_change_period)?;
+        writer.write_u8(self.frames_per_sample)?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_samr() {
+        let src_box = SamrBox {
+            wideband: false,
+            data_reference_index: 1,
+            channelcount: 1,
+            samplesize: 16,
+            samplerate: FixedPointU16::new(8000),
+            damr: DamrBox {
+                vendor: str::parse("FFMP").unwrap(),
+                decoder_version: 0,
+                mode_set: 0x81ff,
+                mode_change_period: 0,
+                frames_per_sample: 1,
+            },
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::SamrBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = SamrBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
@@ -0,0 +1,206 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+/// Sample to group box, mapping runs of samples to entries of the sgpd of
+/// the same grouping type.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SbgpBox {
+    pub version: u8,
+    pub flags: u32,
+    pub grouping_type: FourCC,
+
+    /// Version 1: tells apart several groupings of the same type, e.g. the
+    /// scheme of a 'seig' grouping. Zero for version 0.
+    pub grouping_type_parameter: u32,
+
+    #[serde(skip_serializing)]
+    pub entries: Vec<SbgpEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SbgpEntry {
+    pub sample_count: u32,
+    /// 1-based index into the sgpd entries, or 0 for no group.
+    pub group_description_index: u32,
+}
+
+impl SbgpBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::SbgpBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 4 + 4 + 8 * self.entries.len() as u64;
+        if self.version == 1 {
+            size += 4;
+        }
+        size
+    }
+
+    /// Returns the group description index of a sample, or 0 if the sample
+    /// isn't mapped to a group of this type.
+    pub fn group_description_index(&self, sample_id: u32) -> u32 {
+        let mut first_sample = 1u64;
+        for entry in self.entries.iter() {
+            let next = first_sample + entry.sample_count as u64;
+            if (sample_id as u64) < next {
+                return if sample_id as u64 >= first_sample { entry.group_description_index } else { 0 };
+            }
+            first_sample = next;
+        }
+        0
+    }
+}
+
+impl Mp4Box for SbgpBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("grouping_type={} grouping_type_parameter={} entry_count={}",
+            self.grouping_type, self.grouping_type_parameter, self.entries.len());
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for SbgpBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let grouping_type = reader.read_u32::<BigEndian>()?.into();
+        let grouping_type_parameter = if version == 1 {
+            reader.read_u32::<BigEndian>()?
+        } else {
+            0
+        };
+        let entry_count = reader.read_u32::<BigEndian>()?;
+
+        let remaining = (start + size).saturating_sub(reader.stream_position()?);
+        if entry_count as u64 * 8 > remaining {
+            return Err(Error::InvalidData("sbgp entry_count exceeds the box size"));
+        }
+        let mut entries = Vec::with_capacity(entry_count as usize);
+        for _ in 0..entry_count {
+            entries.push(SbgpEntry {
+                sample_count: reader.read_u32::<BigEndian>()?,
+                group_description_index: reader.read_u32::<BigEndian>()?,
+            });
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(SbgpBox {
+            version,
+            flags,
+            grouping_type,
+            grouping_type_parameter,
+            entries,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for SbgpBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        writer.write_u32::<BigEndian>((&self.grouping_type).into())?;
+        if self.version == 1 {
+            writer.write_u32::<BigEndian>(self.grouping_type_parameter)?;
+        } else if self.grouping_type_parameter != 0 {
+            return Err(Error::InvalidData("sbgp grouping_type_parameter needs version 1"));
+        }
+        writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+        for entry in self.entries.iter() {
+            writer.write_u32::<BigEndian>(entry.sample_count)?;
+            writer.write_u32::<BigEndian>(entry.group_description_index)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::stbl::StblBox;
+    use crate::mp4box::stco::StcoBox;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_sbgp() {
+        let src_box = SbgpBox {
+            version: 1,
+            flags: 0,
+            grouping_type: str::parse("roll").unwrap(),
+            grouping_type_parameter: 7,
+            entries: vec![
+                SbgpEntry { sample_count: 2, group_description_index: 1 },
+                SbgpEntry { sample_count: 3, group_description_index: 0 },
+            ],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::SbgpBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = SbgpBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.group_description_index(2), 1);
+        assert_eq!(dst_box.group_description_index(3), 0);
+        assert_eq!(dst_box.group_description_index(6), 0);
+    }
+
+    #[test]
+    fn test_sbgp_same_grouping_type() {
+        let sbgp = |grouping_type_parameter, group_description_index| SbgpBox {
+            version: 1,
+            grouping_type: str::parse("seig").unwrap(),
+            grouping_type_parameter,
+            entries: vec![SbgpEntry { sample_count: 4, group_description_index }],
+            ..SbgpBox::default()
+        };
+        let src_box = StblBox {
+            stco: Some(StcoBox::default()),
+            sbgps: vec![sbgp(1, 1), sbgp(2, 2)],
+            ..StblBox::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = StblBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box.sbgps, dst_box.sbgps);
+
+        let first = dst_box.sbgp(*b"seig", 1).unwrap();
+        assert_eq!(first.group_description_index(1), 1);
+        let second = dst_box.sbgp(*b"seig", 2).unwrap();
+        assert_eq!(second.group_description_index(1), 2);
+        assert!(dst_box.sbgp(*b"seig", 0).is_none());
+    }
+}
@@ -0,0 +1,179 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SchmBox {
+    pub version: u8,
+    pub flags: u32,
+    pub scheme_type: FourCC,
+    pub scheme_version: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme_uri: Option<String>,
+}
+
+impl SchmBox {
+    pub const FLAG_SCHEME_URI_PRESENT: u32 = 0x000001;
+
+    pub fn new(scheme: EncryptionScheme) -> Self {
+        SchmBox {
+            version: 0,
+            flags: 0,
+            scheme_type: scheme.into(),
+            scheme_version: 0x00010000,
+            scheme_uri: None,
+        }
+    }
+
+    pub fn get_type(&self) -> BoxType {
+        BoxType::SchmBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 8;
+        if let Some(ref uri) = self.scheme_uri {
+            size += uri.len() as u64 + 1;
+        }
+        size
+    }
+
+    /// Returns the Common Encryption scheme, if scheme_type is one.
+    pub fn encryption_scheme(&self) -> Result<EncryptionScheme> {
+        EncryptionScheme::try_from(&self.scheme_type)
+    }
+}
+
+impl Mp4Box for SchmBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("scheme_type={} scheme_version={:#x}",
+            self.scheme_type, self.scheme_version);
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for SchmBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let scheme_type = reader.read_u32::<BigEndian>()?;
+        let scheme_version = reader.read_u32::<BigEndian>()?;
+
+        let scheme_uri = if flags & SchmBox::FLAG_SCHEME_URI_PRESENT != 0 {
+            let uri_size = (start + size)
+                .checked_sub(start + HEADER_SIZE + HEADER_EXT_SIZE + 8)
+                .ok_or(Error::InvalidData("schm box too small"))?;
+            let mut buf = vec![0u8; uri_size as usize];
+            reader.read_exact(&mut buf)?;
+            if let Some(end) = buf.iter().position(|&b| b == 0) {
+                buf.truncate(end);
+            }
+            let uri = String::from_utf8(buf)
+                .map_err(|_| Error::InvalidData("invalid utf8 in scheme_uri"))?;
+            Some(uri)
+        } else {
+            None
+        };
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(SchmBox {
+            version,
+            flags,
+            scheme_type: From::from(scheme_type),
+            scheme_version,
+            scheme_uri,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for SchmBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        let mut flags = self.flags & !SchmBox::FLAG_SCHEME_URI_PRESENT;
+        if self.scheme_uri.is_some() {
+            flags |= SchmBox::FLAG_SCHEME_URI_PRESENT;
+        }
+        write_box_header_ext(writer, self.version, flags)?;
+
+        writer.write_u32::<BigEndian>((&self.scheme_type).into())?;
+        writer.write_u32::<BigEndian>(self.scheme_version)?;
+        if let Some(ref uri) = self.scheme_uri {
+            writer.write_all(uri.as_bytes())?;
+            writer.write_u8(0)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_schm_cbcs() {
+        let src_box = SchmBox::new(EncryptionScheme::Cbcs);
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::SchmBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = SchmBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+
+        let scheme = dst_box.encryption_scheme().unwrap();
+        assert_eq!(scheme, EncryptionScheme::Cbcs);
+        assert!(scheme.is_cbc());
+        assert!(scheme.is_pattern());
+        assert!(!EncryptionScheme::Cenc.is_cbc());
+    }
+
+    #[test]
+    fn test_schm_scheme_uri() {
+        let src_box = SchmBox {
+            version: 0,
+            flags: SchmBox::FLAG_SCHEME_URI_PRESENT,
+            scheme_type: str::parse("abcd").unwrap(),
+            scheme_version: 1,
+            scheme_uri: Some(String::from("urn:example:scheme")),
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = SchmBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert!(dst_box.encryption_scheme().is_err());
+    }
+}
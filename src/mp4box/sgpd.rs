@@ -0,0 +1,293 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+/// Sample group description box. The group description entries, e.g. the
+/// roll distance of a 'roll' group, are kept as raw bytes.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SgpdBox {
+    pub version: u8,
+    pub flags: u32,
+    pub grouping_type: FourCC,
+
+    /// Version 1: the size of every entry, or 0 if each entry is preceded
+    /// by its own size.
+    pub default_length: u32,
+
+    /// Version 2 and later: the entry that applies to samples not mapped
+    /// by any sbgp.
+    pub default_sample_description_index: u32,
+
+    #[serde(skip_serializing)]
+    pub entries: Vec<Vec<u8>>,
+
+    /// Version 0 or 2 entries that can't be told apart, kept as the
+    /// entry_count and the bytes following it. `entries` is then empty.
+    #[serde(skip_serializing)]
+    pub unparsed_entries: Option<(u32, Vec<u8>)>,
+}
+
+impl SgpdBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::SgpdBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 4 + 4;
+        if self.version >= 1 {
+            size += 4;
+        }
+        for entry in self.entries.iter() {
+            if self.version == 1 && self.default_length == 0 {
+                size += 4;
+            }
+            size += entry.len() as u64;
+        }
+        if let Some((_, ref data)) = self.unparsed_entries {
+            size += data.len() as u64;
+        }
+        size
+    }
+}
+
+impl Mp4Box for SgpdBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("grouping_type={} default_length={} entry_count={}",
+            self.grouping_type, self.default_length, self.entries.len());
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for SgpdBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let grouping_type = reader.read_u32::<BigEndian>()?.into();
+        let mut default_length = 0;
+        let mut default_sample_description_index = 0;
+        if version == 1 {
+            default_length = reader.read_u32::<BigEndian>()?;
+        } else if version >= 2 {
+            default_sample_description_index = reader.read_u32::<BigEndian>()?;
+        }
+        let entry_count = reader.read_u32::<BigEndian>()?;
+
+        let end = start + size;
+        let mut remaining = end.saturating_sub(reader.stream_position()?);
+        let min_length = match (version, default_length) {
+            (1, 0) => 4,
+            (1, length) => length as u64,
+            _ => 1,
+        };
+        if entry_count as u64 * min_length > remaining {
+            return Err(Error::InvalidData("sgpd entry_count exceeds the sgpd"));
+        }
+
+        // Outside of version 1 the entries carry no length, which depends
+        // on the grouping type, so the space left is assumed to be split
+        // evenly between them. If it can't be, the entries are kept whole.
+        let implied_length = if version != 1 && entry_count > 0 {
+            if remaining % entry_count as u64 != 0 {
+                let mut data = vec![0u8; remaining as usize];
+                reader.read_exact(&mut data)?;
+                skip_bytes_to(reader, end)?;
+                return Ok(SgpdBox {
+                    version,
+                    flags,
+                    grouping_type,
+                    default_length,
+                    default_sample_description_index,
+                    entries: Vec::new(),
+                    unparsed_entries: Some((entry_count, data)),
+                });
+            }
+            remaining / entry_count as u64
+        } else {
+            0
+        };
+
+        let mut entries = Vec::with_capacity(entry_count as usize);
+        for _ in 0..entry_count {
+            let length = if version != 1 {
+                implied_length
+            } else if default_length == 0 {
+                remaining = remaining.saturating_sub(4);
+                reader.read_u32::<BigEndian>()? as u64
+            } else {
+                default_length as u64
+            };
+            if length > remaining {
+                return Err(Error::InvalidData("sgpd entry extends past the sgpd"));
+            }
+            let mut entry = vec![0u8; length as usize];
+            reader.read_exact(&mut entry)?;
+            entries.push(entry);
+            remaining -= length;
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(SgpdBox {
+            version,
+            flags,
+            grouping_type,
+            default_length,
+            default_sample_description_index,
+            entries,
+            unparsed_entries: None,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for SgpdBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        writer.write_u32::<BigEndian>((&self.grouping_type).into())?;
+        if self.version == 1 {
+            writer.write_u32::<BigEndian>(self.default_length)?;
+        } else if self.version >= 2 {
+            writer.write_u32::<BigEndian>(self.default_sample_description_index)?;
+        }
+        if let Some((entry_count, ref data)) = self.unparsed_entries {
+            writer.write_u32::<BigEndian>(entry_count)?;
+            writer.write_all(data)?;
+            return Ok(size);
+        }
+        writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+        for entry in self.entries.iter() {
+            if self.version == 1 && self.default_length == 0 {
+                writer.write_u32::<BigEndian>(entry.len() as u32)?;
+            } else if self.version == 1 && entry.len() as u32 != self.default_length {
+                return Err(Error::InvalidData("sgpd entry length differs from default_length"));
+            }
+            writer.write_all(entry)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    fn round_trip(src_box: &SgpdBox) -> SgpdBox {
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::SgpdBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        SgpdBox::read_box(&mut reader, header.size).unwrap()
+    }
+
+    #[test]
+    fn test_sgpd_v1_default_length() {
+        let src_box = SgpdBox {
+            version: 1,
+            flags: 0,
+            grouping_type: str::parse("roll").unwrap(),
+            default_length: 2,
+            default_sample_description_index: 0,
+            entries: vec![vec![0xff, 0xfe], vec![0x00, 0x02]],
+            unparsed_entries: None,
+        };
+        assert_eq!(round_trip(&src_box), src_box);
+    }
+
+    #[test]
+    fn test_sgpd_v1_variable_length() {
+        let src_box = SgpdBox {
+            version: 1,
+            flags: 0,
+            grouping_type: str::parse("seig").unwrap(),
+            default_length: 0,
+            default_sample_description_index: 0,
+            entries: vec![vec![0; 20], vec![1, 2, 3], Vec::new(), vec![4; 37]],
+            unparsed_entries: None,
+        };
+        let dst_box = round_trip(&src_box);
+        assert_eq!(dst_box, src_box);
+        let lengths: Vec<usize> = dst_box.entries.iter().map(|entry| entry.len()).collect();
+        assert_eq!(lengths, vec![20, 3, 0, 37]);
+    }
+
+    #[test]
+    fn test_sgpd_v2() {
+        let src_box = SgpdBox {
+            version: 2,
+            flags: 0,
+            grouping_type: str::parse("rap ").unwrap(),
+            default_length: 0,
+            default_sample_description_index: 1,
+            entries: vec![vec![0x80], vec![0x00]],
+            unparsed_entries: None,
+        };
+        assert_eq!(round_trip(&src_box), src_box);
+    }
+
+    #[test]
+    fn test_sgpd_wrong_default_length() {
+        let sgpd = SgpdBox {
+            version: 1,
+            default_length: 2,
+            entries: vec![vec![0; 3]],
+            ..SgpdBox::default()
+        };
+        assert!(sgpd.write_box(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_sgpd_v2_uneven_entries() {
+        let src_box = SgpdBox {
+            version: 2,
+            grouping_type: str::parse("rap ").unwrap(),
+            unparsed_entries: Some((2, vec![0x80, 0x00, 0x01])),
+            ..SgpdBox::default()
+        };
+        let dst_box = round_trip(&src_box);
+        assert_eq!(dst_box, src_box);
+        assert!(dst_box.entries.is_empty());
+    }
+
+    #[test]
+    fn test_sgpd_entry_count_too_large() {
+        let mut buf = Vec::new();
+        BoxHeader::new(BoxType::SgpdBox, 24).write(&mut buf).unwrap();
+        buf.extend_from_slice(&[0, 0, 0, 0]);
+        buf.extend_from_slice(b"roll");
+        buf.extend_from_slice(&u32::MAX.to_be_bytes());
+        buf.extend_from_slice(&[0, 0, 0, 0]);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert!(SgpdBox::read_box(&mut reader, header.size).is_err());
+    }
+}
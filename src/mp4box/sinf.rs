@@ -0,0 +1,331 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+use crate::mp4box::schm::SchmBox;
+use crate::mp4box::tenc::TencBox;
+
+/// Protection scheme information, found in protected sample entries
+/// (encv, enca) and protected items.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SinfBox {
+    pub frma: FrmaBox,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schm: Option<SchmBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schi: Option<SchiBox>,
+}
+
+impl SinfBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::SinfBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + self.frma.box_size();
+        if let Some(ref schm) = self.schm {
+            size += schm.box_size();
+        }
+        if let Some(ref schi) = self.schi {
+            size += schi.box_size();
+        }
+        size
+    }
+
+    pub fn encryption_scheme(&self) -> Option<EncryptionScheme> {
+        self.schm.as_ref().and_then(|schm| schm.encryption_scheme().ok())
+    }
+
+    pub fn tenc(&self) -> Option<&TencBox> {
+        self.schi.as_ref().and_then(|schi| schi.tenc.as_ref())
+    }
+}
+
+impl Mp4Box for SinfBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("original_format={}", self.frma.original_format);
+        Ok(s)
+    }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = vec![&self.frma];
+        if let Some(ref schm) = self.schm {
+            children.push(schm);
+        }
+        if let Some(ref schi) = self.schi {
+            children.push(schi);
+        }
+        children
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for SinfBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let mut frma = None;
+        let mut schm = None;
+        let mut schi = None;
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current < end {
+            // Get box header.
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+
+            match name {
+                BoxType::FrmaBox => {
+                    frma = Some(FrmaBox::read_box(reader, s)?);
+                }
+                BoxType::SchmBox => {
+                    schm = Some(SchmBox::read_box(reader, s)?);
+                }
+                BoxType::SchiBox => {
+                    schi = Some(SchiBox::read_box(reader, s)?);
+                }
+                _ => {
+                    // XXX warn!()
+                    skip_box(reader, s)?;
+                }
+            }
+            current = reader.stream_position()?;
+        }
+
+        let frma = frma.ok_or(Error::BoxNotFound(BoxType::FrmaBox))?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(SinfBox { frma, schm, schi })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for SinfBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        self.frma.write_box(writer)?;
+        if let Some(ref schm) = self.schm {
+            schm.write_box(writer)?;
+        }
+        if let Some(ref schi) = self.schi {
+            schi.write_box(writer)?;
+        }
+
+        Ok(size)
+    }
+}
+
+/// The sample entry type the protected entry replaced, e.g. avc1 for encv.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct FrmaBox {
+    pub original_format: FourCC,
+}
+
+impl FrmaBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::FrmaBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + 4
+    }
+}
+
+impl Mp4Box for FrmaBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("original_format={}", self.original_format);
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for FrmaBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let original_format = reader.read_u32::<BigEndian>()?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(FrmaBox {
+            original_format: From::from(original_format),
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for FrmaBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        writer.write_u32::<BigEndian>((&self.original_format).into())?;
+
+        Ok(size)
+    }
+}
+
+/// Scheme-specific data; for Common Encryption this holds the tenc box.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SchiBox {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenc: Option<TencBox>,
+}
+
+impl SchiBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::SchiBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE;
+        if let Some(ref tenc) = self.tenc {
+            size += tenc.box_size();
+        }
+        size
+    }
+}
+
+impl Mp4Box for SchiBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        Ok(String::new())
+    }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        match self.tenc {
+            Some(ref tenc) => vec![tenc],
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for SchiBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let mut tenc = None;
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current < end {
+            // Get box header.
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+
+            match name {
+                BoxType::TencBox => {
+                    tenc = Some(TencBox::read_box(reader, s)?);
+                }
+                _ => {
+                    // XXX warn!()
+                    skip_box(reader, s)?;
+                }
+            }
+            current = reader.stream_position()?;
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(SchiBox { tenc })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for SchiBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        if let Some(ref tenc) = self.tenc {
+            tenc.write_box(writer)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_sinf() {
+        let src_box = SinfBox {
+            frma: FrmaBox {
+                original_format: str::parse("avc1").unwrap(),
+            },
+            schm: Some(SchmBox::new(EncryptionScheme::Cbcs)),
+            schi: Some(SchiBox {
+                tenc: Some(TencBox {
+                    version: 1,
+                    default_crypt_byte_block: 1,
+                    default_skip_byte_block: 9,
+                    default_is_protected: 1,
+                    default_constant_iv: Some(vec![0x01; 16]),
+                    ..Default::default()
+                }),
+            }),
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::SinfBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = SinfBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.encryption_scheme(), Some(EncryptionScheme::Cbcs));
+        assert_eq!(dst_box.tenc().and_then(|tenc| tenc.pattern()), Some((1, 9)));
+    }
+}
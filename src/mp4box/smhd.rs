@@ -1,4 +1,6 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -8,12 +10,23 @@ use crate::mp4box::*;
 pub struct SmhdBox {
     pub version: u8,
     pub flags: u32,
-
-    #[serde(with = "value_i16")]
     pub balance: FixedPointI8,
 }
 
 impl SmhdBox {
+    /// `balance` ranges from -1.0 (full left) to 1.0 (full right) and is
+    /// clamped to that range.
+    pub fn new(balance: f32) -> Self {
+        SmhdBox {
+            balance: FixedPointI8::new_raw((balance.clamp(-1.0, 1.0) * 256.0).round() as i16),
+            ..SmhdBox::default()
+        }
+    }
+
+    pub fn balance(&self) -> f32 {
+        self.balance.raw_value() as f32 / 256.0
+    }
+
     pub fn get_type(&self) -> BoxType {
         BoxType::SmhdBox
     }
@@ -52,6 +65,7 @@ impl Mp4Box for SmhdBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for SmhdBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -70,6 +84,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for SmhdBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for SmhdBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -84,7 +99,7 @@ impl<W: Write> WriteBox<&mut W> for SmhdBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -109,4 +124,21 @@ mod tests {
         let dst_box = SmhdBox::read_box(&mut reader, header.size).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_smhd_balance() {
+        let src_box = SmhdBox::new(-0.5);
+        assert_eq!(src_box.balance.raw_value(), -128);
+        let json: serde_json::Value = serde_json::from_str(&src_box.to_json().unwrap()).unwrap();
+        assert_eq!(json["balance"], -0.5);
+
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(&buf[12..14], &[0xff, 0x80]);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = SmhdBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(dst_box.balance(), -0.5);
+    }
 }
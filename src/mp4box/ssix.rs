@@ -0,0 +1,171 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+/// Subsegment index: byte ranges of each level within the subsegments
+/// indexed by the preceding sidx.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SsixBox {
+    pub version: u8,
+    pub flags: u32,
+
+    #[serde(skip_serializing)]
+    pub subsegments: Vec<SsixSubsegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SsixSubsegment {
+    pub ranges: Vec<SsixRange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SsixRange {
+    pub level: u8,
+
+    /// Size of the range in bytes. Only the low 24 bits are stored.
+    pub range_size: u32,
+}
+
+impl SsixBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::SsixBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE + 4
+            + self.subsegments.iter().map(|s| 4 + 4 * s.ranges.len() as u64).sum::<u64>()
+    }
+}
+
+impl Mp4Box for SsixBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("subsegment_count={}", self.subsegments.len());
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for SsixBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        let subsegment_count = reader.read_u32::<BigEndian>()?;
+        // Each subsegment takes at least 4 bytes.
+        if HEADER_SIZE + HEADER_EXT_SIZE + 4 + subsegment_count as u64 * 4 > size {
+            return Err(Error::InvalidData("ssix subsegment_count exceeds box size"));
+        }
+        let mut subsegments = Vec::with_capacity(subsegment_count as usize);
+        for _ in 0..subsegment_count {
+            let range_count = reader.read_u32::<BigEndian>()?;
+            let mut ranges = Vec::new();
+            for _ in 0..range_count {
+                let level = reader.read_u8()?;
+                let range_size = read_u24(reader)?;
+                ranges.push(SsixRange { level, range_size });
+            }
+            subsegments.push(SsixSubsegment { ranges });
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(SsixBox {
+            version,
+            flags,
+            subsegments,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for SsixBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(self.subsegments.len() as u32)?;
+        for subsegment in self.subsegments.iter() {
+            writer.write_u32::<BigEndian>(subsegment.ranges.len() as u32)?;
+            for range in subsegment.ranges.iter() {
+                if range.range_size > 0xFF_FFFF {
+                    return Err(Error::InvalidData("ssix range_size exceeds 24 bits"));
+                }
+                writer.write_u8(range.level)?;
+                write_u24(writer, range.range_size)?;
+            }
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_ssix() {
+        let src_box = SsixBox {
+            version: 0,
+            flags: 0,
+            subsegments: vec![
+                SsixSubsegment {
+                    ranges: vec![
+                        SsixRange {
+                            level: 1,
+                            range_size: 4096,
+                        },
+                        SsixRange {
+                            level: 2,
+                            range_size: 12000,
+                        },
+                    ],
+                },
+                SsixSubsegment {
+                    ranges: vec![
+                        SsixRange {
+                            level: 1,
+                            range_size: 3500,
+                        },
+                        SsixRange {
+                            level: 2,
+                            range_size: 0,
+                        },
+                    ],
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::SsixBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = SsixBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
@@ -1,10 +1,15 @@
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, SeekFrom, Write};
 use serde::{Serialize};
 
 use crate::mp4box::*;
 use crate::mp4box::{
     co64::Co64Box,
+    cslg::CslgBox,
     ctts::CttsBox,
+    padb::PadbBox,
+    sbgp::SbgpBox,
+    sgpd::SgpdBox,
     stco::StcoBox,
     stsc::StscBox,
     stsd::StsdBox,
@@ -21,6 +26,9 @@ pub struct StblBox {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ctts: Option<CttsBox>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cslg: Option<CslgBox>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stss: Option<StssBox>,
     pub stsc: StscBox,
@@ -31,6 +39,19 @@ pub struct StblBox {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub co64: Option<Co64Box>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padb: Option<PadbBox>,
+
+    #[serde(rename = "sgpd", skip_serializing_if = "Vec::is_empty")]
+    pub sgpds: Vec<SgpdBox>,
+
+    #[serde(rename = "sbgp", skip_serializing_if = "Vec::is_empty")]
+    pub sbgps: Vec<SbgpBox>,
+
+    /// Other sample tables, e.g. sdtp or saiz, kept as is.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_boxes: Vec<RawBox>,
 }
 
 impl StblBox {
@@ -45,6 +66,9 @@ impl StblBox {
         if let Some(ref ctts) = self.ctts {
             size += ctts.box_size();
         }
+        if let Some(ref cslg) = self.cslg {
+            size += cslg.box_size();
+        }
         if let Some(ref stss) = self.stss {
             size += stss.box_size();
         }
@@ -56,8 +80,65 @@ impl StblBox {
         if let Some(ref co64) = self.co64 {
             size += co64.box_size();
         }
+        if let Some(ref padb) = self.padb {
+            size += padb.box_size();
+        }
+        for sgpd in self.sgpds.iter() {
+            size += sgpd.box_size();
+        }
+        for sbgp in self.sbgps.iter() {
+            size += sbgp.box_size();
+        }
+        for raw in self.unknown_boxes.iter() {
+            size += raw.get_size();
+        }
         size
     }
+
+    /// Returns the sample group description of the given grouping type,
+    /// e.g. `*b"roll"`.
+    pub fn sgpd(&self, grouping_type: [u8; 4]) -> Option<&SgpdBox> {
+        self.sgpds.iter().find(|sgpd| sgpd.grouping_type.value == grouping_type)
+    }
+
+    /// Returns the sample to group mapping of the given grouping type and
+    /// grouping_type_parameter; version 0 sbgp boxes have parameter 0.
+    pub fn sbgp(&self, grouping_type: [u8; 4], grouping_type_parameter: u32) -> Option<&SbgpBox> {
+        self.sbgps.iter().find(|sbgp| {
+            sbgp.grouping_type.value == grouping_type
+                && sbgp.grouping_type_parameter == grouping_type_parameter
+        })
+    }
+
+    /// Returns the chunk offsets from whichever of the stco or co64 boxes is
+    /// present, or `None` if there is neither.
+    pub fn chunk_offsets(&self) -> Option<Vec<u64>> {
+        match (&self.stco, &self.co64) {
+            (Some(stco), _) => Some(stco.entries.iter().map(|o| *o as u64).collect()),
+            (None, Some(co64)) => Some(co64.entries.clone()),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the number of chunks in the stco or co64 box.
+    pub fn chunk_count(&self) -> Option<u32> {
+        match (&self.stco, &self.co64) {
+            (Some(stco), _) => Some(stco.entries.len() as u32),
+            (None, Some(co64)) => Some(co64.entries.len() as u32),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the offset of a chunk, numbered from 1, from the stco or co64
+    /// box.
+    pub fn chunk_offset(&self, chunk_id: u32) -> Option<u64> {
+        let index = chunk_id.checked_sub(1)? as usize;
+        match (&self.stco, &self.co64) {
+            (Some(stco), _) => stco.entries.get(index).map(|o| *o as u64),
+            (None, Some(co64)) => co64.entries.get(index).copied(),
+            (None, None) => None,
+        }
+    }
 }
 
 impl Mp4Box for StblBox {
@@ -77,8 +158,40 @@ impl Mp4Box for StblBox {
         let s = format!("");
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = vec![&self.stsd, &self.stts];
+        if let Some(ref ctts) = self.ctts {
+            children.push(ctts);
+        }
+        if let Some(ref cslg) = self.cslg {
+            children.push(cslg);
+        }
+        if let Some(ref stss) = self.stss {
+            children.push(stss);
+        }
+        children.push(&self.stsc);
+        children.push(&self.stsz);
+        if let Some(ref stco) = self.stco {
+            children.push(stco);
+        }
+        if let Some(ref co64) = self.co64 {
+            children.push(co64);
+        }
+        if let Some(ref padb) = self.padb {
+            children.push(padb);
+        }
+        for sgpd in self.sgpds.iter() {
+            children.push(sgpd);
+        }
+        for sbgp in self.sbgps.iter() {
+            children.push(sbgp);
+        }
+        children
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for StblBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -86,11 +199,16 @@ impl<R: Read + Seek> ReadBox<&mut R> for StblBox {
         let mut stsd = None;
         let mut stts = None;
         let mut ctts = None;
+        let mut cslg = None;
         let mut stss = None;
         let mut stsc = None;
         let mut stsz = None;
         let mut stco = None;
         let mut co64 = None;
+        let mut padb = None;
+        let mut sgpds = Vec::new();
+        let mut sbgps = Vec::new();
+        let mut unknown_boxes = Vec::new();
 
         let mut current = reader.seek(SeekFrom::Current(0))?;
         let end = start + size;
@@ -109,6 +227,9 @@ impl<R: Read + Seek> ReadBox<&mut R> for StblBox {
                 BoxType::CttsBox => {
                     ctts = Some(CttsBox::read_box(reader, s)?);
                 }
+                BoxType::CslgBox => {
+                    cslg = Some(CslgBox::read_box(reader, s)?);
+                }
                 BoxType::StssBox => {
                     stss = Some(StssBox::read_box(reader, s)?);
                 }
@@ -124,6 +245,18 @@ impl<R: Read + Seek> ReadBox<&mut R> for StblBox {
                 BoxType::Co64Box => {
                     co64 = Some(Co64Box::read_box(reader, s)?);
                 }
+                BoxType::PadbBox => {
+                    padb = Some(PadbBox::read_box(reader, s)?);
+                }
+                BoxType::SgpdBox => {
+                    sgpds.push(SgpdBox::read_box(reader, s)?);
+                }
+                BoxType::SbgpBox => {
+                    sbgps.push(SbgpBox::read_box(reader, s)?);
+                }
+                _ if current + s <= end => {
+                    unknown_boxes.push(RawBox::read(reader, &header)?);
+                }
                 _ => {
                     // XXX warn!()
                     skip_box(reader, s)?;
@@ -154,15 +287,21 @@ impl<R: Read + Seek> ReadBox<&mut R> for StblBox {
             stsd: stsd.unwrap(),
             stts: stts.unwrap(),
             ctts: ctts,
+            cslg,
             stss: stss,
             stsc: stsc.unwrap(),
             stsz: stsz.unwrap(),
             stco: stco,
             co64: co64,
+            padb,
+            sgpds,
+            sbgps,
+            unknown_boxes,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for StblBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -173,6 +312,9 @@ impl<W: Write> WriteBox<&mut W> for StblBox {
         if let Some(ref ctts) = self.ctts {
             ctts.write_box(writer)?;
         }
+        if let Some(ref cslg) = self.cslg {
+            cslg.write_box(writer)?;
+        }
         if let Some(ref stss) = self.stss {
             stss.write_box(writer)?;
         }
@@ -184,6 +326,18 @@ impl<W: Write> WriteBox<&mut W> for StblBox {
         if let Some(ref co64) = self.co64 {
             co64.write_box(writer)?;
         }
+        if let Some(ref padb) = self.padb {
+            padb.write_box(writer)?;
+        }
+        for sgpd in self.sgpds.iter() {
+            sgpd.write_box(writer)?;
+        }
+        for sbgp in self.sbgps.iter() {
+            sbgp.write_box(writer)?;
+        }
+        for raw in self.unknown_boxes.iter() {
+            raw.write(writer)?;
+        }
 
         Ok(size)
     }
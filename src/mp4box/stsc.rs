@@ -1,4 +1,6 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -50,6 +52,7 @@ impl Mp4Box for StscBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for StscBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -57,14 +60,20 @@ impl<R: Read + Seek> ReadBox<&mut R> for StscBox {
         let (version, flags) = read_box_header_ext(reader)?;
 
         let entry_count = reader.read_u32::<BigEndian>()?;
-        let mut entries = Vec::with_capacity(entry_count as usize);
-        for _ in 0..entry_count {
+        let mut entries: Vec<StscEntry> = Vec::with_capacity(entry_count as usize);
+        for i in 0..entry_count {
             let entry = StscEntry {
                 first_chunk: reader.read_u32::<BigEndian>()?,
                 samples_per_chunk: reader.read_u32::<BigEndian>()?,
                 sample_description_index: reader.read_u32::<BigEndian>()?,
                 first_sample: 0,
             };
+            // Chunks are numbered from 1 and each entry must start after the
+            // previous one, which the first_sample computation below relies on.
+            let previous_chunk = entries.last().map(|e| e.first_chunk).unwrap_or(0);
+            if entry.first_chunk <= previous_chunk {
+                return Err(Error::InvalidEntry(BoxType::StscBox, i));
+            }
             entries.push(entry);
         }
 
@@ -91,6 +100,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for StscBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for StscBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -109,7 +119,7 @@ impl<W: Write> WriteBox<&mut W> for StscBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -147,4 +157,31 @@ mod tests {
         let dst_box = StscBox::read_box(&mut reader, header.size).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_stsc_decreasing_first_chunk() {
+        let entry = |first_chunk| StscEntry {
+            first_chunk,
+            samples_per_chunk: 1,
+            sample_description_index: 1,
+            first_sample: 0,
+        };
+        for (first_chunks, bad_index) in [(vec![1, 5, 3], 2), (vec![0, 2], 0), (vec![1, 1], 1)] {
+            let src_box = StscBox {
+                version: 0,
+                flags: 0,
+                entries: first_chunks.into_iter().map(entry).collect(),
+            };
+            let mut buf = Vec::new();
+            src_box.write_box(&mut buf).unwrap();
+
+            let mut reader = Cursor::new(&buf);
+            let header = BoxHeader::read(&mut reader).unwrap();
+            let result = StscBox::read_box(&mut reader, header.size);
+            assert!(matches!(
+                result,
+                Err(Error::InvalidEntry(BoxType::StscBox, index)) if index == bad_index
+            ));
+        }
+    }
 }
@@ -1,10 +1,15 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use std::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, SeekFrom, Write};
 use serde::{Serialize};
 
 use crate::mp4box::*;
 use crate::mp4box::{avc1::Avc1Box, hev1::Hev1Box, mp4a::Mp4aBox, tx3g::Tx3gBox};
 use crate::mp4box::vp09::Vp09Box;
+use crate::mp4box::av01::Av01Box;
+use crate::mp4box::samr::SamrBox;
+use crate::mp4box::sinf::SinfBox;
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct StsdBox {
@@ -20,11 +25,21 @@ pub struct StsdBox {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vp09: Option<Vp09Box>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub av01: Option<Av01Box>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mp4a: Option<Mp4aBox>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub samr: Option<SamrBox>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tx3g: Option<Tx3gBox>,
+
+    /// A sample entry of a type this crate doesn't parse, kept as is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unknown: Option<RawBox>,
 }
 
 impl StsdBox {
@@ -40,13 +55,61 @@ impl StsdBox {
             size += hev1.box_size();
         } else if let Some(ref vp09) = self.vp09 {
             size += vp09.box_size();
+        } else if let Some(ref av01) = self.av01 {
+            size += av01.box_size();
         } else if let Some(ref mp4a) = self.mp4a {
             size += mp4a.box_size();
+        } else if let Some(ref samr) = self.samr {
+            size += samr.box_size();
         } else if let Some(ref tx3g) = self.tx3g {
             size += tx3g.box_size();
+        } else if let Some(ref unknown) = self.unknown {
+            size += unknown.get_size();
         }
         size
     }
+
+    /// Returns true if the stsd holds no sample entry at all.
+    pub fn is_empty(&self) -> bool {
+        self.avc1.is_none()
+            && self.hev1.is_none()
+            && self.vp09.is_none()
+            && self.av01.is_none()
+            && self.mp4a.is_none()
+            && self.samr.is_none()
+            && self.tx3g.is_none()
+            && self.unknown.is_none()
+    }
+
+    /// Returns the data_reference_index of the sample entry, if any.
+    pub fn data_reference_index(&self) -> Option<u16> {
+        if let Some(ref avc1) = self.avc1 {
+            Some(avc1.data_reference_index)
+        } else if let Some(ref hev1) = self.hev1 {
+            Some(hev1.data_reference_index)
+        } else if let Some(ref vp09) = self.vp09 {
+            Some(vp09.data_reference_index)
+        } else if let Some(ref av01) = self.av01 {
+            Some(av01.data_reference_index)
+        } else if let Some(ref mp4a) = self.mp4a {
+            Some(mp4a.data_reference_index)
+        } else if let Some(ref samr) = self.samr {
+            Some(samr.data_reference_index)
+        } else {
+            self.tx3g.as_ref().map(|tx3g| tx3g.data_reference_index)
+        }
+    }
+
+    /// Returns the protection scheme info of an encv or enca sample entry.
+    pub fn sinf(&self) -> Option<&SinfBox> {
+        if let Some(ref avc1) = self.avc1 {
+            avc1.sinf.as_ref()
+        } else if let Some(ref mp4a) = self.mp4a {
+            mp4a.sinf.as_ref()
+        } else {
+            None
+        }
+    }
 }
 
 impl Mp4Box for StsdBox {
@@ -66,43 +129,114 @@ impl Mp4Box for StsdBox {
         let s = format!("");
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = Vec::new();
+        if let Some(ref avc1) = self.avc1 {
+            children.push(avc1);
+        }
+        if let Some(ref hev1) = self.hev1 {
+            children.push(hev1);
+        }
+        if let Some(ref vp09) = self.vp09 {
+            children.push(vp09);
+        }
+        if let Some(ref av01) = self.av01 {
+            children.push(av01);
+        }
+        if let Some(ref mp4a) = self.mp4a {
+            children.push(mp4a);
+        }
+        if let Some(ref samr) = self.samr {
+            children.push(samr);
+        }
+        if let Some(ref tx3g) = self.tx3g {
+            children.push(tx3g);
+        }
+        children
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for StsdBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
 
         let (version, flags) = read_box_header_ext(reader)?;
 
-        reader.read_u32::<BigEndian>()?; // XXX entry_count
+        // Only the first sample entry is read.
+        let entry_count = reader.read_u32::<BigEndian>()?;
 
         let mut avc1 = None;
         let mut hev1 = None;
         let mut vp09 = None;
+        let mut av01 = None;
         let mut mp4a = None;
+        let mut samr = None;
         let mut tx3g = None;
+        let mut unknown = None;
+
+        let end = start + size;
+        let current = reader.stream_position()?;
+        if entry_count == 0 || current + HEADER_SIZE > end {
+            skip_bytes_to(reader, end)?;
+            return Ok(StsdBox {
+                version,
+                flags,
+                ..StsdBox::default()
+            });
+        }
 
         // Get box header.
         let header = BoxHeader::read(reader)?;
         let BoxHeader { name, size: s } = header;
+        if current + s > end {
+            return Err(Error::InvalidData("sample entry extends past the stsd"));
+        }
 
         match name {
             BoxType::Avc1Box => {
                 avc1 = Some(Avc1Box::read_box(reader, s)?);
             }
-            BoxType::Hev1Box => {
+            BoxType::Hev1Box | BoxType::Hvc1Box => {
                 hev1 = Some(Hev1Box::read_box(reader, s)?);
             }
             BoxType::Vp09Box => {
                 vp09 = Some(Vp09Box::read_box(reader, s)?);
             }
+            BoxType::Av01Box => {
+                av01 = Some(Av01Box::read_box(reader, s)?);
+            }
             BoxType::Mp4aBox => {
                 mp4a = Some(Mp4aBox::read_box(reader, s)?);
             }
+            BoxType::SamrBox | BoxType::SawbBox => {
+                samr = Some(SamrBox::read_box(reader, s)?);
+            }
             BoxType::Tx3gBox => {
                 tx3g = Some(Tx3gBox::read_box(reader, s)?);
             }
-            _ => {}
+            BoxType::EncvBox => {
+                // 78 bytes of VisualSampleEntry fields precede the children.
+                let format = original_format(reader, s, 78)?;
+                if format == Some(BoxType::Avc1Box.into()) {
+                    avc1 = Some(Avc1Box::read_box(reader, s)?);
+                } else {
+                    unknown = Some(RawBox::read(reader, &header)?);
+                }
+            }
+            BoxType::EncaBox => {
+                // 28 bytes of AudioSampleEntry fields precede the children.
+                let format = original_format(reader, s, 28)?;
+                if format == Some(BoxType::Mp4aBox.into()) {
+                    mp4a = Some(Mp4aBox::read_box(reader, s)?);
+                } else {
+                    unknown = Some(RawBox::read(reader, &header)?);
+                }
+            }
+            _ => {
+                unknown = Some(RawBox::read(reader, &header)?);
+            }
         }
 
         skip_bytes_to(reader, start + size)?;
@@ -113,12 +247,41 @@ impl<R: Read + Seek> ReadBox<&mut R> for StsdBox {
             avc1,
             hev1,
             vp09,
+            av01,
             mp4a,
+            samr,
             tx3g,
+            unknown,
         })
     }
 }
 
+/// Reads the frma of a protected sample entry, then rewinds to the start of
+/// the entry's fields.
+#[cfg(feature = "std")]
+fn original_format<R: Read + Seek>(reader: &mut R, size: u64, fields_size: u64) -> Result<Option<FourCC>> {
+    let start = box_start(reader)?;
+    let end = start + size;
+
+    let mut format = None;
+    let mut current = reader.seek(SeekFrom::Start(start + HEADER_SIZE + fields_size))?;
+    while current + HEADER_SIZE <= end {
+        let header = BoxHeader::read(reader)?;
+        let BoxHeader { name, size: s } = header;
+
+        if name == BoxType::SinfBox {
+            format = Some(SinfBox::read_box(reader, s)?.frma.original_format);
+            break;
+        }
+        skip_box(reader, s)?;
+        current = reader.stream_position()?;
+    }
+
+    reader.seek(SeekFrom::Start(start + HEADER_SIZE))?;
+    Ok(format)
+}
+
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for StsdBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -126,7 +289,8 @@ impl<W: Write> WriteBox<&mut W> for StsdBox {
 
         write_box_header_ext(writer, self.version, self.flags)?;
 
-        writer.write_u32::<BigEndian>(1)?; // entry_count
+        let entry_count = if self.is_empty() { 0 } else { 1 };
+        writer.write_u32::<BigEndian>(entry_count)?;
 
         if let Some(ref avc1) = self.avc1 {
             avc1.write_box(writer)?;
@@ -134,12 +298,93 @@ impl<W: Write> WriteBox<&mut W> for StsdBox {
             hev1.write_box(writer)?;
         } else if let Some(ref vp09) = self.vp09 {
             vp09.write_box(writer)?;
+        } else if let Some(ref av01) = self.av01 {
+            av01.write_box(writer)?;
         } else if let Some(ref mp4a) = self.mp4a {
             mp4a.write_box(writer)?;
+        } else if let Some(ref samr) = self.samr {
+            samr.write_box(writer)?;
         } else if let Some(ref tx3g) = self.tx3g {
             tx3g.write_box(writer)?;
+        } else if let Some(ref unknown) = self.unknown {
+            unknown.write(writer)?;
         }
 
         Ok(size)
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::schm::SchmBox;
+    use crate::mp4box::sinf::FrmaBox;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_stsd_encv() {
+        let config = AvcConfig {
+            width: 320,
+            height: 240,
+            seq_param_set: vec![0x67, 0x64, 0x00, 0x0D],
+            pic_param_set: vec![0x68, 0xEB, 0xE3, 0xCB],
+        };
+        let mut encv = Avc1Box::new(&config, 1);
+        encv.avcc.length_size_minus_one = 3;
+        encv.sinf = Some(SinfBox {
+            frma: FrmaBox {
+                original_format: BoxType::Avc1Box.into(),
+            },
+            schm: Some(SchmBox::new(EncryptionScheme::Cbcs)),
+            schi: None,
+        });
+        let src_box = StsdBox {
+            avc1: Some(encv),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+        assert_eq!(&buf[20..24], b"encv");
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::StsdBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = StsdBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(
+            dst_box.sinf().and_then(|sinf| sinf.encryption_scheme()),
+            Some(EncryptionScheme::Cbcs)
+        );
+    }
+
+    #[test]
+    fn test_stsd_unknown_entry() {
+        let src_box = StsdBox {
+            unknown: Some(RawBox {
+                box_type: str::parse("mjp2").unwrap(),
+                data: vec![0; 86],
+            }),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = StsdBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert!(!dst_box.is_empty());
+
+        let mut buf = Vec::new();
+        StsdBox::default().write_box(&mut buf).unwrap();
+        assert_eq!(&buf[12..16], &[0, 0, 0, 0]);
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert!(StsdBox::read_box(&mut reader, header.size).unwrap().is_empty());
+    }
+}
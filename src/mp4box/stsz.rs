@@ -1,4 +1,6 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -21,7 +23,11 @@ impl StszBox {
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + HEADER_EXT_SIZE + 8 + (4 * self.sample_sizes.len() as u64)
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 8;
+        if self.sample_size == 0 {
+            size += 4 * self.sample_sizes.len() as u64;
+        }
+        size
     }
 }
 
@@ -45,6 +51,7 @@ impl Mp4Box for StszBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for StszBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -73,6 +80,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for StszBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for StszBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -95,7 +103,7 @@ impl<W: Write> WriteBox<&mut W> for StszBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -1,4 +1,6 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -26,6 +28,9 @@ impl SttsBox {
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct SttsEntry {
     pub sample_count: u32,
+    /// May be zero, e.g. for instantaneous metadata samples or the final
+    /// sample of a track. Such samples share the decode time of the next
+    /// sample and are never picked by `Mp4Track::sample_at_time`.
     pub sample_delta: u32,
 }
 
@@ -48,6 +53,7 @@ impl Mp4Box for SttsBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for SttsBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -74,6 +80,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for SttsBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for SttsBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -91,7 +98,7 @@ impl<W: Write> WriteBox<&mut W> for SttsBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
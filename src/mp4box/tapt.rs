@@ -0,0 +1,208 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+/// QuickTime track aperture mode dimensions.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct TaptBox {
+    /// Clean aperture, the size to display the track at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clef: Option<ApertureDimensions>,
+
+    /// Production aperture, the clean aperture without cropping.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prof: Option<ApertureDimensions>,
+
+    /// Encoded pixels aperture.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enof: Option<ApertureDimensions>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApertureDimensions {
+    pub version: u8,
+    pub flags: u32,
+    pub width: FixedPointU16,
+    pub height: FixedPointU16,
+}
+
+impl ApertureDimensions {
+    pub fn new(width: FixedPointU16, height: FixedPointU16) -> Self {
+        ApertureDimensions {
+            version: 0,
+            flags: 0,
+            width,
+            height,
+        }
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE + 8
+    }
+
+    #[cfg(feature = "std")]
+    fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        let (version, flags) = read_box_header_ext(reader)?;
+        let width = FixedPointU16::new_raw(reader.read_u32::<BigEndian>()?);
+        let height = FixedPointU16::new_raw(reader.read_u32::<BigEndian>()?);
+        Ok(ApertureDimensions {
+            version,
+            flags,
+            width,
+            height,
+        })
+    }
+
+    #[cfg(feature = "std")]
+    fn write<W: Write>(&self, writer: &mut W, name: BoxType) -> Result<u64> {
+        let size = self.get_size();
+        BoxHeader::new(name, size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        writer.write_u32::<BigEndian>(self.width.raw_value())?;
+        writer.write_u32::<BigEndian>(self.height.raw_value())?;
+
+        Ok(size)
+    }
+}
+
+impl TaptBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::TaptBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE;
+        for dimensions in [&self.clef, &self.prof, &self.enof].iter().copied().flatten() {
+            size += dimensions.get_size();
+        }
+        size
+    }
+}
+
+impl Mp4Box for TaptBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let mut s = String::new();
+        if let Some(ref clef) = self.clef {
+            s = format!("clean_aperture={}x{}",
+                clef.width.raw_value() as f64 / 65536.0,
+                clef.height.raw_value() as f64 / 65536.0);
+        }
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for TaptBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let mut tapt = TaptBox::default();
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current < end {
+            // Get box header.
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+
+            match name {
+                BoxType::ClefBox => {
+                    tapt.clef = Some(ApertureDimensions::read(reader)?);
+                }
+                BoxType::ProfBox => {
+                    tapt.prof = Some(ApertureDimensions::read(reader)?);
+                }
+                BoxType::EnofBox => {
+                    tapt.enof = Some(ApertureDimensions::read(reader)?);
+                }
+                _ => {}
+            }
+
+            skip_bytes_to(reader, current + s)?;
+            current = reader.stream_position()?;
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(tapt)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for TaptBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        if let Some(ref clef) = self.clef {
+            clef.write(writer, BoxType::ClefBox)?;
+        }
+        if let Some(ref prof) = self.prof {
+            prof.write(writer, BoxType::ProfBox)?;
+        }
+        if let Some(ref enof) = self.enof {
+            enof.write(writer, BoxType::EnofBox)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_tapt_anamorphic() {
+        // 16:9 anamorphic 720x480 displayed at 853.33x480.
+        let src_box = TaptBox {
+            clef: Some(ApertureDimensions::new(
+                FixedPointU16::new_raw(853 << 16 | 0x5555),
+                FixedPointU16::new(480),
+            )),
+            prof: Some(ApertureDimensions::new(
+                FixedPointU16::new_raw(872 << 16 | 0xAAAA),
+                FixedPointU16::new(480),
+            )),
+            enof: Some(ApertureDimensions::new(
+                FixedPointU16::new(720),
+                FixedPointU16::new(480),
+            )),
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::TaptBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = TaptBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+
+        let clef = dst_box.clef.unwrap();
+        assert_eq!(clef.width.value(), 853);
+        assert_eq!(clef.height.value(), 480);
+        assert_eq!(dst_box.enof.unwrap().width.value(), 720);
+    }
+}
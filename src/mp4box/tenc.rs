@@ -0,0 +1,207 @@
+#[cfg(feature = "std")]
+use byteorder::{ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+/// Track encryption defaults from ISO/IEC 23001-7.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct TencBox {
+    pub version: u8,
+    pub flags: u32,
+
+    /// Number of encrypted 16-byte blocks in each pattern. Version 1 only.
+    pub default_crypt_byte_block: u8,
+
+    /// Number of clear 16-byte blocks in each pattern. Version 1 only.
+    pub default_skip_byte_block: u8,
+    pub default_is_protected: u8,
+    pub default_per_sample_iv_size: u8,
+    pub default_kid: [u8; 16],
+
+    /// The IV shared by all samples, used when protected samples carry no
+    /// per-sample IV (e.g. cbcs).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_constant_iv: Option<Vec<u8>>,
+}
+
+impl TencBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::TencBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 20;
+        if self.has_constant_iv() {
+            let iv_size = self.default_constant_iv.as_ref().map(|iv| iv.len()).unwrap_or(0);
+            size += 1 + iv_size as u64;
+        }
+        size
+    }
+
+    /// Returns the (crypt, skip) block pattern for pattern encryption
+    /// schemes, e.g. (1, 9) for cbcs.
+    pub fn pattern(&self) -> Option<(u8, u8)> {
+        if self.version == 0 || (self.default_crypt_byte_block == 0 && self.default_skip_byte_block == 0) {
+            None
+        } else {
+            Some((self.default_crypt_byte_block, self.default_skip_byte_block))
+        }
+    }
+
+    fn has_constant_iv(&self) -> bool {
+        self.default_is_protected == 1 && self.default_per_sample_iv_size == 0
+    }
+}
+
+impl Mp4Box for TencBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("is_protected={} per_sample_iv_size={} crypt_byte_block={} skip_byte_block={}",
+            self.default_is_protected, self.default_per_sample_iv_size,
+            self.default_crypt_byte_block, self.default_skip_byte_block);
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for TencBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        reader.read_u8()?; // reserved
+        let (default_crypt_byte_block, default_skip_byte_block) = if version == 0 {
+            reader.read_u8()?; // reserved
+            (0, 0)
+        } else {
+            let pattern = reader.read_u8()?;
+            (pattern >> 4, pattern & 0x0F)
+        };
+        let default_is_protected = reader.read_u8()?;
+        let default_per_sample_iv_size = reader.read_u8()?;
+        let mut default_kid = [0u8; 16];
+        reader.read_exact(&mut default_kid)?;
+
+        let default_constant_iv = if default_is_protected == 1 && default_per_sample_iv_size == 0 {
+            let iv_size = reader.read_u8()?;
+            let mut iv = vec![0u8; iv_size as usize];
+            reader.read_exact(&mut iv)?;
+            Some(iv)
+        } else {
+            None
+        };
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(TencBox {
+            version,
+            flags,
+            default_crypt_byte_block,
+            default_skip_byte_block,
+            default_is_protected,
+            default_per_sample_iv_size,
+            default_kid,
+            default_constant_iv,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for TencBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u8(0)?; // reserved
+        if self.version == 0 {
+            writer.write_u8(0)?; // reserved
+        } else {
+            if self.default_crypt_byte_block > 0x0F || self.default_skip_byte_block > 0x0F {
+                return Err(Error::InvalidData("tenc pattern block count exceeds 4 bits"));
+            }
+            writer.write_u8(self.default_crypt_byte_block << 4 | self.default_skip_byte_block)?;
+        }
+        writer.write_u8(self.default_is_protected)?;
+        writer.write_u8(self.default_per_sample_iv_size)?;
+        writer.write_all(&self.default_kid)?;
+
+        if self.has_constant_iv() {
+            let iv = self.default_constant_iv.as_deref().unwrap_or(&[]);
+            writer.write_u8(iv.len() as u8)?;
+            writer.write_all(iv)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_tenc_v1_pattern() {
+        let src_box = TencBox {
+            version: 1,
+            flags: 0,
+            default_crypt_byte_block: 1,
+            default_skip_byte_block: 9,
+            default_is_protected: 1,
+            default_per_sample_iv_size: 0,
+            default_kid: [0x11; 16],
+            default_constant_iv: Some(vec![0x22; 16]),
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::TencBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = TencBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.pattern(), Some((1, 9)));
+    }
+
+    #[test]
+    fn test_tenc_v0() {
+        let src_box = TencBox {
+            version: 0,
+            flags: 0,
+            default_is_protected: 1,
+            default_per_sample_iv_size: 8,
+            default_kid: [0x33; 16],
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = TencBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.pattern(), None);
+    }
+}
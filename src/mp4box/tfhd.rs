@@ -1,35 +1,74 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
 use crate::mp4box::*;
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct TfhdBox {
     pub version: u8,
     pub flags: u32,
     pub track_id: u32,
-    pub base_data_offset: u64,
-}
-
-impl Default for TfhdBox {
-    fn default() -> Self {
-        TfhdBox {
-            version: 0,
-            flags: 0,
-            track_id: 0,
-            base_data_offset: 0,
-        }
-    }
+    pub base_data_offset: Option<u64>,
+    pub sample_description_index: Option<u32>,
+    pub default_sample_duration: Option<u32>,
+    pub default_sample_size: Option<u32>,
+    pub default_sample_flags: Option<u32>,
 }
 
 impl TfhdBox {
+    pub const FLAG_BASE_DATA_OFFSET: u32 = 0x01;
+    pub const FLAG_SAMPLE_DESCRIPTION_INDEX: u32 = 0x02;
+    pub const FLAG_DEFAULT_SAMPLE_DURATION: u32 = 0x08;
+    pub const FLAG_DEFAULT_SAMPLE_SIZE: u32 = 0x10;
+    pub const FLAG_DEFAULT_SAMPLE_FLAGS: u32 = 0x20;
+    pub const FLAG_DURATION_IS_EMPTY: u32 = 0x010000;
+    pub const FLAG_DEFAULT_BASE_IS_MOOF: u32 = 0x020000;
+
     pub fn get_type(&self) -> BoxType {
         BoxType::TfhdBox
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + HEADER_EXT_SIZE + 4 + 8
+        let mut sum = HEADER_SIZE + HEADER_EXT_SIZE + 4;
+        if TfhdBox::FLAG_BASE_DATA_OFFSET & self.flags > 0 {
+            sum += 8;
+        }
+        if TfhdBox::FLAG_SAMPLE_DESCRIPTION_INDEX & self.flags > 0 {
+            sum += 4;
+        }
+        if TfhdBox::FLAG_DEFAULT_SAMPLE_DURATION & self.flags > 0 {
+            sum += 4;
+        }
+        if TfhdBox::FLAG_DEFAULT_SAMPLE_SIZE & self.flags > 0 {
+            sum += 4;
+        }
+        if TfhdBox::FLAG_DEFAULT_SAMPLE_FLAGS & self.flags > 0 {
+            sum += 4;
+        }
+        sum
+    }
+
+    /// Resolve sample data offsets relative to the enclosing moof, as
+    /// required for CMAF. Clears any explicit base_data_offset.
+    pub fn set_default_base_is_moof(&mut self) {
+        self.flags &= !TfhdBox::FLAG_BASE_DATA_OFFSET;
+        self.flags |= TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF;
+        self.base_data_offset = None;
+    }
+
+    /// Resolve sample data offsets relative to an explicit file offset.
+    /// Clears the default-base-is-moof flag.
+    pub fn set_base_data_offset(&mut self, offset: u64) {
+        self.flags &= !TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF;
+        self.flags |= TfhdBox::FLAG_BASE_DATA_OFFSET;
+        self.base_data_offset = Some(offset);
+    }
+
+    pub fn default_base_is_moof(&self) -> bool {
+        TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF & self.flags > 0
     }
 }
 
@@ -52,13 +91,38 @@ impl Mp4Box for TfhdBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for TfhdBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
 
         let (version, flags) = read_box_header_ext(reader)?;
         let track_id = reader.read_u32::<BigEndian>()?;
-        let base_data_offset = reader.read_u64::<BigEndian>()?;
+        let base_data_offset = if TfhdBox::FLAG_BASE_DATA_OFFSET & flags > 0 {
+            Some(reader.read_u64::<BigEndian>()?)
+        } else {
+            None
+        };
+        let sample_description_index = if TfhdBox::FLAG_SAMPLE_DESCRIPTION_INDEX & flags > 0 {
+            Some(reader.read_u32::<BigEndian>()?)
+        } else {
+            None
+        };
+        let default_sample_duration = if TfhdBox::FLAG_DEFAULT_SAMPLE_DURATION & flags > 0 {
+            Some(reader.read_u32::<BigEndian>()?)
+        } else {
+            None
+        };
+        let default_sample_size = if TfhdBox::FLAG_DEFAULT_SAMPLE_SIZE & flags > 0 {
+            Some(reader.read_u32::<BigEndian>()?)
+        } else {
+            None
+        };
+        let default_sample_flags = if TfhdBox::FLAG_DEFAULT_SAMPLE_FLAGS & flags > 0 {
+            Some(reader.read_u32::<BigEndian>()?)
+        } else {
+            None
+        };
 
         skip_bytes_to(reader, start + size)?;
 
@@ -67,10 +131,15 @@ impl<R: Read + Seek> ReadBox<&mut R> for TfhdBox {
             flags,
             track_id,
             base_data_offset,
+            sample_description_index,
+            default_sample_duration,
+            default_sample_size,
+            default_sample_flags,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for TfhdBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -78,13 +147,27 @@ impl<W: Write> WriteBox<&mut W> for TfhdBox {
 
         write_box_header_ext(writer, self.version, self.flags)?;
         writer.write_u32::<BigEndian>(self.track_id)?;
-        writer.write_u64::<BigEndian>(self.base_data_offset)?;
+        if TfhdBox::FLAG_BASE_DATA_OFFSET & self.flags > 0 {
+            writer.write_u64::<BigEndian>(self.base_data_offset.unwrap_or(0))?;
+        }
+        if TfhdBox::FLAG_SAMPLE_DESCRIPTION_INDEX & self.flags > 0 {
+            writer.write_u32::<BigEndian>(self.sample_description_index.unwrap_or(1))?;
+        }
+        if TfhdBox::FLAG_DEFAULT_SAMPLE_DURATION & self.flags > 0 {
+            writer.write_u32::<BigEndian>(self.default_sample_duration.unwrap_or(0))?;
+        }
+        if TfhdBox::FLAG_DEFAULT_SAMPLE_SIZE & self.flags > 0 {
+            writer.write_u32::<BigEndian>(self.default_sample_size.unwrap_or(0))?;
+        }
+        if TfhdBox::FLAG_DEFAULT_SAMPLE_FLAGS & self.flags > 0 {
+            writer.write_u32::<BigEndian>(self.default_sample_flags.unwrap_or(0))?;
+        }
 
         Ok(size)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -96,7 +179,38 @@ mod tests {
             version: 0,
             flags: 0,
             track_id: 1,
-            base_data_offset: 0,
+            base_data_offset: None,
+            sample_description_index: None,
+            default_sample_duration: None,
+            default_sample_size: None,
+            default_sample_flags: None,
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::TfhdBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = TfhdBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_tfhd_with_flags() {
+        let src_box = TfhdBox {
+            version: 0,
+            flags: TfhdBox::FLAG_BASE_DATA_OFFSET
+                | TfhdBox::FLAG_DEFAULT_SAMPLE_DURATION
+                | TfhdBox::FLAG_DEFAULT_SAMPLE_SIZE,
+            track_id: 1,
+            base_data_offset: Some(1234),
+            sample_description_index: None,
+            default_sample_duration: Some(1024),
+            default_sample_size: Some(256),
+            default_sample_flags: None,
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -110,4 +224,31 @@ mod tests {
         let dst_box = TfhdBox::read_box(&mut reader, header.size).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_tfhd_base_modes() {
+        let mut src_box = TfhdBox {
+            track_id: 1,
+            ..TfhdBox::default()
+        };
+        src_box.set_base_data_offset(1234);
+        assert_eq!(src_box.flags, TfhdBox::FLAG_BASE_DATA_OFFSET);
+        assert_eq!(src_box.box_size(), 24);
+
+        src_box.set_default_base_is_moof();
+        assert!(src_box.default_base_is_moof());
+        assert_eq!(src_box.flags, TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF);
+        assert_eq!(src_box.box_size(), 16);
+
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), 16);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = TfhdBox::read_box(&mut reader, header.size).unwrap();
+        assert!(dst_box.default_base_is_moof());
+        assert_eq!(dst_box.base_data_offset, None);
+        assert_eq!(src_box, dst_box);
+    }
 }
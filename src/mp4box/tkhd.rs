@@ -1,4 +1,6 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -80,6 +82,11 @@ impl TkhdBox {
         size
     }
 
+    /// Returns true if the duration is the all-ones "unknown" value.
+    pub fn is_duration_unknown(&self) -> bool {
+        is_unknown_duration(self.version, self.duration)
+    }
+
     pub fn set_width(&mut self, width: u16) {
         self.width = FixedPointU16::new(width);
     }
@@ -110,6 +117,7 @@ impl Mp4Box for TkhdBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for TkhdBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -175,6 +183,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for TkhdBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for TkhdBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -222,7 +231,7 @@ impl<W: Write> WriteBox<&mut W> for TkhdBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
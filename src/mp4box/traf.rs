@@ -1,16 +1,112 @@
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, SeekFrom, Write};
 use serde::{Serialize};
 
 use crate::mp4box::*;
-use crate::mp4box::{tfhd::TfhdBox, trun::TrunBox};
+use crate::mp4box::{tfdt::TfdtBox, tfhd::TfhdBox, trun::TrunBox};
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct TrafBox {
     pub tfhd: TfhdBox,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tfdt: Option<TfdtBox>,
+
     pub trun: Option<TrunBox>,
+
+    /// Other children, e.g. senc, saiz or sbgp, kept as is.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_boxes: Vec<RawBox>,
 }
 
+// trun sample flags: sample_depends_on and sample_is_non_sync_sample.
+pub(crate) const SAMPLE_FLAGS_SYNC: u32 = 0x0200_0000;
+pub(crate) const SAMPLE_FLAGS_NON_SYNC: u32 = 0x0101_0000;
+
 impl TrafBox {
+    /// Build the traf of a movie fragment holding `samples` of `track_id`,
+    /// with a default-base-is-moof tfhd, a tfdt and a single trun.
+    ///
+    /// Durations, sizes and flags shared by every sample go into the tfhd
+    /// defaults rather than the trun, and a sync sample followed by non-sync
+    /// samples is written with first_sample_flags. Composition offsets are
+    /// only included if any is non-zero. The trun data_offset is 0 and must
+    /// be set once the size of the moof is known. Without samples, the traf
+    /// has no trun.
+    pub fn from_samples(track_id: u32, base_media_decode_time: u64, samples: &[Mp4Sample]) -> Self {
+        let mut tfhd = TfhdBox {
+            flags: TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF,
+            track_id,
+            ..TfhdBox::default()
+        };
+        if samples.is_empty() {
+            return TrafBox {
+                tfhd,
+                tfdt: Some(TfdtBox::new(base_media_decode_time)),
+                trun: None,
+                unknown_boxes: Vec::new(),
+            };
+        }
+        let mut trun = TrunBox {
+            flags: TrunBox::FLAG_DATA_OFFSET,
+            sample_count: samples.len() as u32,
+            data_offset: Some(0),
+            ..TrunBox::default()
+        };
+
+        let durations: Vec<u32> = samples.iter().map(|sample| sample.duration).collect();
+        match uniform(&durations) {
+            Some(duration) => {
+                tfhd.flags |= TfhdBox::FLAG_DEFAULT_SAMPLE_DURATION;
+                tfhd.default_sample_duration = Some(duration);
+            }
+            None => {
+                trun.flags |= TrunBox::FLAG_SAMPLE_DURATION;
+                trun.sample_durations = durations;
+            }
+        }
+
+        let sizes: Vec<u32> = samples.iter().map(|sample| sample.bytes.len() as u32).collect();
+        match uniform(&sizes) {
+            Some(size) => {
+                tfhd.flags |= TfhdBox::FLAG_DEFAULT_SAMPLE_SIZE;
+                tfhd.default_sample_size = Some(size);
+            }
+            None => {
+                trun.flags |= TrunBox::FLAG_SAMPLE_SIZE;
+                trun.sample_sizes = sizes;
+            }
+        }
+
+        let flags: Vec<u32> = samples.iter()
+            .map(|sample| if sample.is_sync { SAMPLE_FLAGS_SYNC } else { SAMPLE_FLAGS_NON_SYNC })
+            .collect();
+        if let Some(default_flags) = uniform(&flags) {
+            tfhd.flags |= TfhdBox::FLAG_DEFAULT_SAMPLE_FLAGS;
+            tfhd.default_sample_flags = Some(default_flags);
+        } else if let Some(default_flags) = uniform(&flags[1..]) {
+            tfhd.flags |= TfhdBox::FLAG_DEFAULT_SAMPLE_FLAGS;
+            tfhd.default_sample_flags = Some(default_flags);
+            trun.flags |= TrunBox::FLAG_FIRST_SAMPLE_FLAGS;
+            trun.first_sample_flags = Some(flags[0]);
+        } else {
+            trun.flags |= TrunBox::FLAG_SAMPLE_FLAGS;
+            trun.sample_flags = flags;
+        }
+
+        if samples.iter().any(|sample| sample.rendering_offset != 0) {
+            trun.flags |= TrunBox::FLAG_SAMPLE_CTS;
+            trun.sample_cts = samples.iter().map(|sample| sample.rendering_offset as i64).collect();
+        }
+
+        TrafBox {
+            tfhd,
+            tfdt: Some(TfdtBox::new(base_media_decode_time)),
+            trun: Some(trun),
+            unknown_boxes: Vec::new(),
+        }
+    }
+
     pub fn get_type(&self) -> BoxType {
         BoxType::TrafBox
     }
@@ -18,11 +114,71 @@ impl TrafBox {
     pub fn get_size(&self) -> u64 {
         let mut size = HEADER_SIZE;
         size += self.tfhd.box_size();
+        if let Some(ref tfdt) = self.tfdt {
+            size += tfdt.box_size();
+        }
         if let Some(ref trun) = self.trun {
             size += trun.box_size();
         }
+        for raw in self.unknown_boxes.iter() {
+            size += raw.get_size();
+        }
         size
     }
+
+    /// Returns the size of the sample at `index` within this fragment,
+    /// falling back to the tfhd default when trun has no per-sample sizes.
+    pub fn sample_size(&self, index: usize) -> Option<u32> {
+        let trun = self.trun.as_ref()?;
+        if index >= trun.sample_count as usize {
+            return None;
+        }
+        trun.sample_sizes.get(index).copied().or(self.tfhd.default_sample_size)
+    }
+
+    /// Returns the composition time offset of the sample at `index`, or 0
+    /// when trun has no per-sample offsets.
+    pub fn sample_composition_offset(&self, index: usize) -> Option<i64> {
+        let trun = self.trun.as_ref()?;
+        if index >= trun.sample_count as usize {
+            return None;
+        }
+        Some(trun.sample_cts.get(index).copied().unwrap_or(0))
+    }
+
+    /// Returns the duration of the sample at `index`, using the tfhd default,
+    /// or else `default_sample_duration` from trex, when trun has no
+    /// per-sample durations.
+    pub fn sample_duration(&self, index: usize, default_sample_duration: u32) -> Option<u32> {
+        let trun = self.trun.as_ref()?;
+        if index >= trun.sample_count as usize {
+            return None;
+        }
+        let default = self.tfhd.default_sample_duration.unwrap_or(default_sample_duration);
+        Some(trun.sample_durations.get(index).copied().unwrap_or(default))
+    }
+
+    /// Returns the total duration of the samples in this fragment, using the
+    /// tfhd default, or else `default_sample_duration` from trex, for
+    /// samples without a duration of their own.
+    pub fn duration(&self, default_sample_duration: u32) -> u64 {
+        let trun = match self.trun {
+            Some(ref trun) => trun,
+            None => return 0,
+        };
+        let default = self.tfhd.default_sample_duration.unwrap_or(default_sample_duration);
+        (0..trun.sample_count as usize)
+            .map(|i| trun.sample_durations.get(i).copied().unwrap_or(default) as u64)
+            .sum()
+    }
+
+    /// Returns the total size of the sample data described by this fragment.
+    pub fn data_size(&self) -> u64 {
+        let sample_count = self.trun.as_ref().map(|trun| trun.sample_count).unwrap_or(0);
+        (0..sample_count as usize)
+            .map(|i| self.sample_size(i).unwrap_or(0) as u64)
+            .sum()
+    }
 }
 
 impl Mp4Box for TrafBox {
@@ -42,14 +198,28 @@ impl Mp4Box for TrafBox {
         let s = format!("");
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = vec![&self.tfhd];
+        if let Some(ref tfdt) = self.tfdt {
+            children.push(tfdt);
+        }
+        if let Some(ref trun) = self.trun {
+            children.push(trun);
+        }
+        children
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for TrafBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
 
         let mut tfhd = None;
+        let mut tfdt = None;
         let mut trun = None;
+        let mut unknown_boxes = Vec::new();
 
         let mut current = reader.seek(SeekFrom::Current(0))?;
         let end = start + size;
@@ -62,9 +232,15 @@ impl<R: Read + Seek> ReadBox<&mut R> for TrafBox {
                 BoxType::TfhdBox => {
                     tfhd = Some(TfhdBox::read_box(reader, s)?);
                 }
+                BoxType::TfdtBox => {
+                    tfdt = Some(TfdtBox::read_box(reader, s)?);
+                }
                 BoxType::TrunBox => {
                     trun = Some(TrunBox::read_box(reader, s)?);
                 }
+                _ if current + s <= end => {
+                    unknown_boxes.push(RawBox::read(reader, &header)?);
+                }
                 _ => {
                     // XXX warn!()
                     skip_box(reader, s)?;
@@ -82,18 +258,105 @@ impl<R: Read + Seek> ReadBox<&mut R> for TrafBox {
 
         Ok(TrafBox {
             tfhd: tfhd.unwrap(),
+            tfdt,
             trun,
+            unknown_boxes,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for TrafBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
         BoxHeader::new(self.box_type(), size).write(writer)?;
 
         self.tfhd.write_box(writer)?;
+        if let Some(ref tfdt) = self.tfdt {
+            tfdt.write_box(writer)?;
+        }
+        if let Some(ref trun) = self.trun {
+            trun.write_box(writer)?;
+        }
+        for raw in self.unknown_boxes.iter() {
+            raw.write(writer)?;
+        }
 
         Ok(size)
     }
 }
+
+// Returns the value shared by all of `values`, if there are any.
+fn uniform(values: &[u32]) -> Option<u32> {
+    let first = *values.first()?;
+    if values.iter().all(|value| *value == first) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use bytes::Bytes;
+    use std::io::Cursor;
+
+    fn sample(duration: u32, size: usize, is_sync: bool) -> Mp4Sample {
+        Mp4Sample {
+            start_time: 0,
+            duration,
+            rendering_offset: 0,
+            is_sync,
+            sample_description_index: 1,
+            bytes: Bytes::from(vec![0; size]),
+        }
+    }
+
+    #[test]
+    fn test_traf_from_samples() {
+        let samples = vec![sample(1024, 10, true), sample(1024, 20, false), sample(1024, 30, false)];
+        let src_box = TrafBox::from_samples(2, 4096, &samples);
+
+        assert_eq!(src_box.tfhd.track_id, 2);
+        assert_eq!(src_box.tfhd.default_sample_duration, Some(1024));
+        assert_eq!(src_box.tfhd.default_sample_flags, Some(SAMPLE_FLAGS_NON_SYNC));
+        assert_eq!(src_box.tfdt.as_ref().unwrap().base_media_decode_time, 4096);
+        let trun = src_box.trun.as_ref().unwrap();
+        assert_eq!(trun.flags & TrunBox::FLAG_SAMPLE_DURATION, 0);
+        assert!(trun.sample_durations.is_empty());
+        assert_eq!(trun.sample_sizes, vec![10, 20, 30]);
+        assert_eq!(trun.first_sample_flags, Some(SAMPLE_FLAGS_SYNC));
+        assert_eq!(trun.flags & TrunBox::FLAG_SAMPLE_CTS, 0);
+
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::TrafBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = TrafBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_traf_from_samples_varying_durations() {
+        let samples = vec![sample(1000, 10, true), sample(1001, 10, true)];
+        let traf = TrafBox::from_samples(1, 0, &samples);
+
+        assert_eq!(traf.tfhd.default_sample_duration, None);
+        assert_eq!(traf.tfhd.default_sample_size, Some(10));
+        assert_eq!(traf.tfhd.default_sample_flags, Some(SAMPLE_FLAGS_SYNC));
+        let trun = traf.trun.as_ref().unwrap();
+        assert_eq!(trun.sample_durations, vec![1000, 1001]);
+        assert_eq!(trun.first_sample_flags, None);
+
+        let traf = TrafBox::from_samples(1, 0, &[]);
+        assert!(traf.trun.is_none());
+        assert_eq!(traf.sample_size(0), None);
+    }
+}
@@ -1,17 +1,28 @@
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, SeekFrom, Write};
 use serde::{Serialize};
 
 use crate::mp4box::*;
-use crate::mp4box::{edts::EdtsBox, mdia::MdiaBox, tkhd::TkhdBox};
+use crate::mp4box::{edts::EdtsBox, mdia::MdiaBox, tapt::TaptBox, tkhd::TkhdBox, tref::TrefBox};
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct TrakBox {
     pub tkhd: TkhdBox,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tref: Option<TrefBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tapt: Option<TaptBox>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub edts: Option<EdtsBox>,
 
     pub mdia: MdiaBox,
+
+    /// Other children, e.g. a track-level udta or meta, kept as is.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_boxes: Vec<RawBox>,
 }
 
 impl TrakBox {
@@ -22,10 +33,19 @@ impl TrakBox {
     pub fn get_size(&self) -> u64 {
         let mut size = HEADER_SIZE;
         size += self.tkhd.box_size();
+        if let Some(ref tref) = self.tref {
+            size += tref.box_size();
+        }
+        if let Some(ref tapt) = self.tapt {
+            size += tapt.box_size();
+        }
         if let Some(ref edts) = self.edts {
             size += edts.box_size();
         }
         size += self.mdia.box_size();
+        for raw in self.unknown_boxes.iter() {
+            size += raw.get_size();
+        }
         size
     }
 }
@@ -47,15 +67,34 @@ impl Mp4Box for TrakBox {
         let s = format!("");
         Ok(s)
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = vec![&self.tkhd];
+        if let Some(ref tref) = self.tref {
+            children.push(tref);
+        }
+        if let Some(ref tapt) = self.tapt {
+            children.push(tapt);
+        }
+        if let Some(ref edts) = self.edts {
+            children.push(edts);
+        }
+        children.push(&self.mdia);
+        children
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for TrakBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
 
         let mut tkhd = None;
+        let mut tref = None;
+        let mut tapt = None;
         let mut edts = None;
         let mut mdia = None;
+        let mut unknown_boxes = Vec::new();
 
         let mut current = reader.seek(SeekFrom::Current(0))?;
         let end = start + size;
@@ -68,12 +107,21 @@ impl<R: Read + Seek> ReadBox<&mut R> for TrakBox {
                 BoxType::TkhdBox => {
                     tkhd = Some(TkhdBox::read_box(reader, s)?);
                 }
+                BoxType::TrefBox => {
+                    tref = Some(TrefBox::read_box(reader, s)?);
+                }
+                BoxType::TaptBox => {
+                    tapt = Some(TaptBox::read_box(reader, s)?);
+                }
                 BoxType::EdtsBox => {
                     edts = Some(EdtsBox::read_box(reader, s)?);
                 }
                 BoxType::MdiaBox => {
                     mdia = Some(MdiaBox::read_box(reader, s)?);
                 }
+                _ if current + s <= end => {
+                    unknown_boxes.push(RawBox::read(reader, &header)?);
+                }
                 _ => {
                     // XXX warn!()
                     skip_box(reader, s)?;
@@ -94,22 +142,35 @@ impl<R: Read + Seek> ReadBox<&mut R> for TrakBox {
 
         Ok(TrakBox {
             tkhd: tkhd.unwrap(),
+            tref,
+            tapt,
             edts,
             mdia: mdia.unwrap(),
+            unknown_boxes,
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for TrakBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
         BoxHeader::new(self.box_type(), size).write(writer)?;
 
         self.tkhd.write_box(writer)?;
+        if let Some(ref tref) = self.tref {
+            tref.write_box(writer)?;
+        }
+        if let Some(ref tapt) = self.tapt {
+            tapt.write_box(writer)?;
+        }
         if let Some(ref edts) = self.edts {
             edts.write_box(writer)?;
         }
         self.mdia.write_box(writer)?;
+        for raw in self.unknown_boxes.iter() {
+            raw.write(writer)?;
+        }
 
         Ok(size)
     }
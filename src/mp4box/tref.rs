@@ -0,0 +1,149 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct TrefBox {
+    pub references: Vec<TrefEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct TrefEntry {
+    pub reference_type: FourCC,
+    pub track_ids: Vec<u32>,
+}
+
+impl TrefEntry {
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + 4 * self.track_ids.len() as u64
+    }
+}
+
+impl TrefBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::TrefBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE;
+        for reference in self.references.iter() {
+            size += reference.get_size();
+        }
+        size
+    }
+}
+
+impl Mp4Box for TrefBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let references: Vec<String> = self.references.iter()
+            .map(|r| format!("{}={:?}", r.reference_type, r.track_ids))
+            .collect();
+        let s = format!("references={}", references.join(" "));
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for TrefBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let mut references = Vec::new();
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current < end {
+            // Each child is a TrackReferenceTypeBox whose type is the reference type.
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+            if s < HEADER_SIZE {
+                return Err(Error::InvalidData("invalid tref entry size"));
+            }
+
+            let track_id_count = (s - HEADER_SIZE) / 4;
+            let mut track_ids = Vec::with_capacity(track_id_count as usize);
+            for _ in 0..track_id_count {
+                track_ids.push(reader.read_u32::<BigEndian>()?);
+            }
+            references.push(TrefEntry {
+                reference_type: name.into(),
+                track_ids,
+            });
+
+            skip_bytes_to(reader, current + s)?;
+            current = reader.stream_position()?;
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(TrefBox { references })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for TrefBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        for reference in self.references.iter() {
+            let name = BoxType::from(u32::from(reference.reference_type));
+            BoxHeader::new(name, reference.get_size()).write(writer)?;
+            for track_id in reference.track_ids.iter() {
+                writer.write_u32::<BigEndian>(*track_id)?;
+            }
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_tref() {
+        let src_box = TrefBox {
+            references: vec![
+                TrefEntry {
+                    reference_type: str::parse("hint").unwrap(),
+                    track_ids: vec![1],
+                },
+                TrefEntry {
+                    reference_type: str::parse("chap").unwrap(),
+                    track_ids: vec![3, 4],
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::TrefBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = TrefBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(feature = "std")]
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+
+/// Track extension properties. The child boxes, such as the alternative
+/// startup sequence properties (assp), are kept unparsed so they survive a
+/// round trip.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct TrepBox {
+    pub version: u8,
+    pub flags: u32,
+    pub track_id: u32,
+
+    #[serde(skip_serializing)]
+    pub children: Vec<RawBox>,
+}
+
+impl TrepBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::TrepBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 4;
+        for child in self.children.iter() {
+            size += child.get_size();
+        }
+        size
+    }
+
+    /// Returns the first child box of the given type, e.g. `*b"assp"`.
+    pub fn child(&self, box_type: [u8; 4]) -> Option<&RawBox> {
+        self.children.iter().find(|child| child.box_type.value == box_type)
+    }
+}
+
+impl Mp4Box for TrepBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let types: Vec<String> = self.children.iter().map(|child| child.box_type.to_string()).collect();
+        let s = format!("track_id={} children=[{}]", self.track_id, types.join(", "));
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for TrepBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let track_id = reader.read_u32::<BigEndian>()?;
+
+        let mut children = Vec::new();
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current + HEADER_SIZE <= end {
+            let header = BoxHeader::read(reader)?;
+            if current + header.size > end {
+                return Err(Error::InvalidData("trep child box extends past the trep"));
+            }
+            children.push(RawBox::read(reader, &header)?);
+            current = reader.stream_position()?;
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(TrepBox {
+            version,
+            flags,
+            track_id,
+            children,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for TrepBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        writer.write_u32::<BigEndian>(self.track_id)?;
+        for child in self.children.iter() {
+            child.write(writer)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_trep() {
+        let src_box = TrepBox {
+            version: 0,
+            flags: 0,
+            track_id: 2,
+            children: vec![
+                RawBox {
+                    box_type: str::parse("assp").unwrap(),
+                    data: vec![0, 0, 0, 0, 0, 0, 0, 2],
+                },
+                RawBox {
+                    box_type: str::parse("xyzw").unwrap(),
+                    data: vec![1, 2, 3],
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::TrepBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = TrepBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.child(*b"xyzw").unwrap().data, vec![1, 2, 3]);
+    }
+}
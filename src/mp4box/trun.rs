@@ -1,4 +1,6 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
@@ -6,6 +8,9 @@ use crate::mp4box::*;
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct TrunBox {
+    /// Version 0 stores unsigned composition offsets, version 1 signed ones.
+    /// On write the version is chosen from the offsets, so this only reflects
+    /// what was read.
     pub version: u8,
     pub flags: u32,
     pub sample_count: u32,
@@ -19,7 +24,7 @@ pub struct TrunBox {
     #[serde(skip_serializing)]
     pub sample_flags: Vec<u32>,
     #[serde(skip_serializing)]
-    pub sample_cts: Vec<u32>,
+    pub sample_cts: Vec<i64>,
 }
 
 impl TrunBox {
@@ -56,6 +61,15 @@ impl TrunBox {
         }
         sum
     }
+
+    /// Version 1 is needed if any composition offset is negative.
+    pub fn required_version(&self) -> u8 {
+        if self.sample_cts.iter().any(|cts| *cts < 0) {
+            1
+        } else {
+            0
+        }
+    }
 }
 
 impl Mp4Box for TrunBox {
@@ -78,6 +92,7 @@ impl Mp4Box for TrunBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for TrunBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -119,7 +134,11 @@ impl<R: Read + Seek> ReadBox<&mut R> for TrunBox {
             }
 
             if TrunBox::FLAG_SAMPLE_CTS & flags > 0 {
-                let cts = reader.read_u32::<BigEndian>()?;
+                let cts = if version == 0 {
+                    reader.read_u32::<BigEndian>()? as i64
+                } else {
+                    reader.read_i32::<BigEndian>()? as i64
+                };
                 sample_cts.push(cts);
             }
         }
@@ -140,12 +159,14 @@ impl<R: Read + Seek> ReadBox<&mut R> for TrunBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for TrunBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
         BoxHeader::new(self.box_type(), size).write(writer)?;
 
-        write_box_header_ext(writer, self.version, self.flags)?;
+        let version = self.required_version();
+        write_box_header_ext(writer, version, self.flags)?;
 
         writer.write_u32::<BigEndian>(self.sample_count)?;
         if let Some(v) = self.data_offset{
@@ -154,8 +175,18 @@ impl<W: Write> WriteBox<&mut W> for TrunBox {
         if let Some(v) = self.first_sample_flags {
             writer.write_u32::<BigEndian>(v)?;
         }
-        if self.sample_count != self.sample_sizes.len() as u32 {
-            return Err(Error::InvalidData("sample count out of sync"));
+        // Only the per-sample fields that are present need an entry for
+        // every sample; the others come from the tfhd defaults.
+        let fields = [
+            (TrunBox::FLAG_SAMPLE_DURATION, self.sample_durations.len()),
+            (TrunBox::FLAG_SAMPLE_SIZE, self.sample_sizes.len()),
+            (TrunBox::FLAG_SAMPLE_FLAGS, self.sample_flags.len()),
+            (TrunBox::FLAG_SAMPLE_CTS, self.sample_cts.len()),
+        ];
+        for (flag, len) in fields.iter() {
+            if flag & self.flags > 0 && *len != self.sample_count as usize {
+                return Err(Error::InvalidData("sample count out of sync"));
+            }
         }
         for i in 0..self.sample_count as usize {
             if TrunBox::FLAG_SAMPLE_DURATION & self.flags > 0 {
@@ -168,7 +199,15 @@ impl<W: Write> WriteBox<&mut W> for TrunBox {
                 writer.write_u32::<BigEndian>(self.sample_flags[i])?;
             }
             if TrunBox::FLAG_SAMPLE_CTS & self.flags > 0 {
-                writer.write_u32::<BigEndian>(self.sample_cts[i])?;
+                if version == 0 {
+                    let cts: u32 = self.sample_cts[i].try_into()
+                        .map_err(|_| Error::InvalidData("trun composition offset out of range"))?;
+                    writer.write_u32::<BigEndian>(cts)?;
+                } else {
+                    let cts: i32 = self.sample_cts[i].try_into()
+                        .map_err(|_| Error::InvalidData("trun composition offset out of range"))?;
+                    writer.write_i32::<BigEndian>(cts)?;
+                }
             }
         }
 
@@ -176,7 +215,7 @@ impl<W: Write> WriteBox<&mut W> for TrunBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -233,4 +272,32 @@ mod tests {
         let dst_box = TrunBox::read_box(&mut reader, header.size).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_trun_signed_cts() {
+        // Version 1 with one 16 byte sample whose composition offset is -1024.
+        let data = [
+            0x00, 0x00, 0x00, 0x18, b't', b'r', b'u', b'n',
+            0x01, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x10, 0xFF, 0xFF, 0xFC, 0x00,
+        ];
+        let mut reader = Cursor::new(&data);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let trun = TrunBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(trun.version, 1);
+        assert_eq!(trun.sample_cts, vec![-1024]);
+        assert_eq!(trun.required_version(), 1);
+
+        let mut buf = Vec::new();
+        trun.write_box(&mut buf).unwrap();
+        assert_eq!(buf, data);
+
+        // The same bits in version 0 are a large positive offset.
+        let mut data = data;
+        data[8] = 0;
+        let mut reader = Cursor::new(&data);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let trun = TrunBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(trun.sample_cts, vec![0xFFFF_FC00]);
+    }
 }
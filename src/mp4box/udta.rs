@@ -0,0 +1,247 @@
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+use crate::mp4box::asset::AssetBox;
+use crate::mp4box::cprt::CprtBox;
+use crate::mp4box::meta::MetaBox;
+use crate::mp4box::yrrc::YrrcBox;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct UdtaBox {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cprt: Option<CprtBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub yrrc: Option<YrrcBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<MetaBox>,
+
+    /// 3GPP asset information such as titl and auth, in file order.
+    #[serde(rename = "asset", skip_serializing_if = "Vec::is_empty")]
+    pub assets: Vec<AssetBox>,
+
+    /// Other user data, e.g. QuickTime text atoms such as ©nam, kept as is.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_boxes: Vec<RawBox>,
+}
+
+impl UdtaBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::UdtaBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE;
+        if let Some(ref cprt) = self.cprt {
+            size += cprt.box_size();
+        }
+        if let Some(ref yrrc) = self.yrrc {
+            size += yrrc.box_size();
+        }
+        if let Some(ref meta) = self.meta {
+            size += meta.box_size();
+        }
+        for asset in self.assets.iter() {
+            size += asset.box_size();
+        }
+        for raw in self.unknown_boxes.iter() {
+            size += raw.get_size();
+        }
+        size
+    }
+
+    /// Returns the first 3GPP asset box of the given type, e.g.
+    /// `AssetBox::TITLE`.
+    pub fn asset(&self, asset_type: FourCC) -> Option<&AssetBox> {
+        self.assets.iter().find(|asset| asset.asset_type == asset_type)
+    }
+
+    /// Returns the 3GPP asset strings keyed by box type, e.g. "titl". Of
+    /// several boxes of one type, such as titles in different languages,
+    /// the first is used.
+    #[cfg(feature = "std")]
+    pub fn asset_metadata(&self) -> HashMap<String, String> {
+        let mut metadata = HashMap::new();
+        for asset in self.assets.iter() {
+            metadata.entry(asset.asset_type.to_string()).or_insert_with(|| asset.value.clone());
+        }
+        metadata
+    }
+
+    /// Returns the first unparsed child box of the given type, e.g.
+    /// `[0xA9, b'n', b'a', b'm']`.
+    pub fn unknown_box(&self, box_type: [u8; 4]) -> Option<&RawBox> {
+        self.unknown_boxes.iter().find(|raw| raw.box_type.value == box_type)
+    }
+}
+
+impl Mp4Box for UdtaBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        Ok(String::new())
+    }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        let mut children: Vec<&dyn Mp4Box> = Vec::new();
+        if let Some(ref cprt) = self.cprt {
+            children.push(cprt);
+        }
+        if let Some(ref yrrc) = self.yrrc {
+            children.push(yrrc);
+        }
+        if let Some(ref meta) = self.meta {
+            children.push(meta);
+        }
+        for asset in self.assets.iter() {
+            children.push(asset);
+        }
+        children
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for UdtaBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let mut cprt = None;
+        let mut yrrc = None;
+        let mut meta = None;
+        let mut assets = Vec::new();
+        let mut unknown_boxes = Vec::new();
+
+        let mut current = reader.stream_position()?;
+        let end = start + size;
+        while current < end {
+            // Get box header.
+            let header = BoxHeader::read(reader)?;
+            let BoxHeader { name, size: s } = header;
+
+            match name {
+                BoxType::CprtBox => {
+                    cprt = Some(CprtBox::read_box(reader, s)?);
+                }
+                BoxType::YrrcBox => {
+                    yrrc = Some(YrrcBox::read_box(reader, s)?);
+                }
+                BoxType::MetaBox => {
+                    meta = Some(MetaBox::read_box(reader, s)?);
+                }
+                BoxType::TitlBox
+                | BoxType::DscpBox
+                | BoxType::PerfBox
+                | BoxType::AuthBox
+                | BoxType::GnreBox => {
+                    assets.push(AssetBox::read_box(reader, s)?);
+                }
+                _ if current + s <= end => {
+                    unknown_boxes.push(RawBox::read(reader, &header)?);
+                }
+                _ => {
+                    // XXX warn!()
+                    skip_box(reader, s)?;
+                }
+            }
+
+            current = reader.stream_position()?;
+        }
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(UdtaBox {
+            cprt,
+            yrrc,
+            meta,
+            assets,
+            unknown_boxes,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for UdtaBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        if let Some(ref cprt) = self.cprt {
+            cprt.write_box(writer)?;
+        }
+        if let Some(ref yrrc) = self.yrrc {
+            yrrc.write_box(writer)?;
+        }
+        if let Some(ref meta) = self.meta {
+            meta.write_box(writer)?;
+        }
+        for asset in self.assets.iter() {
+            asset.write_box(writer)?;
+        }
+        for raw in self.unknown_boxes.iter() {
+            raw.write(writer)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_udta() {
+        let src_box = UdtaBox {
+            cprt: Some(CprtBox {
+                language: String::from("eng"),
+                notice: String::from("Copyright 2023 Example Corp."),
+                ..CprtBox::default()
+            }),
+            yrrc: Some(YrrcBox {
+                year: 2021,
+                ..YrrcBox::default()
+            }),
+            meta: None,
+            assets: vec![
+                AssetBox::new(AssetBox::TITLE, "eng", "A Title"),
+                AssetBox::new(AssetBox::AUTHOR, "eng", "An Author"),
+            ],
+            unknown_boxes: vec![RawBox {
+                box_type: [0xA9, b'n', b'a', b'm'].into(),
+                data: vec![0, 5, 0x15, 0xC7, b'T', b'i', b't', b'l', b'e'],
+            }],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::UdtaBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = UdtaBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.asset(AssetBox::TITLE).unwrap().language, "eng");
+        let metadata = dst_box.asset_metadata();
+        assert_eq!(metadata.len(), 2);
+        assert_eq!(metadata["auth"], "An Author");
+    }
+}
@@ -1,10 +1,12 @@
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
 use std::io::{Read, Seek, Write};
 use serde::{Serialize};
 
 use crate::mp4box::*;
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct VmhdBox {
     pub version: u8,
     pub flags: u32,
@@ -12,6 +14,19 @@ pub struct VmhdBox {
     pub op_color: RgbColor,
 }
 
+impl Default for VmhdBox {
+    fn default() -> Self {
+        VmhdBox {
+            version: 0,
+            // ISO/IEC 14496-12 fixes the flags at 1; strict demuxers
+            // reject anything else.
+            flags: 1,
+            graphics_mode: 0,
+            op_color: RgbColor::default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct RgbColor {
     pub red: u16,
@@ -53,6 +68,7 @@ impl Mp4Box for VmhdBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for VmhdBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -77,6 +93,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for VmhdBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for VmhdBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -93,7 +110,7 @@ impl<W: Write> WriteBox<&mut W> for VmhdBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -123,4 +140,23 @@ mod tests {
         let dst_box = VmhdBox::read_box(&mut reader, header.size).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_vmhd_flags() {
+        let mut buf = Vec::new();
+        VmhdBox::default().write_box(&mut buf).unwrap();
+        assert_eq!(&buf[8..12], &[0, 0, 0, 1]);
+
+        // Files written with flags 0 are still read.
+        let src_box = VmhdBox {
+            flags: 0,
+            ..VmhdBox::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        let dst_box = VmhdBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(dst_box.flags, 0);
+    }
 }
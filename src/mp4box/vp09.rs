@@ -32,12 +32,20 @@ impl Vp09Box {
     pub const DEFAULT_COMPRESSORNAME: [u8; 32] = [0; 32];
     pub const DEFAULT_DEPTH: u16 = 24;
 
-    pub fn new(config: &Vp9Config) -> Self {
+    /// Like `new`, but rejects a zero data_reference_index or empty
+    /// dimensions instead of producing an unplayable sample entry.
+    pub fn try_new(config: &Vp9Config, data_reference_index: u16) -> Result<Self> {
+        check_data_reference_index(data_reference_index)?;
+        check_dimensions(config.width, config.height)?;
+        Ok(Self::new(config, data_reference_index))
+    }
+
+    pub fn new(config: &Vp9Config, data_reference_index: u16) -> Self {
         Vp09Box {
             version: 0,
             flags: 0,
             start_code: Vp09Box::DEFAULT_START_CODE,
-            data_reference_index: Vp09Box::DEFAULT_DATA_REFERENCE_INDEX,
+            data_reference_index,
             reserved0: Default::default(),
             width: config.width,
             height: config.height,
@@ -51,14 +59,14 @@ impl Vp09Box {
             vpcc: VpccBox {
                 version: VpccBox::DEFAULT_VERSION,
                 flags: 0,
-                profile: 0,
-                level: 0x1F,
-                bit_depth: VpccBox::DEFAULT_BIT_DEPTH,
-                chroma_subsampling: 0,
-                video_full_range_flag: false,
-                color_primaries: 0,
-                transfer_characteristics: 0,
-                matrix_coefficients: 0,
+                profile: config.profile,
+                level: config.level,
+                bit_depth: config.bit_depth,
+                chroma_subsampling: config.chroma_subsampling,
+                video_full_range_flag: config.video_full_range_flag,
+                color_primaries: config.color_primaries,
+                transfer_characteristics: config.transfer_characteristics,
+                matrix_coefficients: config.matrix_coefficients,
                 codec_initialization_data_size: 0,
             },
         }
@@ -81,8 +89,13 @@ impl Mp4Box for Vp09Box {
     fn summary(&self) -> Result<String> {
         Ok(format!("{:?}", self))
     }
+
+    fn child_boxes(&self) -> Vec<&dyn Mp4Box> {
+        vec![&self.vpcc]
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for Vp09Box {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -140,6 +153,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for Vp09Box {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for Vp09Box {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -167,7 +181,7 @@ impl<W: Write> WriteBox<&mut W> for Vp09Box {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -175,7 +189,7 @@ mod tests {
 
     #[test]
     fn test_vpcc() {
-        let src_box = Vp09Box::new(&Vp9Config{ width: 1920, height: 1080 });
+        let src_box = Vp09Box::new(&Vp9Config{ width: 1920, height: 1080, ..Vp9Config::default() }, 1);
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
         assert_eq!(buf.len(), src_box.box_size() as usize);
@@ -40,6 +40,7 @@ impl Mp4Box for VpccBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> ReadBox<&mut R> for VpccBox {
     fn read_box(reader: &mut R, size: u64) -> Result<Self> {
         let start = box_start(reader)?;
@@ -51,6 +52,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for VpccBox {
             let b = reader.read_u8()?;
             (b >> 4, b << 4 >> 5, b & 0x01 == 1)
         };
+        let color_primaries: u8 = reader.read_u8()?;
         let transfer_characteristics: u8 = reader.read_u8()?;
         let matrix_coefficients: u8 = reader.read_u8()?;
         let codec_initialization_data_size: u16 = reader.read_u16::<BigEndian>()?;
@@ -65,7 +67,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for VpccBox {
             bit_depth,
             chroma_subsampling,
             video_full_range_flag,
-            color_primaries: 0,
+            color_primaries,
             transfer_characteristics,
             matrix_coefficients,
             codec_initialization_data_size,
@@ -73,6 +75,7 @@ impl<R: Read + Seek> ReadBox<&mut R> for VpccBox {
     }
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> WriteBox<&mut W> for VpccBox {
     fn write_box(&self, writer: &mut W) -> Result<u64> {
         let size = self.box_size();
@@ -92,7 +95,7 @@ impl<W: Write> WriteBox<&mut W> for VpccBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -108,9 +111,9 @@ mod tests {
             bit_depth: VpccBox::DEFAULT_BIT_DEPTH,
             chroma_subsampling: 0,
             video_full_range_flag: false,
-            color_primaries: 0,
-            transfer_characteristics: 0,
-            matrix_coefficients: 0,
+            color_primaries: 1,
+            transfer_characteristics: 16,
+            matrix_coefficients: 9,
             codec_initialization_data_size: 0,
         };
         let mut buf = Vec::new();
@@ -0,0 +1,201 @@
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+use serde::{Serialize};
+
+use crate::mp4box::*;
+#[cfg(feature = "std")]
+use crate::mp4box::cprt::read_language_tagged_string;
+
+/// XML metadata stored as text, e.g. an MPEG-7 description. The format is
+/// named by the handler of the enclosing meta box.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct XmlBox {
+    pub version: u8,
+    pub flags: u32,
+    pub xml: String,
+}
+
+impl XmlBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::XmlBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE + self.xml.len() as u64 + 1
+    }
+}
+
+impl Mp4Box for XmlBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("length={}", self.xml.len());
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for XmlBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let xml_size = size
+            .checked_sub(HEADER_SIZE + HEADER_EXT_SIZE)
+            .ok_or(Error::InvalidData("xml box too small"))?;
+        let xml = read_language_tagged_string(reader, xml_size)?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(XmlBox {
+            version,
+            flags,
+            xml,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for XmlBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        writer.write_all(self.xml.as_bytes())?;
+        writer.write_all(&[0])?;
+
+        Ok(size)
+    }
+}
+
+/// Binary XML metadata, e.g. MPEG-7 BiM, kept as is.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct BxmlBox {
+    pub version: u8,
+    pub flags: u32,
+
+    #[serde(skip_serializing)]
+    pub data: Vec<u8>,
+}
+
+impl BxmlBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::BxmlBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE + self.data.len() as u64
+    }
+}
+
+impl Mp4Box for BxmlBox {
+    fn box_type(&self) -> BoxType {
+        self.get_type()
+    }
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String> {
+        let s = format!("length={}", self.data.len());
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> ReadBox<&mut R> for BxmlBox {
+    fn read_box(reader: &mut R, size: u64) -> Result<Self> {
+        let start = box_start(reader)?;
+
+        let (version, flags) = read_box_header_ext(reader)?;
+        let data_size = size
+            .checked_sub(HEADER_SIZE + HEADER_EXT_SIZE)
+            .ok_or(Error::InvalidData("bxml box too small"))?;
+        let mut data = vec![0u8; data_size as usize];
+        reader.read_exact(&mut data)?;
+
+        skip_bytes_to(reader, start + size)?;
+
+        Ok(BxmlBox {
+            version,
+            flags,
+            data,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteBox<&mut W> for BxmlBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64> {
+        let size = self.box_size();
+        BoxHeader::new(self.box_type(), size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        writer.write_all(&self.data)?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_xml() {
+        let src_box = XmlBox {
+            version: 0,
+            flags: 0,
+            xml: String::from("<?xml version=\"1.0\"?><Mpeg7><Title>Test</Title></Mpeg7>"),
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::XmlBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = XmlBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_bxml() {
+        let src_box = BxmlBox {
+            version: 0,
+            flags: 0,
+            data: vec![0x01, 0x02, 0x00, 0xFF],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = Cursor::new(&buf);
+        let header = BoxHeader::read(&mut reader).unwrap();
+        assert_eq!(header.name, BoxType::BxmlBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = BxmlBox::read_box(&mut reader, header.size).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
@@ -0,0 +1,230 @@
+//! Splitting of AVC and HEVC samples into NAL units.
+//!
+//! Samples in MP4 files store NAL units with a big-endian length prefix
+//! instead of start codes. The size of the prefix comes from the decoder
+//! configuration record (`length_size_minus_one + 1` in avcC and hvcC).
+
+use crate::*;
+
+/// An H.264 NAL unit within a sample.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AvcNal<'a> {
+    pub nal_unit_type: u8,
+    /// The NAL unit including its header byte, without the length prefix.
+    pub data: &'a [u8],
+}
+
+impl AvcNal<'_> {
+    pub const TYPE_NON_IDR_SLICE: u8 = 1;
+    pub const TYPE_IDR_SLICE: u8 = 5;
+    pub const TYPE_SEI: u8 = 6;
+    pub const TYPE_SPS: u8 = 7;
+    pub const TYPE_PPS: u8 = 8;
+    pub const TYPE_AUD: u8 = 9;
+
+    pub fn is_idr(&self) -> bool {
+        self.nal_unit_type == Self::TYPE_IDR_SLICE
+    }
+
+    /// Returns true for in-band parameter sets (SPS or PPS).
+    pub fn is_parameter_set(&self) -> bool {
+        self.nal_unit_type == Self::TYPE_SPS || self.nal_unit_type == Self::TYPE_PPS
+    }
+}
+
+/// An H.265 NAL unit within a sample.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HevcNal<'a> {
+    pub nal_unit_type: u8,
+    /// The NAL unit including its two header bytes, without the length prefix.
+    pub data: &'a [u8],
+}
+
+impl HevcNal<'_> {
+    pub const TYPE_IDR_W_RADL: u8 = 19;
+    pub const TYPE_IDR_N_LP: u8 = 20;
+    pub const TYPE_VPS: u8 = 32;
+    pub const TYPE_SPS: u8 = 33;
+    pub const TYPE_PPS: u8 = 34;
+    pub const TYPE_AUD: u8 = 35;
+
+    pub fn is_idr(&self) -> bool {
+        self.nal_unit_type == Self::TYPE_IDR_W_RADL || self.nal_unit_type == Self::TYPE_IDR_N_LP
+    }
+
+    /// Returns true for intra random access point pictures (BLA, IDR and
+    /// CRA), which can start a fragment.
+    pub fn is_irap(&self) -> bool {
+        (16..=23).contains(&self.nal_unit_type)
+    }
+
+    /// Returns true for in-band parameter sets (VPS, SPS or PPS).
+    pub fn is_parameter_set(&self) -> bool {
+        (Self::TYPE_VPS..=Self::TYPE_PPS).contains(&self.nal_unit_type)
+    }
+}
+
+/// Iterates over the NAL units of an AVC sample.
+#[derive(Debug, Clone)]
+pub struct AvcSampleSplitter<'a> {
+    inner: LengthPrefixedNals<'a>,
+}
+
+impl<'a> AvcSampleSplitter<'a> {
+    /// `length_size` is the size of the NAL length prefix in bytes.
+    pub fn new(sample: &'a [u8], length_size: u8) -> Result<Self> {
+        Ok(Self {
+            inner: LengthPrefixedNals::new(sample, length_size)?,
+        })
+    }
+}
+
+impl<'a> Iterator for AvcSampleSplitter<'a> {
+    type Item = Result<AvcNal<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = match self.inner.next()? {
+            Ok(data) => data,
+            Err(e) => return Some(Err(e)),
+        };
+        match data.first() {
+            Some(header) => Some(Ok(AvcNal {
+                nal_unit_type: header & 0x1F,
+                data,
+            })),
+            None => Some(Err(Error::InvalidData("empty NAL unit"))),
+        }
+    }
+}
+
+/// Iterates over the NAL units of an HEVC sample.
+#[derive(Debug, Clone)]
+pub struct HevcSampleSplitter<'a> {
+    inner: LengthPrefixedNals<'a>,
+}
+
+impl<'a> HevcSampleSplitter<'a> {
+    /// `length_size` is the size of the NAL length prefix in bytes.
+    pub fn new(sample: &'a [u8], length_size: u8) -> Result<Self> {
+        Ok(Self {
+            inner: LengthPrefixedNals::new(sample, length_size)?,
+        })
+    }
+}
+
+impl<'a> Iterator for HevcSampleSplitter<'a> {
+    type Item = Result<HevcNal<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = match self.inner.next()? {
+            Ok(data) => data,
+            Err(e) => return Some(Err(e)),
+        };
+        if data.len() < 2 {
+            return Some(Err(Error::InvalidData("NAL unit header too short")));
+        }
+        Some(Ok(HevcNal {
+            nal_unit_type: (data[0] >> 1) & 0x3F,
+            data,
+        }))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LengthPrefixedNals<'a> {
+    data: &'a [u8],
+    length_size: usize,
+}
+
+impl<'a> LengthPrefixedNals<'a> {
+    fn new(data: &'a [u8], length_size: u8) -> Result<Self> {
+        if !matches!(length_size, 1 | 2 | 4) {
+            return Err(Error::InvalidData("invalid NAL length size"));
+        }
+        Ok(Self {
+            data,
+            length_size: length_size as usize,
+        })
+    }
+}
+
+impl<'a> Iterator for LengthPrefixedNals<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        if self.data.len() < self.length_size {
+            self.data = &[];
+            return Some(Err(Error::InvalidData("truncated NAL length")));
+        }
+        let (prefix, rest) = self.data.split_at(self.length_size);
+        let length = prefix.iter().fold(0usize, |acc, b| acc << 8 | *b as usize);
+        if rest.len() < length {
+            self.data = &[];
+            return Some(Err(Error::InvalidData("truncated NAL unit")));
+        }
+        let (nal, rest) = rest.split_at(length);
+        self.data = rest;
+        Some(Ok(nal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn length_prefixed(nals: &[&[u8]], length_size: usize) -> Vec<u8> {
+        let mut sample = Vec::new();
+        for nal in nals {
+            let length = (nal.len() as u32).to_be_bytes();
+            sample.extend_from_slice(&length[4 - length_size..]);
+            sample.extend_from_slice(nal);
+        }
+        sample
+    }
+
+    #[test]
+    fn test_avc_sample_splitter() {
+        let sps: &[u8] = &[0x67, 0x42, 0xc0, 0x0d];
+        let pps: &[u8] = &[0x68, 0xce, 0x3c, 0x80];
+        let idr: &[u8] = &[0x65, 0x88, 0x84, 0x00, 0x33];
+        let sample = length_prefixed(&[sps, pps, idr], 4);
+
+        let nals: Vec<AvcNal> = AvcSampleSplitter::new(&sample, 4)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        let types: Vec<u8> = nals.iter().map(|nal| nal.nal_unit_type).collect();
+        assert_eq!(types, vec![AvcNal::TYPE_SPS, AvcNal::TYPE_PPS, AvcNal::TYPE_IDR_SLICE]);
+        assert!(nals[0].is_parameter_set());
+        assert!(!nals[1].is_idr());
+        assert!(nals[2].is_idr());
+        assert_eq!(nals[2].data, idr);
+
+        let truncated = &sample[..sample.len() - 1];
+        let last = AvcSampleSplitter::new(truncated, 4).unwrap().last().unwrap();
+        assert!(last.is_err());
+    }
+
+    #[test]
+    fn test_hevc_sample_splitter() {
+        let vps: &[u8] = &[0x40, 0x01, 0x0c];
+        let idr: &[u8] = &[0x26, 0x01, 0xaf];
+        let trail: &[u8] = &[0x02, 0x01, 0xd0];
+        let sample = length_prefixed(&[vps, idr, trail], 2);
+
+        let nals: Vec<HevcNal> = HevcSampleSplitter::new(&sample, 2)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        let types: Vec<u8> = nals.iter().map(|nal| nal.nal_unit_type).collect();
+        assert_eq!(types, vec![HevcNal::TYPE_VPS, HevcNal::TYPE_IDR_W_RADL, 1]);
+        assert!(nals[0].is_parameter_set());
+        assert!(nals[1].is_idr() && nals[1].is_irap());
+        assert!(!nals[2].is_irap());
+
+        assert!(HevcSampleSplitter::new(&sample, 3).is_err());
+    }
+}
@@ -1,59 +1,258 @@
 use std::collections::HashMap;
-use std::io::{Read, Seek, SeekFrom};
-use std::time::Duration;
+use std::convert::TryFrom;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use byteorder::{BigEndian, ReadBytesExt};
+use bytes::Bytes;
 
 use crate::*;
 use crate::mp4box::*;
+use crate::mp4box::avc1::NalUnit;
+use crate::mp4box::co64::Co64Box;
+use crate::mp4box::cprt::CprtBox;
+use crate::mp4box::hdlr::HdlrBox;
+use crate::mp4box::iinf::InfeBox;
+use crate::mp4box::iloc::IlocItem;
+use crate::mp4box::iref::ItemReference;
+use crate::mp4box::meta::MetaBox;
+use crate::mp4box::mvex::MvexBox;
+use crate::mp4box::sidx::SidxBox;
+use crate::mp4box::sinf::SinfBox;
+use crate::mp4box::ssix::SsixBox;
+use crate::mp4box::stbl::StblBox;
+use crate::mp4box::stco::StcoBox;
+use crate::mp4box::stsd::StsdBox;
+use crate::mp4box::tfhd::TfhdBox;
+use crate::mp4box::trak::TrakBox;
+use crate::mp4box::udta::UdtaBox;
 
 #[derive(Debug)]
 pub struct Mp4Reader<R> {
     reader: R,
     pub ftyp: FtypBox,
+    /// The movie header. An image file such as HEIF, which has a file-level
+    /// meta in its place, gets an empty `MoovBox::default()` without tracks.
     pub moov: MoovBox,
     pub moofs: Vec<MoofBox>,
     pub emsgs: Vec<EmsgBox>,
+    pub meta: Option<MetaBox>,
+    pub sidxs: Vec<SidxBox>,
+    pub ssixs: Vec<SsixBox>,
 
     tracks: HashMap<u32, Mp4Track>,
+    // Start and end offsets of the payload of each mdat box.
+    mdat_ranges: Vec<(u64, u64)>,
+    // Type, offset and size of each top-level box.
+    boxes: Vec<(BoxType, u64, u64)>,
+    free_space: Vec<FreeSpace>,
+    custom_boxes: Vec<CustomBox>,
     size: u64,
 }
 
+/// A top-level region holding nothing the file uses: a free or skip box, or
+/// a stale moov left behind when the file was re-muxed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FreeSpace {
+    pub offset: u64,
+    pub size: u64,
+    /// Whether the region holds an old moov, either as a moov box other
+    /// than the one in use or as a moov renamed to free or skip.
+    pub stale_moov: bool,
+}
+
+/// A fragment whose tfdt doesn't continue where the previous fragment of the
+/// same track ended, as found by [`Mp4Reader::validate_decode_times`].
+/// Times are in the track timescale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeTimeDiscontinuity {
+    pub track_id: u32,
+    /// The mfhd sequence number of the fragment.
+    pub sequence_number: u32,
+    pub expected: u64,
+    pub base_media_decode_time: u64,
+}
+
+/// A chunk whose bytes don't lie within a single mdat box, as found by
+/// [`Mp4Reader::validate_chunk_offsets`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkOutOfBounds {
+    pub track_id: u32,
+    /// The 1-based index of the chunk in the track's stco/co64.
+    pub chunk_id: u32,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl<'a> Mp4Reader<Cursor<&'a [u8]>> {
+    /// Read an MP4 file that is already in memory. Samples are read from
+    /// the borrowed slice.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self> {
+        let size = bytes.len() as u64;
+        Self::read_header(Cursor::new(bytes), size)
+    }
+}
+
 impl<R: Read + Seek> Mp4Reader<R> {
-    pub fn read_header(mut reader: R, size: u64) -> Result<Self> {
+    /// Read the header from `reader`, which extends from its current
+    /// position to the end of the stream.
+    pub fn from_reader(mut reader: R) -> Result<Self> {
+        let start = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
+        Self::read_header(reader, end)
+    }
+
+    /// Returns the types of the tracks in the file, reading nothing but the
+    /// hdlr box of each track, which for large files is much faster than
+    /// [`read_header`](Self::read_header). Tracks of other types are left
+    /// out. Fragmented files list their tracks in moov just the same.
+    pub fn probe_track_types(mut reader: R, size: u64) -> Result<Vec<TrackType>> {
+        let start = reader.stream_position()?;
+        let end = start + size;
+        let mut track_types = Vec::new();
+
+        let mut current = start;
+        while current + HEADER_SIZE <= end {
+            reader.seek(SeekFrom::Start(current))?;
+            let header = BoxHeader::read(&mut reader)?;
+            if header.size < HEADER_SIZE {
+                break;
+            }
+            if header.name != BoxType::MoovBox {
+                current += header.size;
+                continue;
+            }
+
+            for (name, trak, trak_size) in child_boxes(&mut reader, header.name, current, header.size)? {
+                if name != BoxType::TrakBox {
+                    continue;
+                }
+                let mdia = child_boxes(&mut reader, name, trak, trak_size)?
+                    .into_iter()
+                    .find(|(name, _, _)| *name == BoxType::MdiaBox);
+                let hdlr = match mdia {
+                    Some((name, mdia, mdia_size)) => child_boxes(&mut reader, name, mdia, mdia_size)?
+                        .into_iter()
+                        .find(|(name, _, _)| *name == BoxType::HdlrBox),
+                    None => None,
+                };
+                if let Some((_, hdlr, hdlr_size)) = hdlr {
+                    reader.seek(SeekFrom::Start(hdlr + HEADER_SIZE))?;
+                    let hdlr = HdlrBox::read_box(&mut reader, hdlr_size)?;
+                    if let Ok(track_type) = TrackType::try_from(&hdlr.handler_type) {
+                        track_types.push(track_type);
+                    }
+                }
+            }
+            break;
+        }
+        Ok(track_types)
+    }
+
+    /// Returns true if the file of `size` bytes at the current position of
+    /// `reader` has a video track. Like
+    /// [`probe_track_types`](Self::probe_track_types), this reads only the
+    /// hdlr box of each track, to quickly filter large sets of files.
+    pub fn has_video(reader: R, size: u64) -> Result<bool> {
+        Ok(Self::probe_track_types(reader, size)?.contains(&TrackType::Video))
+    }
+
+    /// Returns true if the file has an audio track; see
+    /// [`has_video`](Self::has_video).
+    pub fn has_audio(reader: R, size: u64) -> Result<bool> {
+        Ok(Self::probe_track_types(reader, size)?.contains(&TrackType::Audio))
+    }
+
+    /// Returns true if the file has a subtitle track; see
+    /// [`has_video`](Self::has_video).
+    pub fn has_subtitles(reader: R, size: u64) -> Result<bool> {
+        Ok(Self::probe_track_types(reader, size)?.contains(&TrackType::Subtitle))
+    }
+
+    pub fn read_header(reader: R, size: u64) -> Result<Self> {
+        Self::read_header_with_registry(reader, size, &BoxParserRegistry::new())
+    }
+
+    /// Like [`read_header`](Self::read_header), but parses boxes this crate
+    /// doesn't know with the parsers in `registry`, in place of skipping
+    /// them. See [`custom_boxes`](Self::custom_boxes).
+    pub fn read_header_with_registry(mut reader: R, size: u64, registry: &BoxParserRegistry) -> Result<Self> {
         let start = reader.seek(SeekFrom::Current(0))?;
 
         let mut ftyp = None;
-        let mut moov = None;
+        let mut moovs = Vec::new();
         let mut moofs = Vec::new();
+        let mut moof_offsets = Vec::new();
+        let mut mdat_ranges = Vec::new();
         let mut emsgs = Vec::new();
+        let mut meta = None;
+        let mut sidxs = Vec::new();
+        let mut ssixs = Vec::new();
+        let mut boxes = Vec::new();
+        let mut free_space = Vec::new();
+        let mut custom_boxes = Vec::new();
 
         let mut current = start;
         while current < size {
             // Get box header.
             let header = BoxHeader::read(&mut reader)?;
             let BoxHeader { name, size: s } = header;
+            boxes.push((name, current, s));
 
             // Match and parse the atom boxes.
             match name {
                 BoxType::FtypBox => {
                     ftyp = Some(FtypBox::read_box(&mut reader, s)?);
                 }
-                BoxType::FreeBox => {
+                BoxType::FreeBox | BoxType::SkipBox => {
+                    // A moov can be blanked out by renaming it, which leaves
+                    // its children in place.
+                    let data_start = reader.stream_position()?;
+                    let stale_moov = s >= 2 * HEADER_SIZE && matches!(
+                        BoxHeader::read(&mut reader),
+                        Ok(BoxHeader { name: BoxType::MvhdBox, .. })
+                            | Ok(BoxHeader { name: BoxType::MoovBox, .. })
+                    );
+                    free_space.push(FreeSpace {
+                        offset: current,
+                        size: s,
+                        stale_moov,
+                    });
+                    reader.seek(SeekFrom::Start(data_start))?;
                     skip_box(&mut reader, s)?;
                 }
                 BoxType::MdatBox => {
+                    let data_start = reader.stream_position()?;
+                    mdat_ranges.push((data_start, data_start - HEADER_SIZE + s));
                     skip_box(&mut reader, s)?;
                 }
                 BoxType::MoovBox => {
-                    moov = Some(MoovBox::read_box(&mut reader, s)?);
+                    moovs.push((current, s, MoovBox::read_box(&mut reader, s)?));
                 }
                 BoxType::MoofBox => {
                     let moof = MoofBox::read_box(&mut reader, s)?;
                     moofs.push(moof);
+                    moof_offsets.push(current);
                 }
                 BoxType::EmsgBox => {
                     let emsg = EmsgBox::read_box(&mut reader, s)?;
                     emsgs.push(emsg);
                 }
+                BoxType::MetaBox => {
+                    meta = Some(MetaBox::read_box(&mut reader, s)?);
+                }
+                BoxType::SidxBox => {
+                    sidxs.push(SidxBox::read_box(&mut reader, s)?);
+                }
+                BoxType::SsixBox => {
+                    ssixs.push(SsixBox::read_box(&mut reader, s)?);
+                }
+                _ if registry.contains(&name.into()) => {
+                    let raw = RawBox::read(&mut reader, &header)?;
+                    if let Some(custom) = registry.parse_raw(&raw) {
+                        custom_boxes.push(custom?);
+                    }
+                }
                 _ => {
                     // XXX warn!()
                     skip_box(&mut reader, s)?;
@@ -65,37 +264,88 @@ impl<R: Read + Seek> Mp4Reader<R> {
         if ftyp.is_none() {
             return Err(Error::BoxNotFound(BoxType::FtypBox));
         }
-        if moov.is_none() {
-            return Err(Error::BoxNotFound(BoxType::MoovBox));
+
+        // Of several moov boxes, use the last one whose chunks all lie in an
+        // mdat. The others are stale.
+        let in_mdat = |moov: &MoovBox| moov.traks.iter().all(|trak| {
+            trak.mdia.minf.stbl.chunk_offsets().unwrap_or_default().iter().all(|offset| {
+                mdat_ranges.iter().any(|(start, end)| offset >= start && offset < end)
+            })
+        });
+        let moov = match moovs.iter().rposition(|(_, _, moov)| in_mdat(moov)) {
+            Some(index) => moovs.remove(index).2,
+            None if !moovs.is_empty() => moovs.pop().unwrap().2,
+            None if meta.as_ref().map(MetaBox::is_image) == Some(true) => MoovBox::default(),
+            None => return Err(Error::BoxNotFound(BoxType::MoovBox)),
+        };
+        for (offset, size, _) in moovs {
+            free_space.push(FreeSpace {
+                offset,
+                size,
+                stale_moov: true,
+            });
+        }
+        free_space.sort_by_key(|space| space.offset);
+        let moof_children = moofs.iter().flat_map(|moof| {
+            let traf_children = moof.trafs.iter().flat_map(|traf| traf.unknown_boxes.iter());
+            moof.unknown_boxes.iter().chain(traf_children)
+        });
+        for raw in unknown_children(&moov).into_iter().chain(moof_children) {
+            if let Some(custom) = registry.parse_raw(raw) {
+                custom_boxes.push(custom?);
+            }
         }
 
         let size = current - start;
-        let mut tracks = if let Some(ref moov) = moov {
-            if moov.traks.iter().any(|trak| trak.tkhd.track_id == 0) {
-                return Err(Error::InvalidData("illegal track id 0"));
+        if moov.traks.iter().any(|trak| trak.tkhd.track_id == 0) {
+            return Err(Error::InvalidData("illegal track id 0"));
+        }
+        let mut tracks: HashMap<u32, Mp4Track> = moov.traks.iter()
+            .map(|trak| (trak.tkhd.track_id, Mp4Track::from(trak)))
+            .collect();
+        if let Some(ref mvex) = moov.mvex {
+            for (track_id, track) in tracks.iter_mut() {
+                track.has_trex = mvex.trex(*track_id).is_some();
             }
-            moov.traks.iter()
-                .map(|trak| (trak.tkhd.track_id, Mp4Track::from(trak)))
-                .collect()
-        } else {
-            HashMap::new()
-        };
+        }
 
         // Update tracks if any fragmented (moof) boxes are found.
         if moofs.len() > 0 {
-            let mut default_sample_duration = 0;
-            if let Some(ref moov) = moov {
-                if let Some(ref mvex) = &moov.mvex {
-                    default_sample_duration = mvex.trex.default_sample_duration
-                }
-            }
+            let mvex = moov.mvex.as_ref();
+
+            for (moof, moof_offset) in moofs.iter().zip(moof_offsets) {
+                // Sample data of a traf follows that of the previous traf in the
+                // same moof unless an explicit base is given.
+                let mut data_end = moof_offset;
+                for (i, traf) in moof.trafs.iter().enumerate() {
+                    let base_data_offset = match traf.tfhd.base_data_offset {
+                        Some(offset) => offset,
+                        None if i == 0
+                            || traf.tfhd.flags & TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF > 0 =>
+                        {
+                            moof_offset
+                        }
+                        None => data_end,
+                    };
+                    let data_offset = match traf.trun.as_ref().and_then(|trun| trun.data_offset) {
+                        Some(offset) => base_data_offset.checked_add_signed(offset as i64)
+                            .ok_or(Error::InvalidData("trun data offset out of range"))?,
+                        None => base_data_offset,
+                    };
+                    data_end = data_offset.checked_add(traf.data_size())
+                        .ok_or(Error::InvalidData("traf data extends past 2^64"))?;
 
-            for moof in moofs.iter() {
-                for traf in moof.trafs.iter() {
                     let track_id = traf.tfhd.track_id;
                     if let Some(track) = tracks.get_mut(&track_id) {
-                        track.default_sample_duration = default_sample_duration;
-                        track.trafs.push(traf.clone())
+                        let trex = mvex.and_then(|mvex| mvex.trex(track_id));
+                        track.default_sample_duration = trex
+                            .map(|trex| trex.default_sample_duration)
+                            .unwrap_or(0);
+                        track.default_sample_description_index = trex
+                            .map(|trex| trex.default_sample_description_index)
+                            .unwrap_or(1);
+                        track.trafs.push(traf.clone());
+                        track.traf_data_offsets.push(data_offset);
                     } else {
                         return Err(Error::TrakNotFound(track_id));
                     }
@@ -106,11 +356,18 @@ impl<R: Read + Seek> Mp4Reader<R> {
         Ok(Mp4Reader {
             reader,
             ftyp: ftyp.unwrap(),
-            moov: moov.unwrap(),
+            moov,
             moofs,
             emsgs,
+            meta,
+            sidxs,
+            ssixs,
             size,
             tracks,
+            mdat_ranges,
+            boxes,
+            free_space,
+            custom_boxes,
         })
     }
 
@@ -130,16 +387,320 @@ impl<R: Read + Seek> Mp4Reader<R> {
         &self.ftyp.compatible_brands
     }
 
+    /// Returns the major brand, minor version and compatible brands from ftyp.
+    pub fn brands(&self) -> (FourCC, u32, Vec<FourCC>) {
+        (
+            self.ftyp.major_brand,
+            self.ftyp.minor_version,
+            self.ftyp.compatible_brands.clone(),
+        )
+    }
+
+    /// Returns true if the file declares a DASH or CMAF brand
+    /// ("dash", "cmfc" or "iso6").
+    pub fn is_dash(&self) -> bool {
+        const DASH_BRANDS: [&[u8; 4]; 3] = [b"dash", b"cmfc", b"iso6"];
+        DASH_BRANDS
+            .iter()
+            .any(|brand| self.ftyp.is_compatible_with(FourCC::from(**brand)))
+    }
+
+    /// Returns the movie duration, or zero if it is unknown. See
+    /// [`known_duration`](Self::known_duration).
     pub fn duration(&self) -> Duration {
-        Duration::from_millis(self.moov.mvhd.duration * 1000 / self.moov.mvhd.timescale as u64)
+        self.known_duration().unwrap_or_default()
+    }
+
+    /// Returns the movie duration from the mvhd. Fragmented files often
+    /// mark it as unknown with a duration of all ones, in which case the
+    /// fragment duration of the mehd is returned instead, or `None` if there
+    /// is no mehd.
+    pub fn known_duration(&self) -> Option<Duration> {
+        let mvhd = &self.moov.mvhd;
+        let duration = if mvhd.is_duration_unknown() {
+            self.moov.mvex.as_ref()?.mehd.as_ref()?.fragment_duration
+        } else {
+            mvhd.duration
+        };
+        let millis = (duration * 1000).checked_div(mvhd.timescale as u64)?;
+        Some(Duration::from_millis(millis))
     }
 
     pub fn timescale(&self) -> u32 {
         self.moov.mvhd.timescale
     }
 
+    /// Returns true if the movie is fragmented, i.e. it has an mvex box or
+    /// any moof boxes. This is also true for a DASH init segment on its own.
     pub fn is_fragmented(&self) -> bool {
-        self.moofs.len() != 0
+        self.moov.mvex.is_some() || !self.moofs.is_empty()
+    }
+
+    /// Serialize the whole parsed box tree (ftyp, moov and any sidx, ssix, moof and
+    /// emsg boxes) into a single JSON document. As with [Mp4Box::to_json], large
+    /// sample tables are summarized by their counts rather than listed.
+    pub fn to_json_tree(&self) -> Result<String> {
+        let mut tree = serde_json::Map::new();
+        tree.insert("ftyp".to_string(), json_value(&self.ftyp)?);
+        tree.insert("moov".to_string(), json_value(&self.moov)?);
+        if !self.sidxs.is_empty() {
+            tree.insert("sidx".to_string(), json_value(&self.sidxs)?);
+        }
+        if !self.ssixs.is_empty() {
+            tree.insert("ssix".to_string(), json_value(&self.ssixs)?);
+        }
+        if !self.moofs.is_empty() {
+            tree.insert("moof".to_string(), json_value(&self.moofs)?);
+        }
+        if !self.emsgs.is_empty() {
+            tree.insert("emsg".to_string(), json_value(&self.emsgs)?);
+        }
+        serde_json::to_string_pretty(&tree)
+            .map_err(|_| Error::InvalidData("failed to serialize box tree"))
+    }
+
+    /// Look up a parsed box by a slash separated path of box types, such as
+    /// "moov/trak[2]/mdia/minf/stbl/stsz". A bracketed index picks among
+    /// sibling boxes of the same type and, as in XPath, starts at 1. Without
+    /// an index the first match is used.
+    pub fn find(&self, path: &str) -> Option<&dyn Mp4Box> {
+        let mut candidates: Vec<&dyn Mp4Box> = vec![&self.ftyp];
+        if let Some(ref meta) = self.meta {
+            candidates.push(meta);
+        }
+        candidates.push(&self.moov);
+        candidates.extend(self.sidxs.iter().map(|sidx| sidx as &dyn Mp4Box));
+        candidates.extend(self.ssixs.iter().map(|ssix| ssix as &dyn Mp4Box));
+        candidates.extend(self.moofs.iter().map(|moof| moof as &dyn Mp4Box));
+        candidates.extend(self.emsgs.iter().map(|emsg| emsg as &dyn Mp4Box));
+
+        let mut found = None;
+        for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+            let (name, index) = path_segment(segment)?;
+            let mp4box = candidates
+                .into_iter()
+                .filter(|mp4box| FourCC::from(mp4box.box_type()).to_string() == name)
+                .nth(index.checked_sub(1)?)?;
+            candidates = mp4box.child_boxes();
+            found = Some(mp4box);
+        }
+        found
+    }
+
+    /// Read the bytes of a box as stored in the file, including its header,
+    /// for inspecting boxes this crate doesn't parse or reports a problem
+    /// with. `path` is interpreted as for [`find`](Self::find), but follows
+    /// the boxes on disk, so boxes skipped by the parser can be addressed
+    /// too. Returns `None` if no box matches.
+    pub fn raw_box(&mut self, path: &str) -> Result<Option<Vec<u8>>> {
+        let mut found: Option<(BoxType, u64, u64)> = None;
+        for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+            let (name, index) = match path_segment(segment) {
+                Some(segment) => segment,
+                None => return Ok(None),
+            };
+            let candidates = match found {
+                Some((box_type, offset, size)) => self.raw_child_boxes(box_type, offset, size)?,
+                None => self.boxes.clone(),
+            };
+            found = candidates
+                .into_iter()
+                .filter(|(box_type, _, _)| box_type.to_string() == name)
+                .nth(index.saturating_sub(1));
+            if found.is_none() || index == 0 {
+                return Ok(None);
+            }
+        }
+
+        match found {
+            Some((_, offset, size)) => {
+                let mut buf = vec![0; size as usize];
+                self.reader.seek(SeekFrom::Start(offset))?;
+                self.reader.read_exact(&mut buf)?;
+                Ok(Some(buf))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // Type, offset and size of the boxes inside the box at `offset`. Scanning
+    // stops at the first header that doesn't fit, so leaf boxes, whose
+    // payload isn't made of boxes, yield garbage at worst but never fail.
+    fn raw_child_boxes(&mut self, box_type: BoxType, offset: u64, size: u64) -> Result<Vec<(BoxType, u64, u64)>> {
+        child_boxes(&mut self.reader, box_type, offset, size)
+    }
+
+    /// Returns the mfhd sequence number of each movie fragment in file order.
+    /// Gaps or a decrease indicate dropped or reordered fragments.
+    pub fn fragment_sequence_numbers(&self) -> Vec<u32> {
+        self.moofs.iter().map(|moof| moof.sequence_number()).collect()
+    }
+
+    /// Returns the number of frames summed across the video tracks.
+    pub fn total_frames(&self) -> u64 {
+        self.tracks.values().map(|track| track.frame_count() as u64).sum()
+    }
+
+    /// Returns the movie-level user data from moov/udta, as opposed to the
+    /// user data of individual tracks.
+    pub fn movie_metadata(&self) -> Option<&UdtaBox> {
+        self.moov.udta.as_ref()
+    }
+
+    /// Returns the 3GPP asset metadata (titl, dscp, perf, auth, gnre) from
+    /// moov/udta, keyed by box type; see `UdtaBox::asset_metadata`.
+    pub fn asset_metadata(&self) -> HashMap<String, String> {
+        self.moov.udta.as_ref().map(|udta| udta.asset_metadata()).unwrap_or_default()
+    }
+
+    /// Returns the movie's copyright notice and its language from moov/udta/cprt.
+    pub fn copyright(&self) -> Option<&CprtBox> {
+        self.moov.udta.as_ref().and_then(|udta| udta.cprt.as_ref())
+    }
+
+    /// Returns the 3GPP recording year from moov/udta/yrrc.
+    pub fn recording_year(&self) -> Option<u16> {
+        self.moov.udta.as_ref()?.yrrc.as_ref().map(|yrrc| yrrc.year)
+    }
+
+    /// Returns the text of the XML metadata box, looking in the file-level
+    /// meta first and then in moov/udta/meta. Binary XML (bxml) is left to
+    /// the caller.
+    pub fn xml_metadata(&self) -> Option<String> {
+        let udta_meta = self.moov.udta.as_ref().and_then(|udta| udta.meta.as_ref());
+        self.meta.iter()
+            .chain(udta_meta)
+            .find_map(|meta| meta.xml.as_ref())
+            .map(|xml| xml.xml.clone())
+    }
+
+    /// Returns the cover art from the covr item of moov/udta/meta/ilst.
+    pub fn cover_art(&self) -> Option<CoverArt> {
+        self.moov.udta.as_ref()?.meta.as_ref()?.ilst.as_ref()?.cover_art()
+    }
+
+    /// Returns when the media was recorded, taken from the ©day metadata
+    /// item if present and parseable, otherwise from the mvhd creation time.
+    pub fn creation_date(&self) -> Option<SystemTime> {
+        let day = self.moov.udta.as_ref()
+            .and_then(|udta| udta.meta.as_ref())
+            .and_then(|meta| meta.ilst.as_ref())
+            .and_then(|ilst| ilst.creation_date());
+        if day.is_some() {
+            return day;
+        }
+
+        // mvhd times count seconds since 1904-01-01 UTC; zero means unset.
+        const MAC_EPOCH_OFFSET: u64 = 2082844800;
+        match self.moov.mvhd.creation_time {
+            0 => None,
+            t if t >= MAC_EPOCH_OFFSET => UNIX_EPOCH.checked_add(Duration::from_secs(t - MAC_EPOCH_OFFSET)),
+            t => UNIX_EPOCH.checked_sub(Duration::from_secs(MAC_EPOCH_OFFSET - t)),
+        }
+    }
+
+    /// Returns the primary item ID from the file-level meta/pitm box.
+    pub fn primary_item_id(&self) -> Option<u32> {
+        self.meta.as_ref().and_then(|meta| meta.pitm.as_ref()).map(|pitm| pitm.item_id)
+    }
+
+    /// Returns the item info entries from the file-level meta/iinf box, e.g.
+    /// the image items of a HEIF file.
+    pub fn items(&self) -> &[InfeBox] {
+        match self.meta.as_ref().and_then(|meta| meta.iinf.as_ref()) {
+            Some(iinf) => &iinf.entries,
+            None => &[],
+        }
+    }
+
+    /// Returns the protection scheme of a protected item from the
+    /// file-level meta/ipro box.
+    pub fn item_protection(&self, item_id: u32) -> Option<&SinfBox> {
+        self.meta.as_ref()?.item_protection(item_id)
+    }
+
+    /// Returns the item references from the file-level meta/iref box.
+    pub fn item_references(&self) -> &[ItemReference] {
+        match self.meta.as_ref().and_then(|meta| meta.iref.as_ref()) {
+            Some(iref) => &iref.references,
+            None => &[],
+        }
+    }
+
+    /// Returns the items a derived image item, such as a HEIF grid or
+    /// overlay, is composed of, in order: for a grid, its tiles row by row.
+    pub fn derived_image_items(&self, item_id: u32) -> Vec<u32> {
+        match self.meta.as_ref().and_then(|meta| meta.iref.as_ref()) {
+            Some(iref) => iref.references_from(item_id, ItemReference::DERIVED_IMAGE),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns where the data of an item is stored, from the file-level
+    /// meta/iloc box.
+    pub fn item_location(&self, item_id: u32) -> Option<&IlocItem> {
+        self.meta.as_ref()?.iloc.as_ref()?.item(item_id)
+    }
+
+    /// Read the data of the primary item (e.g. a cover image) as located by
+    /// meta/iloc. Returns `None` if the file has no primary item.
+    pub fn primary_item(&mut self) -> Result<Option<Bytes>> {
+        match self.primary_item_id() {
+            Some(item_id) => self.read_item(item_id).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Read the data of an item, such as a tile of a grid image, as located
+    /// by meta/iloc.
+    pub fn read_item(&mut self, item_id: u32) -> Result<Bytes> {
+        let meta = self.meta.as_ref().ok_or(Error::BoxNotFound(BoxType::MetaBox))?;
+        let iloc = meta.iloc.as_ref().ok_or(Error::BoxNotFound(BoxType::IlocBox))?;
+        let item = iloc.item(item_id).ok_or(Error::InvalidData("item not found in iloc"))?;
+        if item.data_reference_index != 0 {
+            return Err(Error::InvalidData("item in external file is not supported"));
+        }
+
+        let mut data = Vec::new();
+        for extent in item.extents.iter() {
+            let offset = item.base_offset.checked_add(extent.offset)
+                .ok_or(Error::InvalidData("iloc extent offset overflow"))?;
+            match item.construction_method {
+                IlocItem::CONSTRUCTION_FILE_OFFSET => {
+                    // A zero length extent covers the rest of the file.
+                    let length = if extent.length == 0 {
+                        self.size.saturating_sub(offset)
+                    } else {
+                        extent.length
+                    };
+                    match offset.checked_add(length) {
+                        Some(end) if end <= self.size => {}
+                        _ => return Err(Error::InvalidData("iloc extent outside file")),
+                    }
+                    let mut buf = vec![0u8; length as usize];
+                    self.reader.seek(SeekFrom::Start(offset))?;
+                    self.reader.read_exact(&mut buf)?;
+                    data.extend_from_slice(&buf);
+                }
+                IlocItem::CONSTRUCTION_IDAT_OFFSET => {
+                    let idat = meta.idat.as_ref().ok_or(Error::BoxNotFound(BoxType::IdatBox))?;
+                    let start = offset as usize;
+                    let end = if extent.length == 0 {
+                        Some(idat.len())
+                    } else {
+                        offset.checked_add(extent.length).map(|end| end as usize)
+                    };
+                    let slice = end.and_then(|end| idat.get(start..end))
+                        .ok_or(Error::InvalidData("iloc extent outside idat"))?;
+                    data.extend_from_slice(slice);
+                }
+                _ => {
+                    return Err(Error::InvalidData("unsupported iloc construction method"));
+                }
+            }
+        }
+
+        Ok(Bytes::from(data))
     }
 
     pub fn tracks(&self) -> &HashMap<u32, Mp4Track> {
@@ -161,4 +722,2067 @@ impl<R: Read + Seek> Mp4Reader<R> {
             Err(Error::TrakNotFound(track_id))
         }
     }
+
+    /// Reads every sample of a track into memory; see
+    /// `Mp4Track::read_all_samples`.
+    pub fn read_all_samples(&mut self, track_id: u32) -> Result<Vec<Vec<u8>>> {
+        if let Some(track) = self.tracks.get(&track_id) {
+            track.read_all_samples(&mut self.reader)
+        } else {
+            Err(Error::TrakNotFound(track_id))
+        }
+    }
+
+    /// Reads the first keyframe of a track; see `Mp4Track::thumbnail`.
+    pub fn thumbnail(&mut self, track_id: u32) -> Result<Option<Thumbnail>> {
+        if let Some(track) = self.tracks.get(&track_id) {
+            track.thumbnail(&mut self.reader)
+        } else {
+            Err(Error::TrakNotFound(track_id))
+        }
+    }
+
+    /// Split a progressive file into a CMAF init segment and media segments
+    /// of roughly `segment_duration` each, as used for DASH. Segments start
+    /// on a sync sample of the first video track (or the first track if
+    /// there is no video) and the other tracks are cut at the same times.
+    pub fn fragment(&mut self, segment_duration: Duration) -> Result<(Vec<u8>, Vec<Vec<u8>>)> {
+        self.fragment_with_progress(segment_duration, |_, _| {})
+    }
+
+    /// Like [`fragment`](Self::fragment), but calls `progress` with the
+    /// number of samples read so far and the total number of samples.
+    pub fn fragment_with_progress<F: FnMut(u64, u64)>(
+        &mut self,
+        segment_duration: Duration,
+        progress: F,
+    ) -> Result<(Vec<u8>, Vec<Vec<u8>>)> {
+        self.fragment_with_options(segment_duration, &FragmentOptions::default(), progress)
+    }
+
+    /// Like [`fragment_with_progress`](Self::fragment_with_progress), with
+    /// control over how the tracks are rewritten.
+    pub fn fragment_with_options<F: FnMut(u64, u64)>(
+        &mut self,
+        segment_duration: Duration,
+        options: &FragmentOptions,
+        mut progress: F,
+    ) -> Result<(Vec<u8>, Vec<Vec<u8>>)> {
+        if self.is_fragmented() {
+            return Err(Error::InvalidData("file is already fragmented"));
+        }
+
+        let track_ids: Vec<u32> = self.moov.traks.iter().map(|trak| trak.tkhd.track_id).collect();
+        if track_ids.is_empty() {
+            return Err(Error::InvalidData("no tracks to fragment"));
+        }
+        let mut total = 0;
+        for track_id in track_ids.iter() {
+            total += self.sample_count(*track_id)? as u64;
+        }
+        let mut current = 0;
+        let mut samples = Vec::with_capacity(track_ids.len());
+        for track_id in track_ids.iter() {
+            let sample_count = self.sample_count(*track_id)?;
+            let mut track_samples = Vec::with_capacity(sample_count as usize);
+            for sample_id in 1..=sample_count {
+                if let Some(sample) = self.read_sample(*track_id, sample_id)? {
+                    track_samples.push(sample);
+                }
+                current += 1;
+                progress(current, total);
+            }
+            samples.push(track_samples);
+        }
+        let timescales: Vec<u64> = self.moov.traks.iter()
+            .map(|trak| trak.mdia.mdhd.timescale.max(1) as u64)
+            .collect();
+
+        let reference = track_ids.iter()
+            .position(|track_id| matches!(self.tracks[track_id].track_type(), Ok(TrackType::Video)))
+            .unwrap_or(0);
+
+        // Segment boundaries as decode times of the reference track.
+        let reference_timescale = timescales[reference];
+        let target = (segment_duration.as_nanos() * reference_timescale as u128
+            / 1_000_000_000)
+            .max(1) as u64;
+        let mut boundaries = Vec::new();
+        let mut time = 0;
+        for sample in samples[reference].iter() {
+            let next_boundary = (boundaries.len() as u64 + 1) * target;
+            if sample.is_sync && time > 0 && time >= next_boundary {
+                boundaries.push(time);
+            }
+            time += sample.duration as u64;
+        }
+
+        // Assign each sample to the segment whose time range holds its
+        // decode time and remember the decode time of the first sample.
+        let segment_count = boundaries.len() + 1;
+        let mut runs: Vec<Vec<(usize, u64, usize)>> = vec![Vec::new(); segment_count];
+        for (i, track_samples) in samples.iter().enumerate() {
+            let mut time = 0;
+            let mut segment = 0;
+            let mut first = 0;
+            let mut first_time = 0;
+            for (j, sample) in track_samples.iter().enumerate() {
+                while segment < boundaries.len()
+                    && time as u128 * reference_timescale as u128
+                        >= boundaries[segment] as u128 * timescales[i] as u128
+                {
+                    if j > first {
+                        runs[segment].push((i, first_time, j - first));
+                    }
+                    segment += 1;
+                    first = j;
+                    first_time = time;
+                }
+                time += sample.duration as u64;
+            }
+            if track_samples.len() > first {
+                runs[segment].push((i, first_time, track_samples.len() - first));
+            }
+        }
+
+        let mut init = Vec::new();
+        let ftyp = FtypBox {
+            major_brand: str::parse("iso6").unwrap(),
+            minor_version: 0,
+            compatible_brands: vec![
+                str::parse("iso6").unwrap(),
+                str::parse("cmfc").unwrap(),
+                str::parse("dash").unwrap(),
+            ],
+        };
+        ftyp.write_box(&mut init)?;
+        let mut moov = MoovBox {
+            mvhd: self.moov.mvhd.clone(),
+            traks: self.moov.traks.iter().map(empty_sample_tables).collect(),
+            ..MoovBox::default()
+        };
+        moov.mvhd.duration = 0;
+        if options.out_of_band_parameter_sets {
+            for (trak, track_samples) in moov.traks.iter_mut().zip(samples.iter_mut()) {
+                move_parameter_sets(&mut trak.mdia.minf.stbl.stsd, track_samples)?;
+            }
+        }
+        writer::set_fragment_defaults(&mut moov);
+        moov.write_box(&mut init)?;
+
+        // Samples are written to the segments in track order.
+        let mut offsets = vec![0; track_ids.len()];
+        let mut segments = Vec::with_capacity(segment_count);
+        for (segment, segment_runs) in runs.iter().enumerate() {
+            let fragment_runs: Vec<writer::FragmentRun> = segment_runs.iter()
+                .map(|&(i, base_media_decode_time, count)| {
+                    let start = offsets[i];
+                    offsets[i] += count;
+                    writer::FragmentRun {
+                        track_id: track_ids[i],
+                        base_media_decode_time,
+                        samples: &samples[i][start..start + count],
+                    }
+                })
+                .collect();
+            let mut data = Vec::new();
+            writer::write_moof_mdat(&mut data, segment as u32 + 1, &fragment_runs)?;
+            segments.push(data);
+        }
+
+        Ok((init, segments))
+    }
+
+    /// Check that every chunk of every track, from its stco/co64 offset to
+    /// the end of its last sample, lies within an mdat box. Returns the
+    /// first chunk that doesn't, in moov track order.
+    pub fn validate_chunk_offsets(&self) -> Result<Option<ChunkOutOfBounds>> {
+        let chunk_sizes = self.chunk_sizes()?;
+        Ok(self.first_chunk_out_of_bounds(&chunk_sizes))
+    }
+
+    /// Recompute the stco/co64 chunk offsets if `validate_chunk_offsets`
+    /// finds a chunk outside of every mdat box, e.g. because its offset was
+    /// zeroed. The samples are assumed to be stored contiguously from the
+    /// start of the first mdat, one track after another in moov order.
+    /// Returns whether the offsets were rebuilt.
+    pub fn rebuild_chunk_offsets(&mut self) -> Result<bool> {
+        let chunk_sizes = self.chunk_sizes()?;
+        if self.first_chunk_out_of_bounds(&chunk_sizes).is_none() {
+            return Ok(false);
+        }
+
+        let mut offset = match self.mdat_ranges.first() {
+            Some((start, _)) => *start,
+            None => return Err(Error::BoxNotFound(BoxType::MdatBox)),
+        };
+        for (trak, sizes) in self.moov.traks.iter_mut().zip(chunk_sizes.iter()) {
+            let mut offsets = Vec::with_capacity(sizes.len());
+            for size in sizes.iter() {
+                offsets.push(offset);
+                offset += size;
+            }
+
+            let stbl = &mut trak.mdia.minf.stbl;
+            if let Some(ref mut stco) = stbl.stco {
+                if offset > u32::MAX as u64 {
+                    return Err(Error::InvalidData("chunk offset too large for stco"));
+                }
+                stco.entries = offsets.iter().map(|o| *o as u32).collect();
+            } else if let Some(ref mut co64) = stbl.co64 {
+                co64.entries = offsets;
+            }
+
+            if let Some(track) = self.tracks.get_mut(&trak.tkhd.track_id) {
+                track.trak.mdia.minf.stbl.stco = stbl.stco.clone();
+                track.trak.mdia.minf.stbl.co64 = stbl.co64.clone();
+            }
+        }
+        Ok(true)
+    }
+
+    // The chunk sizes of every trak in moov order.
+    fn chunk_sizes(&self) -> Result<Vec<Vec<u64>>> {
+        self.moov.traks.iter().map(|trak| Mp4Track::from(trak).chunk_sizes()).collect()
+    }
+
+    fn first_chunk_out_of_bounds(&self, chunk_sizes: &[Vec<u64>]) -> Option<ChunkOutOfBounds> {
+        for (trak, sizes) in self.moov.traks.iter().zip(chunk_sizes.iter()) {
+            let offsets = trak.mdia.minf.stbl.chunk_offsets().unwrap_or_default();
+            for (i, (offset, size)) in offsets.iter().zip(sizes.iter()).enumerate() {
+                // A chunk whose end overflows can't be in any mdat.
+                let in_mdat = offset.checked_add(*size).is_some_and(|chunk_end| {
+                    self.mdat_ranges.iter().any(|(start, end)| *offset >= *start && chunk_end <= *end)
+                });
+                if !in_mdat {
+                    return Some(ChunkOutOfBounds {
+                        track_id: trak.tkhd.track_id,
+                        chunk_id: i as u32 + 1,
+                        offset: *offset,
+                        size: *size,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Rewrite the timing of a track to a constant frame rate; see
+    /// `Mp4Track::force_cfr`. The movie duration is updated to match.
+    pub fn force_cfr(&mut self, track_id: u32, fps: Ratio<u32>) -> Result<()> {
+        let track = self.tracks.get_mut(&track_id).ok_or(Error::TrakNotFound(track_id))?;
+        track.force_cfr(fps, self.moov.mvhd.timescale)?;
+
+        for trak in self.moov.traks.iter_mut() {
+            if trak.tkhd.track_id == track_id {
+                *trak = track.trak.clone();
+            }
+        }
+        self.moov.mvhd.duration = self.moov.traks.iter()
+            .map(|trak| trak.tkhd.duration)
+            .max()
+            .unwrap_or(0);
+        Ok(())
+    }
+
+    /// Compare every tkhd duration with the one implied by the track's edit
+    /// list or mdhd duration, see `Mp4Track::expected_tkhd_duration`. A
+    /// difference of one tick is allowed for rounding.
+    pub fn validate_track_durations(&self) -> Vec<DurationMismatch> {
+        let timescale = self.moov.mvhd.timescale;
+        let mut mismatches = Vec::new();
+        for trak in self.moov.traks.iter() {
+            if trak.tkhd.is_duration_unknown() {
+                continue;
+            }
+            let expected = self.tracks.get(&trak.tkhd.track_id)
+                .and_then(|track| track.expected_tkhd_duration(timescale));
+            if let Some(expected) = expected {
+                if trak.tkhd.duration.abs_diff(expected) > 1 {
+                    mismatches.push(DurationMismatch {
+                        track_id: trak.tkhd.track_id,
+                        tkhd_duration: trak.tkhd.duration,
+                        expected,
+                    });
+                }
+            }
+        }
+        mismatches
+    }
+
+    /// Set the tkhd durations reported by `validate_track_durations` to
+    /// their expected values and the mvhd duration to the longest track.
+    /// Returns what was corrected.
+    pub fn fix_track_durations(&mut self) -> Vec<DurationMismatch> {
+        let mismatches = self.validate_track_durations();
+        for mismatch in mismatches.iter() {
+            for trak in self.moov.traks.iter_mut() {
+                if trak.tkhd.track_id == mismatch.track_id {
+                    trak.tkhd.duration = mismatch.expected;
+                }
+            }
+            if let Some(track) = self.tracks.get_mut(&mismatch.track_id) {
+                track.trak.tkhd.duration = mismatch.expected;
+            }
+        }
+        if !mismatches.is_empty() {
+            self.moov.mvhd.duration = self.moov.traks.iter()
+                .map(|trak| trak.tkhd.duration)
+                .max()
+                .unwrap_or(0);
+        }
+        mismatches
+    }
+
+    /// Walk the fragments in file order and return the first whose tfdt
+    /// doesn't equal the tfdt of the track's previous fragment plus its
+    /// duration, be it a gap, an overlap or a reset.
+    pub fn validate_decode_times(&self) -> Option<DecodeTimeDiscontinuity> {
+        self.decode_time_discontinuities().into_iter().next().map(|(_, _, d)| d)
+    }
+
+    /// Rewrite the tfdt of every fragment so that each track's fragments
+    /// follow on from one another, keeping the first fragment's time.
+    /// Returns the discontinuities that were removed.
+    pub fn fix_decode_times(&mut self) -> Vec<DecodeTimeDiscontinuity> {
+        let discontinuities = self.decode_time_discontinuities();
+        for (moof_index, traf_index, discontinuity) in discontinuities.iter() {
+            let traf = &mut self.moofs[*moof_index].trafs[*traf_index];
+            if let Some(ref mut tfdt) = traf.tfdt {
+                tfdt.base_media_decode_time = discontinuity.expected;
+                if discontinuity.expected > u32::MAX as u64 {
+                    tfdt.version = 1;
+                }
+            }
+        }
+
+        for track in self.tracks.values_mut() {
+            track.trafs = self.moofs.iter()
+                .flat_map(|moof| moof.trafs.iter())
+                .filter(|traf| traf.tfhd.track_id == track.track_id())
+                .cloned()
+                .collect();
+        }
+        discontinuities.into_iter().map(|(_, _, d)| d).collect()
+    }
+
+    /// Write a copy of the file with every moof serialized from `moofs`,
+    /// e.g. to save the tfdts corrected by
+    /// [`fix_decode_times`](Self::fix_decode_times). The other top-level
+    /// boxes are copied unchanged.
+    ///
+    /// A moof grows if one of its tfdts now needs 64 bits. The trun data
+    /// offsets relative to it and the explicit base data offsets of the
+    /// following trafs are shifted to match, but sidx and mfra are copied
+    /// as is. Returns the number of bytes written.
+    pub fn write_fixed_fragments<W: Write>(&mut self, writer: &mut W) -> Result<u64> {
+        let mut moofs = self.moofs.iter();
+        let mut shift: i64 = 0;
+        let mut size = 0;
+        let mut buf = vec![0; COPY_BUFFER_SIZE];
+        for (name, offset, box_size) in self.boxes.iter() {
+            if *name == BoxType::MoofBox {
+                let mut moof = moofs.next()
+                    .ok_or(Error::InvalidData("moof not read"))?
+                    .clone();
+                let delta = moof.box_size() as i64 - *box_size as i64;
+                shift += delta;
+                for (i, traf) in moof.trafs.iter_mut().enumerate() {
+                    if let Some(ref mut base_data_offset) = traf.tfhd.base_data_offset {
+                        *base_data_offset = base_data_offset.checked_add_signed(shift)
+                            .ok_or(Error::InvalidData("base data offset out of range"))?;
+                        continue;
+                    }
+                    // Without a base data offset, only the first traf and
+                    // those flagged default-base-is-moof count from the moof.
+                    if i > 0 && traf.tfhd.flags & TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF == 0 {
+                        continue;
+                    }
+                    if let Some(data_offset) = traf.trun.as_mut().and_then(|trun| trun.data_offset.as_mut()) {
+                        *data_offset = i32::try_from(*data_offset as i64 + delta)
+                            .map_err(|_| Error::InvalidData("data offset out of range"))?;
+                    }
+                }
+                size += moof.write_box(writer)?;
+                continue;
+            }
+
+            self.reader.seek(SeekFrom::Start(*offset))?;
+            let mut remaining = *box_size;
+            while remaining > 0 {
+                let len = remaining.min(buf.len() as u64) as usize;
+                self.reader.read_exact(&mut buf[..len])?;
+                writer.write_all(&buf[..len])?;
+                remaining -= len as u64;
+            }
+            size += box_size;
+        }
+        Ok(size)
+    }
+
+    // List every discontinuity with the index of its moof and traf, assuming
+    // the earlier ones were fixed.
+    fn decode_time_discontinuities(&self) -> Vec<(usize, usize, DecodeTimeDiscontinuity)> {
+        let mut next_times: HashMap<u32, u64> = HashMap::new();
+        let mut discontinuities = Vec::new();
+        for (i, moof) in self.moofs.iter().enumerate() {
+            for (j, traf) in moof.trafs.iter().enumerate() {
+                let track_id = traf.tfhd.track_id;
+                let default_sample_duration = self.moov.mvex.as_ref()
+                    .and_then(|mvex| mvex.trex(track_id))
+                    .map(|trex| trex.default_sample_duration)
+                    .unwrap_or(0);
+                let expected = next_times.get(&track_id).copied();
+                let start = match (&traf.tfdt, expected) {
+                    (Some(tfdt), Some(expected)) if tfdt.base_media_decode_time != expected => {
+                        discontinuities.push((i, j, DecodeTimeDiscontinuity {
+                            track_id,
+                            sequence_number: moof.mfhd.sequence_number,
+                            expected,
+                            base_media_decode_time: tfdt.base_media_decode_time,
+                        }));
+                        expected
+                    }
+                    (Some(tfdt), _) => tfdt.base_media_decode_time,
+                    (None, expected) => expected.unwrap_or(0),
+                };
+                next_times.insert(track_id, start + traf.duration(default_sample_duration));
+            }
+        }
+        discontinuities
+    }
+
+    /// Write a copy of the file with `moov` moved in front of the media data,
+    /// so playback can start before the whole file has been downloaded. The
+    /// other top-level boxes are copied unchanged, in their original order.
+    ///
+    /// Chunk offsets are shifted by the new position of the box they point
+    /// into; a track's stco is upgraded to co64 if its shifted offsets no
+    /// longer fit in 32 bits. Returns the number of bytes written.
+    pub fn write_faststart<W: Write>(&mut self, writer: &mut W) -> Result<u64> {
+        self.write_faststart_with_progress(writer, |_, _| {})
+    }
+
+    /// Like [`write_faststart`](Self::write_faststart), but calls `progress`
+    /// with the number of media bytes copied so far and the total to copy.
+    pub fn write_faststart_with_progress<W: Write, F: FnMut(u64, u64)>(
+        &mut self,
+        writer: &mut W,
+        progress: F,
+    ) -> Result<u64> {
+        self.write_rearranged(writer, false, progress)
+    }
+
+    /// Like [`write_faststart`](Self::write_faststart), but also drops every
+    /// region listed by [`free_space`](Self::free_space), including stale
+    /// moovs hidden in free boxes.
+    pub fn write_compacted<W: Write>(&mut self, writer: &mut W) -> Result<u64> {
+        self.write_rearranged(writer, true, |_, _| {})
+    }
+
+    fn write_rearranged<W: Write, F: FnMut(u64, u64)>(
+        &mut self,
+        writer: &mut W,
+        compact: bool,
+        mut progress: F,
+    ) -> Result<u64> {
+        if self.is_fragmented() {
+            return Err(Error::InvalidData("file is fragmented"));
+        }
+
+        let free_space = &self.free_space;
+        let data: Vec<(u64, u64)> = self.boxes.iter()
+            .filter(|(name, _, _)| *name != BoxType::FtypBox && *name != BoxType::MoovBox)
+            .filter(|(_, offset, _)| {
+                !compact || !free_space.iter().any(|space| space.offset == *offset)
+            })
+            .map(|(_, offset, size)| (*offset, *size))
+            .collect();
+        let moov = faststart_moov(&self.moov, self.ftyp.box_size(), &data)?;
+
+        let total = data.iter().map(|(_, size)| size).sum();
+        let mut copied = 0;
+        let mut buf = vec![0; COPY_BUFFER_SIZE];
+
+        let mut size = self.ftyp.write_box(writer)?;
+        size += moov.write_box(writer)?;
+        for (offset, box_size) in data {
+            self.reader.seek(SeekFrom::Start(offset))?;
+            let mut remaining = box_size;
+            while remaining > 0 {
+                let len = remaining.min(buf.len() as u64) as usize;
+                self.reader.read_exact(&mut buf[..len])?;
+                writer.write_all(&buf[..len])?;
+                remaining -= len as u64;
+                copied += len as u64;
+                progress(copied, total);
+            }
+            size += box_size;
+        }
+        Ok(size)
+    }
+
+    /// Returns the free and skip boxes and any stale moovs, in file order.
+    /// Re-muxed files can hold both an old moov, often renamed to free, and
+    /// the one in use; the reader uses the last moov whose chunk offsets all
+    /// point into an mdat.
+    pub fn free_space(&self) -> &[FreeSpace] {
+        &self.free_space
+    }
+
+    /// Returns the boxes parsed by the registry passed to
+    /// [`read_header_with_registry`](Self::read_header_with_registry): first
+    /// the top-level ones in file order, then those found inside the moov,
+    /// then those inside the moofs.
+    pub fn custom_boxes(&self) -> &[CustomBox] {
+        &self.custom_boxes
+    }
+
+    /// Returns the total size of the regions in
+    /// [`free_space`](Self::free_space).
+    pub fn wasted_space(&self) -> u64 {
+        self.free_space.iter().map(|space| space.size).sum()
+    }
+
+    /// Write a copy of the file without the track `track_id`. Its trak and
+    /// media data are dropped, tref entries referring to it are removed, and
+    /// the mvhd duration and next_track_id are recomputed for the remaining
+    /// tracks.
+    ///
+    /// The copy holds only ftyp, an mdat with the remaining chunks in their
+    /// original order, and moov. Returns the number of bytes written.
+    pub fn remove_track<W: Write>(&mut self, track_id: u32, writer: &mut W) -> Result<u64> {
+        if self.is_fragmented() {
+            return Err(Error::InvalidData("file is fragmented"));
+        }
+        if !self.tracks.contains_key(&track_id) {
+            return Err(Error::TrakNotFound(track_id));
+        }
+
+        let mut moov = self.moov.clone();
+        moov.traks.retain(|trak| trak.tkhd.track_id != track_id);
+        for trak in moov.traks.iter_mut() {
+            if let Some(ref mut tref) = trak.tref {
+                for reference in tref.references.iter_mut() {
+                    reference.track_ids.retain(|id| *id != track_id);
+                }
+                tref.references.retain(|reference| !reference.track_ids.is_empty());
+                if tref.references.is_empty() {
+                    trak.tref = None;
+                }
+            }
+        }
+        if let Some(ref mut mvex) = moov.mvex {
+            mvex.trexs.retain(|trex| trex.track_id != track_id);
+        }
+        moov.mvhd.next_track_id = moov.traks.iter()
+            .map(|trak| trak.tkhd.track_id)
+            .max()
+            .unwrap_or(0) + 1;
+        moov.mvhd.duration = moov.traks.iter()
+            .map(|trak| trak.tkhd.duration)
+            .max()
+            .unwrap_or(0);
+
+        // The offset, size, trak index and chunk index of every chunk kept.
+        let mut chunks = Vec::new();
+        for (i, trak) in moov.traks.iter().enumerate() {
+            let track = Mp4Track::from(trak);
+            let offsets = trak.mdia.minf.stbl.chunk_offsets().unwrap_or_default();
+            for (j, (offset, size)) in offsets.into_iter().zip(track.chunk_sizes()?).enumerate() {
+                chunks.push((offset, size, i, j));
+            }
+        }
+        self.write_with_chunks(moov, chunks, writer)
+    }
+
+    /// Write a copy of the file holding only the sync samples of each track,
+    /// for scene detection or previews. Every kept sample lasts until the
+    /// next one, and the first from the start of its track, so the tracks
+    /// keep their duration, and the sample tables are rebuilt around them.
+    /// Tracks without an stss are all sync samples and are copied whole.
+    ///
+    /// Like [`remove_track`](Self::remove_track), the copy holds only ftyp,
+    /// mdat and moov. Returns the number of bytes written.
+    pub fn keyframes_only<W: Write>(&mut self, writer: &mut W) -> Result<u64> {
+        if self.is_fragmented() {
+            return Err(Error::InvalidData("file is fragmented"));
+        }
+
+        let mut moov = self.moov.clone();
+        let mut chunks = Vec::new();
+        for (i, trak) in moov.traks.iter_mut().enumerate() {
+            let mut track = Mp4Track::from(trak);
+            for (j, (offset, size)) in track.retain_sync_samples()?.into_iter().enumerate() {
+                chunks.push((offset, size as u64, i, j));
+            }
+            *trak = track.trak;
+        }
+        self.write_with_chunks(moov, chunks, writer)
+    }
+
+    /// Split the file at `times` into independent progressive files, each
+    /// with its own moov and sample tables. Every split point moves forward
+    /// to the next sync sample of the reference track, the first video
+    /// track or else the first track, so that each file starts on a sync
+    /// sample; the other tracks are cut at the same decode time. Split
+    /// points at the start, past the last sync sample, or on the same sync
+    /// sample as an earlier one are ignored.
+    ///
+    /// Edit lists are dropped, so every file starts at time zero. Like
+    /// [`remove_track`](Self::remove_track), each file holds only ftyp, mdat
+    /// and moov.
+    pub fn split_at(&mut self, times: &[Duration]) -> Result<Vec<Vec<u8>>> {
+        if self.is_fragmented() {
+            return Err(Error::InvalidData("file is fragmented"));
+        }
+        if self.moov.traks.is_empty() {
+            return Err(Error::InvalidData("no tracks to split"));
+        }
+
+        let tracks: Vec<Mp4Track> = self.moov.traks.iter().map(Mp4Track::from).collect();
+        let reference = tracks.iter()
+            .position(|track| matches!(track.track_type(), Ok(TrackType::Video)))
+            .unwrap_or(0);
+        let reference_timescale = tracks[reference].timescale().max(1) as u64;
+
+        // Split points as decode times of the reference track.
+        let mut sync_times = Vec::new();
+        for sample_id in tracks[reference].sync_samples() {
+            sync_times.push(tracks[reference].sample_time(sample_id)?.0);
+        }
+        let mut times = times.to_vec();
+        times.sort();
+        let mut boundaries: Vec<u64> = Vec::new();
+        for time in times {
+            let time = (time.as_nanos() * reference_timescale as u128 / 1_000_000_000) as u64;
+            if let Some(boundary) = sync_times.iter().find(|sync_time| **sync_time >= time) {
+                if *boundary > 0 && boundaries.last() < Some(boundary) {
+                    boundaries.push(*boundary);
+                }
+            }
+        }
+
+        // The first sample of each track in each file, and one past the last
+        // sample of the track.
+        let mut cuts = Vec::with_capacity(tracks.len());
+        for track in tracks.iter() {
+            let timescale = track.timescale().max(1) as u128;
+            let mut decode_times = Vec::with_capacity(track.sample_count() as usize);
+            let mut time = 0;
+            for entry in track.trak.mdia.minf.stbl.stts.entries.iter() {
+                for _ in 0..entry.sample_count {
+                    decode_times.push(time);
+                    time += entry.sample_delta as u64;
+                }
+            }
+            let mut track_cuts = vec![1];
+            for boundary in boundaries.iter() {
+                let index = decode_times.partition_point(|time| {
+                    *time as u128 * (reference_timescale as u128) < *boundary as u128 * timescale
+                });
+                track_cuts.push(index as u32 + 1);
+            }
+            track_cuts.push(track.sample_count() + 1);
+            cuts.push(track_cuts);
+        }
+
+        let movie_timescale = self.moov.mvhd.timescale;
+        let mut files = Vec::with_capacity(boundaries.len() + 1);
+        for k in 0..=boundaries.len() {
+            let mut moov = self.moov.clone();
+            let mut chunks = Vec::new();
+            for (i, trak) in moov.traks.iter_mut().enumerate() {
+                let mut track = Mp4Track::from(trak);
+                let samples = track.retain_samples(cuts[i][k]..cuts[i][k + 1], movie_timescale)?;
+                for (j, (offset, size)) in samples.into_iter().enumerate() {
+                    chunks.push((offset, size as u64, i, j));
+                }
+                *trak = track.trak;
+            }
+            moov.mvhd.duration = moov.traks.iter()
+                .map(|trak| trak.tkhd.duration)
+                .max()
+                .unwrap_or(0);
+
+            let mut file = Vec::new();
+            self.write_with_chunks(moov, chunks, &mut file)?;
+            files.push(file);
+        }
+        Ok(files)
+    }
+
+    // Write ftyp, an mdat holding the given chunks in file order, and moov
+    // with its chunk offsets pointing into the new mdat. Chunks are given as
+    // their source offset, size, trak index and chunk index.
+    fn write_with_chunks<W: Write>(
+        &mut self,
+        mut moov: MoovBox,
+        mut chunks: Vec<(u64, u64, usize, usize)>,
+        writer: &mut W,
+    ) -> Result<u64> {
+        chunks.sort_by_key(|(offset, _, _, _)| *offset);
+
+        let data_size: u64 = chunks.iter().map(|(_, size, _, _)| size).sum();
+        let mdat = BoxHeader::new(BoxType::MdatBox, HEADER_SIZE + data_size);
+        let mdat = if mdat.size > u32::MAX as u64 {
+            BoxHeader::new(BoxType::MdatBox, HEADER_SIZE + 8 + data_size)
+        } else {
+            mdat
+        };
+
+        let mut new_offsets = vec![Vec::new(); moov.traks.len()];
+        for (_, _, i, _) in chunks.iter() {
+            new_offsets[*i].push(0);
+        }
+        let mut offset = self.ftyp.box_size() + mdat.size - data_size;
+        for (_, size, i, j) in chunks.iter() {
+            new_offsets[*i][*j] = offset;
+            offset += size;
+        }
+        for (trak, offsets) in moov.traks.iter_mut().zip(new_offsets) {
+            set_chunk_offsets(&mut trak.mdia.minf.stbl, offsets);
+        }
+
+        let mut size = self.ftyp.write_box(writer)?;
+        size += mdat.write(writer)?;
+        let mut buf = vec![0; COPY_BUFFER_SIZE];
+        for (offset, chunk_size, _, _) in chunks {
+            self.reader.seek(SeekFrom::Start(offset))?;
+            let mut remaining = chunk_size;
+            while remaining > 0 {
+                let len = remaining.min(buf.len() as u64) as usize;
+                self.reader.read_exact(&mut buf[..len])?;
+                writer.write_all(&buf[..len])?;
+                remaining -= len as u64;
+            }
+            size += chunk_size;
+        }
+        size += moov.write_box(writer)?;
+        Ok(size)
+    }
+
+    /// Add a trex to moov/mvex, creating the mvex if needed, for every track
+    /// that lacks one so that the movie can be fragmented. The defaults of
+    /// each new trex are taken from the first samples of its track. Returns
+    /// the IDs of the tracks that got a trex.
+    pub fn ensure_mvex(&mut self) -> Result<Vec<u32>> {
+        let mut trexs = Vec::new();
+        for trak in self.moov.traks.iter() {
+            let track = &self.tracks[&trak.tkhd.track_id];
+            if !track.is_fragmented_compatible() {
+                trexs.push(track.default_trex()?);
+            }
+        }
+        if trexs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let track_ids: Vec<u32> = trexs.iter().map(|trex| trex.track_id).collect();
+        for trex in trexs.iter() {
+            let track = self.tracks.get_mut(&trex.track_id).unwrap();
+            track.has_trex = true;
+            track.default_sample_duration = trex.default_sample_duration;
+            track.default_sample_description_index = trex.default_sample_description_index;
+        }
+        self.moov.mvex.get_or_insert_with(MvexBox::default).trexs.extend(trexs);
+        Ok(track_ids)
+    }
+
+    /// Assign sequential track IDs starting at 1, in `moov` order.
+    ///
+    /// Updates every `tkhd`, `tref`, `trex` and `tfhd` that refers to a track,
+    /// and sets the `mvhd` next_track_id past the last assigned ID. Since the
+    /// old numbers may now belong to other tracks, tref entries and trexs
+    /// that refer to a track ID missing from the file, or to one that
+    /// several traks share, are dropped. Fails without changing anything if
+    /// a fragment belongs to a shared ID, as its track is unknown.
+    pub fn renumber_tracks(&mut self) -> Result<()> {
+        let mut counts: HashMap<u32, usize> = HashMap::new();
+        for trak in self.moov.traks.iter() {
+            *counts.entry(trak.tkhd.track_id).or_insert(0) += 1;
+        }
+        let is_shared = |track_id: &u32| counts.get(track_id).copied().unwrap_or(0) > 1;
+        let fragments = self.moofs.iter().flat_map(|moof| moof.trafs.iter());
+        if fragments.map(|traf| traf.tfhd.track_id).any(|track_id| is_shared(&track_id)) {
+            return Err(Error::InvalidData("fragment of a track ID shared by several traks"));
+        }
+        let ids: HashMap<u32, u32> = self.moov.traks.iter()
+            .enumerate()
+            .filter(|(_, trak)| counts[&trak.tkhd.track_id] == 1)
+            .map(|(i, trak)| (trak.tkhd.track_id, i as u32 + 1))
+            .collect();
+        let renumber = |track_id: &mut u32| {
+            if let Some(new_id) = ids.get(track_id) {
+                *track_id = *new_id;
+            }
+        };
+
+        let mut tracks = std::mem::take(&mut self.tracks);
+        for (i, trak) in self.moov.traks.iter_mut().enumerate() {
+            let old_id = trak.tkhd.track_id;
+            trak.tkhd.track_id = i as u32 + 1;
+            renumber_tref(trak, &ids);
+
+            // Of several traks sharing an ID only the last one was read into
+            // a track, together with the fragments of that ID.
+            let mut track = match tracks.remove(&old_id) {
+                Some(mut track) if counts[&old_id] == 1 => {
+                    for traf in track.trafs.iter_mut() {
+                        renumber(&mut traf.tfhd.track_id);
+                    }
+                    track
+                }
+                _ => Mp4Track::from(trak),
+            };
+            track.trak = trak.clone();
+            self.tracks.insert(track.track_id(), track);
+        }
+        if let Some(ref mut mvex) = self.moov.mvex {
+            mvex.trexs.retain(|trex| ids.contains_key(&trex.track_id));
+            for trex in mvex.trexs.iter_mut() {
+                renumber(&mut trex.track_id);
+            }
+        }
+        for moof in self.moofs.iter_mut() {
+            for traf in moof.trafs.iter_mut() {
+                renumber(&mut traf.tfhd.track_id);
+            }
+        }
+        self.moov.mvhd.next_track_id = self.moov.traks.len() as u32 + 1;
+        Ok(())
+    }
+}
+
+// Map the tref of a trak to new track IDs, dropping the IDs without one.
+fn renumber_tref(trak: &mut TrakBox, ids: &HashMap<u32, u32>) {
+    if let Some(ref mut tref) = trak.tref {
+        for reference in tref.references.iter_mut() {
+            reference.track_ids = reference.track_ids.iter()
+                .filter_map(|track_id| ids.get(track_id).copied())
+                .collect();
+        }
+        tref.references.retain(|reference| !reference.track_ids.is_empty());
+        if tref.references.is_empty() {
+            trak.tref = None;
+        }
+    }
+}
+
+// Copy a trak for a fragmented init segment, keeping the sample
+// descriptions but dropping the sample tables and durations.
+fn empty_sample_tables(trak: &TrakBox) -> TrakBox {
+    let mut trak = trak.clone();
+    trak.tkhd.duration = 0;
+    trak.mdia.mdhd.duration = 0;
+    let stbl = &mut trak.mdia.minf.stbl;
+    *stbl = StblBox {
+        stsd: stbl.stsd.clone(),
+        stco: Some(StcoBox::default()),
+        ..StblBox::default()
+    };
+    trak
+}
+
+// Media data is copied in pieces of this size, reporting progress after each.
+const COPY_BUFFER_SIZE: usize = 1 << 20;
+
+// Strip the parameter sets from the samples of an AVC or HEVC track and add
+// any the sample entry doesn't hold yet to its decoder configuration. HEVC
+// sample entries become hvc1.
+fn move_parameter_sets(stsd: &mut StsdBox, samples: &mut [Mp4Sample]) -> Result<()> {
+    if let Some(ref mut avc1) = stsd.avc1 {
+        let avcc = &mut avc1.avcc;
+        let length_size = (avcc.length_size_minus_one & 0x03) + 1;
+        for sample in samples.iter_mut() {
+            let mut bytes = Vec::with_capacity(sample.bytes.len());
+            for nal in AvcSampleSplitter::new(&sample.bytes, length_size)? {
+                let nal = nal?;
+                let parameter_sets = match nal.nal_unit_type {
+                    AvcNal::TYPE_SPS => &mut avcc.sequence_parameter_sets,
+                    AvcNal::TYPE_PPS => &mut avcc.picture_parameter_sets,
+                    _ => {
+                        write_nal(&mut bytes, nal.data, length_size);
+                        continue;
+                    }
+                };
+                if !parameter_sets.iter().any(|nalu| nalu.bytes == nal.data) {
+                    parameter_sets.push(NalUnit::from(nal.data));
+                }
+            }
+            sample.bytes = Bytes::from(bytes);
+        }
+    } else if let Some(ref mut hev1) = stsd.hev1 {
+        let hvcc = &mut hev1.hvcc;
+        let length_size = hvcc.length_size_minus_one + 1;
+        for sample in samples.iter_mut() {
+            let mut bytes = Vec::with_capacity(sample.bytes.len());
+            for nal in HevcSampleSplitter::new(&sample.bytes, length_size)? {
+                let nal = nal?;
+                if nal.is_parameter_set() {
+                    hvcc.add_nal_unit(nal.nal_unit_type, nal.data);
+                } else {
+                    write_nal(&mut bytes, nal.data, length_size);
+                }
+            }
+            sample.bytes = Bytes::from(bytes);
+        }
+        for array in hvcc.arrays.iter_mut() {
+            array.completeness = true;
+        }
+        hev1.hvc1 = true;
+    }
+    Ok(())
+}
+
+fn write_nal(sample: &mut Vec<u8>, nal: &[u8], length_size: u8) {
+    let length = (nal.len() as u32).to_be_bytes();
+    sample.extend_from_slice(&length[4 - length_size as usize..]);
+    sample.extend_from_slice(nal);
+}
+
+// Relocate the chunk offsets of `moov` for a file where it is written at
+// `moov_start`, directly followed by the boxes at the given (offset, size)
+// in the original file. Since upgrading an stco to co64 grows moov, which in
+// turn shifts every offset again, this repeats until the size of moov is
+// stable. Tracks only ever move from stco to co64, so it terminates.
+fn faststart_moov(moov: &MoovBox, moov_start: u64, data: &[(u64, u64)]) -> Result<MoovBox> {
+    let mut relocated = moov.clone();
+    loop {
+        let moov_size = relocated.box_size();
+        let mut layout = Vec::with_capacity(data.len());
+        let mut new_offset = moov_start + moov_size;
+        for (offset, size) in data.iter() {
+            layout.push((*offset, *offset + *size, new_offset));
+            new_offset += size;
+        }
+
+        for (dst, src) in relocated.traks.iter_mut().zip(moov.traks.iter()) {
+            let stbl = &src.mdia.minf.stbl;
+            let offsets: Vec<u64> = match (&stbl.stco, &stbl.co64) {
+                (Some(stco), _) => stco.entries.iter().map(|o| *o as u64).collect(),
+                (None, Some(co64)) => co64.entries.clone(),
+                (None, None) => continue,
+            };
+            let offsets = offsets.iter()
+                .map(|offset| {
+                    layout.iter()
+                        .find(|(start, end, _)| offset >= start && offset < end)
+                        .map(|(start, _, new_start)| new_start + offset - start)
+                        .ok_or(Error::InvalidData("chunk offset outside of media data"))
+                })
+                .collect::<Result<Vec<u64>>>()?;
+
+            set_chunk_offsets(&mut dst.mdia.minf.stbl, offsets);
+        }
+
+        if relocated.box_size() == moov_size {
+            return Ok(relocated);
+        }
+    }
+}
+
+// Store chunk offsets in the stco box, or in co64 if the table already was
+// one or an offset needs more than 32 bits.
+fn set_chunk_offsets(stbl: &mut StblBox, offsets: Vec<u64>) {
+    if stbl.co64.is_some() || offsets.iter().any(|o| *o > u32::MAX as u64) {
+        stbl.stco = None;
+        stbl.co64 = Some(Co64Box {
+            entries: offsets,
+            ..Co64Box::default()
+        });
+    } else {
+        stbl.stco = Some(StcoBox {
+            entries: offsets.iter().map(|o| *o as u32).collect(),
+            ..StcoBox::default()
+        });
+    }
+}
+
+// The children of a moov that its containers keep unparsed.
+fn unknown_children(moov: &MoovBox) -> Vec<&RawBox> {
+    let mut children: Vec<&RawBox> = moov.unknown_boxes.iter().collect();
+    if let Some(ref udta) = moov.udta {
+        children.extend(udta.unknown_boxes.iter());
+    }
+    if let Some(ref mvex) = moov.mvex {
+        for trep in mvex.treps.iter() {
+            children.extend(trep.children.iter());
+        }
+    }
+    for trak in moov.traks.iter() {
+        children.extend(trak.unknown_boxes.iter());
+        children.extend(trak.mdia.unknown_boxes.iter());
+        children.extend(trak.mdia.minf.unknown_boxes.iter());
+        children.extend(trak.mdia.minf.stbl.unknown_boxes.iter());
+        let stsd = &trak.mdia.minf.stbl.stsd;
+        if let Some(ref avc1) = stsd.avc1 {
+            children.extend(avc1.unknown_boxes.iter());
+        }
+        if let Some(ref hev1) = stsd.hev1 {
+            children.extend(hev1.unknown_boxes.iter());
+        }
+        if let Some(ref av01) = stsd.av01 {
+            children.extend(av01.unknown_boxes.iter());
+        }
+        if let Some(ref mp4a) = stsd.mp4a {
+            children.extend(mp4a.unknown_boxes.iter());
+        }
+        if let Some(ref unknown) = stsd.unknown {
+            children.push(unknown);
+        }
+    }
+    children
+}
+
+// List the type, offset and size of the children of the box of the given
+// type at `offset`.
+fn child_boxes<R: Read + Seek>(
+    reader: &mut R,
+    box_type: BoxType,
+    offset: u64,
+    size: u64,
+) -> Result<Vec<(BoxType, u64, u64)>> {
+    let end = offset + size;
+    reader.seek(SeekFrom::Start(offset))?;
+    let header_size = if reader.read_u32::<BigEndian>()? == 1 {
+        HEADER_SIZE + 8
+    } else {
+        HEADER_SIZE
+    };
+
+    let mut children = Vec::new();
+    let mut current = offset + header_size + children_offset(box_type);
+    while current + HEADER_SIZE <= end {
+        reader.seek(SeekFrom::Start(current))?;
+        let header = BoxHeader::read(reader)?;
+        if header.size < HEADER_SIZE || current + header.size > end {
+            break;
+        }
+        children.push((header.name, current, header.size));
+        current += header.size;
+    }
+    Ok(children)
+}
+
+// Split a path segment such as "trak[2]" into the box type and the 1-based
+// index among its siblings.
+fn path_segment(segment: &str) -> Option<(&str, usize)> {
+    match segment.find('[') {
+        Some(i) => {
+            let index = segment[i + 1..].strip_suffix(']')?.parse::<usize>().ok()?;
+            Some((&segment[..i], index))
+        }
+        None => Some((segment, 1)),
+    }
+}
+
+// Bytes between the header of a box and its first child box.
+fn children_offset(box_type: BoxType) -> u64 {
+    match box_type {
+        BoxType::MetaBox => HEADER_EXT_SIZE,
+        BoxType::StsdBox | BoxType::DrefBox => HEADER_EXT_SIZE + 4,
+        BoxType::Avc1Box | BoxType::Hev1Box | BoxType::Hvc1Box | BoxType::Vp09Box | BoxType::EncvBox => 78,
+        BoxType::Mp4aBox | BoxType::EncaBox | BoxType::SamrBox | BoxType::SawbBox => 28,
+        _ => 0,
+    }
+}
+
+fn json_value<T: serde::Serialize>(value: &T) -> Result<serde_json::Value> {
+    serde_json::to_value(value).map_err(|_| Error::InvalidData("failed to serialize box tree"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::ByteOrder;
+    use crate::mp4box::hdlr::HdlrBox;
+    use crate::mp4box::iinf::IinfBox;
+    use crate::mp4box::ilst::{IlstBox, IlstItem};
+    use crate::mp4box::iloc::{IlocBox, IlocExtent};
+    use crate::mp4box::ipro::IproBox;
+    use crate::mp4box::iref::IrefBox;
+    use crate::mp4box::schm::SchmBox;
+    use crate::mp4box::sinf::FrmaBox;
+    use crate::mp4box::tref::{TrefBox, TrefEntry};
+    use crate::mp4box::mp4a::Mp4aBox;
+    use crate::mp4box::udta::UdtaBox;
+    use crate::mp4box::xml::XmlBox;
+    use crate::mp4box::yrrc::YrrcBox;
+    use crate::mp4box::mvex::MvexBox;
+    use crate::mp4box::mehd::MehdBox;
+    use crate::mp4box::trex::TrexBox;
+    use crate::mp4box::stco::StcoBox;
+    use crate::mp4box::stsc::StscEntry;
+    use crate::mp4box::stts::SttsEntry;
+    use crate::mp4box::stss::StssBox;
+    use crate::mp4box::tfdt::TfdtBox;
+    use crate::mp4box::traf::{TrafBox, SAMPLE_FLAGS_NON_SYNC};
+    use crate::mp4box::trun::TrunBox;
+    use std::io::Cursor;
+
+    fn trak(track_id: u32) -> TrakBox {
+        let mut trak = TrakBox::default();
+        trak.tkhd.track_id = track_id;
+        trak.mdia.minf.stbl.stco = Some(StcoBox::default());
+        trak
+    }
+
+    fn read_moov(moov: &MoovBox) -> Mp4Reader<Cursor<Vec<u8>>> {
+        read_ftyp_moov(&FtypBox::default(), moov)
+    }
+
+    fn read_ftyp_moov(ftyp: &FtypBox, moov: &MoovBox) -> Mp4Reader<Cursor<Vec<u8>>> {
+        let buf = ftyp_moov(ftyp, moov);
+        let size = buf.len() as u64;
+        Mp4Reader::read_header(Cursor::new(buf), size).unwrap()
+    }
+
+    fn ftyp_moov(ftyp: &FtypBox, moov: &MoovBox) -> Vec<u8> {
+        let mut buf = Vec::new();
+        ftyp.write_box(&mut buf).unwrap();
+        moov.write_box(&mut buf).unwrap();
+        buf
+    }
+
+    // A moov with track 1, whose samples are in fragments.
+    fn fragmented_moov() -> MoovBox {
+        let mut moov = MoovBox::default();
+        moov.traks.push(trak(1));
+        moov.mvex = Some(MvexBox {
+            trexs: vec![TrexBox {
+                track_id: 1,
+                ..TrexBox::default()
+            }],
+            ..MvexBox::default()
+        });
+        moov
+    }
+
+    #[test]
+    fn test_faststart_moov_co64_upgrade() {
+        let mut moov = MoovBox::default();
+        let mut near = trak(1);
+        near.mdia.minf.stbl.stco = Some(StcoBox {
+            entries: vec![32, u32::MAX - 16],
+            ..StcoBox::default()
+        });
+        moov.traks.push(near);
+        let mut small = trak(2);
+        small.mdia.minf.stbl.stco = Some(StcoBox {
+            entries: vec![64],
+            ..StcoBox::default()
+        });
+        moov.traks.push(small);
+
+        // A single mdat of just under 4 GiB after a 24 byte ftyp.
+        let data = [(24, u32::MAX as u64 - 24)];
+        let relocated = faststart_moov(&moov, 24, &data).unwrap();
+        let moov_size = relocated.box_size();
+        assert!(moov_size > moov.box_size());
+
+        let stbl = &relocated.traks[0].mdia.minf.stbl;
+        assert!(stbl.stco.is_none());
+        let co64 = stbl.co64.as_ref().unwrap();
+        assert_eq!(co64.entries, vec![32 + moov_size, u32::MAX as u64 - 16 + moov_size]);
+        assert!(co64.entries[1] > u32::MAX as u64);
+
+        let stbl = &relocated.traks[1].mdia.minf.stbl;
+        assert_eq!(stbl.stco.as_ref().unwrap().entries, vec![64 + moov_size as u32]);
+        assert!(stbl.co64.is_none());
+
+        assert!(faststart_moov(&moov, 24, &[(24, 16)]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_duration() {
+        let mut moov = MoovBox::default();
+        moov.mvhd.duration = u32::MAX as u64;
+        let mut track = trak(1);
+        track.tkhd.duration = u32::MAX as u64;
+        track.mdia.mdhd.duration = u32::MAX as u64;
+        moov.traks.push(track);
+
+        let mp4 = read_moov(&moov);
+        assert!(mp4.moov.mvhd.is_duration_unknown());
+        assert_eq!(mp4.known_duration(), None);
+        assert_eq!(mp4.duration(), Duration::ZERO);
+        assert_eq!(mp4.tracks()[&1].duration(), Duration::ZERO);
+        assert!(mp4.validate_track_durations().is_empty());
+
+        // Fall back to the mehd, here with 64 bit durations.
+        moov.mvhd.version = 1;
+        moov.mvhd.duration = u64::MAX;
+        moov.mvex = Some(MvexBox {
+            mehd: Some(MehdBox {
+                fragment_duration: 5000,
+                ..MehdBox::default()
+            }),
+            trexs: vec![TrexBox {
+                track_id: 1,
+                ..TrexBox::default()
+            }],
+            ..MvexBox::default()
+        });
+        let mp4 = read_moov(&moov);
+        assert!(mp4.moov.mvhd.is_duration_unknown());
+        assert_eq!(mp4.known_duration(), Some(Duration::from_secs(5)));
+        assert_eq!(mp4.duration(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_fix_track_durations() {
+        let mut moov = MoovBox::default();
+        moov.mvhd.timescale = 1000;
+        moov.mvhd.duration = 2000;
+        let mut track = trak(1);
+        track.mdia.mdhd.timescale = 48000;
+        track.mdia.mdhd.duration = 96000;
+        track.tkhd.duration = 2000;
+        moov.traks.push(track);
+        let mut track = trak(2);
+        track.mdia.mdhd.timescale = 90000;
+        track.mdia.mdhd.duration = 450000;
+        // Written as if in the media timescale.
+        track.tkhd.duration = 450000;
+        moov.traks.push(track);
+
+        let mut mp4 = read_moov(&moov);
+        let expected = vec![DurationMismatch {
+            track_id: 2,
+            tkhd_duration: 450000,
+            expected: 5000,
+        }];
+        assert_eq!(mp4.validate_track_durations(), expected);
+        assert_eq!(mp4.fix_track_durations(), expected);
+        assert!(mp4.validate_track_durations().is_empty());
+        assert_eq!(mp4.moov.traks[1].tkhd.duration, 5000);
+        assert_eq!(mp4.tracks()[&2].trak.tkhd.duration, 5000);
+        assert_eq!(mp4.moov.mvhd.duration, 5000);
+    }
+
+    #[test]
+    fn test_sample_description_index() {
+        let mut moov = MoovBox::default();
+        let mut track = trak(1);
+        let stbl = &mut track.mdia.minf.stbl;
+        stbl.stts.entries = vec![SttsEntry {
+            sample_count: 8,
+            sample_delta: 1,
+        }];
+        stbl.stsz.sample_size = 10;
+        stbl.stsz.sample_count = 8;
+        // The third chunk uses the second sample entry.
+        let entry = |first_chunk, sample_description_index, first_sample| StscEntry {
+            first_chunk,
+            samples_per_chunk: 2,
+            sample_description_index,
+            first_sample,
+        };
+        stbl.stsc.entries = vec![entry(1, 1, 1), entry(3, 2, 5), entry(4, 1, 7)];
+        stbl.stsd.mp4a = Some(Mp4aBox {
+            data_reference_index: 1,
+            ..Mp4aBox::default()
+        });
+
+        // The eight samples follow ftyp and the mdat header, each filled
+        // with its own number.
+        let mut buf = Vec::new();
+        FtypBox::default().write_box(&mut buf).unwrap();
+        let data_start = buf.len() as u32 + HEADER_SIZE as u32;
+        stbl.stco = Some(StcoBox {
+            entries: (0..4).map(|chunk| data_start + chunk * 20).collect(),
+            ..StcoBox::default()
+        });
+        let data: Vec<u8> = (1..=8).flat_map(|i| [i; 10]).collect();
+        BoxHeader::new(BoxType::MdatBox, HEADER_SIZE + data.len() as u64).write(&mut buf).unwrap();
+        buf.extend_from_slice(&data);
+        moov.traks.push(track);
+
+        // StsdBox only writes one sample entry, so append a copy of it as
+        // the second and grow the boxes around it.
+        let mut moov_buf = Vec::new();
+        moov.write_box(&mut moov_buf).unwrap();
+        let find = |buf: &[u8], name: &[u8]| buf.windows(4).position(|w| w == name).unwrap() - 4;
+        let stsd = find(&moov_buf, b"stsd");
+        let entry_start = stsd + 16;
+        let entry_size = BigEndian::read_u32(&moov_buf[entry_start..]) as usize;
+        let entry_bytes = moov_buf[entry_start..entry_start + entry_size].to_vec();
+        moov_buf.splice(entry_start + entry_size..entry_start + entry_size, entry_bytes);
+        BigEndian::write_u32(&mut moov_buf[stsd + 12..], 2);
+        for name in [b"moov", b"trak", b"mdia", b"minf", b"stbl", b"stsd"] {
+            let pos = find(&moov_buf, name);
+            let size = BigEndian::read_u32(&moov_buf[pos..]);
+            BigEndian::write_u32(&mut moov_buf[pos..], size + entry_size as u32);
+        }
+        buf.extend_from_slice(&moov_buf);
+
+        let size = buf.len() as u64;
+        let mut mp4 = Mp4Reader::read_header(Cursor::new(buf), size).unwrap();
+        for sample_id in 1..=8 {
+            let sample = mp4.read_sample(1, sample_id).unwrap().unwrap();
+            assert_eq!(sample.bytes.as_ref(), &[sample_id as u8; 10]);
+            let expected = if sample_id == 5 || sample_id == 6 { 2 } else { 1 };
+            assert_eq!(sample.sample_description_index, expected);
+        }
+
+        let track = mp4.tracks().get(&1).unwrap();
+        let indexes: Vec<u32> = track.chunks().unwrap().iter()
+            .map(|chunk| chunk.sample_description_index)
+            .collect();
+        assert_eq!(indexes, vec![1, 1, 2, 1]);
+        let indexes: Vec<u32> = (1..=8)
+            .map(|sample_id| track.sample_description_index(sample_id).unwrap())
+            .collect();
+        assert_eq!(indexes, vec![1, 1, 1, 1, 2, 2, 1, 1]);
+        assert!(track.sample_description_index(0).is_err());
+        assert!(track.sample_description_index(9).is_err());
+
+        // Rewritten tables keep the index of each sample.
+        let mut track = Mp4Track::from(&track.trak);
+        track.retain_samples(4..8, 1000).unwrap();
+        let indexes: Vec<u32> = (1..=4)
+            .map(|sample_id| track.sample_description_index(sample_id).unwrap())
+            .collect();
+        assert_eq!(indexes, vec![1, 2, 2, 1]);
+    }
+
+    #[test]
+    fn test_custom_boxes_in_trak() {
+        let cust = RawBox {
+            box_type: str::parse("cust").unwrap(),
+            data: vec![7, 8],
+        };
+        let tcus = RawBox {
+            box_type: str::parse("tcus").unwrap(),
+            data: vec![9],
+        };
+        let mut moov = MoovBox::default();
+        let mut track = trak(1);
+        track.unknown_boxes.push(tcus.clone());
+        track.mdia.minf.stbl.unknown_boxes.push(cust.clone());
+        moov.traks.push(track);
+
+        // Without a parser the boxes are kept as is.
+        let mp4 = read_moov(&moov);
+        assert_eq!(mp4.moov.traks[0].unknown_boxes, vec![tcus.clone()]);
+        assert_eq!(mp4.moov.traks[0].mdia.minf.stbl.unknown_boxes, vec![cust.clone()]);
+        assert_eq!(mp4.moov, moov);
+        assert!(mp4.custom_boxes().is_empty());
+
+        let mut registry = BoxParserRegistry::new();
+        for raw in [&cust, &tcus] {
+            registry.register(raw.box_type, |payload| Ok(Box::new(payload.to_vec())));
+        }
+        let buf = ftyp_moov(&FtypBox::default(), &moov);
+        let size = buf.len() as u64;
+        let mp4 = Mp4Reader::read_header_with_registry(Cursor::new(buf), size, &registry).unwrap();
+        let values: Vec<_> = mp4.custom_boxes().iter()
+            .map(|custom| (custom.box_type, custom.downcast_ref::<Vec<u8>>().cloned()))
+            .collect();
+        assert_eq!(values, vec![(tcus.box_type, Some(vec![9])), (cust.box_type, Some(vec![7, 8]))]);
+    }
+
+    #[test]
+    fn test_chunk_offset_overflow() {
+        let mut moov = MoovBox::default();
+        let mut track = trak(1);
+        let stbl = &mut track.mdia.minf.stbl;
+        stbl.stsz.sample_size = 10;
+        stbl.stsz.sample_count = 1;
+        stbl.stsc.entries = vec![StscEntry {
+            first_chunk: 1,
+            samples_per_chunk: 1,
+            sample_description_index: 1,
+            first_sample: 1,
+        }];
+        stbl.stco = None;
+        stbl.co64 = Some(Co64Box {
+            entries: vec![u64::MAX - 4],
+            ..Co64Box::default()
+        });
+        moov.traks.push(track);
+
+        let chunk = read_moov(&moov).validate_chunk_offsets().unwrap().unwrap();
+        assert_eq!((chunk.chunk_id, chunk.offset, chunk.size), (1, u64::MAX - 4, 10));
+    }
+
+    #[test]
+    fn test_empty_stsd() {
+        let mut moov = MoovBox::default();
+        let mut video = trak(1);
+        video.mdia.hdlr.handler_type = str::parse("vide").unwrap();
+        moov.traks.push(video);
+        let buf = ftyp_moov(&FtypBox::default(), &moov);
+        let size = buf.len() as u64;
+        let result = Mp4Reader::read_header(Cursor::new(buf), size);
+        assert!(matches!(result, Err(Error::InvalidData("media track has no sample entry"))));
+
+        // Metadata tracks may have no sample entry.
+        moov.traks[0].mdia.hdlr.handler_type = str::parse("meta").unwrap();
+        let mp4 = read_moov(&moov);
+        assert!(mp4.tracks().get(&1).unwrap().trak.mdia.minf.stbl.stsd.is_empty());
+    }
+
+    #[test]
+    fn test_renumber_tracks() {
+        let mut moov = MoovBox::default();
+        moov.mvhd.next_track_id = 8;
+        moov.traks.push(trak(3));
+        let mut hint = trak(7);
+        hint.tref = Some(TrefBox {
+            references: vec![TrefEntry {
+                reference_type: str::parse("hint").unwrap(),
+                track_ids: vec![3],
+            }],
+        });
+        moov.traks.push(hint);
+
+        let mut mp4 = read_moov(&moov);
+        mp4.renumber_tracks().unwrap();
+
+        let mut track_ids: Vec<u32> = mp4.tracks().keys().copied().collect();
+        track_ids.sort();
+        assert_eq!(track_ids, vec![1, 2]);
+        assert_eq!(mp4.moov.mvhd.next_track_id, 3);
+
+        let tref = mp4.moov.traks[1].tref.as_ref().unwrap();
+        assert_eq!(tref.references[0].track_ids, vec![1]);
+        let track = mp4.tracks().get(&2).unwrap();
+        assert_eq!(track.trak.tref, mp4.moov.traks[1].tref);
+    }
+
+    #[test]
+    fn test_renumber_duplicate_tracks() {
+        let mut moov = MoovBox::default();
+        moov.traks.push(trak(1));
+        moov.traks.push(trak(2));
+        moov.traks.push(trak(2));
+        let mut hint = trak(4);
+        hint.tref = Some(TrefBox {
+            references: vec![TrefEntry {
+                reference_type: str::parse("hint").unwrap(),
+                track_ids: vec![1, 2],
+            }],
+        });
+        moov.traks.push(hint);
+
+        let mut mp4 = read_moov(&moov);
+        assert_eq!(mp4.tracks().len(), 3);
+        mp4.renumber_tracks().unwrap();
+
+        let track_ids: Vec<u32> = mp4.moov.traks.iter().map(|trak| trak.tkhd.track_id).collect();
+        assert_eq!(track_ids, vec![1, 2, 3, 4]);
+        let mut track_ids: Vec<u32> = mp4.tracks().keys().copied().collect();
+        track_ids.sort();
+        assert_eq!(track_ids, vec![1, 2, 3, 4]);
+        assert_eq!(mp4.moov.mvhd.next_track_id, 5);
+
+        // The reference to the shared ID 2 is ambiguous and dropped, as 2
+        // now names the second of the traks that shared it.
+        let tref = mp4.moov.traks[3].tref.as_ref().unwrap();
+        assert_eq!(tref.references[0].track_ids, vec![1]);
+        assert_eq!(mp4.tracks().get(&4).unwrap().trak.tref, mp4.moov.traks[3].tref);
+
+        // A tref left with no known ID goes away, and so does the trex of
+        // the shared ID.
+        moov.traks[3].tref.as_mut().unwrap().references[0].track_ids = vec![2, 9];
+        moov.mvex = Some(MvexBox {
+            trexs: vec![1, 2, 4].into_iter()
+                .map(|track_id| TrexBox { track_id, ..TrexBox::default() })
+                .collect(),
+            ..MvexBox::default()
+        });
+        let mut mp4 = read_moov(&moov);
+        mp4.renumber_tracks().unwrap();
+        assert_eq!(mp4.moov.traks[3].tref, None);
+        let trex_ids: Vec<u32> = mp4.moov.mvex.as_ref().unwrap().trexs.iter()
+            .map(|trex| trex.track_id)
+            .collect();
+        assert_eq!(trex_ids, vec![1, 4]);
+    }
+
+    #[test]
+    fn test_remove_track_tref() {
+        let mut moov = MoovBox::default();
+        moov.traks.push(trak(1));
+        moov.traks.push(trak(2));
+        let mut hint = trak(3);
+        hint.tref = Some(TrefBox {
+            references: vec![
+                TrefEntry {
+                    reference_type: str::parse("hint").unwrap(),
+                    track_ids: vec![2],
+                },
+                TrefEntry {
+                    reference_type: str::parse("cdsc").unwrap(),
+                    track_ids: vec![1, 2],
+                },
+            ],
+        });
+        moov.traks.push(hint);
+
+        let mut buf = Vec::new();
+        read_moov(&moov).remove_track(2, &mut buf).unwrap();
+        let size = buf.len() as u64;
+        let mp4 = Mp4Reader::read_header(Cursor::new(buf), size).unwrap();
+        let track_ids: Vec<u32> = mp4.moov.traks.iter().map(|trak| trak.tkhd.track_id).collect();
+        assert_eq!(track_ids, vec![1, 3]);
+        assert_eq!(mp4.moov.mvhd.next_track_id, 4);
+        let tref = mp4.moov.traks[1].tref.as_ref().unwrap();
+        assert_eq!(tref.references.len(), 1);
+        assert_eq!(tref.references[0].track_ids, vec![1]);
+    }
+
+    #[test]
+    fn test_is_fragmented_init_segment() {
+        let ftyp = FtypBox {
+            major_brand: str::parse("iso6").unwrap(),
+            minor_version: 0,
+            compatible_brands: vec![
+                str::parse("iso6").unwrap(),
+                str::parse("cmfc").unwrap(),
+                str::parse("dash").unwrap(),
+            ],
+        };
+        let moov = fragmented_moov();
+
+        let mp4 = read_ftyp_moov(&ftyp, &moov);
+        assert!(mp4.is_fragmented());
+        assert!(mp4.is_dash());
+
+        let (major, minor, compatible) = mp4.brands();
+        assert_eq!(major, ftyp.major_brand);
+        assert_eq!(minor, 0);
+        assert_eq!(compatible, ftyp.compatible_brands);
+    }
+
+    #[test]
+    fn test_data_reference_index_out_of_range() {
+        let mut moov = MoovBox::default();
+        let mut trak = trak(1);
+        trak.mdia.minf.stbl.stsd.mp4a = Some(Mp4aBox::new(&AacConfig::default(), 2));
+        moov.traks.push(trak);
+
+        let buf = ftyp_moov(&FtypBox::default(), &moov);
+        let size = buf.len() as u64;
+        assert!(Mp4Reader::read_header(Cursor::new(buf), size).is_err());
+    }
+
+    fn write_fragment(buf: &mut Vec<u8>, sequence_number: u32, track_id: u32, samples: &[&[u8]]) {
+        let mut moof = MoofBox::default();
+        moof.mfhd.sequence_number = sequence_number;
+        moof.trafs.push(TrafBox {
+            tfhd: TfhdBox {
+                flags: TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF,
+                track_id,
+                ..TfhdBox::default()
+            },
+            tfdt: None,
+            trun: Some(TrunBox {
+                flags: TrunBox::FLAG_DATA_OFFSET | TrunBox::FLAG_SAMPLE_SIZE,
+                sample_count: samples.len() as u32,
+                data_offset: Some(0),
+                sample_sizes: samples.iter().map(|s| s.len() as u32).collect(),
+                ..TrunBox::default()
+            }),
+            ..TrafBox::default()
+        });
+        // Sample data starts right after the moof and the mdat header.
+        let data_offset = moof.box_size() + HEADER_SIZE;
+        moof.trafs[0].trun.as_mut().unwrap().data_offset = Some(data_offset as i32);
+        moof.write_box(buf).unwrap();
+
+        let data = samples.concat();
+        BoxHeader::new(BoxType::MdatBox, HEADER_SIZE + data.len() as u64)
+            .write(buf)
+            .unwrap();
+        buf.extend_from_slice(&data);
+    }
+
+    #[test]
+    fn test_sample_offset_fragmented() {
+        let moov = fragmented_moov();
+
+        let mut buf = ftyp_moov(&FtypBox::default(), &moov);
+        write_fragment(&mut buf, 1, 1, &[b"abc", b"de"]);
+        write_fragment(&mut buf, 2, 1, &[b"fghi", b"j"]);
+        let size = buf.len() as u64;
+        let mut mp4 = Mp4Reader::read_header(Cursor::new(buf.clone()), size).unwrap();
+
+        let track = mp4.tracks().get(&1).unwrap();
+        for (sample_id, expected) in [b"abc" as &[u8], b"de", b"fghi", b"j"].iter().enumerate() {
+            let offset = track.sample_offset(sample_id as u32 + 1).unwrap() as usize;
+            assert_eq!(&buf[offset..offset + expected.len()], *expected);
+        }
+
+        let sample = mp4.read_sample(1, 3).unwrap().unwrap();
+        assert_eq!(sample.bytes.as_ref(), b"fghi");
+    }
+
+    #[test]
+    fn test_write_fixed_fragments_64_bit_tfdt() {
+        let mut moov = fragmented_moov();
+        moov.mvex.as_mut().unwrap().trexs[0].default_sample_duration = 1;
+
+        let mut buf = ftyp_moov(&FtypBox::default(), &moov);
+        write_fragment(&mut buf, 1, 1, &[b"abc", b"de"]);
+        write_fragment(&mut buf, 2, 1, &[b"fghi", b"j"]);
+        let size = buf.len() as u64;
+        let mut mp4 = Mp4Reader::read_header(Cursor::new(buf), size).unwrap();
+
+        // The second fragment must follow on at 2^32 + 1, which only a
+        // version 1 tfdt holds, so both moofs grow.
+        mp4.moofs[0].trafs[0].tfdt = Some(TfdtBox::new(u32::MAX as u64 - 1));
+        mp4.moofs[1].trafs[0].tfdt = Some(TfdtBox::new(0));
+        assert_eq!(mp4.fix_decode_times().len(), 1);
+        let mut fixed = Vec::new();
+        let written = mp4.write_fixed_fragments(&mut fixed).unwrap();
+        assert_eq!(written, fixed.len() as u64);
+
+        let size = fixed.len() as u64;
+        let mut mp4 = Mp4Reader::read_header(Cursor::new(fixed), size).unwrap();
+        let tfdt = mp4.moofs[1].trafs[0].tfdt.as_ref().unwrap();
+        assert_eq!((tfdt.version, tfdt.base_media_decode_time), (1, u32::MAX as u64 + 1));
+        assert_eq!(mp4.validate_decode_times(), None);
+        for (sample_id, expected) in [b"abc" as &[u8], b"de", b"fghi", b"j"].iter().enumerate() {
+            let sample = mp4.read_sample(1, sample_id as u32 + 1).unwrap().unwrap();
+            assert_eq!(sample.bytes.as_ref(), *expected);
+        }
+    }
+
+    #[test]
+    fn test_trun_data_offset_overflow() {
+        let moov = fragmented_moov();
+        for (base_data_offset, data_offset) in [(u64::MAX - 1, 2), (u64::MAX - 8, 0), (0, -1)] {
+            let mut buf = ftyp_moov(&FtypBox::default(), &moov);
+            let mut moof = MoofBox::default();
+            moof.trafs.push(TrafBox {
+                tfhd: TfhdBox {
+                    flags: TfhdBox::FLAG_BASE_DATA_OFFSET,
+                    track_id: 1,
+                    base_data_offset: Some(base_data_offset),
+                    ..TfhdBox::default()
+                },
+                trun: Some(TrunBox {
+                    flags: TrunBox::FLAG_DATA_OFFSET | TrunBox::FLAG_SAMPLE_SIZE,
+                    sample_count: 1,
+                    data_offset: Some(data_offset),
+                    sample_sizes: vec![16],
+                    ..TrunBox::default()
+                }),
+                ..TrafBox::default()
+            });
+            moof.write_box(&mut buf).unwrap();
+            let size = buf.len() as u64;
+            assert!(Mp4Reader::read_header(Cursor::new(buf), size).is_err());
+        }
+    }
+
+    #[test]
+    fn test_negative_trun_composition_offset() {
+        let moov = fragmented_moov();
+
+        let mut buf = ftyp_moov(&FtypBox::default(), &moov);
+        let mut moof = MoofBox::default();
+        moof.mfhd.sequence_number = 1;
+        moof.trafs.push(TrafBox {
+            tfhd: TfhdBox {
+                flags: TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF,
+                track_id: 1,
+                ..TfhdBox::default()
+            },
+            tfdt: None,
+            trun: Some(TrunBox {
+                flags: TrunBox::FLAG_DATA_OFFSET | TrunBox::FLAG_SAMPLE_SIZE
+                    | TrunBox::FLAG_SAMPLE_CTS,
+                sample_count: 2,
+                data_offset: Some(0),
+                sample_sizes: vec![1, 1],
+                sample_cts: vec![512, -512],
+                ..TrunBox::default()
+            }),
+            ..TrafBox::default()
+        });
+        let data_offset = moof.box_size() + HEADER_SIZE;
+        moof.trafs[0].trun.as_mut().unwrap().data_offset = Some(data_offset as i32);
+        moof.write_box(&mut buf).unwrap();
+        BoxHeader::new(BoxType::MdatBox, HEADER_SIZE + 2).write(&mut buf).unwrap();
+        buf.extend_from_slice(b"ab");
+        let size = buf.len() as u64;
+        let mut mp4 = Mp4Reader::read_header(Cursor::new(buf), size).unwrap();
+
+        assert_eq!(mp4.moofs[0].trafs[0].trun.as_ref().unwrap().version, 1);
+        let sample = mp4.read_sample(1, 1).unwrap().unwrap();
+        assert_eq!(sample.rendering_offset, 512);
+        let sample = mp4.read_sample(1, 2).unwrap().unwrap();
+        assert_eq!(sample.rendering_offset, -512);
+    }
+
+    #[test]
+    fn test_ensure_mvex() {
+        let mut moov = MoovBox::default();
+        let mut track = trak(1);
+        let stbl = &mut track.mdia.minf.stbl;
+        stbl.stts.entries = vec![SttsEntry { sample_count: 60, sample_delta: 40 }];
+        stbl.stsz.sample_size = 10;
+        stbl.stsz.sample_count = 60;
+        stbl.stss = Some(StssBox {
+            entries: vec![1, 31],
+            ..StssBox::default()
+        });
+        stbl.stsc.entries = vec![StscEntry {
+            first_chunk: 1,
+            samples_per_chunk: 60,
+            sample_description_index: 1,
+            first_sample: 1,
+        }];
+        stbl.stco = Some(StcoBox {
+            entries: vec![100],
+            ..StcoBox::default()
+        });
+        moov.traks.push(track);
+
+        let mut mp4 = read_moov(&moov);
+        assert!(mp4.moov.mvex.is_none());
+        assert!(!mp4.tracks().get(&1).unwrap().is_fragmented_compatible());
+
+        assert_eq!(mp4.ensure_mvex().unwrap(), vec![1]);
+        assert!(mp4.tracks().get(&1).unwrap().is_fragmented_compatible());
+        assert!(mp4.ensure_mvex().unwrap().is_empty());
+
+        let mp4 = read_moov(&mp4.moov);
+        let trex = mp4.moov.mvex.as_ref().unwrap().trex(1).unwrap();
+        assert_eq!(trex.default_sample_description_index, 1);
+        assert_eq!(trex.default_sample_duration, 40);
+        assert_eq!(trex.default_sample_size, 10);
+        // The second sample is not a sync sample.
+        assert_eq!(trex.default_sample_flags, SAMPLE_FLAGS_NON_SYNC);
+        assert!(mp4.tracks().get(&1).unwrap().is_fragmented_compatible());
+    }
+
+    #[test]
+    fn test_fragment_sequence_numbers() {
+        let moov = fragmented_moov();
+
+        let mut buf = ftyp_moov(&FtypBox::default(), &moov);
+        write_fragment(&mut buf, 1, 1, &[b"abc"]);
+        write_fragment(&mut buf, 2, 1, &[b"def"]);
+        let size = buf.len() as u64;
+        let mp4 = Mp4Reader::read_header(Cursor::new(buf), size).unwrap();
+
+        assert_eq!(mp4.fragment_sequence_numbers(), vec![1, 2]);
+        assert_eq!(mp4.moofs[1].sequence_number(), 2);
+    }
+
+    #[test]
+    fn test_copyright() {
+        let mut moov = MoovBox::default();
+        moov.traks.push(trak(1));
+        moov.udta = Some(UdtaBox {
+            cprt: Some(CprtBox {
+                language: String::from("eng"),
+                notice: String::from("Copyright 2023 Example Corp."),
+                ..CprtBox::default()
+            }),
+            yrrc: None,
+            meta: None,
+            assets: Vec::new(),
+            unknown_boxes: Vec::new(),
+        });
+
+        let mp4 = read_moov(&moov);
+        let cprt = mp4.copyright().unwrap();
+        assert_eq!(cprt.language, "eng");
+        assert_eq!(cprt.notice, "Copyright 2023 Example Corp.");
+    }
+
+    #[test]
+    fn test_recording_year() {
+        let mut moov = MoovBox::default();
+        moov.traks.push(trak(1));
+        assert_eq!(read_moov(&moov).recording_year(), None);
+
+        moov.udta = Some(UdtaBox {
+            yrrc: Some(YrrcBox {
+                year: 2021,
+                ..YrrcBox::default()
+            }),
+            ..UdtaBox::default()
+        });
+        assert_eq!(read_moov(&moov).recording_year(), Some(2021));
+    }
+
+    #[test]
+    fn test_primary_item() {
+        let image = b"\xFF\xD8cover\xFF\xD9";
+        let ftyp = FtypBox::default();
+
+        let mut meta = MetaBox {
+            hdlr: Some(HdlrBox {
+                handler_type: str::parse("pict").unwrap(),
+                ..HdlrBox::default()
+            }),
+            iloc: Some(IlocBox {
+                offset_size: 4,
+                length_size: 4,
+                items: vec![IlocItem {
+                    item_id: 1,
+                    extents: vec![IlocExtent {
+                        index: 0,
+                        offset: 0,
+                        length: image.len() as u64,
+                    }],
+                    ..IlocItem::default()
+                }],
+                ..IlocBox::default()
+            }),
+            ..MetaBox::default()
+        };
+        meta.set_primary_item(1).unwrap();
+        assert!(meta.set_primary_item(2).is_err());
+
+        // The image data sits in the mdat following ftyp and meta; an image
+        // file has no moov.
+        let data_offset = ftyp.box_size() + meta.box_size() + HEADER_SIZE;
+        meta.iloc.as_mut().unwrap().items[0].extents[0].offset = data_offset;
+
+        let mut buf = Vec::new();
+        ftyp.write_box(&mut buf).unwrap();
+        meta.write_box(&mut buf).unwrap();
+        BoxHeader::new(BoxType::MdatBox, HEADER_SIZE + image.len() as u64)
+            .write(&mut buf)
+            .unwrap();
+        buf.extend_from_slice(image);
+
+        let size = buf.len() as u64;
+        let mut mp4 = Mp4Reader::read_header(Cursor::new(buf), size).unwrap();
+        assert_eq!(mp4.primary_item_id(), Some(1));
+        let item = mp4.primary_item().unwrap().unwrap();
+        assert_eq!(&item[..], &image[..]);
+        assert!(mp4.tracks().is_empty());
+
+        // An extent past the end of the file.
+        meta.iloc.as_mut().unwrap().items[0].extents[0].length = u64::MAX;
+        let mut buf = Vec::new();
+        ftyp.write_box(&mut buf).unwrap();
+        meta.write_box(&mut buf).unwrap();
+        let size = buf.len() as u64;
+        let mut mp4 = Mp4Reader::read_header(Cursor::new(buf), size).unwrap();
+        assert!(mp4.read_item(1).is_err());
+
+        let mut mp4 = read_moov(&MoovBox::default());
+        assert_eq!(mp4.primary_item().unwrap(), None);
+
+        // Without moov, only an image file can be read.
+        meta.hdlr = None;
+        let mut buf = Vec::new();
+        ftyp.write_box(&mut buf).unwrap();
+        meta.write_box(&mut buf).unwrap();
+        let size = buf.len() as u64;
+        assert!(matches!(
+            Mp4Reader::read_header(Cursor::new(buf), size),
+            Err(Error::BoxNotFound(BoxType::MoovBox))
+        ));
+    }
+
+    #[test]
+    fn test_items() {
+        let meta = MetaBox {
+            hdlr: Some(HdlrBox {
+                handler_type: str::parse("pict").unwrap(),
+                ..HdlrBox::default()
+            }),
+            iinf: Some(IinfBox {
+                entries: (1..=2)
+                    .map(|item_id| InfeBox {
+                        version: 2,
+                        item_id,
+                        item_protection_index: item_id as u16 - 1,
+                        item_type: str::parse("hvc1").unwrap(),
+                        ..InfeBox::default()
+                    })
+                    .collect(),
+                ..IinfBox::default()
+            }),
+            // Only item 2 is protected.
+            ipro: Some(IproBox {
+                sinfs: vec![SinfBox {
+                    frma: FrmaBox {
+                        original_format: str::parse("hvc1").unwrap(),
+                    },
+                    schm: Some(SchmBox::new(EncryptionScheme::Cenc)),
+                    schi: None,
+                }],
+                ..IproBox::default()
+            }),
+            ..MetaBox::default()
+        };
+
+        let mut buf = Vec::new();
+        FtypBox::default().write_box(&mut buf).unwrap();
+        meta.write_box(&mut buf).unwrap();
+
+        let size = buf.len() as u64;
+        let mp4 = Mp4Reader::read_header(Cursor::new(buf), size).unwrap();
+        let items = mp4.items();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].item_id, 2);
+        assert!(items.iter().all(|infe| infe.item_type.to_string() == "hvc1"));
+        assert!(mp4.item_protection(1).is_none());
+        let sinf = mp4.item_protection(2).unwrap();
+        assert_eq!(sinf.encryption_scheme(), Some(EncryptionScheme::Cenc));
+
+        assert!(read_moov(&MoovBox::default()).items().is_empty());
+    }
+
+    #[test]
+    fn test_grid_item() {
+        // A 2x2 grid, item 1, derived from four tiles stored in idat.
+        let tiles: [&[u8]; 4] = [b"tile-a", b"tile-b", b"tile-c", b"tile-d"];
+        let mut offset = 0;
+        let mut items = Vec::new();
+        for (i, tile) in tiles.iter().enumerate() {
+            items.push(IlocItem {
+                item_id: i as u32 + 2,
+                construction_method: IlocItem::CONSTRUCTION_IDAT_OFFSET,
+                extents: vec![IlocExtent {
+                    index: 0,
+                    offset,
+                    length: tile.len() as u64,
+                }],
+                ..IlocItem::default()
+            });
+            offset += tile.len() as u64;
+        }
+        let meta = MetaBox {
+            hdlr: Some(HdlrBox {
+                handler_type: str::parse("pict").unwrap(),
+                ..HdlrBox::default()
+            }),
+            iloc: Some(IlocBox {
+                version: 1,
+                offset_size: 4,
+                length_size: 4,
+                items,
+                ..IlocBox::default()
+            }),
+            iref: Some(IrefBox {
+                references: vec![ItemReference {
+                    reference_type: ItemReference::DERIVED_IMAGE,
+                    from_item_id: 1,
+                    to_item_ids: vec![2, 3, 4, 5],
+                }],
+                ..IrefBox::default()
+            }),
+            idat: Some(tiles.concat()),
+            ..MetaBox::default()
+        };
+
+        let mut buf = Vec::new();
+        FtypBox::default().write_box(&mut buf).unwrap();
+        meta.write_box(&mut buf).unwrap();
+        let size = buf.len() as u64;
+        let mut mp4 = Mp4Reader::read_header(Cursor::new(buf), size).unwrap();
+
+        assert_eq!(mp4.item_references().len(), 1);
+        let tile_ids = mp4.derived_image_items(1);
+        assert_eq!(tile_ids, vec![2, 3, 4, 5]);
+        assert!(mp4.derived_image_items(2).is_empty());
+        assert_eq!(mp4.item_location(3).unwrap().extents[0].offset, 6);
+        for (item_id, tile) in tile_ids.into_iter().zip(tiles.iter()) {
+            assert_eq!(&mp4.read_item(item_id).unwrap()[..], *tile);
+        }
+        assert!(mp4.read_item(1).is_err());
+    }
+
+    #[test]
+    fn test_movie_metadata() {
+        let mut moov = MoovBox::default();
+        moov.traks.push(trak(1));
+        assert!(read_moov(&moov).movie_metadata().is_none());
+
+        let name = RawBox {
+            box_type: IlstItem::KEY_NAME.into(),
+            data: vec![0, 5, 0x15, 0xC7, b'M', b'o', b'v', b'i', b'e'],
+        };
+        moov.udta = Some(UdtaBox {
+            meta: Some(MetaBox {
+                hdlr: Some(HdlrBox {
+                    handler_type: str::parse("mdir").unwrap(),
+                    ..HdlrBox::default()
+                }),
+                ilst: Some(IlstBox {
+                    items: vec![IlstItem::text(IlstItem::KEY_NAME, "Movie")],
+                }),
+                ..MetaBox::default()
+            }),
+            unknown_boxes: vec![name.clone()],
+            ..UdtaBox::default()
+        });
+        let mp4 = read_moov(&moov);
+        let udta = mp4.movie_metadata().unwrap();
+        assert_eq!(udta, moov.udta.as_ref().unwrap());
+        assert_eq!(udta.unknown_box(IlstItem::KEY_NAME), Some(&name));
+        let ilst = udta.meta.as_ref().unwrap().ilst.as_ref().unwrap();
+        assert_eq!(ilst.item(IlstItem::KEY_NAME).unwrap().as_text(), Some("Movie"));
+
+        let mut buf = Vec::new();
+        mp4.moov.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len() as u64, moov.box_size());
+    }
+
+    #[test]
+    fn test_xml_metadata() {
+        let mut moov = MoovBox::default();
+        moov.traks.push(trak(1));
+        assert_eq!(read_moov(&moov).xml_metadata(), None);
+
+        moov.udta = Some(UdtaBox {
+            meta: Some(MetaBox {
+                hdlr: Some(HdlrBox {
+                    handler_type: str::parse("mp7t").unwrap(),
+                    ..HdlrBox::default()
+                }),
+                xml: Some(XmlBox {
+                    xml: String::from("<Mpeg7/>"),
+                    ..XmlBox::default()
+                }),
+                ..MetaBox::default()
+            }),
+            ..UdtaBox::default()
+        });
+        let mp4 = read_moov(&moov);
+        assert_eq!(mp4.xml_metadata().as_deref(), Some("<Mpeg7/>"));
+    }
+
+    #[test]
+    fn test_creation_date() {
+        let mut moov = MoovBox::default();
+        moov.traks.push(trak(1));
+        // 2020-01-01T00:00:00Z in seconds since 1904.
+        moov.mvhd.creation_time = 3660681600;
+        let mp4 = read_moov(&moov);
+        assert_eq!(mp4.creation_date(), Some(UNIX_EPOCH + Duration::from_secs(1577836800)));
+
+        moov.udta = Some(UdtaBox {
+            meta: Some(MetaBox {
+                hdlr: Some(HdlrBox {
+                    handler_type: str::parse("mdir").unwrap(),
+                    ..HdlrBox::default()
+                }),
+                ilst: Some(IlstBox {
+                    items: vec![IlstItem::text(IlstItem::KEY_DAY, "2023-06-15T10:30:00Z")],
+                }),
+                ..MetaBox::default()
+            }),
+            ..UdtaBox::default()
+        });
+        let mp4 = read_moov(&moov);
+        assert_eq!(mp4.creation_date(), Some(UNIX_EPOCH + Duration::from_secs(1686825000)));
+
+        moov.udta = None;
+        moov.mvhd.creation_time = 0;
+        assert_eq!(read_moov(&moov).creation_date(), None);
+    }
 }
@@ -0,0 +1,90 @@
+//! Parsers for boxes this crate doesn't know, such as proprietary boxes.
+//!
+//! A [`BoxParserRegistry`] maps box types to closures that parse the box
+//! payload into any type. Passed to [`Mp4Reader::read_header_with_registry`],
+//! it is consulted for every box the reader would otherwise skip or keep as
+//! raw bytes: top-level boxes, and the unknown children kept by the moov,
+//! trak, mdia, minf, stbl, moof and traf containers, udta, trep and sample
+//! entries. The results are returned by
+//! [`Mp4Reader::custom_boxes`].
+//!
+//! ```
+//! use mp4::{BoxParserRegistry, FourCC};
+//!
+//! let mut registry = BoxParserRegistry::new();
+//! registry.register(str::parse::<FourCC>("cust").unwrap(), |payload| {
+//!     Ok(Box::new(payload.len()))
+//! });
+//! ```
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::mp4box::RawBox;
+use crate::*;
+
+/// The value returned by a custom box parser.
+pub type ParsedBox = Box<dyn Any + Send + Sync>;
+
+type BoxParser = Box<dyn Fn(&[u8]) -> Result<ParsedBox> + Send + Sync>;
+
+/// Custom box parsers, keyed by box type.
+#[derive(Default)]
+pub struct BoxParserRegistry {
+    parsers: HashMap<FourCC, BoxParser>,
+}
+
+impl fmt::Debug for BoxParserRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.parsers.keys()).finish()
+    }
+}
+
+impl BoxParserRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `parser` for boxes of type `box_type`, replacing any parser
+    /// registered for it before. The parser gets the box payload, without
+    /// its header.
+    pub fn register<F>(&mut self, box_type: FourCC, parser: F)
+    where
+        F: Fn(&[u8]) -> Result<ParsedBox> + Send + Sync + 'static,
+    {
+        self.parsers.insert(box_type, Box::new(parser));
+    }
+
+    pub fn contains(&self, box_type: &FourCC) -> bool {
+        self.parsers.contains_key(box_type)
+    }
+
+    /// Parse `payload` with the parser for `box_type`, or return `None` if
+    /// there is none.
+    pub fn parse(&self, box_type: &FourCC, payload: &[u8]) -> Option<Result<ParsedBox>> {
+        self.parsers.get(box_type).map(|parser| parser(payload))
+    }
+
+    pub(crate) fn parse_raw(&self, raw: &RawBox) -> Option<Result<CustomBox>> {
+        let value = self.parse(&raw.box_type, &raw.data)?;
+        Some(value.map(|value| CustomBox {
+            box_type: raw.box_type,
+            value,
+        }))
+    }
+}
+
+/// A box parsed by a parser of a [`BoxParserRegistry`].
+#[derive(Debug)]
+pub struct CustomBox {
+    pub box_type: FourCC,
+    pub value: ParsedBox,
+}
+
+impl CustomBox {
+    /// Returns the parsed value if the parser returned a `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+}
@@ -0,0 +1,375 @@
+//! Walking the boxes of an in-memory buffer.
+//!
+//! This works with or without the `std` feature. It splits a byte slice
+//! into boxes and their children, and decodes header boxes such as
+//! [FtypBox], [MvhdBox], [TkhdBox], [MdhdBox] and [HdlrBox] with
+//! [SliceBox::read]. The other boxes are left to the caller.
+
+use core::convert::TryInto;
+
+use crate::mp4box::mdhd::language_string;
+use crate::mp4box::tkhd::Matrix;
+use crate::mp4box::BoxType;
+use crate::*;
+
+const TRUNCATED: Error = Error::InvalidData("data is truncated");
+
+/// A box within a byte slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SliceBox<'a> {
+    pub box_type: [u8; 4],
+    /// The contents of the box, after its header.
+    pub data: &'a [u8],
+}
+
+impl<'a> SliceBox<'a> {
+    /// Returns the version and flags of a full box.
+    pub fn full_box_header(&self) -> Result<(u8, u32)> {
+        let word = self.read_u32(0)?;
+        Ok(((word >> 24) as u8, word & 0x00FF_FFFF))
+    }
+
+    /// Iterates over the child boxes, which start `offset` bytes into the
+    /// contents, e.g. 0 for moov or 4 for the full box meta.
+    pub fn children(&self, offset: usize) -> SliceBoxes<'a> {
+        SliceBoxes {
+            data: self.data.get(offset..).unwrap_or(&[]),
+        }
+    }
+
+    /// Returns the first child box of the given type.
+    pub fn child(&self, offset: usize, box_type: [u8; 4]) -> Result<Option<SliceBox<'a>>> {
+        for child in self.children(offset) {
+            let child = child?;
+            if child.box_type == box_type {
+                return Ok(Some(child));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Decode the box as `T`, which must be of the same type.
+    pub fn read<T: ReadSlice>(&self) -> Result<T> {
+        if self.box_type != T::BOX_TYPE {
+            return Err(Error::BoxNotFound(BoxType::from(u32::from_be_bytes(T::BOX_TYPE))));
+        }
+        T::read_slice(self)
+    }
+
+    pub fn read_u8(&self, offset: usize) -> Result<u8> {
+        self.data.get(offset).copied().ok_or(TRUNCATED)
+    }
+
+    pub fn read_u16(&self, offset: usize) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes(offset)?))
+    }
+
+    pub fn read_u32(&self, offset: usize) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.bytes(offset)?))
+    }
+
+    pub fn read_u64(&self, offset: usize) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.bytes(offset)?))
+    }
+
+    // Read a 32-bit field of a version 0 full box or a 64-bit field of a
+    // version 1 one, returning the value and the offset after it.
+    fn read_versioned(&self, version: u8, offset: usize) -> Result<(u64, usize)> {
+        match version {
+            1 => Ok((self.read_u64(offset)?, offset + 8)),
+            0 => Ok((self.read_u32(offset)? as u64, offset + 4)),
+            _ => Err(Error::InvalidData("version must be 0 or 1")),
+        }
+    }
+
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N]> {
+        offset
+            .checked_add(N)
+            .and_then(|end| self.data.get(offset..end))
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(TRUNCATED)
+    }
+}
+
+/// A box that can be decoded from a [SliceBox], without `std`.
+pub trait ReadSlice: Sized {
+    const BOX_TYPE: [u8; 4];
+
+    fn read_slice(slice_box: &SliceBox) -> Result<Self>;
+}
+
+impl ReadSlice for FtypBox {
+    const BOX_TYPE: [u8; 4] = *b"ftyp";
+
+    fn read_slice(slice_box: &SliceBox) -> Result<Self> {
+        let brands = slice_box.data.get(8..).ok_or(TRUNCATED)?;
+        if brands.len() % 4 != 0 {
+            return Err(Error::InvalidData("invalid ftyp size"));
+        }
+        Ok(FtypBox {
+            major_brand: From::from(slice_box.read_u32(0)?),
+            minor_version: slice_box.read_u32(4)?,
+            compatible_brands: brands
+                .chunks_exact(4)
+                .map(|brand| From::from(u32::from_be_bytes(brand.try_into().unwrap())))
+                .collect(),
+        })
+    }
+}
+
+impl ReadSlice for MvhdBox {
+    const BOX_TYPE: [u8; 4] = *b"mvhd";
+
+    fn read_slice(slice_box: &SliceBox) -> Result<Self> {
+        let (version, flags) = slice_box.full_box_header()?;
+        let (creation_time, offset) = slice_box.read_versioned(version, 4)?;
+        let (modification_time, offset) = slice_box.read_versioned(version, offset)?;
+        let timescale = slice_box.read_u32(offset)?;
+        let (duration, offset) = slice_box.read_versioned(version, offset + 4)?;
+        // Skip volume, reserved fields, the matrix and pre_defined.
+        Ok(MvhdBox {
+            version,
+            flags,
+            creation_time,
+            modification_time,
+            timescale,
+            duration,
+            rate: FixedPointU16::new_raw(slice_box.read_u32(offset)?),
+            next_track_id: slice_box.read_u32(offset + 76)?,
+        })
+    }
+}
+
+impl ReadSlice for TkhdBox {
+    const BOX_TYPE: [u8; 4] = *b"tkhd";
+
+    fn read_slice(slice_box: &SliceBox) -> Result<Self> {
+        let (version, flags) = slice_box.full_box_header()?;
+        let (creation_time, offset) = slice_box.read_versioned(version, 4)?;
+        let (modification_time, offset) = slice_box.read_versioned(version, offset)?;
+        let track_id = slice_box.read_u32(offset)?;
+        let (duration, offset) = slice_box.read_versioned(version, offset + 8)?;
+        let matrix = offset + 16;
+        let matrix_field = |i: usize| -> Result<i32> { Ok(slice_box.read_u32(matrix + 4 * i)? as i32) };
+        Ok(TkhdBox {
+            version,
+            flags,
+            creation_time,
+            modification_time,
+            track_id,
+            duration,
+            layer: slice_box.read_u16(offset + 8)?,
+            alternate_group: slice_box.read_u16(offset + 10)?,
+            volume: FixedPointU8::new_raw(slice_box.read_u16(offset + 12)?),
+            matrix: Matrix {
+                // Little-endian, as TkhdBox reads and writes it.
+                a: i32::from_le_bytes(slice_box.bytes(matrix)?),
+                b: matrix_field(1)?,
+                u: matrix_field(2)?,
+                c: matrix_field(3)?,
+                d: matrix_field(4)?,
+                v: matrix_field(5)?,
+                x: matrix_field(6)?,
+                y: matrix_field(7)?,
+                w: matrix_field(8)?,
+            },
+            width: FixedPointU16::new_raw(slice_box.read_u32(offset + 52)?),
+            height: FixedPointU16::new_raw(slice_box.read_u32(offset + 56)?),
+        })
+    }
+}
+
+impl ReadSlice for MdhdBox {
+    const BOX_TYPE: [u8; 4] = *b"mdhd";
+
+    fn read_slice(slice_box: &SliceBox) -> Result<Self> {
+        let (version, flags) = slice_box.full_box_header()?;
+        let (creation_time, offset) = slice_box.read_versioned(version, 4)?;
+        let (modification_time, offset) = slice_box.read_versioned(version, offset)?;
+        let timescale = slice_box.read_u32(offset)?;
+        let (duration, offset) = slice_box.read_versioned(version, offset + 4)?;
+        Ok(MdhdBox {
+            version,
+            flags,
+            creation_time,
+            modification_time,
+            timescale,
+            duration,
+            language: language_string(slice_box.read_u16(offset)?),
+        })
+    }
+}
+
+impl ReadSlice for HdlrBox {
+    const BOX_TYPE: [u8; 4] = *b"hdlr";
+
+    fn read_slice(slice_box: &SliceBox) -> Result<Self> {
+        let (version, flags) = slice_box.full_box_header()?;
+        // The name runs to the end of the box, less its null terminator.
+        let end = slice_box.data.len().saturating_sub(1);
+        let name = slice_box.data.get(24..end).ok_or(TRUNCATED)?;
+        Ok(HdlrBox {
+            version,
+            flags,
+            handler_type: From::from(slice_box.read_u32(8)?),
+            name: core::str::from_utf8(name).unwrap_or("null").to_string(),
+        })
+    }
+}
+
+/// Iterates over consecutive boxes in a byte slice, as returned by
+/// [boxes] and [SliceBox::children].
+#[derive(Debug, Clone)]
+pub struct SliceBoxes<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for SliceBoxes<'a> {
+    type Item = Result<SliceBox<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let result = next_box(self.data);
+        match result {
+            Ok((_, size)) => self.data = &self.data[size..],
+            Err(_) => self.data = &[],
+        }
+        Some(result.map(|(slice_box, _)| slice_box))
+    }
+}
+
+fn next_box(data: &[u8]) -> Result<(SliceBox<'_>, usize)> {
+    if data.len() < 8 {
+        return Err(TRUNCATED);
+    }
+    let size = u32::from_be_bytes(data[0..4].try_into().unwrap()) as u64;
+    let box_type: [u8; 4] = data[4..8].try_into().unwrap();
+    let (size, header_size) = match size {
+        // A size of 0 means the box runs to the end of the data.
+        0 => (data.len() as u64, 8),
+        1 => {
+            let largesize = data.get(8..16).ok_or(TRUNCATED)?;
+            (u64::from_be_bytes(largesize.try_into().unwrap()), 16)
+        }
+        size => (size, 8),
+    };
+    if size < header_size {
+        return Err(Error::InvalidData("box is smaller than its header"));
+    }
+    if size > data.len() as u64 {
+        return Err(Error::InvalidData("box extends past its parent"));
+    }
+    let size = size as usize;
+    Ok((
+        SliceBox {
+            box_type,
+            data: &data[header_size as usize..size],
+        },
+        size,
+    ))
+}
+
+/// Iterates over the top-level boxes of a buffer holding an MP4 file or a
+/// part of one.
+pub fn boxes(data: &[u8]) -> SliceBoxes<'_> {
+    SliceBoxes { data }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_boxes() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0, 0, 0, 16, b'f', b't', b'y', b'p', b'i', b's', b'o', b'm', 0, 0, 2, 0,
+            0, 0, 0, 32, b'm', b'o', b'o', b'v',
+            0, 0, 0, 24, b'm', b'v', b'h', b'd', 1, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0x03, 0xe8,
+        ];
+        let top: Vec<SliceBox> = boxes(data).map(|b| b.unwrap()).collect();
+        assert_eq!(top.len(), 2);
+        let ftyp = top[0];
+        assert_eq!(&ftyp.box_type, b"ftyp");
+        assert_eq!(ftyp.data, b"isom\0\0\x02\0");
+        assert_eq!(ftyp.read_u32(4).unwrap(), 512);
+
+        let moov = top[1];
+        let mvhd = moov.child(0, *b"mvhd").unwrap().unwrap();
+        assert_eq!(mvhd.full_box_header().unwrap(), (1, 0));
+        assert_eq!(mvhd.read_u64(4).unwrap(), 1);
+        assert_eq!(mvhd.read_u32(12).unwrap(), 1000);
+        assert!(matches!(mvhd.read_u32(14), Err(Error::InvalidData("data is truncated"))));
+        assert!(moov.child(0, *b"trak").unwrap().is_none());
+
+        let truncated = &data[..data.len() - 1];
+        let moov = boxes(truncated).nth(1).unwrap();
+        assert!(matches!(moov, Err(Error::InvalidData("box extends past its parent"))));
+        assert!(matches!(boxes(&data[..4]).next(), Some(Err(Error::InvalidData("data is truncated")))));
+        assert!(ftyp.read_u32(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_slice_read_boxes() {
+        let ftyp = SliceBox {
+            box_type: *b"ftyp",
+            data: b"isom\0\0\x02\0isomiso2mp41",
+        };
+        let ftyp: FtypBox = ftyp.read().unwrap();
+        assert_eq!(ftyp.major_brand, str::parse("isom").unwrap());
+        assert_eq!(ftyp.minor_version, 512);
+        assert_eq!(ftyp.compatible_brands.len(), 3);
+        assert_eq!(ftyp.compatible_brands[2], str::parse("mp41").unwrap());
+
+        // A version 0 mvhd: timescale 1000, duration 5000, rate 1.0 and
+        // next_track_id 3.
+        let mut data = [0u8; 100];
+        data[12..16].copy_from_slice(&1000u32.to_be_bytes());
+        data[16..20].copy_from_slice(&5000u32.to_be_bytes());
+        data[20..24].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+        data[96..100].copy_from_slice(&3u32.to_be_bytes());
+        let mvhd = SliceBox { box_type: *b"mvhd", data: &data };
+        let mvhd: MvhdBox = mvhd.read().unwrap();
+        assert_eq!(
+            mvhd,
+            MvhdBox {
+                duration: 5000,
+                next_track_id: 3,
+                ..MvhdBox::default()
+            }
+        );
+
+        // A version 1 mdhd: 64-bit times, timescale 48000, duration 96000.
+        let mut data = [0u8; 36];
+        data[0] = 1;
+        data[20..24].copy_from_slice(&48000u32.to_be_bytes());
+        data[24..32].copy_from_slice(&96000u64.to_be_bytes());
+        data[32..34].copy_from_slice(&0x15C7u16.to_be_bytes());
+        let mdhd = SliceBox { box_type: *b"mdhd", data: &data };
+        let mdhd: MdhdBox = mdhd.read().unwrap();
+        assert_eq!(mdhd.version, 1);
+        assert_eq!(mdhd.timescale, 48000);
+        assert_eq!(mdhd.duration, 96000);
+        assert_eq!(mdhd.language, "eng");
+
+        let truncated = SliceBox { box_type: *b"mdhd", data: &data[..24] };
+        assert!(matches!(truncated.read::<MdhdBox>(), Err(Error::InvalidData("data is truncated"))));
+        let wrong_type = SliceBox { box_type: *b"tkhd", data: &data };
+        assert!(matches!(wrong_type.read::<MdhdBox>(), Err(Error::BoxNotFound(BoxType::MdhdBox))));
+        data[0] = 2;
+        let bad_version = SliceBox { box_type: *b"mdhd", data: &data };
+        assert!(matches!(bad_version.read::<MdhdBox>(), Err(Error::InvalidData("version must be 0 or 1"))));
+
+        let hdlr = SliceBox {
+            box_type: *b"hdlr",
+            data: b"\0\0\0\0\0\0\0\0vide\0\0\0\0\0\0\0\0\0\0\0\0Video\0",
+        };
+        let hdlr: HdlrBox = hdlr.read().unwrap();
+        assert_eq!(hdlr.handler_type, str::parse("vide").unwrap());
+        assert_eq!(hdlr.name, "Video");
+    }
+}
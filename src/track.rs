@@ -1,35 +1,191 @@
 use bytes::BytesMut;
 use std::cmp;
+use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
 use std::time::Duration;
 
 use crate::mp4box::trak::TrakBox;
-use crate::mp4box::traf::TrafBox;
+use crate::mp4box::traf::{TrafBox, SAMPLE_FLAGS_NON_SYNC, SAMPLE_FLAGS_SYNC};
 use crate::mp4box::*;
 use crate::mp4box::{
     avc1::Avc1Box,
     hev1::Hev1Box,
     vp09::Vp09Box,
+    av01::Av01Box,
+    chnl::ChnlBox,
     ctts::CttsBox,
     ctts::CttsEntry,
-    mp4a::Mp4aBox,
+    dinf::{DataEntryBox, UrlBox},
+    elst::ElstEntry,
+    mp4a::{DecoderSpecificDescriptor, Mp4aBox},
+    samr::SamrBox,
     smhd::SmhdBox,
     stco::StcoBox,
     stsc::StscEntry,
     stss::StssBox,
     stts::SttsEntry,
+    trex::TrexBox,
     tx3g::Tx3gBox,
     vmhd::VmhdBox,
 };
 use crate::*;
 
+/// The sample entry describing a track's codec, as found in its stsd box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum SampleEntry<'a> {
+    Avc1(&'a Avc1Box),
+    Hev1(&'a Hev1Box),
+    Vp09(&'a Vp09Box),
+    Av01(&'a Av01Box),
+    Mp4a(&'a Mp4aBox),
+    /// AMR narrowband (samr) or wideband (sawb) audio.
+    Samr(&'a SamrBox),
+    Tx3g(&'a Tx3gBox),
+    /// The stsd holds no sample entry this crate can parse.
+    Unknown,
+}
+
+impl SampleEntry<'_> {
+    pub fn width(&self) -> Option<u16> {
+        match self {
+            SampleEntry::Avc1(avc1) => Some(avc1.width),
+            SampleEntry::Hev1(hev1) => Some(hev1.width),
+            SampleEntry::Vp09(vp09) => Some(vp09.width),
+            SampleEntry::Av01(av01) => Some(av01.width),
+            _ => None,
+        }
+    }
+
+    pub fn height(&self) -> Option<u16> {
+        match self {
+            SampleEntry::Avc1(avc1) => Some(avc1.height),
+            SampleEntry::Hev1(hev1) => Some(hev1.height),
+            SampleEntry::Vp09(vp09) => Some(vp09.height),
+            SampleEntry::Av01(av01) => Some(av01.height),
+            _ => None,
+        }
+    }
+
+    pub fn channel_count(&self) -> Option<u16> {
+        match self {
+            SampleEntry::Mp4a(mp4a) => Some(mp4a.channelcount),
+            SampleEntry::Samr(samr) => Some(samr.channelcount),
+            _ => None,
+        }
+    }
+
+    /// Returns the sample rate in Hz.
+    pub fn sample_rate(&self) -> Option<u32> {
+        match self {
+            SampleEntry::Mp4a(mp4a) => Some(mp4a.samplerate.value() as u32),
+            SampleEntry::Samr(samr) => Some(samr.samplerate.value() as u32),
+            _ => None,
+        }
+    }
+}
+
+/// A chunk of a non-fragmented track: a run of consecutive samples stored
+/// contiguously in the file, as listed by [`Mp4Track::chunks`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// Absolute file offset of the first sample.
+    pub offset: u64,
+    pub sample_ids: Range<u32>,
+    /// The 1-based index of the stsd entry describing the samples.
+    pub sample_description_index: u32,
+}
+
+/// The decode and presentation times of a sample, in the media timescale,
+/// as listed by [`Mp4Track::timing_table`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleTiming {
+    pub dts: u64,
+    /// The decode time plus the composition offset and the cslg shift, so
+    /// it can be negative with negative composition offsets.
+    pub pts: i64,
+    pub duration: u32,
+}
+
+/// The first sync sample of a track together with the codec configuration
+/// needed to decode it, as returned by [`Mp4Track::thumbnail`].
+#[derive(Debug, PartialEq)]
+pub struct Thumbnail {
+    pub sample_id: u32,
+    pub sample: Mp4Sample,
+    /// See [`Mp4Track::codec_private`]; for AVC and HEVC this holds the
+    /// parameter sets.
+    pub codec_private: Option<Vec<u8>>,
+}
+
+/// A problem found in a track's edit list by [`Mp4Track::validate`]. Entries
+/// are referred to by their index in the elst box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditListIssue {
+    /// The media_time is negative, but not the -1 that marks an empty edit.
+    NegativeMediaTime(usize),
+    /// The media of the second entry starts before that of the first one
+    /// (the previous non-empty edit) has ended.
+    Overlapping(usize, usize),
+    /// The non-empty edits up to and including this entry last longer than
+    /// the media of the track.
+    ExceedsTrackDuration(usize),
+}
+
+/// A tkhd duration that doesn't match the media, as found by
+/// [`Mp4Reader::validate_track_durations`]. Both durations are in the movie
+/// timescale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurationMismatch {
+    pub track_id: u32,
+    pub tkhd_duration: u64,
+    pub expected: u64,
+}
+
+/// The frame rate of a track as returned by [`Mp4Track::exact_frame_rate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameRate {
+    /// Frames per second, e.g. 30000/1001.
+    pub rate: Ratio<u32>,
+    /// Set if the samples have different durations, in which case `rate`
+    /// is the average over the track.
+    pub variable: bool,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TrackConfig {
     pub track_type: TrackType,
     pub timescale: u32,
     pub language: String,
     pub media_conf: MediaConfig,
+
+    /// Locations of the track's data references, where an empty location
+    /// refers to this file. Leave empty for a single self-contained entry.
+    pub data_references: Vec<String>,
+
+    /// 1-based index of the data reference used by the sample entry.
+    pub data_reference_index: u16,
+
+    /// Compositing order of visual tracks; lower layers are in front.
+    pub layer: u16,
+
+    /// Tracks sharing a non-zero alternate group are alternatives to each
+    /// other, such as audio in different languages. 0 for no group.
+    pub alternate_group: u16,
+
+    /// Stereo balance of audio tracks, from -1.0 (full left) to 1.0 (full
+    /// right).
+    pub balance: f32,
+}
+
+impl TrackConfig {
+    /// Set the stereo balance written to the smhd of an audio track.
+    pub fn audio_balance(mut self, balance: f32) -> Self {
+        self.balance = balance;
+        self
+    }
 }
 
 impl From<MediaConfig> for TrackConfig {
@@ -51,6 +207,11 @@ impl From<AvcConfig> for TrackConfig {
             timescale: 1000,               // XXX
             language: String::from("und"), // XXX
             media_conf: MediaConfig::AvcConfig(avc_conf),
+            data_references: Vec::new(),
+            data_reference_index: 1,
+            layer: 0,
+            alternate_group: 0,
+            balance: 0.0,
         }
     }
 }
@@ -62,6 +223,11 @@ impl From<HevcConfig> for TrackConfig {
             timescale: 1000,               // XXX
             language: String::from("und"), // XXX
             media_conf: MediaConfig::HevcConfig(hevc_conf),
+            data_references: Vec::new(),
+            data_reference_index: 1,
+            layer: 0,
+            alternate_group: 0,
+            balance: 0.0,
         }
     }
 }
@@ -73,6 +239,11 @@ impl From<AacConfig> for TrackConfig {
             timescale: 1000,               // XXX
             language: String::from("und"), // XXX
             media_conf: MediaConfig::AacConfig(aac_conf),
+            data_references: Vec::new(),
+            data_reference_index: 1,
+            layer: 0,
+            alternate_group: 0,
+            balance: 0.0,
         }
     }
 }
@@ -84,6 +255,11 @@ impl From<TtxtConfig> for TrackConfig {
             timescale: 1000,               // XXX
             language: String::from("und"), // XXX
             media_conf: MediaConfig::TtxtConfig(txtt_conf),
+            data_references: Vec::new(),
+            data_reference_index: 1,
+            layer: 0,
+            alternate_group: 0,
+            balance: 0.0,
         }
     }
 }
@@ -95,23 +271,48 @@ impl From<Vp9Config> for TrackConfig {
             timescale: 1000,               // XXX
             language: String::from("und"), // XXX
             media_conf: MediaConfig::Vp9Config(vp9_conf),
+            data_references: Vec::new(),
+            data_reference_index: 1,
+            layer: 0,
+            alternate_group: 0,
+            balance: 0.0,
         }
     }
 }
 
+/// A track of an MP4 file, with the fragments that belong to it.
+///
+/// The tkhd duration and the edit list are in the timescale of the movie
+/// rather than that of the track, so methods that read or write them take
+/// a `movie_timescale`, the timescale of the mvhd, as given by
+/// [Mp4Reader::timescale].
 #[derive(Debug)]
 pub struct Mp4Track {
     pub trak: TrakBox,
     pub trafs: Vec<TrafBox>,
 
+    // Absolute file offset of the first sample of each traf.
+    pub(crate) traf_data_offsets: Vec<u64>,
+
     // Fragmented Tracks Defaults.
     pub default_sample_duration: u32,
+    pub default_sample_description_index: u32,
+
+    // Set if moov/mvex holds a trex for this track.
+    pub(crate) has_trex: bool,
 }
 
 impl Mp4Track {
     pub(crate) fn from(trak: &TrakBox) -> Self {
         let trak = trak.clone();
-        Self { trak, trafs: Vec::new(), default_sample_duration: 0, }
+        Self {
+            trak,
+            trafs: Vec::new(),
+            traf_data_offsets: Vec::new(),
+            default_sample_duration: 0,
+            default_sample_description_index: 1,
+            has_trex: false,
+        }
     }
 
     pub fn track_id(&self) -> u32 {
@@ -129,10 +330,17 @@ impl Mp4Track {
             Ok(MediaType::H265)
         } else if self.trak.mdia.minf.stbl.stsd.vp09.is_some() {
             Ok(MediaType::VP9)
-        } else if self.trak.mdia.minf.stbl.stsd.mp4a.is_some() {
-            Ok(MediaType::AAC)
+        } else if let Some(ref mp4a) = self.trak.mdia.minf.stbl.stsd.mp4a {
+            match mp4a.esds {
+                Some(ref esds) if esds.es_desc.dec_config.is_mp3() => Ok(MediaType::MP3),
+                _ => Ok(MediaType::AAC),
+            }
         } else if self.trak.mdia.minf.stbl.stsd.tx3g.is_some() {
             Ok(MediaType::TTXT)
+        } else if self.trak.mdia.minf.stbl.stsd.unknown.as_ref().map(|entry| entry.box_type)
+            == Some(FourCC::from(*b".mp3"))
+        {
+            Ok(MediaType::MP3)
         } else {
             Err(Error::InvalidData("unsupported media type"))
         }
@@ -141,12 +349,16 @@ impl Mp4Track {
     pub fn box_type(&self) -> Result<FourCC> {
         if self.trak.mdia.minf.stbl.stsd.avc1.is_some() {
             Ok(FourCC::from(BoxType::Avc1Box))
-        } else if self.trak.mdia.minf.stbl.stsd.hev1.is_some() {
-            Ok(FourCC::from(BoxType::Hev1Box))
+        } else if let Some(ref hev1) = self.trak.mdia.minf.stbl.stsd.hev1 {
+            Ok(FourCC::from(hev1.box_type()))
         } else if self.trak.mdia.minf.stbl.stsd.vp09.is_some() {
             Ok(FourCC::from(BoxType::Vp09Box))
+        } else if self.trak.mdia.minf.stbl.stsd.av01.is_some() {
+            Ok(FourCC::from(BoxType::Av01Box))
         } else if self.trak.mdia.minf.stbl.stsd.mp4a.is_some() {
             Ok(FourCC::from(BoxType::Mp4aBox))
+        } else if let Some(ref samr) = self.trak.mdia.minf.stbl.stsd.samr {
+            Ok(FourCC::from(samr.box_type()))
         } else if self.trak.mdia.minf.stbl.stsd.tx3g.is_some() {
             Ok(FourCC::from(BoxType::Tx3gBox))
         } else {
@@ -154,6 +366,27 @@ impl Mp4Track {
         }
     }
 
+    pub fn sample_entry(&self) -> SampleEntry<'_> {
+        let stsd = &self.trak.mdia.minf.stbl.stsd;
+        if let Some(ref avc1) = stsd.avc1 {
+            SampleEntry::Avc1(avc1)
+        } else if let Some(ref hev1) = stsd.hev1 {
+            SampleEntry::Hev1(hev1)
+        } else if let Some(ref vp09) = stsd.vp09 {
+            SampleEntry::Vp09(vp09)
+        } else if let Some(ref av01) = stsd.av01 {
+            SampleEntry::Av01(av01)
+        } else if let Some(ref mp4a) = stsd.mp4a {
+            SampleEntry::Mp4a(mp4a)
+        } else if let Some(ref samr) = stsd.samr {
+            SampleEntry::Samr(samr)
+        } else if let Some(ref tx3g) = stsd.tx3g {
+            SampleEntry::Tx3g(tx3g)
+        } else {
+            SampleEntry::Unknown
+        }
+    }
+
     pub fn width(&self) -> u16 {
         if let Some(ref avc1) = self.trak.mdia.minf.stbl.stsd.avc1 {
             avc1.width
@@ -170,6 +403,36 @@ impl Mp4Track {
         }
     }
 
+    /// Returns true if the track's media is in this file, i.e. the dref entry
+    /// referenced by its sample description has the self-contained flag set.
+    /// Tracks referring to external media return false.
+    pub fn is_self_contained(&self) -> bool {
+        let index = self.trak.mdia.minf.stbl.stsd.data_reference_index().unwrap_or(1);
+        if index == 0 {
+            return false;
+        }
+        self.trak.mdia.minf.dinf.dref.entries
+            .get(index as usize - 1)
+            .map(|entry| entry.is_self_contained())
+            .unwrap_or(false)
+    }
+
+    /// Returns the Common Encryption scheme of a protected (encv or enca)
+    /// track.
+    pub fn encryption_scheme(&self) -> Option<EncryptionScheme> {
+        self.trak.mdia.minf.stbl.stsd.sinf()?.encryption_scheme()
+    }
+
+    /// Returns the clean aperture width and height from the QuickTime tapt
+    /// box, i.e. the size anamorphic content should be displayed at.
+    pub fn clean_aperture(&self) -> Option<(f64, f64)> {
+        let clef = self.trak.tapt.as_ref()?.clef.as_ref()?;
+        Some((
+            clef.width.raw_value() as f64 / 65536.0,
+            clef.height.raw_value() as f64 / 65536.0,
+        ))
+    }
+
     pub fn frame_rate(&self) -> f64 {
         let dur_msec = self.duration().as_millis() as u64;
         if dur_msec > 0 {
@@ -179,28 +442,73 @@ impl Mp4Track {
         }
     }
 
-    pub fn sample_freq_index(&self) -> Result<SampleFreqIndex> {
-        if let Some(ref mp4a) = self.trak.mdia.minf.stbl.stsd.mp4a {
-            if let Some(ref esds) = mp4a.esds {
-                SampleFreqIndex::try_from(esds.es_desc.dec_config.dec_specific.freq_index)
-            } else {
-                Err(Error::BoxInStblNotFound(self.track_id(), BoxType::EsdsBox))
-            }
+    /// Returns the frame rate as the media timescale over the sample
+    /// duration, e.g. 24000/1001 rather than 23.976. The duration of the
+    /// last sample is ignored, as muxers often shorten it. Tracks whose
+    /// other samples differ in duration get their average rate instead,
+    /// marked as variable. Fragmented tracks are timed from their trun
+    /// durations. Returns `None` for tracks without timing or if the average
+    /// doesn't fit in 32 bits.
+    pub fn exact_frame_rate(&self) -> Option<FrameRate> {
+        let timescale = self.timescale();
+        let mut entries = if self.trafs.is_empty() {
+            self.trak.mdia.minf.stbl.stts.entries.clone()
         } else {
-            Err(Error::BoxInStblNotFound(self.track_id(), BoxType::Mp4aBox))
+            self.fragment_stts_entries()
+        };
+        if entries.iter().map(|entry| entry.sample_count as u64).sum::<u64>() > 1 {
+            if let Some(last) = entries.iter_mut().rev().find(|entry| entry.sample_count > 0) {
+                last.sample_count -= 1;
+            }
+        }
+        // Samples with a zero delta span no time and don't count as frames.
+        let mut sample_counts: HashMap<u32, u64> = HashMap::new();
+        for entry in entries.iter().filter(|entry| entry.sample_count > 0 && entry.sample_delta > 0) {
+            *sample_counts.entry(entry.sample_delta).or_default() += entry.sample_count as u64;
+        }
+        if timescale == 0 || sample_counts.is_empty() {
+            return None;
+        }
+
+        if sample_counts.len() == 1 {
+            let delta = *sample_counts.keys().next().unwrap();
+            return Some(FrameRate {
+                rate: Ratio::new(timescale, delta),
+                variable: false,
+            });
         }
+        let sample_count: u64 = sample_counts.values().sum();
+        let duration: u64 = sample_counts.iter()
+            .map(|(delta, count)| *delta as u64 * count)
+            .sum();
+        let rate = Ratio::new(timescale as u64 * sample_count, duration);
+        Some(FrameRate {
+            rate: Ratio::new_raw(
+                u32::try_from(*rate.numer()).ok()?,
+                u32::try_from(*rate.denom()).ok()?,
+            ),
+            variable: true,
+        })
+    }
+
+    pub fn sample_freq_index(&self) -> Result<SampleFreqIndex> {
+        SampleFreqIndex::try_from(self.audio_specific_config()?.freq_index)
     }
 
     pub fn channel_config(&self) -> Result<ChannelConfig> {
-        if let Some(ref mp4a) = self.trak.mdia.minf.stbl.stsd.mp4a {
-            if let Some(ref esds) = mp4a.esds {
-                ChannelConfig::try_from(esds.es_desc.dec_config.dec_specific.chan_conf)
-            } else {
-                Err(Error::BoxInStblNotFound(self.track_id(), BoxType::EsdsBox))
-            }
-        } else {
-            Err(Error::BoxInStblNotFound(self.track_id(), BoxType::Mp4aBox))
+        ChannelConfig::try_from(self.audio_specific_config()?.chan_conf)
+    }
+
+    // The AAC configuration from the esds of the mp4a entry.
+    fn audio_specific_config(&self) -> Result<&DecoderSpecificDescriptor> {
+        let mp4a = self.trak.mdia.minf.stbl.stsd.mp4a.as_ref()
+            .ok_or(Error::BoxInStblNotFound(self.track_id(), BoxType::Mp4aBox))?;
+        let esds = mp4a.esds.as_ref()
+            .ok_or(Error::BoxInStblNotFound(self.track_id(), BoxType::EsdsBox))?;
+        if esds.es_desc.dec_config.is_mp3() {
+            return Err(Error::InvalidData("mp3 has no AudioSpecificConfig"));
         }
+        Ok(&esds.es_desc.dec_config.dec_specific)
     }
 
     pub fn language(&self) -> &str {
@@ -211,12 +519,378 @@ impl Mp4Track {
         self.trak.mdia.mdhd.timescale
     }
 
+    pub fn layer(&self) -> u16 {
+        self.trak.tkhd.layer
+    }
+
+    pub fn alternate_group(&self) -> u16 {
+        self.trak.tkhd.alternate_group
+    }
+
+    /// Returns the media duration from the mdhd, or zero if the mdhd marks
+    /// it as unknown.
     pub fn duration(&self) -> Duration {
+        if self.trak.mdia.mdhd.is_duration_unknown() {
+            return Duration::ZERO;
+        }
         Duration::from_micros(
             self.trak.mdia.mdhd.duration * 1_000_000 / self.trak.mdia.mdhd.timescale as u64,
         )
     }
 
+    /// Returns how long a player presents the track, which is the sum of the
+    /// edit list segment durations, including empty edits that delay the
+    /// start and dwells that hold a frame. Without an edit list this is the
+    /// media duration.
+    pub fn presentation_duration(&self, movie_timescale: u32) -> Duration {
+        let elst = match self.trak.edts.as_ref().and_then(|edts| edts.elst.as_ref()) {
+            Some(elst) if movie_timescale > 0 && !elst.entries.is_empty() => elst,
+            _ => return self.duration(),
+        };
+        let total: u64 = elst.entries.iter().map(|entry| entry.segment_duration).sum();
+        Duration::from_micros(total * 1_000_000 / movie_timescale as u64)
+    }
+
+    /// Returns the tkhd duration implied by the rest of the track, in the
+    /// movie timescale: the sum of the edit list segment durations, or else
+    /// the mdhd duration converted from the media timescale. Returns `None`
+    /// if either timescale is zero or the mdhd duration is unknown.
+    pub fn expected_tkhd_duration(&self, movie_timescale: u32) -> Option<u64> {
+        if let Some(elst) = self.trak.edts.as_ref().and_then(|edts| edts.elst.as_ref()) {
+            if !elst.entries.is_empty() {
+                return Some(elst.entries.iter().map(|entry| entry.segment_duration).sum());
+            }
+        }
+        let media_timescale = self.trak.mdia.mdhd.timescale as u128;
+        if movie_timescale == 0 || media_timescale == 0 || self.trak.mdia.mdhd.is_duration_unknown() {
+            return None;
+        }
+        let duration = self.trak.mdia.mdhd.duration as u128 * movie_timescale as u128 / media_timescale;
+        u64::try_from(duration).ok()
+    }
+
+    /// Returns the media time, in the media timescale, presented at
+    /// `presentation_time` in the movie timescale, following the edit list.
+    /// Media time stands still during a dwell, and advances at the edit's
+    /// rate otherwise. Returns `None` during an empty edit or past the end
+    /// of the edit list. Without an edit list the two times are the same,
+    /// up to the timescale conversion.
+    pub fn media_time_at(&self, presentation_time: u64, movie_timescale: u32) -> Option<u64> {
+        let media_timescale = self.trak.mdia.mdhd.timescale as u64;
+        if movie_timescale == 0 || media_timescale == 0 {
+            return None;
+        }
+        let to_media = |time: u64| time * media_timescale / movie_timescale as u64;
+        let elst = match self.trak.edts.as_ref().and_then(|edts| edts.elst.as_ref()) {
+            Some(elst) if !elst.entries.is_empty() => elst,
+            _ => return Some(to_media(presentation_time)),
+        };
+
+        let mut elapsed = 0;
+        for entry in elst.entries.iter() {
+            if presentation_time < elapsed + entry.segment_duration {
+                let media_time = edit_media_time(elst.version, entry);
+                if media_time < 0 {
+                    return None;
+                }
+                if entry.is_dwell() {
+                    return Some(media_time as u64);
+                }
+                // The rate is a 16.16 fixed point value.
+                let rate = ((entry.media_rate as i16 as i64) << 16) | entry.media_rate_fraction as i64;
+                let offset = (to_media(presentation_time - elapsed) as i64 * rate) >> 16;
+                return u64::try_from(media_time + offset).ok();
+            }
+            elapsed += entry.segment_duration;
+        }
+        None
+    }
+
+    /// Check the edit list for negative media times, edits whose media
+    /// overlaps that of the previous edit, and edits that extend past the
+    /// end of the media. Dwells consume no media, so they are only checked
+    /// for a negative media time. Returns no issues if the track has no edit
+    /// list.
+    pub fn validate(&self, movie_timescale: u32) -> Vec<EditListIssue> {
+        let mut issues = Vec::new();
+        let elst = match self.trak.edts.as_ref().and_then(|edts| edts.elst.as_ref()) {
+            Some(elst) => elst,
+            None => return issues,
+        };
+        let media_timescale = self.trak.mdia.mdhd.timescale as u64;
+        if movie_timescale == 0 || media_timescale == 0 {
+            return issues;
+        }
+        let media_duration = self.trak.mdia.mdhd.duration;
+
+        let mut previous: Option<(usize, u64)> = None;
+        let mut total = 0;
+        for (i, entry) in elst.entries.iter().enumerate() {
+            let media_time = edit_media_time(elst.version, entry);
+            if media_time == -1 {
+                continue;
+            }
+            if media_time < 0 {
+                issues.push(EditListIssue::NegativeMediaTime(i));
+                continue;
+            }
+            if entry.is_dwell() {
+                continue;
+            }
+
+            let media_time = media_time as u64;
+            let duration = entry.segment_duration * media_timescale / movie_timescale as u64;
+            if let Some((prev, prev_end)) = previous {
+                if media_time < prev_end {
+                    issues.push(EditListIssue::Overlapping(prev, i));
+                }
+            }
+            previous = Some((i, media_time + duration));
+
+            let exceeded = total > media_duration;
+            total += duration;
+            if !exceeded && total > media_duration {
+                issues.push(EditListIssue::ExceedsTrackDuration(i));
+            }
+        }
+        issues
+    }
+
+    /// Rewrite the sample timing to a constant frame rate of `fps`. Every
+    /// sample gets the same stts delta, up to rounding when the timescale
+    /// isn't a multiple of the rate, and the ctts offsets are recomputed so
+    /// the presentation order is kept. Only the timing changes: no sample is
+    /// added or removed, so variable-rate sections play faster or slower.
+    /// The media times and durations of the edit list, if any, are scaled
+    /// by the ratio of the new to the old media duration, which keeps them
+    /// exact only if the old rate was constant; empty edits are kept as is.
+    pub fn force_cfr(&mut self, fps: Ratio<u32>, movie_timescale: u32) -> Result<()> {
+        if !self.trafs.is_empty() {
+            return Err(Error::InvalidData("cannot retime a fragmented track"));
+        }
+        if *fps.numer() == 0 || *fps.denom() == 0 {
+            return Err(Error::InvalidData("frame rate must be positive"));
+        }
+        let timescale = self.timescale() as u64;
+        if timescale == 0 {
+            return Err(Error::InvalidData("track timescale must be positive"));
+        }
+        let sample_count = self.sample_count();
+        let (numer, denom) = (*fps.numer() as u64, *fps.denom() as u64);
+        let time = |i: u64| {
+            i.checked_mul(timescale)
+                .and_then(|t| t.checked_mul(denom))
+                .map(|t| t / numer)
+                .ok_or(Error::InvalidData("frame time overflows"))
+        };
+        let old_duration: u64 = self.trak.mdia.minf.stbl.stts.entries.iter()
+            .map(|entry| entry.sample_count as u64 * entry.sample_delta as u64)
+            .sum();
+
+        // Presentation order of each sample under the old timing.
+        let ranks = if self.trak.mdia.minf.stbl.ctts.is_some() {
+            let mut order = Vec::with_capacity(sample_count as usize);
+            for sample_id in 1..=sample_count {
+                let (start_time, _) = self.sample_time(sample_id)?;
+                let offset = self.sample_rendering_offset(sample_id) as i64;
+                order.push((start_time as i64 + offset, sample_id));
+            }
+            order.sort();
+            let mut ranks = vec![0; sample_count as usize];
+            for (rank, (_, sample_id)) in order.iter().enumerate() {
+                ranks[*sample_id as usize - 1] = rank as u64;
+            }
+            Some(ranks)
+        } else {
+            None
+        };
+
+        let stbl = &mut self.trak.mdia.minf.stbl;
+        stbl.stts.entries.clear();
+        for i in 0..sample_count as u64 {
+            let delta = u32::try_from(time(i + 1)? - time(i)?)
+                .map_err(|_| Error::InvalidData("frame duration too large"))?;
+            match stbl.stts.entries.last_mut() {
+                Some(entry) if entry.sample_delta == delta => entry.sample_count += 1,
+                _ => stbl.stts.entries.push(SttsEntry {
+                    sample_count: 1,
+                    sample_delta: delta,
+                }),
+            }
+        }
+
+        if let (Some(ctts), Some(ranks)) = (stbl.ctts.as_mut(), ranks) {
+            // Delay presentation by the deepest reordering so that no sample
+            // is presented before it is decoded.
+            let delay = ranks.iter()
+                .enumerate()
+                .map(|(i, rank)| (i as u64).saturating_sub(*rank))
+                .max()
+                .unwrap_or(0);
+            ctts.entries.clear();
+            for (i, rank) in ranks.iter().enumerate() {
+                let offset = time(rank + delay)? as i64 - time(i as u64)? as i64;
+                match ctts.entries.last_mut() {
+                    Some(entry) if entry.sample_offset == offset => entry.sample_count += 1,
+                    _ => ctts.entries.push(CttsEntry {
+                        sample_count: 1,
+                        sample_offset: offset,
+                    }),
+                }
+            }
+        }
+
+        let new_duration = time(sample_count as u64)?;
+        if let Some(elst) = self.trak.edts.as_mut().and_then(|edts| edts.elst.as_mut()) {
+            if old_duration > 0 {
+                let scale = |value: u64| {
+                    u64::try_from(value as u128 * new_duration as u128 / old_duration as u128)
+                        .map_err(|_| Error::InvalidData("edit duration too large"))
+                };
+                let version = elst.version;
+                for entry in elst.entries.iter_mut() {
+                    if edit_media_time(version, entry) < 0 {
+                        continue;
+                    }
+                    entry.media_time = scale(entry.media_time)?;
+                    entry.segment_duration = scale(entry.segment_duration)?;
+                }
+            }
+        }
+
+        self.trak.mdia.mdhd.duration = new_duration;
+        self.trak.tkhd.duration = (new_duration as u128 * movie_timescale as u128
+            / timescale as u128) as u64;
+        Ok(())
+    }
+
+    /// Rewrite the sample tables to hold only the sync samples, each lasting
+    /// until the next one so that the track keeps its duration. The first
+    /// kept sample also takes the time of any samples before it, and so
+    /// starts the track at 0. Every kept sample gets a chunk of its own,
+    /// whose offset is left for the caller to fill in. Returns the source
+    /// offset and size of each kept sample.
+    pub(crate) fn retain_sync_samples(&mut self) -> Result<Vec<(u64, u32)>> {
+        if !self.trafs.is_empty() {
+            return Err(Error::InvalidData("cannot rewrite a fragmented track"));
+        }
+        let total_duration: u64 = self.trak.mdia.minf.stbl.stts.entries.iter()
+            .map(|entry| entry.sample_count as u64 * entry.sample_delta as u64)
+            .sum();
+
+        let sample_ids = self.sync_samples();
+        let mut times = Vec::with_capacity(sample_ids.len() + 1);
+        for sample_id in sample_ids.iter() {
+            times.push(self.sample_time(*sample_id)?.0);
+        }
+        if let Some(first) = times.first_mut() {
+            *first = 0;
+        }
+        times.push(total_duration);
+        let durations: Vec<u64> = times.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        self.rebuild_sample_tables(&sample_ids, &durations)
+    }
+
+    /// Rewrite the sample tables to hold only the samples in `sample_ids`,
+    /// keeping their timing. The mdhd and tkhd durations shrink to match
+    /// and the edit list is dropped. As with `retain_sync_samples`, every
+    /// kept sample gets a chunk of its own.
+    pub(crate) fn retain_samples(
+        &mut self,
+        sample_ids: Range<u32>,
+        movie_timescale: u32,
+    ) -> Result<Vec<(u64, u32)>> {
+        if !self.trafs.is_empty() {
+            return Err(Error::InvalidData("cannot rewrite a fragmented track"));
+        }
+        let sample_ids: Vec<u32> = sample_ids.collect();
+        let mut durations = Vec::with_capacity(sample_ids.len());
+        for sample_id in sample_ids.iter() {
+            durations.push(self.sample_time(*sample_id)?.1 as u64);
+        }
+        let samples = self.rebuild_sample_tables(&sample_ids, &durations)?;
+
+        let duration: u64 = durations.iter().sum();
+        self.trak.mdia.mdhd.duration = duration;
+        self.trak.tkhd.duration = duration * movie_timescale as u64 / self.timescale().max(1) as u64;
+        self.trak.edts = None;
+        Ok(samples)
+    }
+
+    // Rebuild the sample tables around `sample_ids` with the given stts
+    // deltas, one sample per chunk, keeping the sizes, composition offsets
+    // and sync flags. Returns the source offset and size of each sample.
+    fn rebuild_sample_tables(&mut self, sample_ids: &[u32], durations: &[u64]) -> Result<Vec<(u64, u32)>> {
+        let mut samples = Vec::with_capacity(sample_ids.len());
+        let mut rendering_offsets = Vec::with_capacity(sample_ids.len());
+        let mut description_indexes = Vec::with_capacity(sample_ids.len());
+        let mut sync_samples = Vec::new();
+        for (i, sample_id) in sample_ids.iter().enumerate() {
+            samples.push((self.sample_offset(*sample_id)?, self.sample_size(*sample_id)?));
+            rendering_offsets.push(self.sample_rendering_offset(*sample_id) as i64);
+            description_indexes.push(self.sample_description_index(*sample_id)?);
+            if self.is_sync_sample(*sample_id) {
+                sync_samples.push(i as u32 + 1);
+            }
+        }
+
+        let stbl = &mut self.trak.mdia.minf.stbl;
+        stbl.stts.entries.clear();
+        for duration in durations {
+            let delta = u32::try_from(*duration)
+                .map_err(|_| Error::InvalidData("sample duration too large"))?;
+            match stbl.stts.entries.last_mut() {
+                Some(entry) if entry.sample_delta == delta => entry.sample_count += 1,
+                _ => stbl.stts.entries.push(SttsEntry {
+                    sample_count: 1,
+                    sample_delta: delta,
+                }),
+            }
+        }
+        if let Some(ref mut ctts) = stbl.ctts {
+            ctts.entries.clear();
+            for offset in rendering_offsets {
+                match ctts.entries.last_mut() {
+                    Some(entry) if entry.sample_offset == offset => entry.sample_count += 1,
+                    _ => ctts.entries.push(CttsEntry {
+                        sample_count: 1,
+                        sample_offset: offset,
+                    }),
+                }
+            }
+        }
+
+        stbl.stsc.entries.clear();
+        for (i, sample_description_index) in description_indexes.into_iter().enumerate() {
+            match stbl.stsc.entries.last() {
+                Some(entry) if entry.sample_description_index == sample_description_index => {}
+                _ => stbl.stsc.entries.push(StscEntry {
+                    first_chunk: i as u32 + 1,
+                    samples_per_chunk: 1,
+                    sample_description_index,
+                    first_sample: i as u32 + 1,
+                }),
+            }
+        }
+        stbl.stsz.sample_size = 0;
+        stbl.stsz.sample_count = samples.len() as u32;
+        stbl.stsz.sample_sizes = samples.iter().map(|(_, size)| *size).collect();
+
+        stbl.stss = if sync_samples.len() == samples.len() {
+            None
+        } else {
+            Some(StssBox {
+                entries: sync_samples,
+                ..StssBox::default()
+            })
+        };
+        // The per-sample tables no longer line up.
+        stbl.padb = None;
+        stbl.sbgps.clear();
+        stbl.unknown_boxes.clear();
+        Ok(samples)
+    }
+
     pub fn bitrate(&self) -> u32 {
         if let Some(ref mp4a) = self.trak.mdia.minf.stbl.stsd.mp4a {
             if let Some(ref esds) = mp4a.esds {
@@ -250,6 +924,212 @@ impl Mp4Track {
         }
     }
 
+    /// Returns the number of video frames, which is the sample count of a
+    /// video track and 0 for any other track.
+    pub fn frame_count(&self) -> u32 {
+        match self.track_type() {
+            Ok(TrackType::Video) => self.sample_count(),
+            _ => 0,
+        }
+    }
+
+    /// Returns the number of groups of pictures, one per sync sample. Only
+    /// fragmented tracks need to look at the samples themselves.
+    pub fn gop_count(&self) -> u32 {
+        if !self.trafs.is_empty() || self.trak.mdia.minf.stbl.stss.is_some() {
+            return self.sync_samples().len() as u32;
+        }
+        self.sample_count()
+    }
+
+    /// Returns the decode and presentation time of every sample, combining
+    /// stts, ctts and cslg, or for fragmented tracks the tfdt and trun of
+    /// each fragment. A fragment without a tfdt starts where the previous
+    /// one ended.
+    pub fn timing_table(&self) -> Vec<SampleTiming> {
+        let mut table = Vec::with_capacity(self.sample_count() as usize);
+        if !self.trafs.is_empty() {
+            let mut dts = 0;
+            for traf in self.trafs.iter() {
+                if let Some(ref tfdt) = traf.tfdt {
+                    dts = tfdt.base_media_decode_time;
+                }
+                let sample_count = traf.trun.as_ref().map_or(0, |trun| trun.sample_count);
+                for index in 0..sample_count as usize {
+                    let duration = traf.sample_duration(index, self.default_sample_duration)
+                        .unwrap_or(0);
+                    let offset = traf.sample_composition_offset(index).unwrap_or(0);
+                    table.push(SampleTiming {
+                        dts,
+                        pts: dts as i64 + offset,
+                        duration,
+                    });
+                    dts += duration as u64;
+                }
+            }
+            return table;
+        }
+
+        let stbl = &self.trak.mdia.minf.stbl;
+        let shift = stbl.cslg.as_ref().map_or(0, |cslg| cslg.composition_to_dts_shift);
+        let mut offsets = stbl.ctts.iter()
+            .flat_map(|ctts| ctts.entries.iter())
+            .flat_map(|entry| (0..entry.sample_count).map(move |_| entry.sample_offset));
+        let mut dts = 0;
+        for entry in stbl.stts.entries.iter() {
+            for _ in 0..entry.sample_count {
+                let offset = offsets.next().unwrap_or(0);
+                table.push(SampleTiming {
+                    dts,
+                    pts: dts as i64 + offset + shift,
+                    duration: entry.sample_delta,
+                });
+                dts += entry.sample_delta as u64;
+            }
+        }
+        table
+    }
+
+    // The sample durations of the fragments as stts would hold them, one
+    // entry per run of equal durations.
+    fn fragment_stts_entries(&self) -> Vec<SttsEntry> {
+        let mut entries: Vec<SttsEntry> = Vec::new();
+        for traf in self.trafs.iter() {
+            let sample_count = traf.trun.as_ref().map_or(0, |trun| trun.sample_count);
+            for index in 0..sample_count as usize {
+                let sample_delta = traf.sample_duration(index, self.default_sample_duration)
+                    .unwrap_or(0);
+                match entries.last_mut() {
+                    Some(entry) if entry.sample_delta == sample_delta => entry.sample_count += 1,
+                    _ => entries.push(SttsEntry {
+                        sample_count: 1,
+                        sample_delta,
+                    }),
+                }
+            }
+        }
+        entries
+    }
+
+    /// Returns true if moov/mvex has a trex for this track, without which
+    /// fragments of the track can't be read. See [`Mp4Reader::ensure_mvex`].
+    pub fn is_fragmented_compatible(&self) -> bool {
+        self.has_trex
+    }
+
+    // A trex whose defaults match the first samples of the track. The
+    // default flags are those of the second sample, as the first is
+    // usually the only sync sample of a fragment.
+    pub(crate) fn default_trex(&self) -> Result<TrexBox> {
+        let mut trex = TrexBox {
+            track_id: self.track_id(),
+            default_sample_description_index: 1,
+            default_sample_flags: SAMPLE_FLAGS_SYNC,
+            ..TrexBox::default()
+        };
+        let sample_count = self.sample_count();
+        if sample_count > 0 {
+            trex.default_sample_description_index = self.sample_description_index(1)?;
+            trex.default_sample_duration = self.sample_time(1)?.1;
+            trex.default_sample_size = self.sample_size(1)?;
+            if !self.is_sync_sample(sample_count.min(2)) {
+                trex.default_sample_flags = SAMPLE_FLAGS_NON_SYNC;
+            }
+        }
+        Ok(trex)
+    }
+
+    /// Returns the sample whose decode time span holds `time`, in the track
+    /// timescale, or `None` past the end of the track. Samples with a zero
+    /// duration span no time, so a seek onto their decode time picks the
+    /// sample that follows them. Fragments are timed as in
+    /// [Mp4Track::timing_table], and a time in a gap before a fragment picks
+    /// its first sample.
+    pub fn sample_at_time(&self, time: u64) -> Option<u32> {
+        if !self.trafs.is_empty() {
+            let mut sample_id: u64 = 1;
+            let mut dts = 0;
+            for traf in self.trafs.iter() {
+                if let Some(ref tfdt) = traf.tfdt {
+                    dts = tfdt.base_media_decode_time;
+                }
+                let sample_count = traf.trun.as_ref().map_or(0, |trun| trun.sample_count);
+                for index in 0..sample_count as usize {
+                    let duration = traf.sample_duration(index, self.default_sample_duration)
+                        .unwrap_or(0) as u64;
+                    dts = dts.saturating_add(duration);
+                    if time < dts {
+                        return u32::try_from(sample_id).ok();
+                    }
+                    sample_id += 1;
+                }
+            }
+            return None;
+        }
+
+        let mut sample_id = 1;
+        let mut elapsed = 0;
+        for entry in self.trak.mdia.minf.stbl.stts.entries.iter() {
+            let span = entry.sample_count as u64 * entry.sample_delta as u64;
+            if entry.sample_delta > 0 && time < elapsed + span {
+                let index = (time - elapsed) / entry.sample_delta as u64;
+                return u32::try_from(sample_id + index).ok();
+            }
+            sample_id += entry.sample_count as u64;
+            elapsed += span;
+        }
+        None
+    }
+
+    /// Returns the number of samples between consecutive sync samples.
+    /// Tracks without an stss box are all sync samples, so every interval
+    /// is 1.
+    pub fn keyframe_intervals(&self) -> Vec<u32> {
+        self.sync_samples()
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect()
+    }
+
+    /// Returns the largest group of pictures, counted in samples from a sync
+    /// sample up to the next one or to the end of the track.
+    pub fn max_gop_size(&self) -> u32 {
+        let sync_samples = self.sync_samples();
+        let last_gop_size = match sync_samples.last() {
+            Some(last) => self.sample_count() + 1 - last,
+            None => return self.sample_count(),
+        };
+        self.keyframe_intervals()
+            .into_iter()
+            .fold(last_gop_size, cmp::max)
+    }
+
+    pub(crate) fn sync_samples(&self) -> Vec<u32> {
+        match self.trak.mdia.minf.stbl.stss {
+            Some(ref stss) if self.trafs.is_empty() => {
+                // The stss must be sorted, but a broken muxer may repeat or
+                // reorder its entries, or list samples the track doesn't have.
+                let sample_count = self.sample_count();
+                let mut entries: Vec<u32> = stss.entries.iter()
+                    .copied()
+                    .filter(|sample_id| (1..=sample_count).contains(sample_id))
+                    .collect();
+                entries.sort_unstable();
+                entries.dedup();
+                entries
+            }
+            _ => (1..=self.sample_count())
+                .filter(|sample_id| self.is_sync_sample(*sample_id))
+                .collect(),
+        }
+    }
+
+    /// Returns the number of padding bits at the end of a sample from the
+    /// padb box, if the track has one.
+    pub fn sample_padding_bits(&self, sample_id: u32) -> Option<u8> {
+        self.trak.mdia.minf.stbl.padb.as_ref()?.sample_padding(sample_id)
+    }
+
     pub fn video_profile(&self) -> Result<AvcProfile> {
         if let Some(ref avc1) = self.trak.mdia.minf.stbl.stsd.avc1 {
             AvcProfile::try_from((
@@ -292,14 +1172,40 @@ impl Mp4Track {
     }
 
     pub fn audio_profile(&self) -> Result<AudioObjectType> {
-        if let Some(ref mp4a) = self.trak.mdia.minf.stbl.stsd.mp4a {
-            if let Some(ref esds) = mp4a.esds {
-                AudioObjectType::try_from(esds.es_desc.dec_config.dec_specific.profile)
-            } else {
-                Err(Error::BoxInStblNotFound(self.track_id(), BoxType::EsdsBox))
+        AudioObjectType::try_from(self.audio_specific_config()?.audio_object_type())
+    }
+
+    /// Returns the channel layout from the chnl box of the audio sample
+    /// entry, if there is one.
+    pub fn channel_layout(&self) -> Option<&ChnlBox> {
+        self.trak.mdia.minf.stbl.stsd.mp4a.as_ref()?.chnl.as_ref()
+    }
+
+    /// Returns the codec configuration ("extradata") of the sample entry in
+    /// the form transmuxers usually expect:
+    ///
+    /// - AVC: the avcC payload (AVCDecoderConfigurationRecord)
+    /// - HEVC: the hvcC payload (HEVCDecoderConfigurationRecord)
+    /// - VP9: the vpcC payload, starting with its version and flags
+    /// - AV1: the av1C payload (AV1CodecConfigurationRecord)
+    /// - AAC: the AudioSpecificConfig from the esds DecoderSpecificInfo
+    ///
+    /// Returns `None` for other sample entries, or if an mp4a has no esds or
+    /// holds MP3.
+    pub fn codec_private(&self) -> Option<Vec<u8>> {
+        match self.sample_entry() {
+            SampleEntry::Avc1(avc1) => box_payload(&avc1.avcc),
+            SampleEntry::Hev1(hev1) => box_payload(&hev1.hvcc),
+            SampleEntry::Vp09(vp09) => box_payload(&vp09.vpcc),
+            SampleEntry::Av01(av01) => box_payload(&av01.av1c),
+            SampleEntry::Mp4a(mp4a) => {
+                let dec_config = &mp4a.esds.as_ref()?.es_desc.dec_config;
+                if dec_config.is_mp3() {
+                    return None;
+                }
+                Some(dec_config.dec_specific.config_bytes())
             }
-        } else {
-            Err(Error::BoxInStblNotFound(self.track_id(), BoxType::Mp4aBox))
+            _ => None,
         }
     }
 
@@ -319,32 +1225,104 @@ impl Mp4Track {
         Ok(self.trak.mdia.minf.stbl.stsc.entries.len() - 1)
     }
 
-    fn chunk_offset(&self, chunk_id: u32) -> Result<u64> {
-        if self.trak.mdia.minf.stbl.stco.is_none() && self.trak.mdia.minf.stbl.co64.is_none() {
-            return Err(Error::InvalidData("must have either stco or co64 boxes"));
+    /// Returns the 1-based index of the stsd entry that describes a sample:
+    /// that of the stsc entry covering its chunk, or for a fragmented track
+    /// that of the tfhd, else the trex default. Only the first stsd entry is
+    /// parsed, so samples with another index can't be decoded with
+    /// [`sample_entry`](Self::sample_entry).
+    pub fn sample_description_index(&self, sample_id: u32) -> Result<u32> {
+        if !self.trafs.is_empty() {
+            let (traf_idx, _) = self.find_traf_idx_and_sample_idx(sample_id).ok_or(
+                Error::EntryInTrunNotFound(self.track_id(), BoxType::TrunBox, sample_id),
+            )?;
+            return Ok(self.trafs[traf_idx].tfhd.sample_description_index
+                .unwrap_or(self.default_sample_description_index));
         }
-        if let Some(ref stco) = self.trak.mdia.minf.stbl.stco {
-            if let Some(offset) = stco.entries.get(chunk_id as usize - 1) {
-                return Ok(*offset as u64);
-            } else {
-                return Err(Error::EntryInStblNotFound(
-                    self.track_id(),
-                    BoxType::StcoBox,
-                    chunk_id,
-                ));
+        if sample_id == 0 || sample_id > self.sample_count() {
+            return Err(Error::EntryInStblNotFound(self.track_id(), BoxType::StszBox, sample_id));
+        }
+        let stsc_index = self.stsc_index(sample_id)?;
+        Ok(self.trak.mdia.minf.stbl.stsc.entries[stsc_index].sample_description_index)
+    }
+
+    /// Returns the chunks of the track with the samples each one holds, as
+    /// described by the stsc and stco/co64 boxes. The last stsc entry runs
+    /// up to the last chunk. Samples listed beyond the stsz sample count are
+    /// dropped.
+    pub fn chunks(&self) -> Result<Vec<Chunk>> {
+        let stbl = &self.trak.mdia.minf.stbl;
+        let offsets = stbl.chunk_offsets()
+            .ok_or(Error::Box2NotFound(BoxType::StcoBox, BoxType::Co64Box))?;
+        let sample_end = stbl.stsz.sample_count + 1;
+
+        let mut chunks = Vec::with_capacity(offsets.len());
+        let mut sample_id = 1;
+        for (i, entry) in stbl.stsc.entries.iter().enumerate() {
+            let last_chunk = match stbl.stsc.entries.get(i + 1) {
+                Some(next) => next.first_chunk.saturating_sub(1),
+                None => offsets.len() as u32,
+            };
+            for chunk_id in entry.first_chunk..=last_chunk {
+                let offset = match chunk_id.checked_sub(1).and_then(|i| offsets.get(i as usize)) {
+                    Some(offset) => *offset,
+                    None => {
+                        return Err(Error::EntryInStblNotFound(
+                            self.track_id(),
+                            BoxType::StcoBox,
+                            chunk_id,
+                        ))
+                    }
+                };
+                let end = cmp::min(sample_id + entry.samples_per_chunk, sample_end);
+                chunks.push(Chunk {
+                    offset,
+                    sample_ids: sample_id..end,
+                    sample_description_index: entry.sample_description_index,
+                });
+                sample_id = end;
             }
-        } else if let Some(ref co64) = self.trak.mdia.minf.stbl.co64 {
-            if let Some(offset) = co64.entries.get(chunk_id as usize - 1) {
-                return Ok(*offset);
-            } else {
-                return Err(Error::EntryInStblNotFound(
-                    self.track_id(),
-                    BoxType::Co64Box,
-                    chunk_id,
-                ));
+        }
+        Ok(chunks)
+    }
+
+    /// Returns the total size of the samples in each chunk, in chunk order.
+    pub(crate) fn chunk_sizes(&self) -> Result<Vec<u64>> {
+        let stbl = &self.trak.mdia.minf.stbl;
+        let chunk_count = stbl.chunk_count()
+            .ok_or(Error::Box2NotFound(BoxType::StcoBox, BoxType::Co64Box))?;
+
+        let mut chunk_sizes = Vec::with_capacity(chunk_count as usize);
+        let mut sample_id = 1;
+        for (i, entry) in stbl.stsc.entries.iter().enumerate() {
+            let last_chunk = match stbl.stsc.entries.get(i + 1) {
+                Some(next) => next.first_chunk.saturating_sub(1),
+                None => chunk_count,
+            };
+            for _ in entry.first_chunk..=last_chunk {
+                let mut chunk_size = 0;
+                for _ in 0..entry.samples_per_chunk {
+                    chunk_size += self.sample_size(sample_id)? as u64;
+                    sample_id += 1;
+                }
+                chunk_sizes.push(chunk_size);
             }
         }
-        return Err(Error::Box2NotFound(BoxType::StcoBox, BoxType::Co64Box));
+        Ok(chunk_sizes)
+    }
+
+    fn chunk_offset(&self, chunk_id: u32) -> Result<u64> {
+        let stbl = &self.trak.mdia.minf.stbl;
+        if stbl.stco.is_none() && stbl.co64.is_none() {
+            return Err(Error::InvalidData("must have either stco or co64 boxes"));
+        }
+        stbl.chunk_offset(chunk_id).ok_or_else(|| {
+            let box_type = if stbl.stco.is_some() {
+                BoxType::StcoBox
+            } else {
+                BoxType::Co64Box
+            };
+            Error::EntryInStblNotFound(self.track_id(), box_type, chunk_id)
+        })
     }
 
     fn ctts_index(&self, sample_id: u32) -> Result<(usize, u32)> {
@@ -383,8 +1361,8 @@ impl Mp4Track {
     fn sample_size(&self, sample_id: u32) -> Result<u32> {
         if self.trafs.len() > 0 {
             if let Some((traf_idx, sample_idx)) = self.find_traf_idx_and_sample_idx(sample_id) {
-                if let Some(size) = self.trafs[traf_idx].trun.as_ref().unwrap().sample_sizes.get(sample_idx) {
-                    Ok(*size)
+                if let Some(size) = self.trafs[traf_idx].sample_size(sample_idx) {
+                    Ok(size)
                 } else {
                     Err(Error::EntryInTrunNotFound(
                         self.track_id(),
@@ -400,9 +1378,16 @@ impl Mp4Track {
             }
         } else {
             let stsz = &self.trak.mdia.minf.stbl.stsz;
-            if stsz.sample_size > 0 {
+            if stsz.sample_size > 0 && sample_id > 0 && sample_id <= stsz.sample_count {
                 return Ok(stsz.sample_size);
             }
+            if stsz.sample_size > 0 || sample_id == 0 {
+                return Err(Error::EntryInStblNotFound(
+                    self.track_id(),
+                    BoxType::StszBox,
+                    sample_id,
+                ));
+            }
             if let Some(size) = stsz.sample_sizes.get(sample_id as usize - 1) {
                 Ok(*size)
             } else {
@@ -428,10 +1413,17 @@ impl Mp4Track {
         }
     }
 
-    fn sample_offset(&self, sample_id: u32) -> Result<u64> {
+    /// Returns the absolute file offset of a sample, for both progressive
+    /// (stsc/stco) and fragmented (moof/trun) files.
+    pub fn sample_offset(&self, sample_id: u32) -> Result<u64> {
         if self.trafs.len() > 0 {
-            if let Some((traf_idx, _sample_idx)) = self.find_traf_idx_and_sample_idx(sample_id) {
-                Ok(self.trafs[traf_idx].tfhd.base_data_offset as u64)
+            if let Some((traf_idx, sample_idx)) = self.find_traf_idx_and_sample_idx(sample_id) {
+                let traf = &self.trafs[traf_idx];
+                let mut sample_offset = self.traf_data_offsets[traf_idx];
+                for i in 0..sample_idx {
+                    sample_offset += traf.sample_size(i).unwrap_or(0) as u64;
+                }
+                Ok(sample_offset)
             } else {
                 Err(Error::BoxInTrafNotFound(
                     self.track_id(),
@@ -463,7 +1455,7 @@ impl Mp4Track {
         }
     }
 
-    fn sample_time(&self, sample_id: u32) -> Result<(u64, u32)> {
+    pub(crate) fn sample_time(&self, sample_id: u32) -> Result<(u64, u32)> {
         let stts = &self.trak.mdia.minf.stbl.stts;
 
         let mut sample_count = 1;
@@ -493,10 +1485,19 @@ impl Mp4Track {
     }
 
     fn sample_rendering_offset(&self, sample_id: u32) -> i32 {
+        if !self.trafs.is_empty() {
+            return self.find_traf_idx_and_sample_idx(sample_id)
+                .and_then(|(traf_idx, sample_idx)| {
+                    self.trafs[traf_idx].sample_composition_offset(sample_idx)
+                })
+                .map(|offset| offset.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+                .unwrap_or(0);
+        }
         if let Some(ref ctts) = self.trak.mdia.minf.stbl.ctts {
             if let Ok((ctts_index, _)) = self.ctts_index(sample_id) {
                 let ctts_entry = ctts.entries.get(ctts_index).unwrap();
-                return ctts_entry.sample_offset;
+                return ctts_entry.sample_offset
+                    .clamp(i32::MIN as i64, i32::MAX as i64) as i32;
             }
         }
         0
@@ -518,6 +1519,38 @@ impl Mp4Track {
         }
     }
 
+    /// Reads the first sync sample, or the first sample if there are none,
+    /// from `reader` for a decoder to render a poster frame. Returns `None`
+    /// if the track has no samples.
+    pub fn thumbnail<R: Read + Seek>(&self, reader: &mut R) -> Result<Option<Thumbnail>> {
+        let sample_id = match self.sync_samples().first() {
+            Some(sample_id) => *sample_id,
+            None if self.sample_count() > 0 => 1,
+            None => return Ok(None),
+        };
+        Ok(self.read_sample(reader, sample_id)?.map(|sample| Thumbnail {
+            sample_id,
+            sample,
+            codec_private: self.codec_private(),
+        }))
+    }
+
+    /// Reads the data of every sample in decode order from `reader`, which
+    /// must be positioned over the same file this track was parsed from.
+    ///
+    /// The whole track is held in memory at once, so this is meant for small
+    /// files and tests; use `Mp4Reader::read_sample` to stream larger ones.
+    pub fn read_all_samples<R: Read + Seek>(&self, reader: &mut R) -> Result<Vec<Vec<u8>>> {
+        let mut samples = Vec::with_capacity(self.sample_count() as usize);
+        for sample_id in 1..=self.sample_count() {
+            match self.read_sample(reader, sample_id)? {
+                Some(sample) => samples.push(sample.bytes.to_vec()),
+                None => break,
+            }
+        }
+        Ok(samples)
+    }
+
     pub(crate) fn read_sample<R: Read + Seek>(
         &self,
         reader: &mut R,
@@ -532,22 +1565,47 @@ impl Mp4Track {
 
         let mut buffer = vec![0x0u8; sample_size as usize];
         reader.seek(SeekFrom::Start(sample_offset))?;
-        reader.read_exact(&mut buffer)?;
+        reader.read_exact(&mut buffer).map_err(|err| match err.kind() {
+            // The file was truncated, e.g. by an interrupted download.
+            io::ErrorKind::UnexpectedEof => Error::UnexpectedEof(self.track_id(), sample_id),
+            _ => Error::IoError(err),
+        })?;
 
         let (start_time, duration) = self.sample_time(sample_id).unwrap(); // XXX
         let rendering_offset = self.sample_rendering_offset(sample_id);
         let is_sync = self.is_sync_sample(sample_id);
+        let sample_description_index = self.sample_description_index(sample_id)?;
 
         Ok(Some(Mp4Sample {
             start_time,
             duration,
             rendering_offset,
             is_sync,
+            sample_description_index,
             bytes: Bytes::from(buffer),
         }))
     }
 }
 
+// The signed media_time of an edit, where -1 marks an empty edit.
+fn edit_media_time(version: u8, entry: &ElstEntry) -> i64 {
+    if version == 1 {
+        entry.media_time as i64
+    } else {
+        entry.media_time as u32 as i32 as i64
+    }
+}
+
+// Serializes a configuration box and drops its 8-byte header.
+fn box_payload<B>(config: &B) -> Option<Vec<u8>>
+where
+    B: for<'a> WriteBox<&'a mut Vec<u8>>,
+{
+    let mut buf = Vec::new();
+    config.write_box(&mut buf).ok()?;
+    Some(buf.split_off(HEADER_SIZE as usize))
+}
+
 // TODO creation_time, modification_time
 #[derive(Debug, Default)]
 pub(crate) struct Mp4TrackWriter {
@@ -568,11 +1626,28 @@ impl Mp4TrackWriter {
     pub(crate) fn new(track_id: u32, config: &TrackConfig) -> Result<Self> {
         let mut trak = TrakBox::default();
         trak.tkhd.track_id = track_id;
+        trak.tkhd.layer = config.layer;
+        trak.tkhd.alternate_group = config.alternate_group;
         trak.mdia.mdhd.timescale = config.timescale;
         trak.mdia.mdhd.language = config.language.to_owned();
         trak.mdia.hdlr.handler_type = config.track_type.into();
         // XXX largesize
         trak.mdia.minf.stbl.stco = Some(StcoBox::default());
+
+        if !config.data_references.is_empty() {
+            trak.mdia.minf.dinf.dref.entries = config
+                .data_references
+                .iter()
+                .map(|location| DataEntryBox::Url(UrlBox::new(location)))
+                .collect();
+        }
+        let data_reference_index = config.data_reference_index;
+        if data_reference_index == 0
+            || data_reference_index as usize > trak.mdia.minf.dinf.dref.entries.len()
+        {
+            return Err(Error::InvalidData("data_reference_index out of range"));
+        }
+
         match config.media_conf {
             MediaConfig::AvcConfig(ref avc_config) => {
                 trak.tkhd.set_width(avc_config.width);
@@ -581,7 +1656,7 @@ impl Mp4TrackWriter {
                 let vmhd = VmhdBox::default();
                 trak.mdia.minf.vmhd = Some(vmhd);
 
-                let avc1 = Avc1Box::new(avc_config);
+                let avc1 = Avc1Box::new(avc_config, data_reference_index);
                 trak.mdia.minf.stbl.stsd.avc1 = Some(avc1);
             }
             MediaConfig::HevcConfig(ref hevc_config) => {
@@ -591,24 +1666,30 @@ impl Mp4TrackWriter {
                 let vmhd = VmhdBox::default();
                 trak.mdia.minf.vmhd = Some(vmhd);
 
-                let hev1 = Hev1Box::new(hevc_config);
+                let hev1 = Hev1Box::new(hevc_config, data_reference_index);
                 trak.mdia.minf.stbl.stsd.hev1 = Some(hev1);
             }
             MediaConfig::Vp9Config(ref config) => {
                 trak.tkhd.set_width(config.width);
                 trak.tkhd.set_height(config.height);
 
-                trak.mdia.minf.stbl.stsd.vp09 = Some(Vp09Box::new(config));
+                let vmhd = VmhdBox::default();
+                trak.mdia.minf.vmhd = Some(vmhd);
+
+                trak.mdia.minf.stbl.stsd.vp09 = Some(Vp09Box::new(config, data_reference_index));
             }
             MediaConfig::AacConfig(ref aac_config) => {
-                let smhd = SmhdBox::default();
+                let smhd = SmhdBox::new(config.balance);
                 trak.mdia.minf.smhd = Some(smhd);
 
-                let mp4a = Mp4aBox::new(aac_config);
+                let mp4a = Mp4aBox::new(aac_config, data_reference_index);
                 trak.mdia.minf.stbl.stsd.mp4a = Some(mp4a);
             }
             MediaConfig::TtxtConfig(ref _ttxt_config) => {
-                let tx3g = Tx3gBox::default();
+                let tx3g = Tx3gBox {
+                    data_reference_index,
+                    ..Tx3gBox::default()
+                };
                 trak.mdia.minf.stbl.stsd.tx3g = Some(tx3g);
             }
 
@@ -673,6 +1754,42 @@ impl Mp4TrackWriter {
         self.trak.mdia.minf.stbl.stts.entries.push(entry);
     }
 
+    /// Apply `policy` to the final sample if its duration is zero. Returns
+    /// the track duration in the movie timescale.
+    pub(crate) fn set_final_sample_duration(
+        &mut self,
+        policy: FinalSampleDuration,
+        movie_timescale: u32,
+    ) -> u64 {
+        let entries = &mut self.trak.mdia.minf.stbl.stts.entries;
+        let last = match entries.last() {
+            Some(last) if last.sample_delta == 0 => last.clone(),
+            _ => return self.trak.tkhd.duration,
+        };
+        let duration = match policy {
+            FinalSampleDuration::Unchanged => 0,
+            FinalSampleDuration::RepeatPrevious if last.sample_count == 1 && entries.len() >= 2 => {
+                entries[entries.len() - 2].sample_delta
+            }
+            FinalSampleDuration::RepeatPrevious => 0,
+            FinalSampleDuration::Explicit(duration) => duration,
+        };
+        if duration == 0 {
+            return self.trak.tkhd.duration;
+        }
+
+        // Split the final sample off its entry, or merge it with the
+        // previous entry if the durations match.
+        if last.sample_count > 1 {
+            entries.last_mut().unwrap().sample_count -= 1;
+        } else {
+            entries.pop();
+        }
+        self.update_sample_times(duration);
+        self.update_durations(duration, movie_timescale);
+        self.trak.tkhd.duration
+    }
+
     fn update_rendering_offsets(&mut self, offset: i32) {
         let ctts = if let Some(ref mut ctts) = self.trak.mdia.minf.stbl.ctts {
             ctts
@@ -693,7 +1810,7 @@ impl Mp4TrackWriter {
         };
 
         if let Some(ref mut entry) = ctts.entries.last_mut() {
-            if entry.sample_offset == offset {
+            if entry.sample_offset == offset as i64 {
                 entry.sample_count += 1;
                 return;
             }
@@ -701,7 +1818,7 @@ impl Mp4TrackWriter {
 
         let entry = CttsEntry {
             sample_count: 1,
-            sample_offset: offset,
+            sample_offset: offset as i64,
         };
         ctts.entries.push(entry);
     }
@@ -735,8 +1852,10 @@ impl Mp4TrackWriter {
 
     fn update_durations(&mut self, dur: u32, movie_timescale: u32) {
         self.trak.mdia.mdhd.duration += dur as u64;
-        self.trak.tkhd.duration +=
-            dur as u64 * movie_timescale as u64 / self.trak.mdia.mdhd.timescale as u64;
+        // Convert the total rather than each sample to avoid accumulating
+        // rounding errors.
+        self.trak.tkhd.duration = self.trak.mdia.mdhd.duration * movie_timescale as u64
+            / self.trak.mdia.mdhd.timescale as u64;
     }
 
     pub(crate) fn write_sample<W: Write + Seek>(
@@ -762,6 +1881,11 @@ impl Mp4TrackWriter {
         Ok(self.trak.tkhd.duration)
     }
 
+    /// Returns the size of the samples buffered for the next chunk.
+    pub(crate) fn pending_chunk_size(&self) -> u64 {
+        self.chunk_buffer.len() as u64
+    }
+
     // XXX largesize
     fn chunk_count(&self) -> u32 {
         let stco = self.trak.mdia.minf.stbl.stco.as_ref().unwrap();
@@ -835,3 +1959,397 @@ impl Mp4TrackWriter {
         Ok(self.trak.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp4box::cslg::CslgBox;
+    use crate::mp4box::co64::Co64Box;
+    use crate::mp4box::edts::EdtsBox;
+    use crate::mp4box::elst::ElstBox;
+    use crate::mp4box::tfdt::TfdtBox;
+    use crate::mp4box::trun::TrunBox;
+
+    // A trak in `timescale` whose samples, timed by `stts`, are 10 bytes each
+    // and all stored in one chunk at offset 100.
+    fn trak(timescale: u32, stts: Vec<SttsEntry>) -> TrakBox {
+        let mut trak = TrakBox::default();
+        trak.tkhd.track_id = 1;
+        trak.mdia.mdhd.timescale = timescale;
+        let stbl = &mut trak.mdia.minf.stbl;
+        let sample_count = stts.iter().map(|entry| entry.sample_count).sum();
+        stbl.stts.entries = stts;
+        stbl.stsz.sample_size = 10;
+        stbl.stsz.sample_count = sample_count;
+        if sample_count > 0 {
+            stbl.stsc.entries = vec![StscEntry {
+                first_chunk: 1,
+                samples_per_chunk: sample_count,
+                sample_description_index: 1,
+                first_sample: 1,
+            }];
+        }
+        stbl.stco = Some(StcoBox {
+            entries: vec![100],
+            ..StcoBox::default()
+        });
+        trak
+    }
+
+    fn edts(entries: Vec<ElstEntry>) -> Option<EdtsBox> {
+        Some(EdtsBox {
+            elst: Some(ElstBox {
+                version: 0,
+                flags: 0,
+                entries,
+            }),
+        })
+    }
+
+    fn edit(segment_duration: u64, media_time: u64, media_rate: u16) -> ElstEntry {
+        ElstEntry {
+            segment_duration,
+            media_time,
+            media_rate,
+            media_rate_fraction: 0,
+        }
+    }
+
+    #[test]
+    fn test_mp3_media_type() {
+        let mut trak = trak(1000, Vec::new());
+        let mut mp4a = Mp4aBox::default();
+        mp4a.esds.as_mut().unwrap().es_desc.dec_config.object_type_indication = 0x6B;
+        trak.mdia.minf.stbl.stsd.mp4a = Some(mp4a);
+
+        let track = Mp4Track::from(&trak);
+        assert_eq!(track.media_type().unwrap(), MediaType::MP3);
+        assert_eq!(track.media_type().unwrap().to_string(), "mp3");
+        assert!(track.audio_profile().is_err());
+        assert!(track.sample_freq_index().is_err());
+        assert_eq!(track.codec_private(), None);
+    }
+
+    #[test]
+    fn test_validate_edit_list() {
+        let mut trak = trak(48000, Vec::new());
+        trak.mdia.mdhd.duration = 96000;
+        trak.edts = edts(vec![
+            edit(500, 0xFFFF_FFFF, 1),
+            edit(1000, 0, 1),
+            edit(1000, 24000, 1),
+        ]);
+        let track = Mp4Track::from(&trak);
+        assert_eq!(track.validate(1000), vec![EditListIssue::Overlapping(1, 2)]);
+
+        trak.edts = edts(vec![
+            edit(1000, 0, 1),
+            edit(1500, 48000, 1),
+            edit(100, 0xFFFF_FFFE, 1),
+        ]);
+        let track = Mp4Track::from(&trak);
+        assert_eq!(
+            track.validate(1000),
+            vec![
+                EditListIssue::ExceedsTrackDuration(1),
+                EditListIssue::NegativeMediaTime(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dwell_edit() {
+        let mut trak = trak(48000, Vec::new());
+        trak.mdia.mdhd.duration = 96000;
+        // Play a second, hold the frame at 1s for half a second, then play
+        // on from there.
+        trak.edts = edts(vec![
+            edit(1000, 0, 1),
+            edit(500, 48000, 0),
+            edit(1000, 48000, 1),
+        ]);
+
+        let track = Mp4Track::from(&trak);
+        assert!(track.trak.edts.as_ref().unwrap().elst.as_ref().unwrap().entries[1].is_dwell());
+        assert!(track.validate(1000).is_empty());
+        assert_eq!(track.presentation_duration(1000), Duration::from_millis(2500));
+        assert_eq!(track.media_time_at(500, 1000), Some(24000));
+        assert_eq!(track.media_time_at(1000, 1000), Some(48000));
+        assert_eq!(track.media_time_at(1499, 1000), Some(48000));
+        assert_eq!(track.media_time_at(1500, 1000), Some(48000));
+        assert_eq!(track.media_time_at(2000, 1000), Some(72000));
+        assert_eq!(track.media_time_at(2500, 1000), None);
+    }
+
+    #[test]
+    fn test_presentation_duration() {
+        let mut trak = trak(90000, Vec::new());
+        trak.mdia.mdhd.duration = 900000;
+        let track = Mp4Track::from(&trak);
+        assert_eq!(track.presentation_duration(1000), Duration::from_secs(10));
+
+        // Skip the first second of media and stop after eight seconds.
+        trak.edts = edts(vec![edit(8000, 90000, 1)]);
+        let track = Mp4Track::from(&trak);
+        assert_eq!(track.duration(), Duration::from_secs(10));
+        assert_eq!(track.presentation_duration(1000), Duration::from_secs(8));
+    }
+
+    #[test]
+    fn test_retain_unsorted_sync_samples() {
+        let mut trak = trak(1000, vec![SttsEntry { sample_count: 4, sample_delta: 10 }]);
+        trak.mdia.minf.stbl.stss = Some(StssBox {
+            entries: vec![3, 1, 3],
+            ..StssBox::default()
+        });
+
+        let mut track = Mp4Track::from(&trak);
+        assert_eq!(track.retain_sync_samples().unwrap(), vec![(100, 10), (120, 10)]);
+        let stbl = &track.trak.mdia.minf.stbl;
+        assert_eq!(stbl.stts.entries, vec![SttsEntry { sample_count: 2, sample_delta: 20 }]);
+        assert_eq!(stbl.stss, None);
+    }
+
+    #[test]
+    fn test_retain_sync_samples_leading_non_sync() {
+        let mut trak = trak(1000, vec![SttsEntry { sample_count: 4, sample_delta: 10 }]);
+        trak.mdia.mdhd.duration = 40;
+        trak.mdia.minf.stbl.stss = Some(StssBox {
+            entries: vec![2, 4],
+            ..StssBox::default()
+        });
+
+        // The first sync sample starts the track, so its duration holds.
+        let mut track = Mp4Track::from(&trak);
+        assert_eq!(track.retain_sync_samples().unwrap(), vec![(110, 10), (130, 10)]);
+        assert_eq!(track.timing_table(), vec![
+            SampleTiming { dts: 0, pts: 0, duration: 30 },
+            SampleTiming { dts: 30, pts: 30, duration: 10 },
+        ]);
+        assert_eq!(track.trak.mdia.mdhd.duration, 40);
+    }
+
+    #[test]
+    fn test_chunks() {
+        let mut trak = trak(1000, vec![SttsEntry { sample_count: 8, sample_delta: 1 }]);
+        let stbl = &mut trak.mdia.minf.stbl;
+        stbl.stsc.entries = vec![
+            StscEntry {
+                first_chunk: 1,
+                samples_per_chunk: 3,
+                sample_description_index: 1,
+                first_sample: 1,
+            },
+            StscEntry {
+                first_chunk: 3,
+                samples_per_chunk: 1,
+                sample_description_index: 1,
+                first_sample: 7,
+            },
+        ];
+        stbl.stco = Some(StcoBox {
+            entries: vec![100, 200, 300, 400],
+            ..StcoBox::default()
+        });
+
+        let chunks = Mp4Track::from(&trak).chunks().unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                Chunk { offset: 100, sample_ids: 1..4, sample_description_index: 1 },
+                Chunk { offset: 200, sample_ids: 4..7, sample_description_index: 1 },
+                Chunk { offset: 300, sample_ids: 7..8, sample_description_index: 1 },
+                Chunk { offset: 400, sample_ids: 8..9, sample_description_index: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_co64_sample_offsets() {
+        let mut trak = trak(1000, vec![SttsEntry { sample_count: 4, sample_delta: 1 }]);
+        let stbl = &mut trak.mdia.minf.stbl;
+        stbl.stsc.entries[0].samples_per_chunk = 2;
+        stbl.stco = None;
+        stbl.co64 = Some(Co64Box {
+            entries: vec![64, u32::MAX as u64 + 100],
+            ..Co64Box::default()
+        });
+
+        let track = Mp4Track::from(&trak);
+        let stbl = &track.trak.mdia.minf.stbl;
+        assert_eq!(stbl.chunk_count(), Some(2));
+        assert_eq!(stbl.chunk_offset(2), Some(u32::MAX as u64 + 100));
+        assert_eq!(stbl.chunk_offset(3), None);
+        assert_eq!(track.sample_offset(2).unwrap(), 74);
+        assert_eq!(track.sample_offset(3).unwrap(), u32::MAX as u64 + 100);
+        assert_eq!(track.sample_offset(4).unwrap(), u32::MAX as u64 + 110);
+        assert_eq!(track.chunks().unwrap()[1].offset, u32::MAX as u64 + 100);
+    }
+
+    #[test]
+    fn test_stts_zero_delta() {
+        let trak = trak(1000, vec![
+            SttsEntry { sample_count: 2, sample_delta: 1000 },
+            SttsEntry { sample_count: 3, sample_delta: 0 },
+            SttsEntry { sample_count: 2, sample_delta: 1000 },
+        ]);
+
+        let track = Mp4Track::from(&trak);
+        let times: Vec<(u64, u32)> = (1..=7)
+            .map(|sample_id| track.sample_time(sample_id).unwrap())
+            .collect();
+        assert_eq!(times, vec![
+            (0, 1000), (1000, 1000),
+            (2000, 0), (2000, 0), (2000, 0),
+            (2000, 1000), (3000, 1000),
+        ]);
+
+        assert_eq!(track.sample_at_time(0), Some(1));
+        assert_eq!(track.sample_at_time(1999), Some(2));
+        assert_eq!(track.sample_at_time(2000), Some(6));
+        assert_eq!(track.sample_at_time(3999), Some(7));
+        assert_eq!(track.sample_at_time(4000), None);
+    }
+
+    #[test]
+    fn test_timing_table() {
+        let mut trak = trak(1000, vec![SttsEntry { sample_count: 4, sample_delta: 1000 }]);
+        let stbl = &mut trak.mdia.minf.stbl;
+        // I P B B, presented as I B B P, with offsets shifted back by 1000.
+        stbl.ctts = Some(CttsBox {
+            entries: vec![
+                CttsEntry { sample_count: 1, sample_offset: 0 },
+                CttsEntry { sample_count: 1, sample_offset: 2000 },
+                CttsEntry { sample_count: 2, sample_offset: -1000 },
+            ],
+            ..CttsBox::default()
+        });
+        stbl.cslg = Some(CslgBox {
+            composition_to_dts_shift: 1000,
+            ..CslgBox::default()
+        });
+
+        let track = Mp4Track::from(&trak);
+        let table = track.timing_table();
+        let dts: Vec<u64> = table.iter().map(|timing| timing.dts).collect();
+        let pts: Vec<i64> = table.iter().map(|timing| timing.pts).collect();
+        assert_eq!(dts, vec![0, 1000, 2000, 3000]);
+        assert_eq!(pts, vec![1000, 4000, 2000, 3000]);
+        assert!(table.iter().all(|timing| timing.duration == 1000));
+
+        // Fragments: the second has no tfdt, so it follows the first.
+        let mut track = Mp4Track::from(&trak);
+        track.default_sample_duration = 500;
+        let traf = |tfdt: Option<u64>, sample_cts: Vec<i64>| TrafBox {
+            tfdt: tfdt.map(TfdtBox::new),
+            trun: Some(TrunBox {
+                flags: TrunBox::FLAG_SAMPLE_CTS,
+                sample_count: sample_cts.len() as u32,
+                sample_cts,
+                ..TrunBox::default()
+            }),
+            ..TrafBox::default()
+        };
+        track.trafs = vec![traf(Some(9000), vec![500, 1000]), traf(None, vec![-500])];
+        let table = track.timing_table();
+        assert_eq!(table, vec![
+            SampleTiming { dts: 9000, pts: 9500, duration: 500 },
+            SampleTiming { dts: 9500, pts: 10500, duration: 500 },
+            SampleTiming { dts: 10000, pts: 9500, duration: 500 },
+        ]);
+    }
+
+    #[test]
+    fn test_force_cfr() {
+        let mut trak = trak(30000, vec![
+            SttsEntry { sample_count: 1, sample_delta: 1000 },
+            SttsEntry { sample_count: 1, sample_delta: 2000 },
+            SttsEntry { sample_count: 2, sample_delta: 1500 },
+        ]);
+        // I P B B, presented as I B B P.
+        trak.mdia.minf.stbl.ctts = Some(CttsBox {
+            entries: vec![
+                CttsEntry { sample_count: 1, sample_offset: 1000 },
+                CttsEntry { sample_count: 1, sample_offset: 5000 },
+                CttsEntry { sample_count: 2, sample_offset: 0 },
+            ],
+            ..CttsBox::default()
+        });
+
+        let mut track = Mp4Track::from(&trak);
+        track.force_cfr(Ratio::new(30, 1), 1000).unwrap();
+
+        let stbl = &track.trak.mdia.minf.stbl;
+        assert_eq!(stbl.stts.entries, vec![SttsEntry { sample_count: 4, sample_delta: 1000 }]);
+        let offsets: Vec<(u32, i64)> = stbl.ctts.as_ref().unwrap().entries.iter()
+            .map(|entry| (entry.sample_count, entry.sample_offset))
+            .collect();
+        assert_eq!(offsets, vec![(1, 1000), (1, 3000), (2, 0)]);
+        assert_eq!(track.trak.mdia.mdhd.duration, 4000);
+        assert_eq!(track.trak.tkhd.duration, 133);
+        assert_eq!(track.sample_at_time(2500), Some(3));
+    }
+
+    #[test]
+    fn test_force_cfr_edit_list() {
+        let mut trak = trak(25, vec![SttsEntry { sample_count: 50, sample_delta: 1 }]);
+        // An empty edit of 100 ms, then the media from its third frame.
+        let empty = edit(100, u32::MAX as u64, 1);
+        trak.edts = edts(vec![empty.clone(), edit(1920, 2, 1)]);
+
+        // 25 fps to 50 fps halves every time of the media.
+        let mut track = Mp4Track::from(&trak);
+        track.force_cfr(Ratio::new(50, 1), 1000).unwrap();
+        let elst = track.trak.edts.as_ref().unwrap().elst.as_ref().unwrap();
+        assert_eq!(elst.entries[0], empty);
+        assert_eq!((elst.entries[1].media_time, elst.entries[1].segment_duration), (1, 960));
+        assert_eq!(track.trak.tkhd.duration, 1000);
+    }
+
+    #[test]
+    fn test_force_cfr_bad_timing() {
+        let mut trak = trak(0, vec![SttsEntry { sample_count: 2, sample_delta: 1 }]);
+        assert!(Mp4Track::from(&trak).force_cfr(Ratio::new(30, 1), 1000).is_err());
+
+        // A huge timescale and frame duration overflow the frame times.
+        trak.mdia.mdhd.timescale = u32::MAX;
+        assert!(Mp4Track::from(&trak).force_cfr(Ratio::new(1, u32::MAX), 1000).is_err());
+    }
+
+    #[test]
+    fn test_exact_frame_rate() {
+        // 23.976 fps, with a shorter final sample.
+        let cfr = trak(24000, vec![
+            SttsEntry { sample_count: 47, sample_delta: 1001 },
+            SttsEntry { sample_count: 1, sample_delta: 500 },
+        ]);
+        let rate = Mp4Track::from(&cfr).exact_frame_rate().unwrap();
+        assert_eq!(rate.rate, Ratio::new(24000, 1001));
+        assert!(!rate.variable);
+
+        // Four frames in 120ms, ignoring the last one.
+        let vfr = trak(1000, vec![
+            SttsEntry { sample_count: 2, sample_delta: 40 },
+            SttsEntry { sample_count: 2, sample_delta: 20 },
+            SttsEntry { sample_count: 1, sample_delta: 40 },
+        ]);
+        let rate = Mp4Track::from(&vfr).exact_frame_rate().unwrap();
+        assert_eq!(rate.rate, Ratio::new(100, 3));
+        assert!(rate.variable);
+    }
+
+    #[test]
+    fn test_sync_samples_out_of_range() {
+        let mut trak = trak(1000, vec![SttsEntry { sample_count: 4, sample_delta: 10 }]);
+        trak.mdia.minf.stbl.stss = Some(StssBox {
+            entries: vec![0, 3, 1, 9],
+            ..StssBox::default()
+        });
+
+        let track = Mp4Track::from(&trak);
+        assert_eq!(track.sync_samples(), vec![1, 3]);
+        assert_eq!(track.gop_count(), 2);
+        assert_eq!(track.keyframe_intervals(), vec![2]);
+        assert_eq!(track.max_gop_size(), 2);
+    }
+}
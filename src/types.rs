@@ -1,6 +1,6 @@
-use std::convert::TryFrom;
-use std::fmt;
-use serde::{Serialize};
+use core::convert::TryFrom;
+use core::fmt;
+use serde::{Serialize, Serializer};
 
 use crate::mp4box::*;
 use crate::*;
@@ -8,7 +8,7 @@ use crate::*;
 pub use bytes::Bytes;
 pub use num_rational::Ratio;
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct FixedPointU8(Ratio<u16>);
 
 impl FixedPointU8 {
@@ -29,7 +29,13 @@ impl FixedPointU8 {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+impl Serialize for FixedPointU8 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        serializer.serialize_f64(*self.0.numer() as f64 / *self.0.denom() as f64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct FixedPointI8(Ratio<i16>);
 
 impl FixedPointI8 {
@@ -50,7 +56,13 @@ impl FixedPointI8 {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+impl Serialize for FixedPointI8 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        serializer.serialize_f64(*self.0.numer() as f64 / *self.0.denom() as f64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct FixedPointU16(Ratio<u32>);
 
 impl FixedPointU16 {
@@ -71,6 +83,12 @@ impl FixedPointU16 {
     }
 }
 
+impl Serialize for FixedPointU16 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        serializer.serialize_f64(*self.0.numer() as f64 / *self.0.denom() as f64)
+    }
+}
+
 impl fmt::Debug for BoxType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let fourcc: FourCC = From::from(self.clone());
@@ -85,12 +103,12 @@ impl fmt::Display for BoxType {
     }
 }
 
-#[derive(Default, PartialEq, Clone, Copy, Serialize)]
+#[derive(Default, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct FourCC {
     pub value: [u8; 4],
 }
 
-impl std::str::FromStr for FourCC {
+impl core::str::FromStr for FourCC {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
@@ -147,6 +165,12 @@ impl fmt::Display for FourCC {
     }
 }
 
+impl Serialize for FourCC {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
 const DISPLAY_TYPE_VIDEO: &str = "Video";
 const DISPLAY_TYPE_AUDIO: &str = "Audio";
 const DISPLAY_TYPE_SUBTITLE: &str = "Subtitle";
@@ -216,6 +240,7 @@ const MEDIA_TYPE_H264: &str = "h264";
 const MEDIA_TYPE_H265: &str = "h265";
 const MEDIA_TYPE_VP9: &str = "vp9";
 const MEDIA_TYPE_AAC: &str = "aac";
+const MEDIA_TYPE_MP3: &str = "mp3";
 const MEDIA_TYPE_TTXT: &str = "ttxt";
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -224,6 +249,7 @@ pub enum MediaType {
     H265,
     VP9,
     AAC,
+    MP3,
     TTXT,
 }
 
@@ -242,6 +268,7 @@ impl TryFrom<&str> for MediaType {
             MEDIA_TYPE_H265 => Ok(MediaType::H265),
             MEDIA_TYPE_VP9 => Ok(MediaType::VP9),
             MEDIA_TYPE_AAC => Ok(MediaType::AAC),
+            MEDIA_TYPE_MP3 => Ok(MediaType::MP3),
             MEDIA_TYPE_TTXT => Ok(MediaType::TTXT),
             _ => Err(Error::InvalidData("unsupported media type")),
         }
@@ -255,6 +282,7 @@ impl Into<&str> for MediaType {
             MediaType::H265 => MEDIA_TYPE_H265,
             MediaType::VP9 => MEDIA_TYPE_VP9,
             MediaType::AAC => MEDIA_TYPE_AAC,
+            MediaType::MP3 => MEDIA_TYPE_MP3,
             MediaType::TTXT => MEDIA_TYPE_TTXT,
         }
     }
@@ -267,6 +295,7 @@ impl Into<&str> for &MediaType {
             MediaType::H265 => MEDIA_TYPE_H265,
             MediaType::VP9 => MEDIA_TYPE_VP9,
             MediaType::AAC => MEDIA_TYPE_AAC,
+            MediaType::MP3 => MEDIA_TYPE_MP3,
             MediaType::TTXT => MEDIA_TYPE_TTXT,
         }
     }
@@ -571,12 +600,42 @@ pub struct AvcConfig {
 pub struct HevcConfig {
     pub width: u16,
     pub height: u16,
+    /// Parameter set NAL units, including their headers, for the hvcC. The
+    /// profile, tier, level and bit depths are taken from the first SPS.
+    pub vid_param_sets: Vec<Vec<u8>>,
+    pub seq_param_sets: Vec<Vec<u8>>,
+    pub pic_param_sets: Vec<Vec<u8>>,
 }
 
-#[derive(Debug, PartialEq, Clone, Default)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Vp9Config {
     pub width: u16,
     pub height: u16,
+    pub profile: u8,
+    pub level: u8,
+    pub bit_depth: u8,
+    pub chroma_subsampling: u8,
+    pub video_full_range_flag: bool,
+    pub color_primaries: u8,
+    pub transfer_characteristics: u8,
+    pub matrix_coefficients: u8,
+}
+
+impl Default for Vp9Config {
+    fn default() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            profile: 0,
+            level: 0x1F,
+            bit_depth: 8,
+            chroma_subsampling: 0,
+            video_full_range_flag: false,
+            color_primaries: 0,
+            transfer_characteristics: 0,
+            matrix_coefficients: 0,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -610,12 +669,114 @@ pub enum MediaConfig {
     TtxtConfig(TtxtConfig),
 }
 
+const SCHEME_TYPE_CENC_FOURCC: [u8; 4] = [b'c', b'e', b'n', b'c'];
+const SCHEME_TYPE_CBC1_FOURCC: [u8; 4] = [b'c', b'b', b'c', b'1'];
+const SCHEME_TYPE_CENS_FOURCC: [u8; 4] = [b'c', b'e', b'n', b's'];
+const SCHEME_TYPE_CBCS_FOURCC: [u8; 4] = [b'c', b'b', b'c', b's'];
+
+/// Common Encryption (ISO/IEC 23001-7) protection schemes, as signalled by
+/// the scheme_type of a schm box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EncryptionScheme {
+    /// AES-CTR, full sample encryption.
+    Cenc,
+    /// AES-CBC, full sample encryption.
+    Cbc1,
+    /// AES-CTR, pattern encryption.
+    Cens,
+    /// AES-CBC, pattern encryption with a constant IV (FairPlay/HLS).
+    Cbcs,
+}
+
+impl EncryptionScheme {
+    pub fn is_cbc(&self) -> bool {
+        matches!(self, EncryptionScheme::Cbc1 | EncryptionScheme::Cbcs)
+    }
+
+    pub fn is_pattern(&self) -> bool {
+        matches!(self, EncryptionScheme::Cens | EncryptionScheme::Cbcs)
+    }
+}
+
+impl fmt::Display for EncryptionScheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fourcc: FourCC = (*self).into();
+        write!(f, "{}", fourcc)
+    }
+}
+
+impl TryFrom<&FourCC> for EncryptionScheme {
+    type Error = Error;
+    fn try_from(fourcc: &FourCC) -> Result<EncryptionScheme> {
+        match fourcc.value {
+            SCHEME_TYPE_CENC_FOURCC => Ok(EncryptionScheme::Cenc),
+            SCHEME_TYPE_CBC1_FOURCC => Ok(EncryptionScheme::Cbc1),
+            SCHEME_TYPE_CENS_FOURCC => Ok(EncryptionScheme::Cens),
+            SCHEME_TYPE_CBCS_FOURCC => Ok(EncryptionScheme::Cbcs),
+            _ => Err(Error::InvalidData("unsupported encryption scheme")),
+        }
+    }
+}
+
+impl From<EncryptionScheme> for FourCC {
+    fn from(scheme: EncryptionScheme) -> FourCC {
+        match scheme {
+            EncryptionScheme::Cenc => SCHEME_TYPE_CENC_FOURCC.into(),
+            EncryptionScheme::Cbc1 => SCHEME_TYPE_CBC1_FOURCC.into(),
+            EncryptionScheme::Cens => SCHEME_TYPE_CENS_FOURCC.into(),
+            EncryptionScheme::Cbcs => SCHEME_TYPE_CBCS_FOURCC.into(),
+        }
+    }
+}
+
+/// How the writer fills in the duration of a track's final sample when it
+/// was written with a duration of zero, e.g. because a live capture ended
+/// mid-sample.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FinalSampleDuration {
+    /// Keep the zero duration.
+    #[default]
+    Unchanged,
+    /// Use the duration of the sample before it.
+    RepeatPrevious,
+    /// Use the given duration, in the track timescale.
+    Explicit(u32),
+}
+
+/// Options for [`Mp4Reader::fragment_with_options`](crate::Mp4Reader::fragment_with_options).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FragmentOptions {
+    /// Move the parameter sets of AVC and HEVC tracks from the samples into
+    /// the sample entry, and write HEVC tracks as hvc1 rather than hev1.
+    /// Safari only plays fragmented HEVC in this form.
+    pub out_of_band_parameter_sets: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    Bmp,
+    Unknown,
+}
+
+/// Cover art from the covr metadata item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverArt {
+    pub format: ImageFormat,
+    pub bytes: Vec<u8>,
+}
+
 #[derive(Debug)]
 pub struct Mp4Sample {
     pub start_time: u64,
     pub duration: u32,
     pub rendering_offset: i32,
     pub is_sync: bool,
+    /// The 1-based index of the stsd entry describing the sample, as
+    /// returned by `Mp4Track::sample_description_index`. The writer always
+    /// uses the first entry.
+    pub sample_description_index: u32,
     pub bytes: Bytes,
 }
 
@@ -625,6 +786,7 @@ impl PartialEq for Mp4Sample {
             && self.duration == other.duration
             && self.rendering_offset == other.rendering_offset
             && self.is_sync == other.is_sync
+            && self.sample_description_index == other.sample_description_index
             && self.bytes.len() == other.bytes.len() // XXX for easy check
     }
 }
@@ -1,7 +1,21 @@
 use byteorder::{BigEndian, WriteBytesExt};
-use std::io::{Seek, SeekFrom, Write};
+use std::cmp;
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Cursor, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::mp4box::*;
+use crate::mp4box::mfhd::MfhdBox;
+use crate::mp4box::mvex::MvexBox;
+use crate::mp4box::sidx::{SidxBox, SidxReference};
+use crate::mp4box::tfdt::TfdtBox;
+use crate::mp4box::tfhd::TfhdBox;
+use crate::mp4box::traf::{TrafBox, SAMPLE_FLAGS_NON_SYNC, SAMPLE_FLAGS_SYNC};
+use crate::mp4box::trak::TrakBox;
+use crate::mp4box::trex::TrexBox;
+use crate::mp4box::trun::TrunBox;
 use crate::track::Mp4TrackWriter;
 use crate::*;
 
@@ -13,6 +27,23 @@ pub struct Mp4Config {
     pub timescale: u32,
 }
 
+impl Default for Mp4Config {
+    /// Brands for a progressive MP4 file, with a 1000 Hz movie timescale.
+    fn default() -> Self {
+        Self {
+            major_brand: FourCC::from(*b"isom"),
+            minor_version: 512,
+            compatible_brands: vec![
+                FourCC::from(*b"isom"),
+                FourCC::from(*b"iso2"),
+                FourCC::from(*b"avc1"),
+                FourCC::from(*b"mp41"),
+            ],
+            timescale: 1000,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Mp4Writer<W> {
     writer: W,
@@ -20,6 +51,8 @@ pub struct Mp4Writer<W> {
     mdat_pos: u64,
     timescale: u32,
     duration: u64,
+    final_sample_duration: FinalSampleDuration,
+    max_mdat_size: Option<u64>,
 }
 
 impl<W> Mp4Writer<W> {
@@ -81,9 +114,41 @@ impl<W: Write + Seek> Mp4Writer<W> {
             mdat_pos,
             timescale,
             duration,
+            final_sample_duration: FinalSampleDuration::default(),
+            max_mdat_size: None,
         })
     }
 
+    /// Like `write_start` with the default config, but with the given ftyp
+    /// brands, e.g. to target players that expect "mp42" or "dash".
+    pub fn with_brands(
+        writer: W,
+        major_brand: FourCC,
+        minor_version: u32,
+        compatible_brands: Vec<FourCC>,
+    ) -> Result<Self> {
+        let config = Mp4Config {
+            major_brand,
+            minor_version,
+            compatible_brands,
+            ..Mp4Config::default()
+        };
+        Self::write_start(writer, &config)
+    }
+
+    /// Split the media data across several mdat boxes of at most
+    /// `max_size` bytes each, including the box header. A chunk is never
+    /// split, so a single chunk larger than this gets an mdat of its own.
+    pub fn set_max_mdat_size(&mut self, max_size: u64) {
+        self.max_mdat_size = Some(max_size);
+    }
+
+    /// Set how a zero duration of each track's final sample is filled in
+    /// when the file is finished.
+    pub fn set_final_sample_duration(&mut self, policy: FinalSampleDuration) {
+        self.final_sample_duration = policy;
+    }
+
     pub fn add_track(&mut self, config: &TrackConfig) -> Result<()> {
         let track_id = self.tracks.len() as u32 + 1;
         let track = Mp4TrackWriter::new(track_id, config)?;
@@ -102,17 +167,52 @@ impl<W: Write + Seek> Mp4Writer<W> {
             return Err(Error::TrakNotFound(track_id));
         }
 
-        let track_dur = if let Some(ref mut track) = self.tracks.get_mut(track_id as usize - 1) {
-            track.write_sample(&mut self.writer, sample, self.timescale)?
-        } else {
-            return Err(Error::TrakNotFound(track_id));
+        let pending = match self.tracks.get(track_id as usize - 1) {
+            Some(track) => track.pending_chunk_size(),
+            None => return Err(Error::TrakNotFound(track_id)),
         };
+        // The sample may complete the track's chunk, so make room for all of it.
+        self.reserve_mdat_space(pending + sample.bytes.len() as u64)?;
+
+        let track = &mut self.tracks[track_id as usize - 1];
+        let track_dur = track.write_sample(&mut self.writer, sample, self.timescale)?;
 
         self.update_durations(track_dur);
 
         Ok(())
     }
 
+    /// Write the samples yielded by `samples` to a track, pulling one at a
+    /// time. As with `write_sample`, only the track's current chunk is held
+    /// in memory and it is written out as soon as it is full, so samples can
+    /// come straight from an encoder without being collected first.
+    pub fn write_samples<I: IntoIterator<Item = Mp4Sample>>(
+        &mut self,
+        track_id: u32,
+        samples: I,
+    ) -> Result<()> {
+        for sample in samples {
+            self.write_sample(track_id, &sample)?;
+        }
+        Ok(())
+    }
+
+    /// Start a new mdat if writing `size` more bytes would take the current
+    /// one past the configured maximum.
+    fn reserve_mdat_space(&mut self, size: u64) -> Result<()> {
+        let max_mdat_size = match self.max_mdat_size {
+            Some(max_mdat_size) => max_mdat_size,
+            None => return Ok(()),
+        };
+        let mdat_size = self.writer.stream_position()? - self.mdat_pos;
+        if mdat_size > HEADER_SIZE && mdat_size + size > max_mdat_size {
+            self.update_mdat_size()?;
+            self.mdat_pos = self.writer.stream_position()?;
+            BoxHeader::new(BoxType::MdatBox, HEADER_SIZE).write(&mut self.writer)?;
+        }
+        Ok(())
+    }
+
     fn update_mdat_size(&mut self) -> Result<()> {
         let mdat_end = self.writer.seek(SeekFrom::Current(0))?;
         let mdat_size = mdat_end - self.mdat_pos;
@@ -128,14 +228,787 @@ impl<W: Write + Seek> Mp4Writer<W> {
     pub fn write_end(&mut self) -> Result<()> {
         let mut moov = MoovBox::default();
 
-        for track in self.tracks.iter_mut() {
+        for i in 0..self.tracks.len() {
+            self.reserve_mdat_space(self.tracks[i].pending_chunk_size())?;
+            let track = &mut self.tracks[i];
+            let track_dur = track.set_final_sample_duration(self.final_sample_duration, self.timescale);
+            self.duration = cmp::max(self.duration, track_dur);
             moov.traks.push(track.write_end(&mut self.writer)?);
         }
         self.update_mdat_size()?;
 
         moov.mvhd.timescale = self.timescale;
         moov.mvhd.duration = self.duration;
+        moov.mvhd.next_track_id = self.tracks.len() as u32 + 1;
         moov.write_box(&mut self.writer)?;
         Ok(())
     }
 }
+
+impl Mp4Writer<Cursor<Vec<u8>>> {
+    /// Returns a complete MP4 file with a single track for `media_config`
+    /// and no samples: the sample entry is filled in and every sample table
+    /// is empty. Useful as a template or a test fixture.
+    pub fn empty(media_config: MediaConfig) -> Result<Vec<u8>> {
+        let mut writer = Self::write_start(Cursor::new(Vec::new()), &Mp4Config::default())?;
+        writer.add_track(&TrackConfig::from(media_config))?;
+        writer.write_end()?;
+        Ok(writer.into_writer().into_inner())
+    }
+}
+
+impl Mp4Writer<BufWriter<File>> {
+    /// Write an MP4 file to `path` through a temporary file in the same
+    /// directory, which is renamed over `path` only once the file is
+    /// complete.
+    ///
+    /// `write` adds the tracks and samples to a writer started with
+    /// `config`; `write_end` is then called and the file synced before the
+    /// rename. If any step fails the temporary file is removed, and a file
+    /// already at `path` is left as it was.
+    pub fn write_atomic<P, F>(path: P, config: &Mp4Config, write: F) -> Result<()>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        let path = path.as_ref();
+        let temp_path = temp_path(path)?;
+        let result = Self::write_file(&temp_path, config, write)
+            .and_then(|_| fs::rename(&temp_path, path).map_err(Error::from));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    fn write_file<F>(path: &Path, config: &Mp4Config, write: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        let file = File::create(path)?;
+        let mut writer = Self::write_start(BufWriter::new(file), config)?;
+        write(&mut writer)?;
+        writer.write_end()?;
+        let file = writer.into_writer().into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+// A hidden file next to `path`, so that the final rename stays on the same
+// file system.
+fn temp_path(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or(Error::InvalidData("output path has no file name"))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    Ok(path.with_file_name(temp_name))
+}
+
+/// Writes sample data to a sink that need not support seeking, such as a
+/// network stream, while building the sample tables in memory. The moov box
+/// describing the samples is written separately with [Mp4StreamWriter::write_moov].
+#[derive(Debug)]
+pub struct Mp4StreamWriter<W> {
+    sink: OffsetWriter<W>,
+    tracks: Vec<Mp4TrackWriter>,
+    timescale: u32,
+    duration: u64,
+}
+
+impl<W: Write> Mp4StreamWriter<W> {
+    /// Create a writer whose first sample byte will be stored at
+    /// `data_offset` in the final file, e.g. right after the mdat header.
+    pub fn new(sink: W, data_offset: u64, timescale: u32) -> Self {
+        Self {
+            sink: OffsetWriter {
+                inner: sink,
+                offset: data_offset,
+            },
+            tracks: Vec::new(),
+            timescale,
+            duration: 0,
+        }
+    }
+
+    pub fn add_track(&mut self, config: &TrackConfig) -> Result<()> {
+        let track_id = self.tracks.len() as u32 + 1;
+        let track = Mp4TrackWriter::new(track_id, config)?;
+        self.tracks.push(track);
+        Ok(())
+    }
+
+    pub fn write_sample(&mut self, track_id: u32, sample: &Mp4Sample) -> Result<()> {
+        if track_id == 0 {
+            return Err(Error::TrakNotFound(track_id));
+        }
+
+        let track_dur = if let Some(ref mut track) = self.tracks.get_mut(track_id as usize - 1) {
+            track.write_sample(&mut self.sink, sample, self.timescale)?
+        } else {
+            return Err(Error::TrakNotFound(track_id));
+        };
+
+        if track_dur > self.duration {
+            self.duration = track_dur;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the file offset just past the last sample byte written to
+    /// the sink.
+    pub fn position(&self) -> u64 {
+        self.sink.offset
+    }
+
+    /// Flush any buffered samples to the sink and write the moov box to
+    /// `writer`. Returns the size of the moov box.
+    pub fn write_moov<M: Write>(&mut self, writer: &mut M) -> Result<u64> {
+        let mut moov = MoovBox::default();
+
+        for track in self.tracks.iter_mut() {
+            moov.traks.push(track.write_end(&mut self.sink)?);
+        }
+        self.sink.flush()?;
+
+        moov.mvhd.timescale = self.timescale;
+        moov.mvhd.duration = self.duration;
+        moov.mvhd.next_track_id = self.tracks.len() as u32 + 1;
+        moov.write_box(writer)?;
+        Ok(moov.box_size())
+    }
+
+    /// Consume self, returning the sample data sink.
+    pub fn into_sink(self) -> W {
+        self.sink.inner
+    }
+}
+
+/// Writes a fragmented MP4: an init segment (ftyp and a moov with mvex)
+/// followed by one moof and mdat pair per call to
+/// [FragmentWriter::write_fragment].
+///
+/// A writer made with [FragmentWriter::new] writes the init segment along
+/// with the first fragment, and each fragment as soon as it is complete. One
+/// made with [FragmentWriter::with_sidx] places a segment index in front of
+/// the fragments, so it holds them in a temporary file until the file is
+/// finalized.
+#[derive(Debug)]
+pub struct FragmentWriter<W> {
+    writer: W,
+    ftyp: FtypBox,
+    timescale: u32,
+    traks: Vec<TrakBox>,
+    decode_times: Vec<u64>,
+    sequence_number: u32,
+    spill: Option<TempFile>,
+    fragments: Vec<Fragment>,
+}
+
+// What the sidx needs to know about a fragment.
+#[derive(Debug)]
+struct Fragment {
+    track_id: u32,
+    earliest_time: u64,
+    duration: u64,
+    starts_with_sap: bool,
+    size: u64,
+}
+
+impl<W: Write> FragmentWriter<W> {
+    pub fn new(writer: W, config: &Mp4Config) -> Self {
+        Self {
+            writer,
+            ftyp: FtypBox {
+                major_brand: config.major_brand,
+                minor_version: config.minor_version,
+                compatible_brands: config.compatible_brands.clone(),
+            },
+            timescale: config.timescale,
+            traks: Vec::new(),
+            decode_times: Vec::new(),
+            sequence_number: 1,
+            spill: None,
+            fragments: Vec::new(),
+        }
+    }
+
+    /// Like [FragmentWriter::new], but [FragmentWriter::finalize] also writes
+    /// a sidx box in front of the first moof, as needed for single-file DASH
+    /// on-demand. The first track is the reference stream: each of its
+    /// fragments starts a subsegment, which also holds the fragments of other
+    /// tracks written after it.
+    ///
+    /// Fragments are written to a temporary file in [std::env::temp_dir]
+    /// and copied to `writer` once the sidx is known.
+    pub fn with_sidx(writer: W, config: &Mp4Config) -> Result<Self> {
+        let mut fragment_writer = Self::new(writer, config);
+        fragment_writer.spill = Some(TempFile::create()?);
+        Ok(fragment_writer)
+    }
+
+    pub fn add_track(&mut self, config: &TrackConfig) -> Result<()> {
+        if self.sequence_number > 1 {
+            return Err(Error::InvalidData("track added after the first fragment"));
+        }
+        let track_id = self.traks.len() as u32 + 1;
+        let mut track = Mp4TrackWriter::new(track_id, config)?;
+        // No samples are written, so this only yields the sample description.
+        let trak = track.write_end(&mut io::Cursor::new(Vec::new()))?;
+        self.traks.push(trak);
+        self.decode_times.push(0);
+        Ok(())
+    }
+
+    /// Write `samples` of one track as a single fragment.
+    pub fn write_fragment(&mut self, track_id: u32, samples: &[Mp4Sample]) -> Result<()> {
+        if track_id == 0 || track_id as usize > self.traks.len() {
+            return Err(Error::TrakNotFound(track_id));
+        }
+        if samples.is_empty() {
+            return Err(Error::InvalidData("fragment has no samples"));
+        }
+
+        let decode_time = self.decode_times[track_id as usize - 1];
+        let runs = [FragmentRun {
+            track_id,
+            base_media_decode_time: decode_time,
+            samples,
+        }];
+        let size = match self.spill {
+            Some(ref spill) => {
+                let mut writer = BufWriter::new(&spill.file);
+                let size = write_moof_mdat(&mut writer, self.sequence_number, &runs)?;
+                writer.flush()?;
+                size
+            }
+            None => {
+                if self.sequence_number == 1 {
+                    self.write_init_segment()?;
+                }
+                write_moof_mdat(&mut self.writer, self.sequence_number, &runs)?
+            }
+        };
+
+        let duration: u64 = samples.iter().map(|sample| sample.duration as u64).sum();
+        if self.spill.is_some() {
+            self.fragments.push(Fragment {
+                track_id,
+                earliest_time: earliest_presentation_time(decode_time, samples),
+                duration,
+                starts_with_sap: samples[0].is_sync,
+                size,
+            });
+        }
+        self.decode_times[track_id as usize - 1] += duration;
+        self.sequence_number += 1;
+
+        Ok(())
+    }
+
+    /// Write whatever is still pending, returning the inner writer: the init
+    /// segment if no fragment was written, or for a writer made with
+    /// [FragmentWriter::with_sidx], the init segment, the sidx and all
+    /// fragments.
+    pub fn finalize(mut self) -> Result<W> {
+        match self.spill.take() {
+            Some(mut spill) => {
+                self.write_init_segment()?;
+                self.sidx()?.write_box(&mut self.writer)?;
+                spill.file.seek(SeekFrom::Start(0))?;
+                io::copy(&mut spill.file, &mut self.writer)?;
+            }
+            None if self.sequence_number == 1 => self.write_init_segment()?,
+            None => {}
+        }
+        Ok(self.writer)
+    }
+
+    fn write_init_segment(&mut self) -> Result<()> {
+        self.ftyp.write_box(&mut self.writer)?;
+
+        let mut moov = MoovBox::default();
+        moov.mvhd.timescale = self.timescale;
+        moov.traks = self.traks.clone();
+        set_fragment_defaults(&mut moov);
+        moov.write_box(&mut self.writer)?;
+        Ok(())
+    }
+
+    fn sidx(&self) -> Result<SidxBox> {
+        let reference_id = 1;
+        let timescale = self
+            .traks
+            .first()
+            .map(|trak| trak.mdia.mdhd.timescale)
+            .unwrap_or(self.timescale);
+
+        // Fragments of other tracks written before the first one of the
+        // reference track belong to the first subsegment.
+        let mut subsegments: Vec<(&Fragment, u64)> = Vec::new();
+        let mut leading_size = 0;
+        for fragment in self.fragments.iter() {
+            let size = fragment.size;
+            if fragment.track_id == reference_id {
+                subsegments.push((fragment, size + leading_size));
+                leading_size = 0;
+            } else if let Some((_, subsegment_size)) = subsegments.last_mut() {
+                *subsegment_size += size;
+            } else {
+                leading_size += size;
+            }
+        }
+        if subsegments.is_empty() && !self.fragments.is_empty() {
+            return Err(Error::InvalidData("no fragments of the sidx reference track"));
+        }
+
+        let earliest_presentation_time = subsegments
+            .first()
+            .map(|(fragment, _)| fragment.earliest_time)
+            .unwrap_or(0);
+        let mut references = Vec::with_capacity(subsegments.len());
+        for (fragment, size) in subsegments {
+            references.push(SidxReference {
+                reference_type: false,
+                referenced_size: u32::try_from(size)
+                    .map_err(|_| Error::InvalidData("sidx subsegment too large"))?,
+                subsegment_duration: u32::try_from(fragment.duration)
+                    .map_err(|_| Error::InvalidData("sidx subsegment too long"))?,
+                starts_with_sap: fragment.starts_with_sap,
+                sap_type: if fragment.starts_with_sap { 1 } else { 0 },
+                sap_delta_time: 0,
+            });
+        }
+
+        Ok(SidxBox {
+            version: if earliest_presentation_time > u32::MAX as u64 { 1 } else { 0 },
+            flags: 0,
+            reference_id,
+            timescale,
+            earliest_presentation_time,
+            // Fragments follow the sidx directly.
+            first_offset: 0,
+            references,
+        })
+    }
+}
+
+// The presentation time of the first sample shown in a fragment starting at
+// `decode_time`, which differs from its decode time when samples are
+// reordered.
+fn earliest_presentation_time(decode_time: u64, samples: &[Mp4Sample]) -> u64 {
+    let mut sample_time = decode_time as i128;
+    let mut earliest = i128::MAX;
+    for sample in samples.iter() {
+        earliest = cmp::min(earliest, sample_time + sample.rendering_offset as i128);
+        sample_time += sample.duration as i128;
+    }
+    earliest.clamp(0, u64::MAX as i128) as u64
+}
+
+/// Prepare moov for a fragmented file: add an mvex with a trex for every
+/// track and update next_track_id.
+pub(crate) fn set_fragment_defaults(moov: &mut MoovBox) {
+    moov.mvhd.next_track_id = moov
+        .traks
+        .iter()
+        .map(|trak| trak.tkhd.track_id)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    moov.mvex = Some(MvexBox {
+        mehd: None,
+        trexs: moov
+            .traks
+            .iter()
+            .map(|trak| TrexBox {
+                track_id: trak.tkhd.track_id,
+                default_sample_description_index: 1,
+                ..TrexBox::default()
+            })
+            .collect(),
+        treps: Vec::new(),
+    });
+}
+
+/// The samples of one track within a movie fragment.
+pub(crate) struct FragmentRun<'a> {
+    pub(crate) track_id: u32,
+    pub(crate) base_media_decode_time: u64,
+    pub(crate) samples: &'a [Mp4Sample],
+}
+
+/// Write a moof with one traf per run, followed by an mdat holding the
+/// sample data of all runs in order. Returns the number of bytes written.
+pub(crate) fn write_moof_mdat<W: Write>(
+    writer: &mut W,
+    sequence_number: u32,
+    runs: &[FragmentRun],
+) -> Result<u64> {
+    let mut moof = MoofBox {
+        mfhd: MfhdBox {
+            sequence_number,
+            ..MfhdBox::default()
+        },
+        trafs: runs.iter().map(traf_from_run).collect(),
+        unknown_boxes: Vec::new(),
+    };
+
+    // Sample data of each traf follows that of the previous one in the mdat
+    // right after moof. Offsets are relative to moof.
+    let mut data_offset = moof.box_size() + HEADER_SIZE;
+    for (traf, run) in moof.trafs.iter_mut().zip(runs.iter()) {
+        if let Some(ref mut trun) = traf.trun {
+            trun.data_offset = Some(data_offset as i32);
+        }
+        data_offset += run.samples.iter().map(|sample| sample.bytes.len() as u64).sum::<u64>();
+    }
+    let mdat_size = data_offset - moof.box_size();
+
+    moof.write_box(writer)?;
+    BoxHeader::new(BoxType::MdatBox, mdat_size).write(writer)?;
+    for run in runs.iter() {
+        for sample in run.samples.iter() {
+            writer.write_all(&sample.bytes)?;
+        }
+    }
+
+    Ok(moof.box_size() + mdat_size)
+}
+
+fn traf_from_run(run: &FragmentRun) -> TrafBox {
+    let mut trun = TrunBox {
+        flags: TrunBox::FLAG_DATA_OFFSET
+            | TrunBox::FLAG_SAMPLE_DURATION
+            | TrunBox::FLAG_SAMPLE_SIZE
+            | TrunBox::FLAG_SAMPLE_FLAGS,
+        sample_count: run.samples.len() as u32,
+        data_offset: Some(0),
+        ..TrunBox::default()
+    };
+    if run.samples.iter().any(|sample| sample.rendering_offset != 0) {
+        trun.flags |= TrunBox::FLAG_SAMPLE_CTS;
+    }
+    for sample in run.samples.iter() {
+        trun.sample_durations.push(sample.duration);
+        trun.sample_sizes.push(sample.bytes.len() as u32);
+        trun.sample_flags.push(if sample.is_sync {
+            SAMPLE_FLAGS_SYNC
+        } else {
+            SAMPLE_FLAGS_NON_SYNC
+        });
+        if TrunBox::FLAG_SAMPLE_CTS & trun.flags > 0 {
+            trun.sample_cts.push(sample.rendering_offset as i64);
+        }
+    }
+
+    TrafBox {
+        tfhd: TfhdBox {
+            flags: TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF,
+            track_id: run.track_id,
+            ..TfhdBox::default()
+        },
+        tfdt: Some(TfdtBox::new(run.base_media_decode_time)),
+        trun: Some(trun),
+        unknown_boxes: Vec::new(),
+    }
+}
+
+// Tracks the file offset of a sink so that chunk offsets can be recorded
+// without seeking. Only reporting the current position is supported.
+#[derive(Debug)]
+struct OffsetWriter<W> {
+    inner: W,
+    offset: u64,
+}
+
+impl<W: Write> Write for OffsetWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W> Seek for OffsetWriter<W> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Current(0) => Ok(self.offset),
+            _ => Err(io::Error::new(io::ErrorKind::Unsupported, "sink is not seekable")),
+        }
+    }
+}
+
+// A file in the temporary directory that is removed when dropped.
+#[derive(Debug)]
+struct TempFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl TempFile {
+    fn create() -> Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            ".mp4-fragments.{}.{}.tmp",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(Self { path, file })
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_stream_writer() {
+        let ftyp = FtypBox::default();
+        let data_offset = ftyp.box_size() + HEADER_SIZE;
+
+        let mut writer = Mp4StreamWriter::new(Vec::new(), data_offset, 1000);
+        writer.add_track(&TrackConfig::from(AacConfig::default())).unwrap();
+        let mut samples = Vec::new();
+        for i in 0..3u8 {
+            let sample = Mp4Sample {
+                start_time: i as u64 * 1024,
+                duration: 1024,
+                rendering_offset: 0,
+                is_sync: true,
+                sample_description_index: 1,
+                bytes: Bytes::from(vec![i; 100 + i as usize]),
+            };
+            writer.write_sample(1, &sample).unwrap();
+            samples.push(sample);
+        }
+
+        let mut moov_buf = Vec::new();
+        writer.write_moov(&mut moov_buf).unwrap();
+        assert_eq!(writer.position(), data_offset + 303);
+        let sink = writer.into_sink();
+
+        // Sample bytes land in the sink in order.
+        let expected: Vec<u8> = samples.iter().flat_map(|s| s.bytes.to_vec()).collect();
+        assert_eq!(sink, expected);
+
+        // The moov offsets point at the samples once the file is assembled.
+        let mut file = Vec::new();
+        ftyp.write_box(&mut file).unwrap();
+        BoxHeader::new(BoxType::MdatBox, HEADER_SIZE + sink.len() as u64)
+            .write(&mut file)
+            .unwrap();
+        file.extend_from_slice(&sink);
+        file.extend_from_slice(&moov_buf);
+
+        let size = file.len() as u64;
+        let mut mp4 = Mp4Reader::read_header(Cursor::new(file), size).unwrap();
+        let stco = mp4.moov.traks[0].mdia.minf.stbl.stco.clone().unwrap();
+        assert_eq!(stco.entries[0] as u64, data_offset);
+        for (i, sample) in samples.iter().enumerate() {
+            let read = mp4.read_sample(1, i as u32 + 1).unwrap().unwrap();
+            assert_eq!(read.bytes, sample.bytes);
+        }
+    }
+
+    #[test]
+    fn test_fragment_writer_sidx() {
+        let config = Mp4Config {
+            major_brand: str::parse("iso6").unwrap(),
+            minor_version: 0,
+            compatible_brands: vec![str::parse("iso6").unwrap(), str::parse("dash").unwrap()],
+            timescale: 1000,
+        };
+        let mut writer = FragmentWriter::with_sidx(Vec::new(), &config).unwrap();
+        writer.add_track(&TrackConfig::from(AacConfig::default())).unwrap();
+
+        let mut samples = Vec::new();
+        for i in 0..3u8 {
+            let fragment: Vec<Mp4Sample> = (0..=i)
+                .map(|j| Mp4Sample {
+                    start_time: 0,
+                    duration: 1024,
+                    rendering_offset: 0,
+                    is_sync: true,
+                    sample_description_index: 1,
+                    bytes: Bytes::from(vec![i * 10 + j; 50 + j as usize]),
+                })
+                .collect();
+            writer.write_fragment(1, &fragment).unwrap();
+            samples.extend(fragment);
+        }
+        let file = writer.finalize().unwrap();
+
+        // Collect the size of each moof and mdat pair following the sidx.
+        let mut reader = Cursor::new(&file);
+        let mut fragment_sizes = Vec::new();
+        let mut sidx_end = 0;
+        while reader.position() < file.len() as u64 {
+            let start = reader.position();
+            let header = BoxHeader::read(&mut reader).unwrap();
+            match header.name {
+                BoxType::SidxBox => sidx_end = start + header.size,
+                BoxType::MoofBox => fragment_sizes.push(header.size),
+                BoxType::MdatBox => *fragment_sizes.last_mut().unwrap() += header.size,
+                _ => {}
+            }
+            reader.set_position(start + header.size);
+        }
+
+        let size = file.len() as u64;
+        let mut mp4 = Mp4Reader::read_header(Cursor::new(file.clone()), size).unwrap();
+        assert!(mp4.is_fragmented());
+        assert_eq!(mp4.moofs.len(), 3);
+        for moof in mp4.moofs.iter() {
+            // CMAF requires offsets relative to the moof, with no explicit base.
+            let tfhd = &moof.trafs[0].tfhd;
+            assert!(tfhd.default_base_is_moof());
+            assert_eq!(tfhd.flags & TfhdBox::FLAG_BASE_DATA_OFFSET, 0);
+            assert_eq!(tfhd.base_data_offset, None);
+        }
+
+        let sidx = &mp4.sidxs[0];
+        assert_eq!(sidx.reference_id, 1);
+        assert_eq!(sidx.first_offset, 0);
+        assert_eq!(sidx.references.len(), mp4.moofs.len());
+        let referenced_sizes: Vec<u64> = sidx.references.iter()
+            .map(|r| r.referenced_size as u64)
+            .collect();
+        assert_eq!(referenced_sizes, fragment_sizes);
+        let durations: Vec<u32> = sidx.references.iter().map(|r| r.subsegment_duration).collect();
+        assert_eq!(durations, vec![1024, 2048, 3072]);
+        assert_eq!(sidx_end + referenced_sizes.iter().sum::<u64>(), size);
+
+        for (i, sample) in samples.iter().enumerate() {
+            let read = mp4.read_sample(1, i as u32 + 1).unwrap().unwrap();
+            assert_eq!(read.bytes, sample.bytes);
+        }
+    }
+
+    #[test]
+    fn test_fragment_writer_sidx_two_tracks() {
+        let mut writer = FragmentWriter::with_sidx(Vec::new(), &Mp4Config {
+            major_brand: str::parse("iso6").unwrap(),
+            minor_version: 0,
+            compatible_brands: vec![str::parse("iso6").unwrap(), str::parse("dash").unwrap()],
+            timescale: 1000,
+        }).unwrap();
+        writer.add_track(&TrackConfig::from(Vp9Config {
+            width: 320,
+            height: 240,
+            ..Vp9Config::default()
+        })).unwrap();
+        writer.add_track(&TrackConfig {
+            timescale: 48000,
+            ..TrackConfig::from(AacConfig::default())
+        }).unwrap();
+
+        let sample = |duration: u32, size: usize| Mp4Sample {
+            start_time: 0,
+            duration,
+            rendering_offset: 0,
+            is_sync: true,
+            sample_description_index: 1,
+            bytes: Bytes::from(vec![0; size]),
+        };
+        // Two seconds of video in one second fragments, each followed by the
+        // audio of the same second. The video is shown one frame late.
+        for _ in 0..2 {
+            let video: Vec<Mp4Sample> = (0..25)
+                .map(|_| Mp4Sample {
+                    rendering_offset: 40,
+                    ..sample(40, 200)
+                })
+                .collect();
+            let audio: Vec<Mp4Sample> = (0..47).map(|_| sample(1024, 20)).collect();
+            writer.write_fragment(1, &video).unwrap();
+            writer.write_fragment(2, &audio).unwrap();
+        }
+        let file = writer.finalize().unwrap();
+
+        let mut reader = Cursor::new(&file);
+        let mut sidx_end = 0;
+        while reader.position() < file.len() as u64 {
+            let start = reader.position();
+            let header = BoxHeader::read(&mut reader).unwrap();
+            if header.name == BoxType::SidxBox {
+                sidx_end = start + header.size;
+            }
+            reader.set_position(start + header.size);
+        }
+
+        let size = file.len() as u64;
+        let mp4 = Mp4Reader::read_header(Cursor::new(file), size).unwrap();
+        assert_eq!(mp4.moofs.len(), 4);
+        let sidx = &mp4.sidxs[0];
+        assert_eq!(sidx.timescale, mp4.tracks()[&1].timescale());
+        assert_eq!(sidx.earliest_presentation_time, 40);
+        let durations: Vec<u32> = sidx.references.iter().map(|r| r.subsegment_duration).collect();
+        assert_eq!(durations, vec![1000, 1000]);
+
+        let referenced_size: u64 = sidx.references.iter().map(|r| r.referenced_size as u64).sum();
+        assert_eq!(sidx_end + referenced_size, size);
+    }
+
+    #[test]
+    fn test_fragment_writer_streams() {
+        let mut writer = FragmentWriter::new(Vec::new(), &Mp4Config::default());
+        writer.add_track(&TrackConfig::from(AacConfig::default())).unwrap();
+        let samples: Vec<Mp4Sample> = (0..2u8)
+            .map(|i| Mp4Sample {
+                start_time: 0,
+                duration: 1024,
+                rendering_offset: 0,
+                is_sync: true,
+                sample_description_index: 1,
+                bytes: Bytes::from(vec![i; 10]),
+            })
+            .collect();
+
+        // The init segment and each fragment are written right away.
+        writer.write_fragment(1, &samples[..1]).unwrap();
+        let init_and_fragment = writer.writer.len();
+        assert!(init_and_fragment > 0);
+        writer.write_fragment(1, &samples[1..]).unwrap();
+        assert!(writer.writer.len() > init_and_fragment);
+        assert!(writer.add_track(&TrackConfig::from(AacConfig::default())).is_err());
+
+        let file = writer.finalize().unwrap();
+        let size = file.len() as u64;
+        let mut mp4 = Mp4Reader::read_header(Cursor::new(file), size).unwrap();
+        assert_eq!(mp4.moofs.len(), 2);
+        assert!(mp4.sidxs.is_empty());
+        for (i, sample) in samples.iter().enumerate() {
+            let read = mp4.read_sample(1, i as u32 + 1).unwrap().unwrap();
+            assert_eq!(read.bytes, sample.bytes);
+        }
+
+        // Without fragments, only the init segment is written.
+        let mut writer = FragmentWriter::new(Vec::new(), &Mp4Config::default());
+        writer.add_track(&TrackConfig::from(AacConfig::default())).unwrap();
+        let file = writer.finalize().unwrap();
+        let size = file.len() as u64;
+        let mp4 = Mp4Reader::read_header(Cursor::new(file), size).unwrap();
+        assert!(mp4.is_fragmented());
+        assert_eq!(mp4.tracks().len(), 1);
+    }
+}
@@ -1,6 +1,9 @@
-use mp4::{AudioObjectType, AvcProfile, ChannelConfig, MediaType, SampleFreqIndex, TrackType};
+use mp4::{
+    AacConfig, AudioObjectType, AvcProfile, ChannelConfig, FourCC, MediaType, Mp4Config, Mp4Writer,
+    SampleFreqIndex, TrackConfig, TrackType,
+};
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Cursor};
 use std::time::Duration;
 
 #[test]
@@ -16,6 +19,8 @@ fn test_read_mp4() {
 
     // ftyp.
     assert_eq!(4, mp4.compatible_brands().len());
+    assert!(!mp4.is_fragmented());
+    assert!(!mp4.is_dash());
 
     // Check compatible_brands.
     let brands = vec![
@@ -45,6 +50,7 @@ fn test_read_mp4() {
             duration: 512,
             rendering_offset: 0,
             is_sync: true,
+            sample_description_index: 1,
             bytes: mp4::Bytes::from(vec![0x0u8; 751]),
         }
     );
@@ -62,6 +68,7 @@ fn test_read_mp4() {
             duration: 1024,
             rendering_offset: 0,
             is_sync: true,
+            sample_description_index: 1,
             bytes: mp4::Bytes::from(vec![0x0u8; 179]),
         }
     );
@@ -74,6 +81,7 @@ fn test_read_mp4() {
             duration: 1024,
             rendering_offset: 0,
             is_sync: true,
+            sample_description_index: 1,
             bytes: mp4::Bytes::from(vec![0x0u8; 180]),
         }
     );
@@ -86,6 +94,7 @@ fn test_read_mp4() {
             duration: 896,
             rendering_offset: 0,
             is_sync: true,
+            sample_description_index: 1,
             bytes: mp4::Bytes::from(vec![0x0u8; 160]),
         }
     );
@@ -118,4 +127,1443 @@ fn test_read_mp4() {
     );
     assert_eq!(track2.channel_config().unwrap(), ChannelConfig::Mono);
     assert_eq!(track2.bitrate(), 67695);
+    assert_eq!(track2.keyframe_intervals(), vec![1, 1]);
+    assert_eq!(track2.max_gop_size(), 1);
+}
+
+fn mp4_config() -> Mp4Config {
+    Mp4Config {
+        major_brand: str::parse("isom").unwrap(),
+        minor_version: 512,
+        compatible_brands: vec![str::parse("isom").unwrap(), str::parse("mp41").unwrap()],
+        timescale: 1000,
+    }
+}
+
+#[test]
+fn test_write_with_brands() {
+    let mp42: FourCC = str::parse("mp42").unwrap();
+    let isom: FourCC = str::parse("isom").unwrap();
+    let mut writer = Mp4Writer::with_brands(
+        Cursor::new(Vec::new()),
+        mp42.clone(),
+        1,
+        vec![mp42.clone(), isom.clone()],
+    )
+    .unwrap();
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    assert_eq!(mp4.major_brand(), &mp42);
+    assert_eq!(mp4.minor_version(), 1);
+    assert_eq!(mp4.compatible_brands(), &[mp42, isom][..]);
+    assert_eq!(mp4.timescale(), Mp4Config::default().timescale);
+}
+
+#[test]
+fn test_write_alternate_group() {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    for language in ["eng", "fra"].iter() {
+        writer
+            .add_track(&TrackConfig {
+                language: language.to_string(),
+                alternate_group: 1,
+                ..TrackConfig::from(AacConfig::default())
+            })
+            .unwrap();
+    }
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    for track_id in 1..=2 {
+        let track = mp4.tracks().get(&track_id).unwrap();
+        assert_eq!(track.alternate_group(), 1);
+        assert_eq!(track.layer(), 0);
+    }
+}
+
+#[test]
+fn test_write_audio_balance() {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    writer
+        .add_track(&TrackConfig::from(AacConfig::default()).audio_balance(-0.5))
+        .unwrap();
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let smhd = mp4.tracks().get(&1).unwrap().trak.mdia.minf.smhd.as_ref().unwrap();
+    assert_eq!(smhd.balance(), -0.5);
+}
+
+#[test]
+fn test_write_data_reference_index() {
+    let mut track_conf = TrackConfig::from(AacConfig::default());
+    track_conf.data_references = vec![
+        String::new(),
+        String::from("http://example.com/media.mp4"),
+    ];
+    track_conf.data_reference_index = 2;
+
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    writer.add_track(&track_conf).unwrap();
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let size = data.len() as u64;
+    let mp4 = mp4::Mp4Reader::read_header(Cursor::new(data), size).unwrap();
+
+    let track = mp4.tracks().get(&1).unwrap();
+    let dref = &track.trak.mdia.minf.dinf.dref;
+    assert_eq!(dref.entries.len(), 2);
+    assert!(dref.entries[0].is_self_contained());
+    assert!(!dref.entries[1].is_self_contained());
+
+    let mp4a = track.trak.mdia.minf.stbl.stsd.mp4a.as_ref().unwrap();
+    assert_eq!(mp4a.data_reference_index, 2);
+}
+
+#[test]
+fn test_is_self_contained() {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    for data_reference_index in 1..=2 {
+        let mut track_conf = TrackConfig::from(AacConfig::default());
+        track_conf.data_references = vec![
+            String::new(),
+            String::from("http://example.com/media.mp4"),
+        ];
+        track_conf.data_reference_index = data_reference_index;
+        writer.add_track(&track_conf).unwrap();
+    }
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    assert!(mp4.tracks().get(&1).unwrap().is_self_contained());
+    assert!(!mp4.tracks().get(&2).unwrap().is_self_contained());
+}
+
+#[test]
+fn test_write_data_reference_index_out_of_range() {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+
+    let mut track_conf = TrackConfig::from(AacConfig::default());
+    track_conf.data_reference_index = 2;
+    assert!(writer.add_track(&track_conf).is_err());
+
+    track_conf.data_reference_index = 0;
+    assert!(writer.add_track(&track_conf).is_err());
+}
+
+#[test]
+fn test_read_mp4_from_bytes() {
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    assert_eq!(mp4.size(), data.len() as u64);
+    assert_eq!(mp4.tracks().len(), 2);
+
+    let sample = mp4.read_sample(1, 1).unwrap().unwrap();
+    assert_eq!(sample.bytes.len(), 751);
+
+    let mp4 = mp4::Mp4Reader::from_reader(Cursor::new(data.clone())).unwrap();
+    assert_eq!(mp4.size(), data.len() as u64);
+    assert_eq!(mp4.tracks().len(), 2);
+}
+
+fn write_aac_samples(sizes: &[usize]) -> Vec<u8> {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    writer
+        .add_track(&TrackConfig::from(AacConfig::default()))
+        .unwrap();
+    for (i, size) in sizes.iter().enumerate() {
+        let sample = mp4::Mp4Sample {
+            start_time: i as u64 * 1024,
+            duration: 1024,
+            rendering_offset: 0,
+            is_sync: true,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(vec![i as u8; *size]),
+        };
+        writer.write_sample(1, &sample).unwrap();
+    }
+    writer.write_end().unwrap();
+    writer.into_writer().into_inner()
+}
+
+#[test]
+fn test_write_atomic() {
+    let dir = std::env::temp_dir().join(format!("mp4-write-atomic-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("out.mp4");
+    std::fs::write(&path, b"original").unwrap();
+    let sample = mp4::Mp4Sample {
+        start_time: 0,
+        duration: 1024,
+        rendering_offset: 0,
+        is_sync: true,
+        sample_description_index: 1,
+        bytes: bytes::Bytes::from(vec![0; 100]),
+    };
+
+    // A failure part way through leaves the existing file alone.
+    let result = Mp4Writer::write_atomic(&path, &mp4_config(), |writer| {
+        writer.add_track(&TrackConfig::from(AacConfig::default()))?;
+        writer.write_sample(1, &sample)?;
+        writer.write_sample(2, &sample)
+    });
+    assert!(matches!(result, Err(mp4::Error::TrakNotFound(2))));
+    assert_eq!(std::fs::read(&path).unwrap(), b"original");
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    Mp4Writer::write_atomic(&path, &mp4_config(), |writer| {
+        writer.add_track(&TrackConfig::from(AacConfig::default()))?;
+        writer.write_sample(1, &sample)
+    })
+    .unwrap();
+    let data = std::fs::read(&path).unwrap();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    assert_eq!(mp4.tracks().get(&1).unwrap().sample_count(), 1);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_write_stsz_uniform_size() {
+    let data = write_aac_samples(&[100, 100, 100]);
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    let stsz = &mp4.tracks().get(&1).unwrap().trak.mdia.minf.stbl.stsz;
+    assert_eq!(stsz.sample_size, 100);
+    assert_eq!(stsz.sample_count, 3);
+    assert!(stsz.sample_sizes.is_empty());
+
+    for sample_id in 1..=3 {
+        let sample = mp4.read_sample(1, sample_id).unwrap().unwrap();
+        assert_eq!(sample.bytes.len(), 100);
+        assert_eq!(sample.bytes[0], sample_id as u8 - 1);
+    }
+    assert!(mp4.read_sample(1, 4).unwrap().is_none());
+}
+
+#[test]
+fn test_write_stsz_per_sample_sizes() {
+    let data = write_aac_samples(&[100, 100, 37]);
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    let stsz = &mp4.tracks().get(&1).unwrap().trak.mdia.minf.stbl.stsz;
+    assert_eq!(stsz.sample_size, 0);
+    assert_eq!(stsz.sample_count, 3);
+    assert_eq!(stsz.sample_sizes, vec![100, 100, 37]);
+
+    let sizes: Vec<usize> = (1..=3)
+        .map(|sample_id| mp4.read_sample(1, sample_id).unwrap().unwrap().bytes.len())
+        .collect();
+    assert_eq!(sizes, vec![100, 100, 37]);
+}
+
+#[test]
+fn test_keyframe_intervals() {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    let vp9_config = mp4::Vp9Config {
+        width: 320,
+        height: 240,
+        ..mp4::Vp9Config::default()
+    };
+    writer.add_track(&TrackConfig::from(vp9_config)).unwrap();
+    for i in 0..75u32 {
+        let sample = mp4::Mp4Sample {
+            start_time: i as u64 * 40,
+            duration: 40,
+            rendering_offset: 0,
+            is_sync: i % 30 == 0,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(vec![0u8; 10]),
+        };
+        writer.write_sample(1, &sample).unwrap();
+    }
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let track = mp4.tracks().get(&1).unwrap();
+    assert_eq!(track.keyframe_intervals(), vec![30, 30]);
+    assert_eq!(track.max_gop_size(), 30);
+}
+
+#[test]
+fn test_frame_and_gop_count() {
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    assert_eq!(mp4.total_frames(), 1);
+    assert_eq!(mp4.tracks().get(&1).unwrap().gop_count(), 1);
+    assert_eq!(mp4.tracks().get(&2).unwrap().frame_count(), 0);
+
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    let vp9_config = mp4::Vp9Config {
+        width: 320,
+        height: 240,
+        ..mp4::Vp9Config::default()
+    };
+    writer.add_track(&TrackConfig::from(vp9_config)).unwrap();
+    for i in 0..75u32 {
+        let sample = mp4::Mp4Sample {
+            start_time: i as u64 * 40,
+            duration: 40,
+            rendering_offset: 0,
+            is_sync: i % 30 == 0,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(vec![0u8; 10]),
+        };
+        writer.write_sample(1, &sample).unwrap();
+    }
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let track = mp4.tracks().get(&1).unwrap();
+    assert_eq!(track.frame_count(), 75);
+    assert_eq!(track.gop_count(), 3);
+    assert_eq!(mp4.total_frames(), 75);
+}
+
+#[test]
+fn test_slice_header_boxes() {
+    use mp4::slice::{self, SliceBox};
+    use mp4::{FtypBox, HdlrBox, MdhdBox, MvhdBox, TkhdBox};
+
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    let top: Vec<SliceBox> = slice::boxes(&data).map(|b| b.unwrap()).collect();
+    let ftyp: FtypBox = top.iter().find(|b| &b.box_type == b"ftyp").unwrap().read().unwrap();
+    assert_eq!(ftyp, mp4.ftyp);
+
+    let moov = top.iter().find(|b| &b.box_type == b"moov").unwrap();
+    let mvhd: MvhdBox = moov.child(0, *b"mvhd").unwrap().unwrap().read().unwrap();
+    assert_eq!(mvhd, mp4.moov.mvhd);
+
+    let traks: Vec<SliceBox> = moov.children(0)
+        .map(|b| b.unwrap())
+        .filter(|b| &b.box_type == b"trak")
+        .collect();
+    assert_eq!(traks.len(), mp4.moov.traks.len());
+    for (trak, expected) in traks.iter().zip(mp4.moov.traks.iter()) {
+        let tkhd: TkhdBox = trak.child(0, *b"tkhd").unwrap().unwrap().read().unwrap();
+        assert_eq!(tkhd, expected.tkhd);
+
+        let mdia = trak.child(0, *b"mdia").unwrap().unwrap();
+        let mdhd: MdhdBox = mdia.child(0, *b"mdhd").unwrap().unwrap().read().unwrap();
+        assert_eq!(mdhd, expected.mdia.mdhd);
+        let hdlr: HdlrBox = mdia.child(0, *b"hdlr").unwrap().unwrap().read().unwrap();
+        assert_eq!(hdlr, expected.mdia.hdlr);
+    }
+}
+
+#[test]
+fn test_to_json_tree() {
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let json = mp4.to_json_tree().unwrap();
+
+    let tree: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(tree["ftyp"]["major_brand"], "isom");
+
+    let traks = tree["moov"]["trak"].as_array().unwrap();
+    assert_eq!(traks.len(), 2);
+    assert_eq!(traks[0]["tkhd"]["track_id"], 1);
+    assert_eq!(traks[0]["tkhd"]["width"], 320.0);
+    assert!(traks[0]["mdia"]["minf"]["stbl"]["stsd"]["avc1"].is_object());
+    assert!(traks[1]["mdia"]["minf"]["stbl"]["stsd"]["mp4a"].is_object());
+    assert_eq!(traks[1]["mdia"]["hdlr"]["handler_type"], "soun");
+}
+
+#[test]
+fn test_write_vp9_track() {
+    let vp9_config = mp4::Vp9Config {
+        width: 1920,
+        height: 1080,
+        profile: 2,
+        level: 41,
+        bit_depth: 10,
+        chroma_subsampling: 1,
+        video_full_range_flag: true,
+        color_primaries: 9,
+        transfer_characteristics: 16,
+        matrix_coefficients: 9,
+    };
+
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    writer.add_track(&TrackConfig::from(vp9_config.clone())).unwrap();
+    let sample = mp4::Mp4Sample {
+        start_time: 0,
+        duration: 40,
+        rendering_offset: 0,
+        is_sync: true,
+        sample_description_index: 1,
+        bytes: bytes::Bytes::from(vec![0u8; 10]),
+    };
+    writer.write_sample(1, &sample).unwrap();
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let track = mp4.tracks().get(&1).unwrap();
+    assert_eq!(track.media_type().unwrap(), MediaType::VP9);
+    assert_eq!(track.width(), 1920);
+    assert_eq!(track.height(), 1080);
+
+    let vpcc = &track.trak.mdia.minf.stbl.stsd.vp09.as_ref().unwrap().vpcc;
+    assert_eq!(vpcc.profile, vp9_config.profile);
+    assert_eq!(vpcc.level, vp9_config.level);
+    assert_eq!(vpcc.bit_depth, vp9_config.bit_depth);
+    assert_eq!(vpcc.chroma_subsampling, vp9_config.chroma_subsampling);
+    assert_eq!(vpcc.video_full_range_flag, vp9_config.video_full_range_flag);
+    assert_eq!(vpcc.color_primaries, vp9_config.color_primaries);
+    assert_eq!(vpcc.transfer_characteristics, vp9_config.transfer_characteristics);
+    assert_eq!(vpcc.matrix_coefficients, vp9_config.matrix_coefficients);
+}
+
+#[test]
+fn test_sample_offset_progressive() {
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    let track1 = mp4.tracks().get(&1).unwrap();
+    assert_eq!(track1.sample_offset(1).unwrap(), 1500);
+
+    // Samples 2 and 3 of the audio track share the second chunk.
+    let track2 = mp4.tracks().get(&2).unwrap();
+    assert_eq!(track2.sample_offset(1).unwrap(), 1321);
+    assert_eq!(track2.sample_offset(2).unwrap(), 2251);
+    assert_eq!(track2.sample_offset(3).unwrap(), 2251 + 180);
+}
+
+#[test]
+fn test_rebuild_chunk_offsets() {
+    let mut data = write_aac_samples(&[100, 120, 80]);
+    let original = mp4::Mp4Reader::from_bytes(&data)
+        .unwrap()
+        .tracks()
+        .get(&1)
+        .unwrap()
+        .trak
+        .mdia
+        .minf
+        .stbl
+        .stco
+        .clone()
+        .unwrap();
+    assert!(!original.entries.is_empty());
+
+    // Zero out every stco entry.
+    let pos = data.windows(4).position(|w| w == b"stco").unwrap();
+    let entries_start = pos + 4 + 4 + 4;
+    let entries_end = entries_start + 4 * original.entries.len();
+    data[entries_start..entries_end].iter_mut().for_each(|b| *b = 0);
+
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    assert!(mp4.rebuild_chunk_offsets().unwrap());
+    assert!(!mp4.rebuild_chunk_offsets().unwrap());
+
+    let stco = mp4.tracks().get(&1).unwrap().trak.mdia.minf.stbl.stco.as_ref().unwrap();
+    assert_eq!(stco.entries, original.entries);
+    assert_eq!(mp4.moov.traks[0].mdia.minf.stbl.stco.as_ref().unwrap(), &original);
+
+    for (i, size) in [100, 120, 80].iter().enumerate() {
+        let sample = mp4.read_sample(1, i as u32 + 1).unwrap().unwrap();
+        assert_eq!(sample.bytes.len(), *size);
+        assert!(sample.bytes.iter().all(|b| *b == i as u8));
+    }
+}
+
+#[test]
+fn test_validate_chunk_offsets() {
+    let mut data = write_aac_samples(&[100, 120, 80]);
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    assert_eq!(mp4.validate_chunk_offsets().unwrap(), None);
+    let chunk_count = mp4.moov.traks[0].mdia.minf.stbl.stco.as_ref().unwrap().entries.len();
+
+    // Point the last chunk at the moov box.
+    let moov_offset = (data.windows(4).position(|w| w == b"moov").unwrap() - 4) as u32;
+    let pos = data.windows(4).position(|w| w == b"stco").unwrap();
+    let entry = pos + 4 + 4 + 4 + 4 * (chunk_count - 1);
+    data[entry..entry + 4].copy_from_slice(&moov_offset.to_be_bytes());
+
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let chunk = mp4.validate_chunk_offsets().unwrap().unwrap();
+    assert_eq!(chunk.track_id, 1);
+    assert_eq!(chunk.chunk_id, chunk_count as u32);
+    assert_eq!(chunk.offset, moov_offset as u64);
+
+    assert!(mp4.rebuild_chunk_offsets().unwrap());
+    assert_eq!(mp4.validate_chunk_offsets().unwrap(), None);
+}
+
+#[test]
+fn test_fragment_into_segments() {
+    // 6 seconds of 25 fps video with a sync sample every second, plus AAC
+    // audio at 48 kHz.
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    let vp9_config = mp4::Vp9Config {
+        width: 320,
+        height: 240,
+        ..mp4::Vp9Config::default()
+    };
+    writer.add_track(&TrackConfig::from(vp9_config)).unwrap();
+    writer
+        .add_track(&TrackConfig {
+            timescale: 48000,
+            ..TrackConfig::from(mp4::AacConfig::default())
+        })
+        .unwrap();
+    for i in 0..150u32 {
+        let sample = mp4::Mp4Sample {
+            start_time: i as u64 * 40,
+            duration: 40,
+            rendering_offset: 0,
+            is_sync: i % 25 == 0,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(i.to_be_bytes().to_vec()),
+        };
+        writer.write_sample(1, &sample).unwrap();
+    }
+    for i in 0..282u32 {
+        let sample = mp4::Mp4Sample {
+            start_time: i as u64 * 1024,
+            duration: 1024,
+            rendering_offset: 0,
+            is_sync: true,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(vec![0xAA; 8]),
+        };
+        writer.write_sample(2, &sample).unwrap();
+    }
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let (init, segments) = mp4.fragment(Duration::from_secs(2)).unwrap();
+    assert_eq!(segments.len(), 3);
+
+    let mut audio_samples = 0;
+    for (i, segment) in segments.iter().enumerate() {
+        let file = [init.as_slice(), segment.as_slice()].concat();
+        let mut fragment = mp4::Mp4Reader::from_bytes(&file).unwrap();
+        assert!(fragment.is_dash());
+        assert_eq!(fragment.moofs.len(), 1);
+        assert_eq!(fragment.moofs[0].sequence_number(), i as u32 + 1);
+
+        let trafs = &fragment.moofs[0].trafs;
+        assert_eq!(trafs.len(), 2);
+        assert_eq!(trafs[0].tfdt.as_ref().unwrap().base_media_decode_time, i as u64 * 2000);
+        assert_eq!(trafs[0].trun.as_ref().unwrap().sample_count, 50);
+        audio_samples += trafs[1].trun.as_ref().unwrap().sample_count;
+
+        // Each segment starts with the sync sample at its boundary.
+        let sample = fragment.read_sample(1, 1).unwrap().unwrap();
+        assert_eq!(sample.bytes.as_ref(), &(i as u32 * 50).to_be_bytes());
+    }
+    assert_eq!(audio_samples, 282);
+}
+
+#[test]
+fn test_fix_decode_times() {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    let vp9_config = mp4::Vp9Config {
+        width: 320,
+        height: 240,
+        ..mp4::Vp9Config::default()
+    };
+    writer.add_track(&TrackConfig::from(vp9_config)).unwrap();
+    for i in 0..100u32 {
+        let sample = mp4::Mp4Sample {
+            start_time: i as u64 * 40,
+            duration: 40,
+            rendering_offset: 0,
+            is_sync: i % 25 == 0,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(i.to_be_bytes().to_vec()),
+        };
+        writer.write_sample(1, &sample).unwrap();
+    }
+    writer.write_end().unwrap();
+    let data = writer.into_writer().into_inner();
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let (init, segments) = mp4.fragment(Duration::from_secs(2)).unwrap();
+    assert_eq!(segments.len(), 2);
+
+    // Append the first segment again, so its tfdt goes back to zero.
+    let file = [&init[..], &segments[0], &segments[1], &segments[0]].concat();
+    let mut stream = mp4::Mp4Reader::from_bytes(&file).unwrap();
+    let discontinuity = mp4::DecodeTimeDiscontinuity {
+        track_id: 1,
+        sequence_number: 1,
+        expected: 4000,
+        base_media_decode_time: 0,
+    };
+    assert_eq!(stream.validate_decode_times(), Some(discontinuity));
+
+    assert_eq!(stream.fix_decode_times(), vec![discontinuity]);
+    assert_eq!(stream.validate_decode_times(), None);
+    let tfdt = stream.moofs[2].trafs[0].tfdt.as_ref().unwrap();
+    assert_eq!(tfdt.base_media_decode_time, 4000);
+    assert_eq!(stream.tracks().get(&1).unwrap().trafs[2].tfdt, stream.moofs[2].trafs[0].tfdt);
+
+    let mut fixed = Vec::new();
+    assert_eq!(stream.write_fixed_fragments(&mut fixed).unwrap(), file.len() as u64);
+    assert_eq!(fixed.len(), file.len());
+    let mut fixed = mp4::Mp4Reader::from_bytes(&fixed).unwrap();
+    assert_eq!(fixed.validate_decode_times(), None);
+    let tfdt = fixed.moofs[2].trafs[0].tfdt.as_ref().unwrap();
+    assert_eq!(tfdt.base_media_decode_time, 4000);
+    let sample = fixed.read_sample(1, 101).unwrap().unwrap();
+    assert_eq!(sample.bytes.as_ref(), &0u32.to_be_bytes());
+}
+
+#[test]
+fn test_fragment_out_of_band_parameter_sets() {
+    let vps: &[u8] = &[0x40, 0x01, 0x0c, 0x01];
+    let sps: &[u8] = &[0x42, 0x01, 0x01, 0x01];
+    let pps: &[u8] = &[0x44, 0x01, 0xc1, 0x72];
+    let idr: &[u8] = &[0x26, 0x01, 0xaf, 0x06];
+    let trail: &[u8] = &[0x02, 0x01, 0xd0, 0x09];
+    let length_prefixed = |nals: &[&[u8]]| {
+        let mut sample = Vec::new();
+        for nal in nals {
+            sample.extend_from_slice(&(nal.len() as u32).to_be_bytes());
+            sample.extend_from_slice(nal);
+        }
+        sample
+    };
+
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    let hevc_config = mp4::HevcConfig {
+        width: 320,
+        height: 240,
+        ..mp4::HevcConfig::default()
+    };
+    writer.add_track(&TrackConfig::from(hevc_config)).unwrap();
+    for i in 0..4u32 {
+        let nals = if i % 2 == 0 { vec![vps, sps, pps, idr] } else { vec![trail] };
+        let sample = mp4::Mp4Sample {
+            start_time: i as u64 * 40,
+            duration: 40,
+            rendering_offset: 0,
+            is_sync: i % 2 == 0,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(length_prefixed(&nals)),
+        };
+        writer.write_sample(1, &sample).unwrap();
+    }
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let options = mp4::FragmentOptions {
+        out_of_band_parameter_sets: true,
+    };
+    let (init, segments) = mp4
+        .fragment_with_options(Duration::from_millis(80), &options, |_, _| {})
+        .unwrap();
+    assert_eq!(segments.len(), 2);
+
+    let init_mp4 = mp4::Mp4Reader::from_bytes(&init).unwrap();
+    let track = init_mp4.tracks().get(&1).unwrap();
+    assert_eq!(track.box_type().unwrap().to_string(), "hvc1");
+    let hvcc = &track.trak.mdia.minf.stbl.stsd.hev1.as_ref().unwrap().hvcc;
+    for (nal_unit_type, nal) in [(32, vps), (33, sps), (34, pps)] {
+        let array = hvcc.array(nal_unit_type).unwrap();
+        assert!(array.completeness);
+        assert_eq!(array.nalus.len(), 1);
+        assert_eq!(array.nalus[0].bytes, nal);
+    }
+
+    for segment in segments.iter() {
+        let file = [init.as_slice(), segment.as_slice()].concat();
+        let mut fragment = mp4::Mp4Reader::from_bytes(&file).unwrap();
+        let sync = fragment.read_sample(1, 1).unwrap().unwrap();
+        assert_eq!(sync.bytes.as_ref(), length_prefixed(&[idr]).as_slice());
+        let other = fragment.read_sample(1, 2).unwrap().unwrap();
+        assert_eq!(other.bytes.as_ref(), length_prefixed(&[trail]).as_slice());
+    }
+}
+
+#[test]
+fn test_write_empty() {
+    let avc_config = mp4::AvcConfig {
+        width: 320,
+        height: 240,
+        seq_param_set: vec![0x67, 0x64, 0x00, 0x0D, 0xAC],
+        pic_param_set: vec![0x68, 0xEE, 0x3C, 0x80],
+    };
+    let data = Mp4Writer::empty(mp4::MediaConfig::AvcConfig(avc_config)).unwrap();
+
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    assert_eq!(mp4.tracks().len(), 1);
+    let track = mp4.tracks().get(&1).unwrap();
+    assert_eq!(track.sample_count(), 0);
+    assert_eq!(track.box_type().unwrap().to_string(), "avc1");
+    assert_eq!(track.track_type().unwrap(), TrackType::Video);
+    assert_eq!(track.width(), 320);
+    assert_eq!(track.sequence_parameter_set().unwrap(), &[0x67, 0x64, 0x00, 0x0D, 0xAC]);
+    assert_eq!(track.chunks().unwrap().len(), 0);
+}
+
+#[test]
+fn test_write_vmhd_flags() {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    let vp9_config = mp4::Vp9Config {
+        width: 320,
+        height: 240,
+        ..mp4::Vp9Config::default()
+    };
+    writer.add_track(&TrackConfig::from(vp9_config)).unwrap();
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let vmhd = mp4.tracks().get(&1).unwrap().trak.mdia.minf.vmhd.as_ref().unwrap();
+    assert_eq!(vmhd.flags, 1);
+}
+
+#[test]
+fn test_probe_track_types() {
+    let f = File::open("tests/samples/minimal.mp4").unwrap();
+    let size = f.metadata().unwrap().len();
+    let track_types = mp4::Mp4Reader::probe_track_types(BufReader::new(f), size).unwrap();
+    assert_eq!(track_types, vec![TrackType::Video, TrackType::Audio]);
+    let f = File::open("tests/samples/minimal.mp4").unwrap();
+    assert!(mp4::Mp4Reader::has_audio(BufReader::new(f), size).unwrap());
+
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    let vp9_config = mp4::Vp9Config {
+        width: 320,
+        height: 240,
+        ..mp4::Vp9Config::default()
+    };
+    writer.add_track(&TrackConfig::from(vp9_config)).unwrap();
+    for i in 0..4u32 {
+        let sample = mp4::Mp4Sample {
+            start_time: i as u64 * 40,
+            duration: 40,
+            rendering_offset: 0,
+            is_sync: i == 0,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(vec![0u8; 10]),
+        };
+        writer.write_sample(1, &sample).unwrap();
+    }
+    writer.write_end().unwrap();
+    let data = writer.into_writer().into_inner();
+
+    let size = data.len() as u64;
+    let track_types = mp4::Mp4Reader::probe_track_types(Cursor::new(&data), size).unwrap();
+    assert_eq!(track_types, vec![TrackType::Video]);
+    assert!(mp4::Mp4Reader::has_video(Cursor::new(&data), size).unwrap());
+    assert!(!mp4::Mp4Reader::has_audio(Cursor::new(&data), size).unwrap());
+    assert!(!mp4::Mp4Reader::has_subtitles(Cursor::new(&data), size).unwrap());
+
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let (init, segments) = mp4.fragment(Duration::from_secs(1)).unwrap();
+    let fragmented = [init, segments.concat()].concat();
+    let size = fragmented.len() as u64;
+    let track_types = mp4::Mp4Reader::probe_track_types(Cursor::new(&fragmented), size).unwrap();
+    assert_eq!(track_types, vec![TrackType::Video]);
+    assert!(mp4::Mp4Reader::has_video(Cursor::new(&fragmented), size).unwrap());
+}
+
+#[test]
+fn test_traf_from_samples() {
+    use mp4::{Mp4Box, WriteBox};
+
+    let samples: Vec<mp4::Mp4Sample> = (0..3u8)
+        .map(|i| mp4::Mp4Sample {
+            start_time: i as u64 * 1024,
+            duration: 1024,
+            rendering_offset: 0,
+            is_sync: true,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(vec![i; 10]),
+        })
+        .collect();
+    let traf = mp4::TrafBox::from_samples(1, 0, &samples);
+    assert_eq!(traf.sample_size(2), Some(10));
+    assert_eq!(traf.duration(0), 3072);
+
+    let mut buf = Vec::new();
+    traf.write_box(&mut buf).unwrap();
+    assert_eq!(buf.len() as u64, traf.box_size());
+    assert_eq!(&buf[4..8], b"traf");
+}
+
+#[test]
+fn test_write_durations_from_samples() {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    let vp9_config = mp4::Vp9Config {
+        width: 320,
+        height: 240,
+        ..mp4::Vp9Config::default()
+    };
+    writer
+        .add_track(&TrackConfig {
+            timescale: 30,
+            ..TrackConfig::from(vp9_config)
+        })
+        .unwrap();
+    for i in 0..30u32 {
+        let sample = mp4::Mp4Sample {
+            start_time: i as u64,
+            duration: 1,
+            rendering_offset: 0,
+            is_sync: i == 0,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(vec![0u8; 10]),
+        };
+        writer.write_sample(1, &sample).unwrap();
+    }
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let track = mp4.tracks().get(&1).unwrap();
+    assert_eq!(track.trak.mdia.mdhd.duration, 30);
+    assert_eq!(mp4.timescale(), 1000);
+    assert_eq!(track.trak.tkhd.duration, 1000);
+    assert_eq!(mp4.moov.mvhd.duration, 1000);
+    assert_eq!(mp4.duration(), Duration::from_secs(1));
+}
+
+#[test]
+fn test_find_box_by_path() {
+    use mp4::Mp4Box;
+
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    let stsz = mp4.find("moov/trak[2]/mdia/minf/stbl/stsz").unwrap();
+    assert_eq!(mp4::FourCC::from(stsz.box_type()).to_string(), "stsz");
+    let track = mp4.tracks().get(&2).unwrap();
+    assert_eq!(stsz.box_size(), track.trak.mdia.minf.stbl.stsz.box_size());
+    assert_eq!(stsz.summary().unwrap(), track.trak.mdia.minf.stbl.stsz.summary().unwrap());
+
+    // Without an index the first matching box is used.
+    let avc1 = mp4.find("moov/trak/mdia/minf/stbl/stsd/avc1").unwrap();
+    assert_eq!(mp4::FourCC::from(avc1.box_type()).to_string(), "avc1");
+
+    assert!(mp4.find("moov/trak[3]").is_none());
+    assert!(mp4.find("moov/trak[0]").is_none());
+    assert!(mp4.find("moov/free").is_none());
+}
+
+#[test]
+fn test_final_sample_duration() {
+    for (policy, expected) in [
+        (mp4::FinalSampleDuration::Unchanged, 0),
+        (mp4::FinalSampleDuration::RepeatPrevious, 1024),
+        (mp4::FinalSampleDuration::Explicit(512), 512),
+    ] {
+        let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+        writer.set_final_sample_duration(policy);
+        writer
+            .add_track(&TrackConfig {
+                timescale: 48000,
+                ..TrackConfig::from(AacConfig::default())
+            })
+            .unwrap();
+        for i in 0..3u64 {
+            // The capture ended before the duration of the last sample was known.
+            let sample = mp4::Mp4Sample {
+                start_time: i * 1024,
+                duration: if i < 2 { 1024 } else { 0 },
+                rendering_offset: 0,
+                is_sync: true,
+                sample_description_index: 1,
+                bytes: bytes::Bytes::from(vec![0u8; 10]),
+            };
+            writer.write_sample(1, &sample).unwrap();
+        }
+        writer.write_end().unwrap();
+
+        let data = writer.into_writer().into_inner();
+        let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+        let track = mp4.tracks().get(&1).unwrap();
+        let stts = &track.trak.mdia.minf.stbl.stts;
+        let total: u32 = stts.entries.iter().map(|e| e.sample_count).sum();
+        assert_eq!(total, 3);
+        assert_eq!(track.trak.mdia.mdhd.duration, 2048 + expected as u64);
+
+        let last = mp4.read_sample(1, 3).unwrap().unwrap();
+        assert_eq!(last.duration, expected);
+    }
+}
+
+#[test]
+fn test_ftyp_is_compatible_with() {
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    for brand in ["isom", "iso2", "avc1", "mp41"] {
+        assert!(mp4.ftyp.is_compatible_with(str::parse(brand).unwrap()), "{}", brand);
+    }
+    for brand in ["dash", "iso5", "qt  "] {
+        assert!(!mp4.ftyp.is_compatible_with(str::parse(brand).unwrap()), "{}", brand);
+    }
+    assert_eq!(mp4.ftyp.compatible_brands.len(), 4);
+}
+
+#[test]
+fn test_write_faststart() {
+    let data = write_aac_samples(&[100, 200, 300]);
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    let mut buf = Vec::new();
+    let size = mp4.write_faststart(&mut buf).unwrap();
+    assert_eq!(size, buf.len() as u64);
+    assert_eq!(size, data.len() as u64);
+    assert_eq!(&buf[mp4.ftyp.get_size() as usize + 4..][..4], b"moov");
+
+    let mut fast = mp4::Mp4Reader::from_bytes(&buf).unwrap();
+    for sample_id in 1..=3 {
+        let expected = mp4.read_sample(1, sample_id).unwrap().unwrap();
+        let sample = fast.read_sample(1, sample_id).unwrap().unwrap();
+        assert_eq!(sample.bytes, expected.bytes);
+    }
+}
+
+#[test]
+fn test_stale_moov() {
+    // The sample file is ftyp, moov, free and mdat. Blank out its moov by
+    // renaming it to free, then append the real moov after the mdat.
+    let original = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let moov = &original[32..32 + 1273];
+    assert_eq!(&moov[4..8], b"moov");
+    let mut data = original.clone();
+    data[36..40].copy_from_slice(b"free");
+    data.extend_from_slice(moov);
+
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let free_space = mp4.free_space();
+    assert_eq!(free_space.len(), 2);
+    assert_eq!((free_space[0].offset, free_space[0].size), (32, 1273));
+    assert!(free_space[0].stale_moov);
+    assert_eq!((free_space[1].offset, free_space[1].size), (1305, 8));
+    assert!(!free_space[1].stale_moov);
+    assert_eq!(mp4.wasted_space(), 1281);
+
+    let mut buf = Vec::new();
+    let size = mp4.write_compacted(&mut buf).unwrap();
+    assert_eq!(size, mp4.ftyp.get_size() + mp4.moov.get_size() + 1278);
+    assert_eq!(size, buf.len() as u64);
+
+    let mut compact = mp4::Mp4Reader::from_bytes(&buf).unwrap();
+    assert!(compact.free_space().is_empty());
+    for track_id in 1..=2 {
+        for sample_id in 1..=mp4.sample_count(track_id).unwrap() {
+            let expected = mp4.read_sample(track_id, sample_id).unwrap().unwrap();
+            let sample = compact.read_sample(track_id, sample_id).unwrap().unwrap();
+            assert_eq!(sample.bytes, expected.bytes);
+        }
+    }
+}
+
+#[test]
+fn test_remove_track() {
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    assert_eq!(mp4.tracks().len(), 2);
+
+    let mut buf = Vec::new();
+    let size = mp4.remove_track(2, &mut buf).unwrap();
+    assert_eq!(size, buf.len() as u64);
+    assert!(buf.len() < data.len());
+    assert!(mp4.remove_track(3, &mut Vec::new()).is_err());
+
+    let size = buf.len() as u64;
+    assert!(mp4::Mp4Reader::has_video(Cursor::new(&buf), size).unwrap());
+    assert!(!mp4::Mp4Reader::has_audio(Cursor::new(&buf), size).unwrap());
+    let mut video = mp4::Mp4Reader::from_bytes(&buf).unwrap();
+    assert_eq!(video.tracks().len(), 1);
+    assert_eq!(video.moov.mvhd.next_track_id, 2);
+    assert_eq!(video.moov.mvhd.duration, mp4.moov.traks[0].tkhd.duration);
+    for sample_id in 1..=mp4.sample_count(1).unwrap() {
+        let expected = mp4.read_sample(1, sample_id).unwrap().unwrap();
+        let sample = video.read_sample(1, sample_id).unwrap().unwrap();
+        assert_eq!(sample.bytes, expected.bytes);
+    }
+}
+
+#[test]
+fn test_keyframes_only() {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    let vp9_config = mp4::Vp9Config {
+        width: 320,
+        height: 240,
+        ..mp4::Vp9Config::default()
+    };
+    writer.add_track(&TrackConfig::from(vp9_config)).unwrap();
+    for i in 0..300u32 {
+        let sample = mp4::Mp4Sample {
+            start_time: i as u64 * 40,
+            duration: 40,
+            rendering_offset: 0,
+            is_sync: i % 30 == 0,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(i.to_be_bytes().to_vec()),
+        };
+        writer.write_sample(1, &sample).unwrap();
+    }
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let mut buf = Vec::new();
+    let size = mp4.keyframes_only(&mut buf).unwrap();
+    assert_eq!(size, buf.len() as u64);
+
+    let mut keyframes = mp4::Mp4Reader::from_bytes(&buf).unwrap();
+    assert_eq!(keyframes.sample_count(1).unwrap(), 10);
+    let track = keyframes.tracks().get(&1).unwrap();
+    assert_eq!(track.duration(), mp4.tracks().get(&1).unwrap().duration());
+    assert_eq!(track.keyframe_intervals(), vec![1; 9]);
+    for sample_id in 1..=10 {
+        let sample = keyframes.read_sample(1, sample_id).unwrap().unwrap();
+        assert_eq!(sample.start_time, (sample_id as u64 - 1) * 1200);
+        assert_eq!(sample.duration, 1200);
+        assert!(sample.is_sync);
+        assert_eq!(sample.bytes, ((sample_id - 1) * 30).to_be_bytes().to_vec());
+    }
+}
+
+#[test]
+fn test_split_at() {
+    // Nine seconds of video with a sync sample every second, and audio.
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    let vp9_config = mp4::Vp9Config {
+        width: 320,
+        height: 240,
+        ..mp4::Vp9Config::default()
+    };
+    writer.add_track(&TrackConfig::from(vp9_config)).unwrap();
+    writer
+        .add_track(&TrackConfig {
+            timescale: 1000,
+            ..TrackConfig::from(mp4::AacConfig::default())
+        })
+        .unwrap();
+    let video = (0..225u32).map(|i| mp4::Mp4Sample {
+        start_time: i as u64 * 40,
+        duration: 40,
+        rendering_offset: 0,
+        is_sync: i % 25 == 0,
+        sample_description_index: 1,
+        bytes: bytes::Bytes::from(i.to_be_bytes().to_vec()),
+    });
+    writer.write_samples(1, video).unwrap();
+    let audio = (0..90u32).map(|i| mp4::Mp4Sample {
+        start_time: i as u64 * 100,
+        duration: 100,
+        rendering_offset: 0,
+        is_sync: true,
+        sample_description_index: 1,
+        bytes: bytes::Bytes::from((1000 + i).to_be_bytes().to_vec()),
+    });
+    writer.write_samples(2, audio).unwrap();
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let clips = mp4
+        .split_at(&[Duration::from_secs(3), Duration::from_secs(6)])
+        .unwrap();
+    assert_eq!(clips.len(), 3);
+
+    for (k, clip) in clips.iter().enumerate() {
+        let mut clip = mp4::Mp4Reader::from_bytes(clip).unwrap();
+        assert_eq!(clip.duration(), Duration::from_secs(3));
+        assert_eq!(clip.sample_count(1).unwrap(), 75);
+        assert_eq!(clip.sample_count(2).unwrap(), 30);
+        assert_eq!(clip.tracks().get(&1).unwrap().keyframe_intervals(), vec![25, 25]);
+
+        let first = clip.read_sample(1, 1).unwrap().unwrap();
+        assert!(first.is_sync);
+        assert_eq!(first.start_time, 0);
+        for sample_id in 1..=75 {
+            let sample = clip.read_sample(1, sample_id).unwrap().unwrap();
+            let expected = k as u32 * 75 + sample_id - 1;
+            assert_eq!(sample.bytes, expected.to_be_bytes().to_vec());
+        }
+        for sample_id in 1..=30 {
+            let sample = clip.read_sample(2, sample_id).unwrap().unwrap();
+            let expected = 1000 + k as u32 * 30 + sample_id - 1;
+            assert_eq!(sample.bytes, expected.to_be_bytes().to_vec());
+        }
+    }
+
+    // Split points move forward to the next sync sample.
+    let clips = mp4.split_at(&[Duration::from_millis(2500)]).unwrap();
+    let clip = mp4::Mp4Reader::from_bytes(&clips[0]).unwrap();
+    assert_eq!(clip.sample_count(1).unwrap(), 75);
+}
+
+#[test]
+fn test_custom_box_parser() {
+    #[derive(Debug, PartialEq)]
+    struct Custom {
+        version: u8,
+        name: String,
+    }
+
+    let mut data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    data.extend_from_slice(&[0, 0, 0, 14, b'c', b'u', b's', b't', 2]);
+    data.extend_from_slice(b"hello");
+
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = calls.clone();
+    let mut registry = mp4::BoxParserRegistry::new();
+    registry.register(str::parse("cust").unwrap(), move |payload| {
+        counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(Box::new(Custom {
+            version: payload[0],
+            name: String::from_utf8_lossy(&payload[1..]).into_owned(),
+        }))
+    });
+
+    let size = data.len() as u64;
+    let mp4 = mp4::Mp4Reader::read_header_with_registry(Cursor::new(&data), size, &registry).unwrap();
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert_eq!(mp4.custom_boxes().len(), 1);
+    let custom = &mp4.custom_boxes()[0];
+    assert_eq!(custom.box_type, str::parse::<FourCC>("cust").unwrap());
+    assert_eq!(
+        custom.downcast_ref::<Custom>(),
+        Some(&Custom {
+            version: 2,
+            name: String::from("hello"),
+        })
+    );
+
+    // Without the registry the box is skipped.
+    let mp4 = mp4::Mp4Reader::read_header(Cursor::new(&data), size).unwrap();
+    assert!(mp4.custom_boxes().is_empty());
+    assert_eq!(mp4.tracks().len(), 2);
+}
+
+#[test]
+fn test_write_samples_from_iterator() {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    writer
+        .add_track(&TrackConfig {
+            timescale: 1000,
+            ..TrackConfig::from(mp4::AacConfig::default())
+        })
+        .unwrap();
+    let samples = (0..1000u32).map(|i| mp4::Mp4Sample {
+        start_time: i as u64 * 40,
+        duration: 40,
+        rendering_offset: 0,
+        is_sync: true,
+        sample_description_index: 1,
+        bytes: bytes::Bytes::from(i.to_be_bytes().to_vec()),
+    });
+    writer.write_samples(1, samples).unwrap();
+    writer.write_end().unwrap();
+
+    // Samples were flushed a chunk of one second at a time.
+    let data = writer.into_writer().into_inner();
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let chunks = mp4.tracks().get(&1).unwrap().chunks().unwrap();
+    assert_eq!(chunks.len(), 40);
+    assert!(chunks.iter().all(|chunk| chunk.sample_ids.len() == 25));
+    for sample_id in [1, 500, 1000] {
+        let sample = mp4.read_sample(1, sample_id).unwrap().unwrap();
+        assert_eq!(sample.bytes.as_ref(), &(sample_id - 1).to_be_bytes());
+    }
+}
+
+#[test]
+fn test_progress() {
+    let data = write_aac_samples(&[100, 200, 300]);
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    let mut calls = Vec::new();
+    mp4.fragment_with_progress(Duration::from_secs(1), |current, total| {
+        calls.push((current, total))
+    })
+    .unwrap();
+    assert_eq!(calls, vec![(1, 3), (2, 3), (3, 3)]);
+
+    let mut calls = Vec::new();
+    let mut buf = Vec::new();
+    mp4.write_faststart_with_progress(&mut buf, |current, total| calls.push((current, total)))
+        .unwrap();
+    assert!(!calls.is_empty());
+    assert!(calls.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    let (current, total) = *calls.last().unwrap();
+    assert_eq!(current, total);
+    assert!(total >= 600);
+}
+
+#[test]
+fn test_raw_box() {
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    let stsd = mp4.raw_box("moov/trak[2]/mdia/minf/stbl/stsd").unwrap().unwrap();
+    assert_eq!(&stsd[0..4], &(stsd.len() as u32).to_be_bytes());
+    assert_eq!(&stsd[4..8], b"stsd");
+    assert_eq!(&stsd[20..24], b"mp4a");
+
+    let esds = mp4.raw_box("moov/trak[2]/mdia/minf/stbl/stsd/mp4a/esds").unwrap().unwrap();
+    assert_eq!(&esds[4..8], b"esds");
+    let free = mp4.raw_box("free").unwrap().unwrap();
+    assert_eq!(free, b"\0\0\0\x08free");
+
+    assert!(mp4.raw_box("moov/trak[3]").unwrap().is_none());
+    assert!(mp4.raw_box("moov/trak[0]").unwrap().is_none());
+}
+
+#[test]
+fn test_read_all_samples() {
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    let samples = mp4.read_all_samples(2).unwrap();
+    assert_eq!(samples.len(), 3);
+    let mut total = 0;
+    for sample_id in 1..=3 {
+        total += mp4.read_sample(2, sample_id).unwrap().unwrap().bytes.len();
+    }
+    assert_eq!(samples.iter().map(|s| s.len()).sum::<usize>(), total);
+
+    let track = mp4.tracks().get(&1).unwrap();
+    let samples = track.read_all_samples(&mut std::io::Cursor::new(&data)).unwrap();
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0].len(), 751);
+
+    assert!(mp4.read_all_samples(3).is_err());
+}
+
+#[test]
+fn test_read_truncated_mdat() {
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    // Cut the file inside the second audio sample.
+    let truncated = &data[..2300];
+    let mut mp4 = mp4::Mp4Reader::from_bytes(truncated).unwrap();
+    assert_eq!(mp4.tracks().len(), 2);
+
+    assert_eq!(mp4.read_sample(2, 1).unwrap().unwrap().bytes.len(), 179);
+    assert_eq!(mp4.read_sample(1, 1).unwrap().unwrap().bytes.len(), 751);
+    assert!(matches!(mp4.read_sample(2, 2), Err(mp4::Error::UnexpectedEof(2, 2))));
+    assert!(matches!(mp4.read_sample(2, 3), Err(mp4::Error::UnexpectedEof(2, 3))));
+}
+
+#[test]
+fn test_write_max_mdat_size() {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    writer.set_max_mdat_size(2100);
+    writer
+        .add_track(&TrackConfig::from(AacConfig::default()))
+        .unwrap();
+    for i in 0..5 {
+        let sample = mp4::Mp4Sample {
+            start_time: i as u64 * 1024,
+            duration: 1024,
+            rendering_offset: 0,
+            is_sync: true,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(vec![i as u8; 1000]),
+        };
+        writer.write_sample(1, &sample).unwrap();
+    }
+    writer.write_end().unwrap();
+    let data = writer.into_writer().into_inner();
+
+    // Walk the top-level boxes and collect the mdat sizes.
+    let mut mdat_sizes = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let size = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+        if &data[pos + 4..pos + 8] == b"mdat" {
+            mdat_sizes.push(size);
+        }
+        pos += size as usize;
+    }
+    assert_eq!(mdat_sizes, vec![2008, 2008, 1008]);
+
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    for sample_id in 1..=5 {
+        let sample = mp4.read_sample(1, sample_id).unwrap().unwrap();
+        assert_eq!(sample.bytes.len(), 1000);
+        assert!(sample.bytes.iter().all(|b| *b == sample_id as u8 - 1));
+    }
+}
+
+#[test]
+fn test_sample_entry() {
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    let video = mp4.tracks().get(&1).unwrap().sample_entry();
+    match video {
+        mp4::SampleEntry::Avc1(avc1) => assert_eq!(avc1.avcc.avc_profile_indication, 100),
+        other => panic!("unexpected sample entry {:?}", other),
+    }
+    assert_eq!(video.width(), Some(320));
+    assert_eq!(video.height(), Some(240));
+    assert_eq!(video.channel_count(), None);
+
+    let audio = mp4.tracks().get(&2).unwrap().sample_entry();
+    assert!(matches!(audio, mp4::SampleEntry::Mp4a(_)));
+    assert_eq!(audio.channel_count(), Some(2));
+    assert_eq!(audio.sample_rate(), Some(48000));
+    assert_eq!(audio.width(), None);
+}
+
+#[test]
+fn test_thumbnail() {
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    // The video track has no stss, so its only sample is the first sync
+    // sample: 751 bytes at offset 1500, starting with a 686-byte SEI NAL.
+    let thumbnail = mp4.thumbnail(1).unwrap().unwrap();
+    assert_eq!(thumbnail.sample_id, 1);
+    assert!(thumbnail.sample.is_sync);
+    assert_eq!(thumbnail.sample.start_time, 0);
+    assert_eq!(&thumbnail.sample.bytes[..], &data[1500..2251]);
+    assert_eq!(&thumbnail.sample.bytes[..5], &[0x00, 0x00, 0x02, 0xAE, 0x06]);
+
+    // The avcC payload: version 1, High profile (100), level 1.3, with one
+    // SPS and one PPS.
+    let avcc = thumbnail.codec_private.unwrap();
+    assert_eq!(avcc.len(), 41);
+    assert_eq!(&avcc[..4], &[0x01, 0x64, 0x00, 0x0D]);
+    let start = data.windows(4).position(|w| w == b"avcC").unwrap() + 4;
+    assert_eq!(avcc, &data[start..start + 41]);
+}
+
+#[test]
+fn test_codec_private() {
+    let data = std::fs::read("tests/samples/minimal.mp4").unwrap();
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+
+    // The avcC payload starts with configurationVersion 1 and the profile.
+    let video = mp4.tracks().get(&1).unwrap();
+    let avcc = video.codec_private().unwrap();
+    assert_eq!(avcc[0], 1);
+    assert_eq!(avcc[1], 100);
+    let start = data.windows(4).position(|w| w == b"avcC").unwrap() + 4;
+    assert_eq!(avcc, &data[start..start + avcc.len()]);
+
+    // The whole AudioSpecificConfig as stored in the esds: AAC-LC, 48 kHz
+    // (index 3), channel configuration 1, then the SBR sync extension.
+    let audio = mp4.tracks().get(&2).unwrap();
+    let asc = vec![0x11, 0x88, 0x56, 0xE5, 0x00];
+    assert_eq!(audio.codec_private().unwrap(), asc);
+    assert!(data.windows(asc.len()).any(|w| w == asc.as_slice()));
+}
+
+// A fragmented file with one video track in a 1000 Hz timescale, holding a
+// fragment with samples of the given durations for each entry of
+// `fragment_durations`.
+fn write_fragments(fragment_durations: &[&[u32]]) -> Vec<u8> {
+    let mut writer = mp4::FragmentWriter::new(Vec::new(), &mp4_config());
+    writer
+        .add_track(&TrackConfig {
+            timescale: 1000,
+            ..TrackConfig::from(mp4::Vp9Config {
+                width: 320,
+                height: 240,
+                ..mp4::Vp9Config::default()
+            })
+        })
+        .unwrap();
+    for durations in fragment_durations {
+        let samples: Vec<mp4::Mp4Sample> = durations
+            .iter()
+            .map(|&duration| mp4::Mp4Sample {
+                start_time: 0,
+                duration,
+                rendering_offset: 0,
+                is_sync: true,
+                sample_description_index: 1,
+                bytes: bytes::Bytes::from(vec![0; 4]),
+            })
+            .collect();
+        writer.write_fragment(1, &samples).unwrap();
+    }
+    writer.finalize().unwrap()
+}
+
+#[test]
+fn test_sample_at_time_fragmented() {
+    let data = write_fragments(&[&[40, 40], &[20, 60]]);
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let track = &mp4.tracks()[&1];
+    assert!(track.is_fragmented_compatible());
+    assert_eq!(track.sample_at_time(0), Some(1));
+    assert_eq!(track.sample_at_time(79), Some(2));
+    assert_eq!(track.sample_at_time(80), Some(3));
+    assert_eq!(track.sample_at_time(100), Some(4));
+    assert_eq!(track.sample_at_time(159), Some(4));
+    assert_eq!(track.sample_at_time(160), None);
+}
+
+#[test]
+fn test_exact_frame_rate_fragmented() {
+    // 25 fps, with a shorter final sample.
+    let data = write_fragments(&[&[40, 40], &[40, 40, 10]]);
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let rate = mp4.tracks()[&1].exact_frame_rate().unwrap();
+    assert_eq!(rate.rate, mp4::Ratio::new(25, 1));
+    assert!(!rate.variable);
+
+    // Four frames in 120ms, ignoring the last one.
+    let data = write_fragments(&[&[40, 20], &[20, 40], &[40]]);
+    let mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    let rate = mp4.tracks()[&1].exact_frame_rate().unwrap();
+    assert_eq!(rate.rate, mp4::Ratio::new(100, 3));
+    assert!(rate.variable);
+}
+
+#[test]
+fn test_force_cfr() {
+    let mut writer = Mp4Writer::write_start(Cursor::new(Vec::new()), &mp4_config()).unwrap();
+    let vp9_config = mp4::Vp9Config {
+        width: 320,
+        height: 240,
+        ..mp4::Vp9Config::default()
+    };
+    writer
+        .add_track(&TrackConfig {
+            timescale: 30000,
+            ..TrackConfig::from(vp9_config)
+        })
+        .unwrap();
+    let mut start_time = 0;
+    for duration in [1000, 2000, 1500, 1500] {
+        let sample = mp4::Mp4Sample {
+            start_time,
+            duration,
+            rendering_offset: 0,
+            is_sync: true,
+            sample_description_index: 1,
+            bytes: bytes::Bytes::from(vec![0; 4]),
+        };
+        writer.write_sample(1, &sample).unwrap();
+        start_time += duration as u64;
+    }
+    writer.write_end().unwrap();
+
+    let data = writer.into_writer().into_inner();
+    let mut mp4 = mp4::Mp4Reader::from_bytes(&data).unwrap();
+    mp4.force_cfr(1, mp4::Ratio::new(30, 1)).unwrap();
+    // Four frames at 30 fps, in the 1000 Hz movie timescale.
+    assert_eq!(mp4.moov.mvhd.duration, 133);
+    assert_eq!(mp4.moov.traks[0].mdia.mdhd.duration, 4000);
+    assert_eq!(mp4.tracks()[&1].trak, mp4.moov.traks[0]);
 }